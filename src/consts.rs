@@ -1,5 +1,7 @@
 //! Constant markers used when parsing `Ink` lines.
 
+use crate::line::AlternativeKind;
+
 /************************
  * Line content markers *
  ************************/
@@ -13,9 +15,22 @@ pub const STICKY_CHOICE_MARKER: char = '+';
 /// Marker for a gather point.
 pub const GATHER_MARKER: char = '-';
 
+/// Marker for the beginning of a multiline conditional block.
+pub const BLOCK_BEGIN_MARKER: char = '{';
+
+/// Marker for the end of a multiline conditional block.
+pub const BLOCK_END_MARKER: char = '}';
+
+/// Keyword for the branch in a multiline conditional block which is followed if no
+/// other branch condition is fulfilled.
+pub const ELSE_KEYWORD: &'static str = "else";
+
 /// Marker for a divert to another knot, stitch or label in the story.
 pub const DIVERT_MARKER: &'static str = "->";
 
+/// Marker for a thread which merges the choices of another knot, stitch or label into the story.
+pub const THREAD_MARKER: &'static str = "<-";
+
 /// Marker for glue which joins separate lines together without a newline character.
 pub const GLUE_MARKER: &'static str = "<>";
 
@@ -25,6 +40,10 @@ pub const GLUE_MARKER: &'static str = "<>";
 /// (or the end of the line) will be a single tag.
 pub const TAG_MARKER: char = '#';
 
+/// Marker which escapes the character after it, which is then read as text instead
+/// of markup. An escaped escape marker is a literal backslash: `\\`.
+pub const ESCAPE_MARKER: char = '\\';
+
 /********************
  * Sequence markers *
  ********************/
@@ -41,6 +60,19 @@ pub const SHUFFLE_MARKER: char = '~';
 /// Marker for sequence item separator.
 pub const SEQUENCE_SEPARATOR: &'static str = "|";
 
+/// Keywords for the kinds of alternative sequences, which begin multiline alternative
+/// blocks (`{stopping:`) or inline alternatives (`{cycle: a|b}`).
+///
+/// Keywords which begin with another keyword are listed first.
+pub const SEQUENCE_KEYWORDS: &[(&'static str, AlternativeKind)] = &[
+    ("shuffle once", AlternativeKind::ShuffleOnce),
+    ("shuffle stopping", AlternativeKind::ShuffleStopping),
+    ("shuffle", AlternativeKind::Shuffle),
+    ("stopping", AlternativeKind::Sequence),
+    ("cycle", AlternativeKind::Cycle),
+    ("once", AlternativeKind::OnceOnly),
+];
+
 /****************
  * Knot markers *
  ****************/
@@ -58,11 +90,9 @@ pub const STITCH_MARKER: &'static str = "=";
 /// Marker for line comments, which will be ignored when parsing a story.
 pub const LINE_COMMENT_MARKER: &'static str = "//";
 
-#[allow(dead_code)]
 /// Marker to begin multiline comments.
 pub const MULTILINE_COMMENT_BEGIN_MARKER: &'static str = "/*";
 
-#[allow(dead_code)]
 /// Marker to end multiline comments.
 pub const MULTILINE_COMMENT_END_MARKER: &'static str = "*/";
 
@@ -95,14 +125,28 @@ pub const CONST_MARKER: &'static str = "CONST";
 /// Marker for global variable.
 pub const VARIABLE_MARKER: &'static str = "VAR";
 
-#[allow(dead_code)]
 /// Marker for lists.
 pub const LIST_MARKER: &'static str = "LIST";
 
-#[allow(dead_code)]
 /// Variable assignment marker.
 pub const ASSIGNMENT_MARKER: char = '~';
 
+/// Marker for declaring a temporary variable in an assignment.
+pub const TEMPORARY_VARIABLE_MARKER: &'static str = "temp";
+
+/************************
+ * Function definitions *
+ ************************/
+
+/// Marker for a knot which defines a function: `=== function name(a, b) ===`.
+pub const FUNCTION_MARKER: &'static str = "function";
+
+/// Marker for a function parameter which is passed by reference.
+pub const REF_PARAMETER_MARKER: &'static str = "ref";
+
+/// Marker for returning a value from a function in a logic line.
+pub const RETURN_MARKER: &'static str = "return";
+
 /***********************
  * Meta data variables *
  ***********************/
@@ -132,6 +176,7 @@ pub const RESERVED_FUNCTIONS: &[&'static str] = &[
     "FLOOR",
     "RANDOM",
     "POW",
+    "READ_COUNT",
     "LIST_ALL",
     "LIST_COUNT",
     "LIST_MIN",
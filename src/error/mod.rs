@@ -6,5 +6,5 @@ pub mod parse;
 pub(crate) mod runtime;
 
 pub use parse::ReadError;
-pub use runtime::{variable, InklingError, InternalError};
+pub use runtime::{variable, InklingError, InternalError, ReplayError, ReplayErrorKind};
 pub use utils::MetaData;
@@ -5,6 +5,7 @@ use std::{error::Error, fmt};
 use crate::{
     error::utils::{write_line_information, MetaData},
     knot::Address,
+    line::Variable,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -23,10 +24,27 @@ pub enum InvalidAddressErrorKind {
     BadFormat { line: String },
     /// The address does not reference a knot, stitch or variable in the story.
     UnknownAddress { name: String },
+    /// The address was moved to with a different number of arguments than the parameters
+    /// of its knot or stitch.
+    InvalidNumberOfArguments {
+        address: String,
+        num_parameters: usize,
+        num_arguments: usize,
+    },
+    /// The address was assigned to but does not reference a variable.
+    NotAVariable { address: Address },
+    /// The address was diverted to but is a variable which does not hold a divert target.
+    NotADivertTarget { name: String, value: Variable },
     /// Tried to validate an address but the given current knot did not exist in the system.
     UnknownCurrentAddress { address: Address },
     /// The address references a `Knot` that is not in the story.
     UnknownKnot { knot_name: String },
+    /// The address references a label that is not present in the `Stitch`.
+    UnknownLabel {
+        knot_name: String,
+        stitch_name: String,
+        label_name: String,
+    },
     /// The address references a `Stitch` that is not present in the current `Knot`.
     UnknownStitch {
         knot_name: String,
@@ -60,6 +78,28 @@ impl fmt::Display for InvalidAddressErrorKind {
 
         match self {
             BadFormat { line } => write!(f, "address was incorrectly formatted ('{}')", line),
+            InvalidNumberOfArguments {
+                address,
+                num_parameters,
+                num_arguments,
+            } => write!(
+                f,
+                "'{}' has {} parameters but was moved to with {} arguments",
+                address, num_parameters, num_arguments
+            ),
+            NotAVariable { address } => write!(
+                f,
+                "tried to assign a value to '{}' which is not a variable",
+                address.to_string()
+            ),
+            NotADivertTarget { name, value } => write!(
+                f,
+                "tried to divert to variable '{}' which has value '{}' of type {} \
+                 instead of a divert target",
+                name,
+                value.to_error_string(),
+                value.variant_string()
+            ),
             UnknownAddress { name } => write!(
                 f,
                 "could not find knot or variable with name '{}' in the story",
@@ -74,6 +114,15 @@ impl fmt::Display for InvalidAddressErrorKind {
             UnknownKnot { knot_name } => {
                 write!(f, "no knot with name '{}' in the story", knot_name)
             }
+            UnknownLabel {
+                knot_name,
+                stitch_name,
+                label_name,
+            } => write!(
+                f,
+                "no label with name '{}' in stitch '{}' of knot '{}'",
+                label_name, stitch_name, knot_name
+            ),
             UnknownStitch {
                 knot_name,
                 stitch_name,
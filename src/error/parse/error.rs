@@ -3,6 +3,7 @@
 use std::{error::Error, fmt};
 
 use crate::error::parse::{
+    include::IncludeError,
     parse::{print_parse_error, ParseError},
     validate::{print_validation_error, ValidationError},
 };
@@ -15,6 +16,8 @@ use crate::error::parse::{
 pub enum ReadError {
     /// Attempted to construct a story from an empty file/string.
     Empty,
+    /// Could not load a file included by the story.
+    IncludeError(IncludeError),
     /// Encountered one or more errors while parsing lines to construct the story.
     ParseError(ParseError),
     /// Encountered one or more errors while validating a successfully parsed story.
@@ -42,6 +45,7 @@ pub fn print_read_error(error: &ReadError) -> Result<String, fmt::Error> {
 impl Error for ReadError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self {
+            ReadError::IncludeError(err) => Some(err),
             ReadError::ParseError(err) => Some(err),
             ReadError::ValidationError(err) => Some(err),
             _ => None,
@@ -55,6 +59,7 @@ impl fmt::Display for ReadError {
 
         match self {
             Empty => write!(f, "Could not parse story: no content was available"),
+            IncludeError(err) => write!(f, "Could not include file: {}", err),
             ParseError(err) => write!(f, "{}", err),
             ValidationError(err) => write!(f, "{}", err),
         }
@@ -63,6 +68,7 @@ impl fmt::Display for ReadError {
 
 impl_from_error![
     ReadError;
+    [IncludeError, IncludeError],
     [ParseError, ParseError],
     [ValidationError, ValidationError]
 ];
@@ -0,0 +1,57 @@
+//! Errors from loading included files of a story.
+
+use std::{error::Error, fmt};
+
+use crate::error::utils::{write_line_information, MetaData};
+
+#[derive(Clone, Debug)]
+/// Error from loading the files of a story which is divided into several files.
+pub struct IncludeError {
+    /// Name of file that could not be included.
+    pub name: String,
+    /// Kind of error.
+    pub kind: IncludeErrorKind,
+    /// Information about the origin of the `INCLUDE` line that caused this error.
+    ///
+    /// Not set if the error was caused by the root file of the story.
+    pub meta_data: Option<MetaData>,
+}
+
+#[derive(Clone, Debug)]
+/// Variant of error from including a file.
+pub enum IncludeErrorKind {
+    /// The loader could not load the file.
+    CouldNotLoad {
+        /// Message of the error returned by the loader.
+        message: String,
+    },
+    /// The file was included by itself, either directly or through other included files.
+    IncludeCycle {
+        /// Names of all files in the cycle, beginning and ending with the included file.
+        cycle: Vec<String>,
+    },
+}
+
+impl Error for IncludeError {}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use IncludeErrorKind::*;
+
+        if let Some(meta_data) = &self.meta_data {
+            write_line_information(f, meta_data)?;
+        }
+
+        match &self.kind {
+            CouldNotLoad { message } => {
+                write!(f, "could not load file '{}': {}", self.name, message)
+            }
+            IncludeCycle { cycle } => write!(
+                f,
+                "file '{}' is included by itself: {}",
+                self.name,
+                cycle.join(" -> ")
+            ),
+        }
+    }
+}
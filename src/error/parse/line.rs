@@ -34,10 +34,10 @@ pub enum LineErrorKind {
     ExpectedEndOfLine { tail: String },
     /// Could not read a numerical expression.
     ExpressionError(ExpressionError),
-    /// Found several divert markers which indicates unimplemented tunnels.
-    FoundTunnel,
     /// Found an address with invalid characters.
     InvalidAddress { address: String },
+    /// Found an assignment marker but no valid assignment.
+    InvalidAssignment { content: String },
     /// A choice has both non-sticky and sticky markers.
     StickyAndNonSticky,
     /// Found unmatched curly braces.
@@ -89,18 +89,18 @@ impl fmt::Display for LineErrorKind {
                 tail
             ),
             ExpressionError(err) => write!(f, "could not parse an expression: {}", err),
-            FoundTunnel => write!(
-                f,
-                "Found multiple divert markers. In the `Ink` language this indicates \
-                 a `tunnel` for the story to pass through, but these are not yet implemented \
-                 in `inkling`."
-            ),
             InvalidAddress { address } => write!(
                 f,
                 "found an invalid address to knot, stitch or variable '{}': \
                  contains invalid characters",
                 address
             ),
+            InvalidAssignment { content } => write!(
+                f,
+                "found an assignment marker but could not read a variable assignment \
+                 (`~ variable = value`) from '{}'",
+                content
+            ),
             StickyAndNonSticky => write!(
                 f,
                 "Encountered a line which has both non-sticky ('{}') and sticky ('{}') \
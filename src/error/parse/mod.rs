@@ -4,6 +4,7 @@ pub mod address;
 pub mod condition;
 mod error;
 pub mod expression;
+pub mod include;
 pub mod knot;
 pub mod line;
 mod parse;
@@ -3,8 +3,9 @@
 use std::{error::Error, fmt};
 
 use crate::error::{
-    parse::variable::VariableError,
+    parse::{expression::ExpressionError, variable::VariableError},
     utils::{write_line_information, MetaData},
+    InklingError,
 };
 
 #[derive(Clone, Debug)]
@@ -21,14 +22,39 @@ pub struct PreludeError {
 #[derive(Clone, Debug)]
 /// Variant of error from parsing the prelude.
 pub enum PreludeErrorKind {
+    /// Initial values of constants refer to each other in a cycle.
+    ConstantCycle {
+        /// Names of the constants in the cycle, beginning and ending with the same name.
+        names: Vec<String>,
+    },
+    /// Could not evaluate the initial value of a global variable.
+    CouldNotEvaluate(InklingError),
+    /// External function with given name was declared multiple times.
+    DuplicateExternalFunction { name: String },
+    /// List with given name was defined multiple times.
+    DuplicateList { name: String },
     /// Variable with given name was defined multiple times.
     DuplicateVariable { name: String },
+    /// Could not parse the signature of an external function declaration.
+    InvalidExternalFunction,
+    /// Could not parse a list definition.
+    InvalidList,
+    /// Could not parse the initial value of a global variable as an expression.
+    InvalidInitialValue(ExpressionError),
     /// Could not parse a global variable.
     InvalidVariable(VariableError),
     /// No `=` sign was find in a variable assignment line.
     NoVariableAssignment,
     /// No variable name was found in a variable assignment line.
     NoVariableName,
+    /// Initial value of a global variable refers to a name which is not a constant.
+    ///
+    /// Initial values may only refer to constants, since the values of other variables
+    /// are not known until the story is followed.
+    NonConstantReference { name: String },
+    /// Initial value of a global variable refers to a name which is neither a declared
+    /// variable, a constant nor a list item.
+    UnknownReference { name: String },
 }
 
 impl Error for PreludeError {
@@ -40,6 +66,8 @@ impl Error for PreludeError {
 impl Error for PreludeErrorKind {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self {
+            PreludeErrorKind::CouldNotEvaluate(err) => Some(err),
+            PreludeErrorKind::InvalidInitialValue(err) => Some(err),
             PreludeErrorKind::InvalidVariable(err) => Some(err),
             _ => None,
         }
@@ -48,6 +76,8 @@ impl Error for PreludeErrorKind {
 
 impl_from_error![
     PreludeErrorKind;
+    [CouldNotEvaluate, InklingError],
+    [InvalidInitialValue, ExpressionError],
     [InvalidVariable, VariableError]
 ];
 
@@ -63,12 +93,43 @@ impl fmt::Display for PreludeErrorKind {
         use PreludeErrorKind::*;
 
         match &self {
+            ConstantCycle { names } => write!(
+                f,
+                "initial values of constants refer to each other in a cycle: {}",
+                names.join(" -> ")
+            ),
+            CouldNotEvaluate(err) => write!(f, "could not evaluate initial value: {}", err),
+            DuplicateExternalFunction { name } => write!(
+                f,
+                "found second declaration of external function '{}'",
+                name
+            ),
+            DuplicateList { name } => write!(f, "found second definition of list '{}'", name),
             DuplicateVariable { name } => {
                 write!(f, "found second definition of global variable '{}'", name)
             }
+            InvalidExternalFunction => write!(
+                f,
+                "could not parse external function declaration: expected `EXTERNAL name(a, b)`"
+            ),
+            InvalidList => write!(
+                f,
+                "could not parse list definition: expected `LIST name = a, (b), c`"
+            ),
+            InvalidInitialValue(err) => write!(f, "could not parse initial value: {}", err),
             InvalidVariable(err) => write!(f, "could not parse variable: {}", err),
             NoVariableAssignment => write!(f, "no variable assignment ('=') in line"),
             NoVariableName => write!(f, "no variable name in line"),
+            NonConstantReference { name } => write!(
+                f,
+                "initial value refers to '{}', which is not a constant",
+                name
+            ),
+            UnknownReference { name } => write!(
+                f,
+                "initial value refers to '{}', which is not a variable, constant or list item",
+                name
+            ),
         }
     }
 }
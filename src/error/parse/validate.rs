@@ -5,11 +5,14 @@
 //! throughout the entire story. Any invalid types or names will yield a
 //! [`ValidationError`][crate::error::parse::validate::ValidationError].
 
-use crate::error::{
-    parse::address::InvalidAddressError,
-    runtime::variable::VariableError,
-    utils::{write_line_information, MetaData},
-    InklingError,
+use crate::{
+    error::{
+        parse::address::InvalidAddressError,
+        runtime::variable::VariableError,
+        utils::{write_line_information, MetaData},
+        InklingError,
+    },
+    line::Variable,
 };
 
 use std::{
@@ -25,7 +28,8 @@ pub struct ValidationError {
     /// Errors from name space collisions between knots, stitches and variables.
     ///
     /// Stitches or global variables may not have the same name as any knot in the story. Nor may
-    /// stitches have the same name as any global variable.
+    /// stitches have the same name as any global variable. Temporary variables may not shadow
+    /// any knot, stitch or global variable.
     ///
     /// This is to ensure that addresses are well determined. Internal addresses to stitches
     /// within knots can exclude the knot name, meaning that if a stitch and knot share a name
@@ -44,6 +48,14 @@ pub struct ValidationError {
     /// See [`Variable`][crate::line::Variable] for more information about valid operations
     /// and comparisons between variables.
     pub variable_errors: Vec<InvalidVariableExpression>,
+    /// Errors from calls to functions which are not declared in the story, or which
+    /// are called with the wrong number of arguments.
+    pub function_call_errors: Vec<InvalidFunctionCall>,
+    /// Errors from content in functions which would leave the function.
+    ///
+    /// Functions are followed until they return, so they cannot contain choices, diverts,
+    /// threads or tunnels.
+    pub function_content_errors: Vec<InvalidFunctionContent>,
 }
 
 impl ValidationError {
@@ -53,6 +65,8 @@ impl ValidationError {
             invalid_address_errors: Vec::new(),
             name_space_errors: Vec::new(),
             variable_errors: Vec::new(),
+            function_call_errors: Vec::new(),
+            function_content_errors: Vec::new(),
         }
     }
 
@@ -66,13 +80,15 @@ impl ValidationError {
         self.invalid_address_errors.len()
             + self.name_space_errors.len()
             + self.variable_errors.len()
+            + self.function_call_errors.len()
+            + self.function_content_errors.len()
     }
 }
 
 #[derive(Clone, Debug)]
 /// Error type for invalid variables inside expressions and conditions.
 pub struct InvalidVariableExpression {
-    /// Whether the error is in a condition or expression.
+    /// Whether the error is in an assignment, condition or expression.
     pub expression_kind: ExpressionKind,
     /// Variant of error that was encountered.
     pub kind: InvalidVariableExpressionError,
@@ -83,6 +99,7 @@ pub struct InvalidVariableExpression {
 #[derive(Clone, Debug)]
 /// Kind of encountered invalid expression.
 pub enum ExpressionKind {
+    Assignment,
     Condition,
     Expression,
 }
@@ -101,6 +118,61 @@ pub enum InvalidVariableExpressionError {
     Internal(InklingError),
 }
 
+#[derive(Clone, Debug)]
+/// Error type for invalid calls to functions.
+pub struct InvalidFunctionCall {
+    /// Name of called function.
+    pub name: String,
+    /// Variant of error that was encountered.
+    pub kind: InvalidFunctionCallKind,
+    /// Information about the origin of the line containing this error.
+    pub meta_data: MetaData,
+}
+
+#[derive(Clone, Debug)]
+/// Error variant for invalid calls to functions.
+pub enum InvalidFunctionCallKind {
+    /// A built-in function was called with an argument of the wrong type.
+    InvalidArgument {
+        /// Value of the invalid argument.
+        value: Variable,
+    },
+    /// The argument for a parameter which is passed by reference is not a single variable.
+    InvalidRefArgument {
+        /// Name of the `ref` parameter.
+        parameter: String,
+    },
+    /// No function with the name was declared in the story.
+    UnknownFunction,
+    /// The function was called with a different number of arguments than it was declared with.
+    WrongNumberOfArguments {
+        /// Number of parameters in the function declaration.
+        expected: usize,
+        /// Number of arguments in the call.
+        found: usize,
+    },
+}
+
+#[derive(Clone, Debug)]
+/// Error type for content in a function which would leave it.
+pub struct InvalidFunctionContent {
+    /// Name of the function.
+    pub name: String,
+    /// Kind of content that was found.
+    pub kind: InvalidFunctionContentKind,
+    /// Information about the origin of the line containing this error.
+    pub meta_data: MetaData,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Kind of content which is not allowed in functions.
+pub enum InvalidFunctionContentKind {
+    Choice,
+    Divert,
+    Thread,
+    Tunnel,
+}
+
 #[derive(Clone, Debug)]
 /// Error type for name space collisions.
 pub struct NameSpaceCollision {
@@ -121,6 +193,7 @@ pub struct NameSpaceCollision {
 pub enum CollisionKind {
     Knot,
     Stitch,
+    TemporaryVariable,
     Variable,
 }
 
@@ -140,6 +213,14 @@ pub(super) fn print_validation_error(error: &ValidationError) -> Result<String,
         write!(&mut buffer, "{}\n", err)?;
     }
 
+    for err in &error.function_call_errors {
+        write!(&mut buffer, "{}\n", err)?;
+    }
+
+    for err in &error.function_content_errors {
+        write!(&mut buffer, "{}\n", err)?;
+    }
+
     Ok(buffer)
 }
 
@@ -147,6 +228,10 @@ impl Error for ValidationError {}
 
 impl Error for NameSpaceCollision {}
 
+impl Error for InvalidFunctionCall {}
+
+impl Error for InvalidFunctionContent {}
+
 impl Error for InvalidVariableExpression {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match &self.kind {
@@ -169,11 +254,14 @@ impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Encountered {} invalid address, {} name space collision \
-             and {} invalid variable errors during validation",
+            "Encountered {} invalid address, {} name space collision, \
+             {} invalid variable, {} invalid function call and {} invalid function content \
+             errors during validation",
             self.invalid_address_errors.len(),
             self.name_space_errors.len(),
-            self.variable_errors.len()
+            self.variable_errors.len(),
+            self.function_call_errors.len(),
+            self.function_content_errors.len()
         )
     }
 }
@@ -195,9 +283,65 @@ impl fmt::Display for InvalidVariableExpression {
     }
 }
 
+impl fmt::Display for InvalidFunctionCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_line_information(f, &self.meta_data)?;
+
+        match &self.kind {
+            InvalidFunctionCallKind::InvalidArgument { value } => write!(
+                f,
+                "Called function '{}' with argument '{}' of invalid type {}",
+                self.name,
+                value.to_error_string(),
+                value.variant_string()
+            ),
+            InvalidFunctionCallKind::InvalidRefArgument { parameter } => write!(
+                f,
+                "Called function '{}' with an argument for `ref` parameter '{}' which is \
+                 not a variable",
+                self.name, parameter
+            ),
+            InvalidFunctionCallKind::UnknownFunction => write!(
+                f,
+                "Called function '{}' which is not declared in the story",
+                self.name
+            ),
+            InvalidFunctionCallKind::WrongNumberOfArguments { expected, found } => write!(
+                f,
+                "Called function '{}' with {} arguments, but it takes {}",
+                self.name, found, expected
+            ),
+        }
+    }
+}
+
+impl fmt::Display for InvalidFunctionContent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_line_information(f, &self.meta_data)?;
+
+        write!(
+            f,
+            "Function '{}' contains a {}, but functions can only print text and return values",
+            self.name, self.kind
+        )
+    }
+}
+
+impl fmt::Display for InvalidFunctionContentKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self {
+            InvalidFunctionContentKind::Choice => write!(f, "choice"),
+            InvalidFunctionContentKind::Divert => write!(f, "divert"),
+            InvalidFunctionContentKind::Thread => write!(f, "thread"),
+            InvalidFunctionContentKind::Tunnel => write!(f, "tunnel"),
+        }
+    }
+}
+
 impl fmt::Display for ExpressionKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match &self {
+            ExpressionKind::Assignment => write!(f, "assignment"),
             ExpressionKind::Condition => write!(f, "condition"),
             ExpressionKind::Expression => write!(f, "expression"),
         }
@@ -221,6 +365,7 @@ impl fmt::Display for CollisionKind {
         match &self {
             CollisionKind::Knot => write!(f, "knot"),
             CollisionKind::Stitch => write!(f, "stitch"),
+            CollisionKind::TemporaryVariable => write!(f, "temporary variable"),
             CollisionKind::Variable => write!(f, "global variable"),
         }
     }
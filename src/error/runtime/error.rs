@@ -3,7 +3,11 @@
 use std::{error::Error, fmt};
 
 use crate::{
-    error::{runtime::internal::StackError, variable::VariableError, InternalError},
+    error::{
+        runtime::internal::{ProcessError, ProcessErrorKind, StackError},
+        variable::VariableError,
+        InternalError,
+    },
     line::Variable,
     story::{Choice, Location},
 };
@@ -29,6 +33,13 @@ impl Error for InklingError {}
 pub enum InklingError {
     /// Tried to assign a new value to a CONST variable.
     AssignedToConst { name: String },
+    /// A function bound to an external function returned an error when called.
+    ExternalFunctionError {
+        /// Name of external function.
+        name: String,
+        /// Message of the error returned by the function.
+        message: String,
+    },
     /// Internal errors caused by `inkling`.
     Internal(InternalError),
     /// Use of a `Location` which does not exist in the story.
@@ -40,6 +51,55 @@ pub enum InklingError {
         /// List of choices that were available for the selection
         presented_choices: Vec<Choice>,
     },
+    /// Tried to bind a function with a different number of arguments than the external
+    /// function was declared with.
+    InvalidExternalFunctionArity {
+        /// Name of external function.
+        name: String,
+        /// Number of parameters in the `EXTERNAL` declaration.
+        num_declared: usize,
+        /// Number of arguments of the bound function.
+        num_bound: usize,
+    },
+    /// Diverted to a variable which does not hold a divert target.
+    InvalidDivertTarget {
+        /// Name of variable.
+        name: String,
+        /// Value of the variable.
+        value: Variable,
+    },
+    /// A built-in function was called with an argument of the wrong type.
+    InvalidFunctionArgument {
+        /// Name of function.
+        name: String,
+        /// Value of the invalid argument.
+        value: Variable,
+    },
+    /// A function defined in the story encountered a divert or choice, which functions
+    /// cannot contain.
+    InvalidFunctionFlow {
+        /// Name of function.
+        name: String,
+    },
+    /// Moved to a location with a different number of arguments than the parameters
+    /// of its knot or stitch.
+    InvalidNumberOfArguments {
+        /// Location that was moved to.
+        location: Location,
+        /// Number of parameters of the knot or stitch.
+        num_parameters: usize,
+        /// Number of given arguments.
+        num_arguments: usize,
+    },
+    /// Used a list item which is not defined by any list in the story.
+    InvalidListItem { name: String },
+    /// Tried to rewind the story by more choices than have been recorded in its history.
+    InvalidRewind {
+        /// Number of choices to rewind by.
+        steps: usize,
+        /// Number of made choices which are recorded in the history.
+        num_recorded: usize,
+    },
     /// Used a variable name that is not present in the story as an input variable.
     InvalidVariable { name: String },
     /// Called `make_choice` when no choice had been requested.
@@ -52,6 +112,14 @@ pub enum InklingError {
     OutOfContent,
     /// Tried to print a variable that cannot be printed.
     PrintInvalidVariable { name: String, value: Variable },
+    /// Encountered a `return` statement outside of a function.
+    ReturnOutsideFunction { location: Location },
+    /// Encountered a tunnel return `->->` outside of a tunnel.
+    TunnelReturnOutsideTunnel { location: Location },
+    /// External functions have not been bound to functions before the story was resumed.
+    UnboundExternalFunctions { names: Vec<String> },
+    /// Tried to bind a function to an external function that was not declared in the story.
+    UnknownExternalFunction { name: String },
     /// Invalid variable assignment or operation.
     VariableError(VariableError),
 }
@@ -62,6 +130,17 @@ impl From<StackError> for InklingError {
     }
 }
 
+impl From<ProcessError> for InklingError {
+    /// Errors from the story or user input which were encountered when processing
+    /// content are returned as they are. All other errors are internal.
+    fn from(err: ProcessError) -> Self {
+        match err.kind {
+            ProcessErrorKind::InklingError(err) => *err,
+            _ => InklingError::Internal(InternalError::CouldNotProcess(err)),
+        }
+    }
+}
+
 impl_from_error![
     InklingError;
     [Internal, InternalError],
@@ -76,16 +155,35 @@ impl fmt::Display for InklingError {
             AssignedToConst { name } => {
                 write!(f, "Tried to assign a value to CONST variable '{}'", name)
             }
+            ExternalFunctionError { name, message } => write!(
+                f,
+                "Call to external function '{}' returned an error: {}",
+                name, message
+            ),
             Internal(err) => write!(f, "INTERNAL ERROR: {}", err),
             InvalidAddress {
-                location: Location { knot, stitch },
-            } => match stitch {
-                Some(stitch_name) => write!(
+                location:
+                    Location {
+                        knot,
+                        stitch,
+                        label,
+                    },
+            } => match (stitch, label) {
+                (_, Some(label_name)) => write!(
+                    f,
+                    "Invalid address: location '{}' does not contain a label named '{}'",
+                    stitch
+                        .as_ref()
+                        .map(|s| format!("{}.{}", knot, s))
+                        .unwrap_or(knot.clone()),
+                    label_name
+                ),
+                (Some(stitch_name), None) => write!(
                     f,
                     "Invalid address: knot '{}' does not contain a stitch named '{}'",
                     knot, stitch_name
                 ),
-                None => write!(
+                (None, None) => write!(
                     f,
                     "Invalid address: story does not contain a knot name '{}'",
                     knot
@@ -102,6 +200,68 @@ impl fmt::Display for InklingError {
                 presented_choices.len(),
                 presented_choices.len() - 1
             ),
+            InvalidExternalFunctionArity {
+                name,
+                num_declared,
+                num_bound,
+            } => write!(
+                f,
+                "Invalid external function: '{}' is declared with {} parameters \
+                 but the bound function takes {} arguments",
+                name, num_declared, num_bound
+            ),
+            InvalidDivertTarget { name, value } => write!(
+                f,
+                "Invalid divert: variable '{}' has value '{}' of type {} which is not \
+                 a divert target",
+                name,
+                value.to_error_string(),
+                value.variant_string()
+            ),
+            InvalidFunctionArgument { name, value } => write!(
+                f,
+                "Invalid argument to function '{}': cannot be called with value '{}' of type {}",
+                name,
+                value.to_error_string(),
+                value.variant_string()
+            ),
+            InvalidFunctionFlow { name } => write!(
+                f,
+                "Function '{}' encountered a divert or choice, which functions cannot contain",
+                name
+            ),
+            InvalidListItem { name } => write!(
+                f,
+                "Invalid list item: no list in the story contains an item '{}'",
+                name
+            ),
+            InvalidNumberOfArguments {
+                location: Location { knot, stitch, .. },
+                num_parameters,
+                num_arguments,
+            } => {
+                write!(
+                    f,
+                    "Invalid number of arguments: location has {} parameters but was moved to \
+                     with {} arguments (knot: {}",
+                    num_parameters, num_arguments, knot
+                )?;
+
+                if let Some(name) = stitch {
+                    write!(f, ", stitch: {}", name)?;
+                }
+
+                write!(f, ")")
+            }
+            InvalidRewind {
+                steps,
+                num_recorded,
+            } => write!(
+                f,
+                "Invalid rewind: tried to rewind {} choices but only {} choices are recorded \
+                 in the history",
+                steps, num_recorded
+            ),
             InvalidVariable { name } => write!(
                 f,
                 "Invalid variable: no variable with  name '{}' exists in the story",
@@ -113,7 +273,7 @@ impl fmt::Display for InklingError {
                  and assert that a branching choice is returned before calling this again."
             ),
             OutOfChoices {
-                location: Location { knot, stitch },
+                location: Location { knot, stitch, .. },
             } => {
                 write!(
                     f,
@@ -134,6 +294,27 @@ impl fmt::Display for InklingError {
                 "Cannot print variable '{}' which has value '{:?}': invalid type",
                 name, value
             ),
+            ReturnOutsideFunction { location } => write!(
+                f,
+                "Encountered a `return` statement outside of a function (knot: {})",
+                location.knot
+            ),
+            TunnelReturnOutsideTunnel { location } => write!(
+                f,
+                "Encountered a tunnel return `->->` outside of a tunnel (knot: {})",
+                location.knot
+            ),
+            UnboundExternalFunctions { names } => write!(
+                f,
+                "External functions have not been bound to functions: {}",
+                names.join(", ")
+            ),
+            UnknownExternalFunction { name } => write!(
+                f,
+                "Tried to bind a function to '{}' which is not declared as \
+                 an external function in the story",
+                name
+            ),
             VariableError(err) => write!(f, "{}", err),
         }
     }
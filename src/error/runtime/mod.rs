@@ -1,7 +1,9 @@
 #[macro_use]
 pub(crate) mod error;
 pub(crate) mod internal;
+pub(crate) mod replay;
 pub mod variable;
 
 pub use error::InklingError;
 pub use internal::InternalError;
+pub use replay::{ReplayError, ReplayErrorKind};
@@ -0,0 +1,72 @@
+//! Errors from replaying a recorded playthrough.
+
+use std::{error::Error, fmt};
+
+use crate::error::InklingError;
+
+impl Error for ReplayError {}
+
+#[derive(Clone, Debug)]
+/// Error from replaying a [`ReplayLog`][crate::story::ReplayLog] on a story.
+///
+/// Marks the first step of the log at which the story diverged from the recorded
+/// playthrough, which is likely due to the script having changed since it was recorded.
+pub struct ReplayError {
+    /// Index of the step in the log which could not be replayed.
+    pub step: usize,
+    /// Error variant.
+    pub kind: ReplayErrorKind,
+}
+
+#[derive(Clone, Debug)]
+/// Error variant for diverging replays.
+pub enum ReplayErrorKind {
+    /// The text of the choice at the recorded index differs from the recorded text.
+    ChoiceTextDiffers {
+        /// Text of the recorded choice.
+        expected: String,
+        /// Text of the choice at the same index in the story.
+        found: String,
+    },
+    /// Replaying the step raised an error, for example if a location or variable
+    /// no longer exists in the story.
+    Error(InklingError),
+    /// The recorded choice index is not in the set of presented choices.
+    InvalidChoice {
+        /// Index of the recorded choice.
+        index: usize,
+        /// Number of choices presented by the story.
+        num_choices: usize,
+    },
+    /// The lines returned by the step differ from the recorded lines.
+    LinesDiffer,
+    /// A choice was recorded but the story is not at a set of choices.
+    NotAtChoice,
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use ReplayErrorKind::*;
+
+        write!(f, "Replay diverged at step {}: ", self.step)?;
+
+        match &self.kind {
+            ChoiceTextDiffers { expected, found } => write!(
+                f,
+                "the recorded choice was '{}' but the story presented '{}'",
+                expected, found
+            ),
+            Error(err) => write!(f, "{}", err),
+            InvalidChoice { index, num_choices } => write!(
+                f,
+                "the recorded choice index was {} but the story presented {} choices",
+                index, num_choices
+            ),
+            LinesDiffer => write!(f, "the story returned different lines than were recorded"),
+            NotAtChoice => write!(
+                f,
+                "a choice was recorded but the story is not at a set of choices"
+            ),
+        }
+    }
+}
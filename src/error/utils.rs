@@ -14,11 +14,16 @@ use serde::{Deserialize, Serialize};
 pub struct MetaData {
     /// Which line in the original story the item originated from.
     pub(crate) line_index: u32,
+    /// Name of file that the item originated from, if the story was read from several files.
+    pub(crate) file_name: Option<String>,
 }
 
 impl fmt::Display for MetaData {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "line {}", self.line())
+        match &self.file_name {
+            Some(file_name) => write!(f, "{}, line {}", file_name, self.line()),
+            None => write!(f, "line {}", self.line()),
+        }
     }
 }
 
@@ -30,6 +35,22 @@ impl MetaData {
     pub fn line(&self) -> u32 {
         self.line_index + 1
     }
+
+    /// Get the name of the file that the corresponding data is from.
+    ///
+    /// Only set for stories which were read from several files using a
+    /// [`StoryLoader`][crate::StoryLoader].
+    pub fn file_name(&self) -> Option<&str> {
+        self.file_name.as_ref().map(|name| name.as_str())
+    }
+
+    /// Create meta data for a line in a named file.
+    pub(crate) fn from_file(file_name: &str, line_index: usize) -> Self {
+        MetaData {
+            line_index: line_index as u32,
+            file_name: Some(file_name.to_string()),
+        }
+    }
 }
 
 /// Write meta data information for a line or piece of content in a story.
@@ -83,6 +104,7 @@ impl From<usize> for MetaData {
     fn from(line_index: usize) -> Self {
         MetaData {
             line_index: line_index as u32,
+            file_name: None,
         }
     }
 }
@@ -90,7 +112,10 @@ impl From<usize> for MetaData {
 #[cfg(test)]
 impl From<()> for MetaData {
     fn from(_: ()) -> Self {
-        MetaData { line_index: 0 }
+        MetaData {
+            line_index: 0,
+            file_name: None,
+        }
     }
 }
 
@@ -100,7 +125,22 @@ mod tests {
 
     #[test]
     fn meta_data_from_index_sets_index() {
-        assert_eq!(MetaData::from(6), MetaData { line_index: 6 });
+        assert_eq!(
+            MetaData::from(6),
+            MetaData {
+                line_index: 6,
+                file_name: None
+            }
+        );
+    }
+
+    #[test]
+    fn meta_data_display_includes_file_name_if_set() {
+        assert_eq!(&format!("{}", MetaData::from(6)), "line 7");
+        assert_eq!(
+            &format!("{}", MetaData::from_file("château.ink", 6)),
+            "château.ink, line 7"
+        );
     }
 
     #[test]
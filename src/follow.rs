@@ -1,16 +1,20 @@
 //! Results and data that is used or encountered when following, or walking through, a story.
 
 use crate::{
-    error::InklingError,
-    knot::Address,
-    line::InternalChoice,
-    story::{rng::StoryRng, types::VariableSet},
+    error::{utils::MetaData, InklingError},
+    knot::{Address, FunctionSet},
+    line::{InternalChoice, Variable},
+    node::Stack,
+    story::{
+        rng::StoryRng,
+        types::{ExternalFunctionSet, ListDefinitionSet, VariableSet},
+    },
 };
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 /// Convenience type for a result of the encountered event and main error type.
 pub type FollowResult = Result<EncounteredEvent, InklingError>;
@@ -25,8 +29,33 @@ pub enum EncounteredEvent {
     BranchingChoice(Vec<ChoiceInfo>),
     /// Divert to a new knot with the given name.
     Divert(Address),
+    /// Divert to a new knot or stitch and bind the given values to its parameters.
+    DivertWithArguments {
+        address: Address,
+        arguments: Vec<Variable>,
+    },
     /// Finished with the current node or story.
     Done,
+    /// Returned from a function, with the returned value if one was given.
+    Return(Option<Variable>),
+    /// Call a set of tunnels in order with their arguments, then divert to an address
+    /// if one is given.
+    ///
+    /// If no address is given the story returns to where the tunnels were called from.
+    TunnelCall {
+        targets: Vec<(Address, Vec<Variable>)>,
+        divert: Option<Address>,
+    },
+    /// Start a thread at the given address with arguments for its parameters, which merges
+    /// its choices into the story.
+    Thread {
+        address: Address,
+        arguments: Vec<Variable>,
+    },
+    /// Return from the current tunnel, or divert to an address instead if one is given.
+    TunnelReturn(Option<Address>),
+    /// Paused after a line of text, when the story is followed one line at a time.
+    Paused,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -36,32 +65,154 @@ pub struct ChoiceInfo {
     pub num_visited: u32,
     /// Choice data to process before presenting to the user.
     pub choice_data: InternalChoice,
+    /// Index of the choice in the branching node.
+    pub index: usize,
+    /// Thread that the choice was encountered in, if it was not in the main flow.
+    pub thread: Option<ThreadState>,
 }
 
 impl ChoiceInfo {
     /// Create the information container from given data.
-    pub fn from_choice(choice: &InternalChoice, num_visited: u32) -> Self {
+    pub fn from_choice(choice: &InternalChoice, num_visited: u32, index: usize) -> Self {
         ChoiceInfo {
             num_visited,
             choice_data: choice.clone(),
+            index,
+            thread: None,
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// State of a thread when it encountered a set of choices.
+///
+/// The choices of threads are merged into the set of choices that is presented to the user.
+/// If one of them is selected the story continues from this state.
+pub struct ThreadState {
+    /// Address of the stitch that the choices were encountered in.
+    pub address: Address,
+    /// Position of the branching point in the stitch.
+    pub stack: Stack,
+    /// Temporary variables of the thread.
+    pub temp_variables: HashMap<String, Variable>,
+    /// Tunnels which the thread was following.
+    pub tunnel_stack: Vec<TunnelFrame>,
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Data used during a follow through knots and nodes.
+///
+/// Apart from the lists and functions, which are shared with the compiled story, and
+/// the bound external functions, this is the state of a single playthrough of a story.
+/// The content of the story is not changed when it is followed.
 pub struct FollowData {
     /// Number of times a knot and stitch address has been visited.
     pub knot_visit_counts: HashMap<String, HashMap<String, u32>>,
+    /// Turn index at which a knot and stitch address was last visited.
+    ///
+    /// Addresses which have not been visited are not present.
+    pub knot_visit_turns: HashMap<String, HashMap<String, u32>>,
+    /// Number of choices which have been made in the story.
+    pub turn_index: u32,
+    /// Number of choices which have been accepted into the set currently being presented.
+    pub choice_count: u32,
+    /// Number of times a labelled choice or gather has been visited.
+    ///
+    /// Indexed by knot, stitch and label names.
+    pub label_visit_counts: HashMap<String, HashMap<String, HashMap<String, u32>>>,
+    /// Number of times the branch of a choice has been visited, by branch identifier.
+    ///
+    /// Branches which have not been visited are not present.
+    pub branch_visit_counts: HashMap<usize, u32>,
+    /// Last recorded position in the content of a stitch, by knot and stitch names.
+    ///
+    /// Stitches which are followed from their beginning are not present.
+    pub stacks: HashMap<String, HashMap<String, Stack>>,
+    /// Active list of item indices of alternatives and alternative blocks, by identifier.
+    ///
+    /// The lists are in reverse item order, see
+    /// [`Alternative`][crate::line::Alternative]. Alternatives which have not yet been
+    /// processed are not present.
+    pub alternative_inds: HashMap<usize, Vec<usize>>,
     /// Global variables in story.
     pub variables: VariableSet,
+    /// Lists defined in the story, which list variables take their items from.
+    ///
+    /// Shared with the compiled story and not saved with the state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub lists: Arc<ListDefinitionSet>,
+    /// Temporary variables declared in the currently visited knot or stitch.
+    ///
+    /// Cleared whenever the story moves to a new location.
+    pub temp_variables: HashMap<String, Variable>,
+    /// External functions declared in the story, with their bound functions.
+    ///
+    /// Bound functions belong to a single `Story` and are not saved with the state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub external_functions: ExternalFunctionSet,
+    /// Functions defined in the story.
+    ///
+    /// Shared with the compiled story and not saved with the state.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub functions: Arc<FunctionSet>,
+    /// Stack of functions which are currently being called, with the innermost call last.
+    pub call_stack: Vec<CallFrame>,
+    /// Text printed by called functions which has not yet been added to the calling line.
+    ///
+    /// Lines which are not glued together are separated by newline characters.
+    pub function_text: String,
+    /// Stack of addresses to continue from when tunnels return, with the innermost tunnel last.
+    pub tunnel_stack: Vec<TunnelFrame>,
     /// Random number generator
     pub rng: StoryRng,
+    /// Whether to pause the follow after every line of text in the main flow.
+    ///
+    /// Set while the story is continued one line at a time. Lines in functions
+    /// are never paused after.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub pause_after_lines: bool,
+    /// Whether to pause the follow before content which assigns variables or calls
+    /// functions in the main flow.
+    ///
+    /// Set while the story looks ahead to the line after a line which is continued to,
+    /// so that the logic is evaluated when the story is continued past it.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub pause_before_logic: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Function call which is being evaluated.
+pub struct CallFrame {
+    /// Name of called function.
+    pub name: String,
+    /// Temporary variables of the caller, which are restored when the function returns.
+    pub caller_temp_variables: HashMap<String, Variable>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Address to continue the story from when a tunnel returns.
+pub struct TunnelFrame {
+    /// Address to continue from.
+    pub address: Address,
+    /// Position in the stitch of the address to continue from.
+    ///
+    /// Set if the tunnel was called from that stitch, in which case the story continues
+    /// after the line which called it. Otherwise the stitch is followed from its start.
+    pub stack: Option<Stack>,
+    /// Temporary variables to continue with when the tunnel returns.
+    ///
+    /// These are the temporary variables of the caller, or the arguments of the next tunnel
+    /// if several tunnels were called in a chain.
+    pub caller_temp_variables: HashMap<String, Variable>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Processed text from a full line.
 ///
 /// This is the result from calling [`process_line`][crate::process::process_line] on a single
@@ -80,6 +231,12 @@ pub struct LineText {
     pub glue_end: bool,
     /// Tags associated with the line.
     pub tags: Vec<String>,
+    /// Information about the origin of the line in the story file or text.
+    pub meta_data: MetaData,
+    /// Address of the stitch that the line was followed in.
+    ///
+    /// Set by the stitch after the line has been processed.
+    pub address: Option<Address>,
 }
 
 #[cfg(test)]
@@ -110,6 +267,8 @@ impl LineTextBuilder {
             glue_begin: self.glue_begin,
             glue_end: self.glue_end,
             tags: self.tags,
+            meta_data: ().into(),
+            address: None,
         }
     }
 
@@ -133,7 +292,14 @@ impl LineTextBuilder {
 /// Builder for `FollowData` during tests
 pub struct FollowDataBuilder {
     knot_visit_counts: HashMap<String, HashMap<String, u32>>,
+    label_visit_counts: HashMap<String, HashMap<String, HashMap<String, u32>>>,
+    knot_visit_turns: HashMap<String, HashMap<String, u32>>,
+    turn_index: u32,
     variables: VariableSet,
+    lists: ListDefinitionSet,
+    temp_variables: HashMap<String, Variable>,
+    external_functions: ExternalFunctionSet,
+    functions: FunctionSet,
     rng: StoryRng,
 }
 
@@ -142,7 +308,14 @@ impl FollowDataBuilder {
     pub fn new() -> Self {
         FollowDataBuilder {
             knot_visit_counts: HashMap::new(),
+            label_visit_counts: HashMap::new(),
+            knot_visit_turns: HashMap::new(),
+            turn_index: 0,
             variables: VariableSet::new(),
+            lists: HashMap::new(),
+            temp_variables: HashMap::new(),
+            external_functions: HashMap::new(),
+            functions: HashMap::new(),
             rng: StoryRng::default(),
         }
     }
@@ -152,11 +325,44 @@ impl FollowDataBuilder {
         self
     }
 
+    pub fn with_labels(
+        mut self,
+        label_visit_counts: HashMap<String, HashMap<String, HashMap<String, u32>>>,
+    ) -> Self {
+        self.label_visit_counts = label_visit_counts;
+        self
+    }
+
+    pub fn with_turns(
+        mut self,
+        turn_index: u32,
+        knot_visit_turns: HashMap<String, HashMap<String, u32>>,
+    ) -> Self {
+        self.turn_index = turn_index;
+        self.knot_visit_turns = knot_visit_turns;
+        self
+    }
+
     pub fn with_variables(mut self, variables: VariableSet) -> Self {
         self.variables = variables;
         self
     }
 
+    pub fn with_lists(mut self, lists: ListDefinitionSet) -> Self {
+        self.lists = lists;
+        self
+    }
+
+    pub fn with_external_functions(mut self, external_functions: ExternalFunctionSet) -> Self {
+        self.external_functions = external_functions;
+        self
+    }
+
+    pub fn with_functions(mut self, functions: FunctionSet) -> Self {
+        self.functions = functions;
+        self
+    }
+
     pub fn with_rng(mut self, rng: StoryRng) -> Self {
         self.rng = rng;
         self
@@ -165,8 +371,24 @@ impl FollowDataBuilder {
     pub fn build(self) -> FollowData {
         FollowData {
             knot_visit_counts: self.knot_visit_counts,
+            knot_visit_turns: self.knot_visit_turns,
+            turn_index: self.turn_index,
+            choice_count: 0,
+            label_visit_counts: self.label_visit_counts,
+            branch_visit_counts: HashMap::new(),
+            stacks: HashMap::new(),
+            alternative_inds: HashMap::new(),
             variables: self.variables,
+            lists: Arc::new(self.lists),
+            temp_variables: self.temp_variables,
+            external_functions: self.external_functions,
+            functions: Arc::new(self.functions),
+            call_stack: Vec::new(),
+            function_text: String::new(),
+            tunnel_stack: Vec::new(),
             rng: self.rng,
+            pause_after_lines: false,
+            pause_before_logic: false,
         }
     }
 }
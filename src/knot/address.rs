@@ -14,6 +14,7 @@ use crate::{
         InternalError,
     },
     knot::KnotSet,
+    line::Variable,
     log::Logger,
     story::validate::{KnotValidationInfo, ValidateContent, ValidationData},
 };
@@ -33,13 +34,29 @@ pub enum Address {
     Raw(String),
     /// Divert address to mark that a story is finished.
     End,
+    /// Divert address to mark that the current flow or thread is finished.
+    Done,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 pub enum AddressKind {
-    Location { knot: String, stitch: String },
-    GlobalVariable { name: String },
+    Location {
+        knot: String,
+        stitch: String,
+    },
+    /// Labelled choice or gather inside a stitch.
+    Label {
+        knot: String,
+        stitch: String,
+        label: String,
+    },
+    GlobalVariable {
+        name: String,
+    },
+    TemporaryVariable {
+        name: String,
+    },
 }
 
 impl From<AddressKind> for Address {
@@ -72,6 +89,11 @@ impl Address {
     }
 
     /// Validate that a specified location exists in the knotset and create it's `Address`.
+    ///
+    /// Locations with a label are validated as labels inside of the given stitch, or inside
+    /// of the default stitch of the knot if no stitch is given. If a location has a stitch
+    /// but no label, and that stitch does not exist in the knot, a label with the name
+    /// is searched for in the default stitch, since `knot.label` is a valid shorthand.
     pub fn from_location(
         location: &Location,
         knots: &KnotSet,
@@ -84,24 +106,51 @@ impl Address {
 
         let stitch_name = location.stitch.as_ref().unwrap_or(&knot.default_stitch);
 
-        if knot.stitches.contains_key(stitch_name) {
-            Ok(Address::Validated(AddressKind::Location {
+        let get_label_address = |stitch_name: &String, label: &String| {
+            knot.stitches
+                .get(stitch_name)
+                .filter(|stitch| stitch.get_label_stack(label).is_some())
+                .map(|_| {
+                    Address::Validated(AddressKind::Label {
+                        knot: location.knot.clone(),
+                        stitch: stitch_name.clone(),
+                        label: label.clone(),
+                    })
+                })
+        };
+
+        match (knot.stitches.contains_key(stitch_name), &location.label) {
+            (true, None) => Ok(Address::Validated(AddressKind::Location {
                 knot: location.knot.clone(),
                 stitch: stitch_name.clone(),
-            }))
-        } else {
-            Err(InvalidAddressErrorKind::UnknownStitch {
+            })),
+            (true, Some(label)) => {
+                get_label_address(stitch_name, label).ok_or(InvalidAddressErrorKind::UnknownLabel {
+                    knot_name: location.knot.clone(),
+                    stitch_name: stitch_name.clone(),
+                    label_name: label.clone(),
+                })
+            }
+            (false, None) => get_label_address(&knot.default_stitch, stitch_name).ok_or(
+                InvalidAddressErrorKind::UnknownStitch {
+                    knot_name: location.knot.clone(),
+                    stitch_name: stitch_name.clone(),
+                },
+            ),
+            (false, Some(..)) => Err(InvalidAddressErrorKind::UnknownStitch {
                 knot_name: location.knot.clone(),
                 stitch_name: stitch_name.clone(),
-            })
+            }),
         }
     }
 
     /// Get the knot name of a validated address.
     pub fn get_knot(&self) -> Result<&str, InternalError> {
         match self {
-            Address::Validated(AddressKind::Location { knot, .. }) => Ok(knot),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
+            Address::Validated(AddressKind::Location { knot, .. })
+            | Address::Validated(AddressKind::Label { knot, .. }) => Ok(knot),
+            Address::Validated(AddressKind::GlobalVariable { name })
+            | Address::Validated(AddressKind::TemporaryVariable { name }) => {
                 Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
             }
             _ => Err(InternalError::UseOfUnvalidatedAddress {
@@ -113,8 +162,10 @@ impl Address {
     /// Get the stitch name of a validateed address.
     pub fn get_stitch(&self) -> Result<&str, InternalError> {
         match self {
-            Address::Validated(AddressKind::Location { stitch, .. }) => Ok(stitch),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
+            Address::Validated(AddressKind::Location { stitch, .. })
+            | Address::Validated(AddressKind::Label { stitch, .. }) => Ok(stitch),
+            Address::Validated(AddressKind::GlobalVariable { name })
+            | Address::Validated(AddressKind::TemporaryVariable { name }) => {
                 Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
             }
             _ => Err(InternalError::UseOfUnvalidatedAddress {
@@ -126,8 +177,10 @@ impl Address {
     /// Get knot and stitch names from a validated address.
     pub fn get_knot_and_stitch(&self) -> Result<(&str, &str), InternalError> {
         match self {
-            Address::Validated(AddressKind::Location { knot, stitch }) => Ok((knot, stitch)),
-            Address::Validated(AddressKind::GlobalVariable { name }) => {
+            Address::Validated(AddressKind::Location { knot, stitch })
+            | Address::Validated(AddressKind::Label { knot, stitch, .. }) => Ok((knot, stitch)),
+            Address::Validated(AddressKind::GlobalVariable { name })
+            | Address::Validated(AddressKind::TemporaryVariable { name }) => {
                 Err(InternalError::UseOfVariableAsLocation { name: name.clone() })
             }
             _ => Err(InternalError::UseOfUnvalidatedAddress {
@@ -139,7 +192,8 @@ impl Address {
     /// Get a string representation of the address as `Ink` would write it.
     pub fn to_string(&self) -> String {
         match &self {
-            Address::Validated(AddressKind::GlobalVariable { name }) => name.clone(),
+            Address::Validated(AddressKind::GlobalVariable { name })
+            | Address::Validated(AddressKind::TemporaryVariable { name }) => name.clone(),
             Address::Validated(AddressKind::Location { knot, stitch }) => {
                 if stitch.as_str() == ROOT_KNOT_NAME {
                     format!("{}", knot)
@@ -147,8 +201,86 @@ impl Address {
                     format!("{}.{}", knot, stitch)
                 }
             }
+            Address::Validated(AddressKind::Label {
+                knot,
+                stitch,
+                label,
+            }) => {
+                if stitch.as_str() == ROOT_KNOT_NAME {
+                    format!("{}.{}", knot, label)
+                } else {
+                    format!("{}.{}.{}", knot, stitch, label)
+                }
+            }
             Address::Raw(content) => content.clone(),
-            Address::End => "END".to_string(),
+            Address::End => END_KNOT.to_string(),
+            Address::Done => DONE_KNOT.to_string(),
+        }
+    }
+
+    /// Validate that the address can be diverted to with a number of arguments.
+    ///
+    /// Knots and stitches have to be given an argument for every parameter, while labels are
+    /// moved to without arguments. Variables have to hold divert targets, unless their values
+    /// are unknown until the story is followed.
+    pub fn validate_divert_target(
+        &self,
+        num_arguments: usize,
+        error: &mut ValidationError,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        let kind = match self {
+            Address::Validated(AddressKind::Location { knot, stitch }) => data
+                .knots
+                .get(knot)
+                .and_then(|knot| knot.stitches.get(stitch))
+                .map(|stitch| stitch.parameters.len())
+                .filter(|&num_parameters| num_parameters != num_arguments)
+                .map(
+                    |num_parameters| InvalidAddressErrorKind::InvalidNumberOfArguments {
+                        address: self.to_string(),
+                        num_parameters,
+                        num_arguments,
+                    },
+                ),
+            Address::Validated(AddressKind::Label { .. }) if num_arguments > 0 => {
+                Some(InvalidAddressErrorKind::InvalidNumberOfArguments {
+                    address: self.to_string(),
+                    num_parameters: 0,
+                    num_arguments,
+                })
+            }
+            Address::Validated(AddressKind::GlobalVariable { name }) => data
+                .follow_data
+                .variables
+                .get(name)
+                .map(|info| &info.variable)
+                .filter(|variable| !matches!(variable, Variable::Divert(..)))
+                .map(|variable| InvalidAddressErrorKind::NotADivertTarget {
+                    name: name.clone(),
+                    value: variable.clone(),
+                }),
+            Address::Validated(AddressKind::TemporaryVariable { name })
+                if !data.unknown_temporary_variables.contains(name) =>
+            {
+                data.follow_data
+                    .temp_variables
+                    .get(name)
+                    .filter(|variable| !matches!(variable, Variable::Divert(..)))
+                    .map(|variable| InvalidAddressErrorKind::NotADivertTarget {
+                        name: name.clone(),
+                        value: variable.clone(),
+                    })
+            }
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            error.invalid_address_errors.push(InvalidAddressError {
+                kind,
+                meta_data: meta_data.clone(),
+            });
         }
     }
 
@@ -159,19 +291,24 @@ impl Address {
         data: &ValidationData,
     ) -> Result<(), InvalidAddressErrorKind> {
         match self {
-            Address::Raw(ref target) if target == DONE_KNOT || target == END_KNOT => {
+            Address::Raw(ref target) if target == DONE_KNOT => {
+                *self = Address::Done;
+            }
+            Address::Raw(ref target) if target == END_KNOT => {
                 *self = Address::End;
             }
             Address::Raw(ref target) => {
                 let address = match split_address_into_parts(target.trim())? {
-                    (knot, Some(stitch)) => get_location_from_parts(knot, stitch, &data.knots)?,
+                    (head, Some(tail)) => {
+                        get_location_from_parts(head, tail, current_location, &data.knots)?
+                    }
                     (needle, None) => get_address_from_needle(needle, current_location, data)?,
                 }
                 .into();
 
                 *self = address;
             }
-            Address::Validated { .. } | Address::End => (),
+            Address::Validated { .. } | Address::End | Address::Done => (),
         }
 
         Ok(())
@@ -227,28 +364,88 @@ fn split_address_into_parts(
     }
 }
 
-/// Verify and return the full address to a stitch from its parts.
+/// Verify and return the full address to a stitch or label from its parts.
+///
+/// The address may be a stitch (`knot.stitch`), a label in the default stitch of a knot
+/// (`knot.label`), a label in a stitch of the current knot (`stitch.label`) or a fully
+/// qualified label (`knot.stitch.label`).
 fn get_location_from_parts(
-    knot_name: String,
-    stitch_name: String,
+    head: String,
+    tail: String,
+    current_address: &Address,
     knots: &HashMap<String, KnotValidationInfo>,
 ) -> Result<AddressKind, InvalidAddressErrorKind> {
-    let KnotValidationInfo { stitches, .. } =
-        knots
-            .get(&knot_name)
+    if let (knot_name, Some(label)) = split_address_into_parts(&tail)? {
+        return get_label_from_parts(head, knot_name, label, knots);
+    }
+
+    if let Some(KnotValidationInfo {
+        default_stitch,
+        stitches,
+        ..
+    }) = knots.get(&head)
+    {
+        if stitches.contains_key(&tail) {
+            Ok(AddressKind::Location {
+                knot: head,
+                stitch: tail,
+            })
+        } else {
+            get_label_from_parts(head.clone(), default_stitch.clone(), tail.clone(), knots).map_err(
+                |_| InvalidAddressErrorKind::UnknownStitch {
+                    knot_name: head,
+                    stitch_name: tail,
+                },
+            )
+        }
+    } else {
+        let current_knot = current_address
+            .get_knot()
+            .ok()
+            .filter(|knot_name| {
+                knots
+                    .get(*knot_name)
+                    .map(|knot| knot.stitches.contains_key(&head))
+                    .unwrap_or(false)
+            })
             .ok_or(InvalidAddressErrorKind::UnknownKnot {
-                knot_name: knot_name.clone(),
+                knot_name: head.clone(),
             })?;
 
-    if stitches.contains_key(&stitch_name) {
-        Ok(AddressKind::Location {
+        get_label_from_parts(current_knot.to_string(), head, tail, knots)
+    }
+}
+
+/// Verify and return the full address to a label from its parts.
+fn get_label_from_parts(
+    knot_name: String,
+    stitch_name: String,
+    label_name: String,
+    knots: &HashMap<String, KnotValidationInfo>,
+) -> Result<AddressKind, InvalidAddressErrorKind> {
+    let stitch = knots
+        .get(&knot_name)
+        .ok_or(InvalidAddressErrorKind::UnknownKnot {
+            knot_name: knot_name.clone(),
+        })?
+        .stitches
+        .get(&stitch_name)
+        .ok_or(InvalidAddressErrorKind::UnknownStitch {
+            knot_name: knot_name.clone(),
+            stitch_name: stitch_name.clone(),
+        })?;
+
+    if stitch.labels.contains(&label_name) {
+        Ok(AddressKind::Label {
             knot: knot_name,
             stitch: stitch_name,
+            label: label_name,
         })
     } else {
-        Err(InvalidAddressErrorKind::UnknownStitch {
-            knot_name: knot_name.clone(),
-            stitch_name: stitch_name.clone(),
+        Err(InvalidAddressErrorKind::UnknownLabel {
+            knot_name,
+            stitch_name,
+            label_name,
         })
     }
 }
@@ -260,8 +457,8 @@ fn get_location_from_parts(
 /// with the name is returned.
 ///
 /// If the name is not found in the current knot's stitches, or in the set of knot names,
-/// the variable listing is searched. If a match is found the address will be returned
-/// as a global variable.
+/// the labels of the current stitch and then the variable listings are searched. Temporary
+/// variables of the current stitch are searched before global variables.
 fn get_address_from_needle(
     needle: String,
     current_address: &Address,
@@ -272,6 +469,17 @@ fn get_address_from_needle(
 
     let matches_stitch_in_current_knot = current_stitches.contains(&needle);
     let matches_knot = data.knots.get(&needle);
+    let matches_label_in_current_stitch = current_address
+        .get_stitch()
+        .ok()
+        .and_then(|stitch_name| {
+            data.knots
+                .get(&current_knot_name)
+                .and_then(|knot| knot.stitches.get(stitch_name))
+        })
+        .map(|stitch| stitch.labels.contains(&needle))
+        .unwrap_or(false);
+    let matches_temporary_variable = data.follow_data.temp_variables.contains_key(&needle);
     let matches_variable = data.follow_data.variables.contains_key(&needle);
 
     if matches_stitch_in_current_knot {
@@ -284,6 +492,14 @@ fn get_address_from_needle(
             knot: needle,
             stitch: knot_info.default_stitch.clone(),
         })
+    } else if matches_label_in_current_stitch {
+        Ok(AddressKind::Label {
+            knot: current_knot_name,
+            stitch: current_address.get_stitch().unwrap().to_string(),
+            label: needle,
+        })
+    } else if matches_temporary_variable {
+        Ok(AddressKind::TemporaryVariable { name: needle })
     } else if matches_variable {
         Ok(AddressKind::GlobalVariable { name: needle })
     } else {
@@ -633,7 +849,7 @@ You find yourself in Tripoli, the capital of Libya.
 
         let mut done_address = Address::Raw("DONE".to_string());
         assert!(validate_address(&mut done_address, &current_address, &data).is_ok());
-        assert_eq!(done_address, Address::End);
+        assert_eq!(done_address, Address::Done);
     }
 
     #[test]
@@ -722,4 +938,219 @@ You find yourself in Cairo, the capital of Egypt.
             })
         );
     }
+
+    fn get_label(knot: &str, stitch: &str, label: &str) -> Address {
+        Address::Validated(AddressKind::Label {
+            knot: knot.to_string(),
+            stitch: stitch.to_string(),
+            label: label.to_string(),
+        })
+    }
+
+    #[test]
+    fn string_representation_of_label_is_knot_dot_stitch_dot_label() {
+        assert_eq!(
+            &get_label("tripoli", "cinema", "lobby").to_string(),
+            "tripoli.cinema.lobby"
+        );
+        assert_eq!(
+            &get_label("tripoli", ROOT_KNOT_NAME, "lobby").to_string(),
+            "tripoli.lobby"
+        );
+    }
+
+    #[test]
+    fn labels_are_validated_from_all_address_formats() {
+        let content = "
+== tripoli
+- (harbor) The harbor.
+
+= cinema
+- (lobby) The lobby.
+= market
+-> END
+
+== addis_ababa
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let in_cinema = Address::from_parts_unchecked("tripoli", Some("cinema"));
+        let in_market = Address::from_parts_unchecked("tripoli", Some("market"));
+        let in_addis_ababa = Address::from_knot("addis_ababa");
+
+        let mut address = Address::Raw("lobby".to_string());
+        assert!(validate_address(&mut address, &in_cinema, &data).is_ok());
+        assert_eq!(address, get_label("tripoli", "cinema", "lobby"));
+
+        let mut address = Address::Raw("cinema.lobby".to_string());
+        assert!(validate_address(&mut address, &in_market, &data).is_ok());
+        assert_eq!(address, get_label("tripoli", "cinema", "lobby"));
+
+        let mut address = Address::Raw("tripoli.cinema.lobby".to_string());
+        assert!(validate_address(&mut address, &in_addis_ababa, &data).is_ok());
+        assert_eq!(address, get_label("tripoli", "cinema", "lobby"));
+
+        let mut address = Address::Raw("tripoli.harbor".to_string());
+        assert!(validate_address(&mut address, &in_addis_ababa, &data).is_ok());
+        assert_eq!(address, get_label("tripoli", ROOT_KNOT_NAME, "harbor"));
+    }
+
+    #[test]
+    fn labels_in_other_stitches_are_not_found_with_just_the_label_name() {
+        let content = "
+== tripoli
+= cinema
+- (lobby) The lobby.
+= market
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let in_market = Address::from_parts_unchecked("tripoli", Some("market"));
+
+        let mut address = Address::Raw("lobby".to_string());
+        assert!(validate_address(&mut address, &in_market, &data).is_err());
+    }
+
+    #[test]
+    fn unknown_labels_yield_unknown_label_errors() {
+        let content = "
+== tripoli
+= cinema
+- (lobby) The lobby.
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let current_address = Address::from_parts_unchecked("tripoli", Some("cinema"));
+
+        let mut address = Address::Raw("tripoli.cinema.balcony".to_string());
+
+        match validate_address(&mut address, &current_address, &data) {
+            Err(InvalidAddressError {
+                kind: InvalidAddressErrorKind::UnknownLabel { label_name, .. },
+                ..
+            }) => assert_eq!(&label_name, "balcony"),
+            other => panic!(
+                "expected `InvalidAddressErrorKind::UnknownLabel` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn addresses_from_locations_with_labels_are_validated_to_labels() {
+        let content = "
+== tripoli
+- (harbor) The harbor.
+
+= cinema
+- (lobby) The lobby.
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+
+        assert_eq!(
+            Address::from_location(&"tripoli.cinema.lobby".into(), &knots).unwrap(),
+            get_label("tripoli", "cinema", "lobby")
+        );
+
+        assert_eq!(
+            Address::from_location(&"tripoli.harbor".into(), &knots).unwrap(),
+            get_label("tripoli", ROOT_KNOT_NAME, "harbor")
+        );
+
+        assert!(Address::from_location(&"tripoli.cinema.balcony".into(), &knots).is_err());
+    }
+
+    fn validate_divert_target(
+        address: &Address,
+        num_arguments: usize,
+        data: &ValidationData,
+    ) -> Result<(), InvalidAddressErrorKind> {
+        let mut error = ValidationError::new();
+
+        address.validate_divert_target(num_arguments, &mut error, &().into(), data);
+
+        match error.invalid_address_errors.first() {
+            Some(err) => Err(err.kind.clone()),
+            None => Ok(()),
+        }
+    }
+
+    #[test]
+    fn divert_targets_must_be_given_an_argument_for_every_parameter() {
+        let content = "
+== meet(character, mood)
+Hello, {character}.
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+        let data = ValidationData::from_data(&knots, &HashMap::new());
+
+        let address = Address::from_knot("meet");
+
+        assert!(validate_divert_target(&address, 2, &data).is_ok());
+
+        match validate_divert_target(&address, 1, &data) {
+            Err(InvalidAddressErrorKind::InvalidNumberOfArguments {
+                num_parameters: 2,
+                num_arguments: 1,
+                ..
+            }) => (),
+            other => panic!(
+                "expected `InvalidAddressErrorKind::InvalidNumberOfArguments` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn divert_targets_which_are_variables_must_hold_divert_targets() {
+        let content = "
+== addis_ababa
+You find yourself in Addis Ababa, the capital of Ethiopia.
+-> END
+";
+
+        let knots = read_knots_from_string(content).unwrap();
+
+        let variables = &[
+            ("counter".to_string(), Variable::Int(0)),
+            (
+                "destination".to_string(),
+                Variable::Divert(Address::from_knot("addis_ababa")),
+            ),
+        ]
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(i, (name, var))| (name, VariableInfo::new(var, i)))
+        .collect();
+
+        let data = ValidationData::from_data(&knots, &variables);
+
+        let destination = Address::variable_unchecked("destination");
+        assert!(validate_divert_target(&destination, 0, &data).is_ok());
+
+        let counter = Address::variable_unchecked("counter");
+
+        match validate_divert_target(&counter, 0, &data) {
+            Err(InvalidAddressErrorKind::NotADivertTarget { name, value }) => {
+                assert_eq!(&name, "counter");
+                assert_eq!(value, Variable::Int(0));
+            }
+            other => panic!(
+                "expected `InvalidAddressErrorKind::NotADivertTarget` but got {:?}",
+                other
+            ),
+        }
+    }
 }
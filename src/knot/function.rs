@@ -0,0 +1,435 @@
+//! Functions defined in the story.
+//!
+//! Functions are written as knots with a function marker and a signature in their header:
+//! `=== function name(a, ref b) ===`. Their content is kept in a `Stitch`, which is
+//! followed every time that the function is called. Functions can print text, call other
+//! functions and return values, but cannot contain choices or divert to other content.
+
+use crate::{
+    consts::{FUNCTION_MARKER, REF_PARAMETER_MARKER, ROOT_KNOT_NAME, STITCH_MARKER},
+    error::{parse::knot::KnotNameError, utils::MetaData, InklingError},
+    follow::{CallFrame, EncounteredEvent, FollowData, LineDataBuffer},
+    knot::{validate_name, Stitch},
+    line::Variable,
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, mem};
+
+/// Convenience type for a set of `Function`s.
+///
+/// The function names are used as keys in the collection.
+pub type FunctionSet = HashMap<String, Function>;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Function defined in the story.
+pub struct Function {
+    /// Parameters of the function, in the order of the arguments they are called with.
+    pub parameters: Vec<FunctionParameter>,
+    /// Content of the function.
+    pub stitch: Stitch,
+    /// Information about the origin of this function in the story file or text.
+    pub meta_data: MetaData,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Parameter of a function defined in the story.
+pub struct FunctionParameter {
+    /// Name of the parameter, which is a temporary variable inside the function.
+    pub name: String,
+    /// Whether the parameter is passed by reference: `ref name`.
+    ///
+    /// The final value of such parameters are assigned to the variables that were given
+    /// as arguments when the function returns.
+    pub is_ref: bool,
+}
+
+/// Result of a call to a function defined in the story.
+pub struct FunctionResult {
+    /// Value returned by the function, if any.
+    pub value: Option<Variable>,
+    /// Values of the parameters when the function returned.
+    pub parameters: HashMap<String, Variable>,
+}
+
+impl Function {
+    /// Call the function with the given arguments.
+    ///
+    /// The arguments are set as the temporary variables of the function, which replace
+    /// those of the caller until the function returns. Text that is printed by the function
+    /// is added to the [function text][crate::follow::FollowData::function_text] of
+    /// the data, which is added to the line that called the function.
+    ///
+    /// # Errors
+    /// *   [`InvalidFunctionFlow`][crate::error::InklingError::InvalidFunctionFlow]: if
+    ///     the function encountered a divert, tunnel, thread or a choice.
+    pub fn call(
+        &self,
+        name: &str,
+        arguments: Vec<Variable>,
+        data: &mut FollowData,
+    ) -> Result<FunctionResult, InklingError> {
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.clone())
+            .zip(arguments)
+            .collect();
+
+        data.call_stack.push(CallFrame {
+            name: name.to_string(),
+            caller_temp_variables: mem::replace(&mut data.temp_variables, parameters),
+        });
+
+        let mut text = mem::take(&mut data.function_text);
+        let mut buffer = Vec::new();
+
+        let result = self.stitch.follow(&mut buffer, data);
+
+        let caller_temp_variables = data
+            .call_stack
+            .pop()
+            .map(|frame| frame.caller_temp_variables)
+            .unwrap_or_default();
+
+        let parameters = mem::replace(&mut data.temp_variables, caller_temp_variables);
+
+        text.push_str(&get_function_text(&buffer));
+        data.function_text = text;
+
+        match result? {
+            EncounteredEvent::Done => Ok(FunctionResult {
+                value: None,
+                parameters,
+            }),
+            EncounteredEvent::Return(value) => Ok(FunctionResult { value, parameters }),
+            EncounteredEvent::BranchingChoice(..)
+            | EncounteredEvent::Divert(..)
+            | EncounteredEvent::DivertWithArguments { .. }
+            | EncounteredEvent::Thread { .. }
+            | EncounteredEvent::TunnelCall { .. }
+            | EncounteredEvent::TunnelReturn(..) => Err(InklingError::InvalidFunctionFlow {
+                name: name.to_string(),
+            }),
+            EncounteredEvent::Paused => unreachable!("the follow does not pause in functions"),
+        }
+    }
+}
+
+/// Return a map of all functions with their number of visits set to 0.
+///
+/// The content of a function is indexed like a knot with a single root stitch.
+pub fn get_empty_function_counts(functions: &FunctionSet) -> HashMap<String, HashMap<String, u32>> {
+    functions
+        .keys()
+        .map(|name| {
+            let mut stitch_count = HashMap::new();
+            stitch_count.insert(ROOT_KNOT_NAME.to_string(), 0);
+
+            (name.clone(), stitch_count)
+        })
+        .collect()
+}
+
+/// Return a map of all labels in functions, with their number of visits set to 0.
+///
+/// The labels are mapped by function name and the root stitch name.
+pub fn get_empty_function_label_counts(
+    functions: &FunctionSet,
+) -> HashMap<String, HashMap<String, HashMap<String, u32>>> {
+    functions
+        .iter()
+        .map(|(name, function)| {
+            let labels = function
+                .stitch
+                .root
+                .get_labels()
+                .into_iter()
+                .map(|(label, _)| (label, 0))
+                .collect();
+
+            let mut stitches = HashMap::new();
+            stitches.insert(ROOT_KNOT_NAME.to_string(), labels);
+
+            (name.clone(), stitches)
+        })
+        .collect()
+}
+
+/// Read the name and parameters of a function from the header line of a knot.
+///
+/// Returns `None` if the line is not the header of a function. The header may omit
+/// the parenthesis if the function has no parameters: `=== function name ===`.
+pub fn read_function_signature(
+    line: &str,
+) -> Option<Result<(String, Vec<FunctionParameter>), KnotNameError>> {
+    let signature = line
+        .trim_start_matches(STITCH_MARKER)
+        .trim_end_matches(STITCH_MARKER)
+        .trim()
+        .strip_prefix(FUNCTION_MARKER)
+        .filter(|tail| tail.starts_with(char::is_whitespace))?
+        .trim();
+
+    let result = match signature.find('(') {
+        Some(i) if signature.ends_with(')') => {
+            let name = signature.get(..i).unwrap().trim();
+            let inner = signature.get(i + 1..signature.len() - 1).unwrap();
+
+            validate_name(name).and_then(|name| {
+                read_function_parameters(inner).map(|parameters| (name, parameters))
+            })
+        }
+        _ => validate_name(signature).map(|name| (name, Vec::new())),
+    };
+
+    Some(result)
+}
+
+/// Read the comma separated parameters of a function signature.
+fn read_function_parameters(content: &str) -> Result<Vec<FunctionParameter>, KnotNameError> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    content
+        .split(',')
+        .map(|parameter| {
+            let parameter = parameter.trim();
+
+            let (name, is_ref) = match parameter
+                .strip_prefix(REF_PARAMETER_MARKER)
+                .filter(|tail| tail.starts_with(char::is_whitespace))
+            {
+                Some(tail) => (tail.trim(), true),
+                None => (parameter, false),
+            };
+
+            validate_name(name).map(|name| FunctionParameter { name, is_ref })
+        })
+        .collect()
+}
+
+/// Join the lines of text printed by a function into a single string.
+///
+/// Lines which are not glued to each other are separated by newline characters, which
+/// divide the calling line into separate lines when it is processed.
+fn get_function_text(buffer: &LineDataBuffer) -> String {
+    let mut text = String::new();
+
+    let mut lines = buffer
+        .iter()
+        .filter(|line| !line.text.trim().is_empty())
+        .peekable();
+
+    while let Some(line) = lines.next() {
+        text.push_str(&line.text);
+
+        if let Some(next_line) = lines.peek() {
+            if !(line.glue_end || next_line.glue_begin) {
+                text.push('\n');
+            }
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        follow::{FollowDataBuilder, LineTextBuilder},
+        knot::parse_stitch_from_lines,
+    };
+
+    fn get_function(lines: &[&str], parameters: &[&str]) -> Function {
+        let lines = lines
+            .iter()
+            .map(|line| (*line, MetaData::from(0)))
+            .collect::<Vec<_>>();
+
+        let stitch =
+            parse_stitch_from_lines(&lines, "function", ROOT_KNOT_NAME, ().into()).unwrap();
+
+        Function {
+            parameters: parameters
+                .iter()
+                .map(|name| FunctionParameter {
+                    name: name.to_string(),
+                    is_ref: false,
+                })
+                .collect(),
+            stitch,
+            meta_data: ().into(),
+        }
+    }
+
+    fn mock_follow_data(function: &Function) -> FollowData {
+        let mut functions = HashMap::new();
+        functions.insert("function".to_string(), function.clone());
+
+        FollowDataBuilder::new()
+            .with_knots(get_empty_function_counts(&functions))
+            .with_labels(get_empty_function_label_counts(&functions))
+            .with_functions(functions)
+            .build()
+    }
+
+    #[test]
+    fn function_header_is_read_into_name_and_parameters() {
+        let (name, parameters) = read_function_signature("=== function add(x, ref y) ===")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&name, "add");
+        assert_eq!(
+            parameters,
+            vec![
+                FunctionParameter {
+                    name: "x".to_string(),
+                    is_ref: false
+                },
+                FunctionParameter {
+                    name: "y".to_string(),
+                    is_ref: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn function_header_may_omit_parenthesis_without_parameters() {
+        let (name, parameters) = read_function_signature("== function greet")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&name, "greet");
+        assert!(parameters.is_empty());
+
+        let (name, parameters) = read_function_signature("== function greet()")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(&name, "greet");
+        assert!(parameters.is_empty());
+    }
+
+    #[test]
+    fn knot_headers_without_function_marker_are_not_functions() {
+        assert!(read_function_signature("== knot").is_none());
+        assert!(read_function_signature("== functional").is_none());
+    }
+
+    #[test]
+    fn invalid_function_or_parameter_names_yield_errors() {
+        assert!(read_function_signature("== function add(x y)")
+            .unwrap()
+            .is_err());
+        assert!(read_function_signature("== function add(x, )")
+            .unwrap()
+            .is_err());
+        assert!(read_function_signature("== function a b").unwrap().is_err());
+        assert!(read_function_signature("== function add(x")
+            .unwrap()
+            .is_err());
+    }
+
+    #[test]
+    fn calling_function_returns_returned_value() {
+        let function = get_function(&["~ return 5"], &[]);
+        let mut data = mock_follow_data(&function);
+
+        let result = function.call("function", Vec::new(), &mut data).unwrap();
+
+        assert_eq!(result.value, Some(Variable::Int(5)));
+    }
+
+    #[test]
+    fn function_without_return_returns_no_value() {
+        let function = get_function(&["Hello!"], &[]);
+        let mut data = mock_follow_data(&function);
+
+        let result = function.call("function", Vec::new(), &mut data).unwrap();
+
+        assert!(result.value.is_none());
+    }
+
+    #[test]
+    fn content_after_return_is_not_followed() {
+        let function = get_function(&["Before", "~ return", "After"], &[]);
+        let mut data = mock_follow_data(&function);
+
+        function.call("function", Vec::new(), &mut data).unwrap();
+
+        assert_eq!(&data.function_text, "Before");
+    }
+
+    #[test]
+    fn arguments_are_set_as_temporary_variables_and_caller_variables_are_restored() {
+        let function = get_function(&["Hello!"], &["x"]);
+        let mut data = mock_follow_data(&function);
+
+        data.temp_variables
+            .insert("y".to_string(), Variable::Int(1));
+
+        let result = function
+            .call("function", vec![Variable::Int(5)], &mut data)
+            .unwrap();
+
+        assert_eq!(result.parameters.get("x"), Some(&Variable::Int(5)));
+        assert_eq!(result.parameters.get("y"), None);
+
+        assert_eq!(data.temp_variables.get("y"), Some(&Variable::Int(1)));
+        assert_eq!(data.temp_variables.get("x"), None);
+        assert!(data.call_stack.is_empty());
+    }
+
+    #[test]
+    fn printed_text_is_added_after_pending_function_text() {
+        let function = get_function(&["Hello!"], &[]);
+        let mut data = mock_follow_data(&function);
+
+        data.function_text = "Pending ".to_string();
+
+        function.call("function", Vec::new(), &mut data).unwrap();
+
+        assert_eq!(&data.function_text, "Pending Hello!");
+    }
+
+    #[test]
+    fn function_text_separates_lines_with_newlines_unless_glued() {
+        let buffer = vec![
+            LineTextBuilder::from_string("One").build(),
+            LineTextBuilder::from_string("Two").with_glue_end().build(),
+            LineTextBuilder::from_string("Three").build(),
+            LineTextBuilder::from_string("  ").build(),
+            LineTextBuilder::from_string("Four")
+                .with_glue_begin()
+                .build(),
+        ];
+
+        assert_eq!(&get_function_text(&buffer), "One\nTwoThreeFour");
+    }
+
+    #[test]
+    fn functions_with_choices_yield_error() {
+        let function = get_function(&["*   Choice"], &[]);
+        let mut data = mock_follow_data(&function);
+
+        match function.call("function", Vec::new(), &mut data) {
+            Err(InklingError::InvalidFunctionFlow { name }) => assert_eq!(&name, "function"),
+            Err(other) => panic!(
+                "expected `InklingError::InvalidFunctionFlow` but got {:?}",
+                other
+            ),
+            Ok(..) => panic!("expected `InklingError::InvalidFunctionFlow` but got a result"),
+        }
+    }
+}
@@ -1,13 +1,20 @@
 //! Story structure collections: `Knot`s, `Stitch`es and utilities.
 
 mod address;
+mod function;
 mod stitch;
 mod utils;
 
 pub use address::{Address, AddressKind};
+pub use function::{
+    get_empty_function_counts, get_empty_function_label_counts, read_function_signature, Function,
+    FunctionParameter, FunctionSet,
+};
 pub use stitch::{
-    parse_stitch_from_lines, read_knot_name, read_stitch_name, Knot, KnotSet, Stitch,
+    parse_stitch_from_lines, read_knot_signature, read_stitch_signature, validate_name, Knot,
+    KnotSet, Stitch,
 };
 pub use utils::{
-    get_empty_knot_counts, get_mut_stitch, get_num_visited, get_stitch, increment_num_visited,
+    get_divert_target, get_empty_knot_counts, get_empty_label_counts, get_num_visited, get_stack,
+    get_stitch, get_turns_since, increment_num_visited, set_stack, take_stack,
 };
@@ -18,11 +18,14 @@ use crate::{
     consts::{KNOT_MARKER, RESERVED_KEYWORDS, STITCH_MARKER},
     error::{
         parse::knot::{KnotErrorKind, KnotNameError},
+        runtime::internal::StackError,
         utils::MetaData,
+        InternalError,
     },
-    follow::{EncounteredEvent, FollowData, FollowResult, LineDataBuffer},
-    line::parse_line,
-    node::{parse_root_node, Follow, RootNode, Stack},
+    follow::{EncounteredEvent, FollowData, FollowResult, LineDataBuffer, LineText},
+    knot::{get_stack, set_stack, take_stack, Address, AddressKind},
+    line::{parse_lines, LineChunk},
+    node::{parse_root_node, Follow, NodeItem, RootNode, Stack},
 };
 
 #[cfg(feature = "serde_support")]
@@ -50,8 +53,8 @@ pub struct Knot {
     pub default_stitch: String,
     /// Map of `Stitches` belonging to this `Knot`.
     pub stitches: HashMap<String, Stitch>,
-    /// Tags associated with this knot.
-    pub tags: Vec<String>,
+    /// Tags associated with this knot, which are processed when they are retrieved.
+    pub tags: Vec<LineChunk>,
     /// Information about the origin of this knot in the story file or text.
     pub meta_data: MetaData,
 }
@@ -63,47 +66,113 @@ pub struct Knot {
 pub struct Stitch {
     /// Graph of story content, which may or may not branch.
     pub root: RootNode,
-    /// Last recorded position inside the `root` graph of content.
-    pub stack: Stack,
+    /// Names of parameters which are bound as temporary variables when the stitch is entered.
+    ///
+    /// The parameters of a knot belong to its default stitch, before any of its own.
+    pub parameters: Vec<String>,
     /// Information about the origin of this stitch in the story file or text.
     pub meta_data: MetaData,
 }
 
 impl Stitch {
     /// Follow a story while reading every line into a buffer.
-    pub fn follow(&mut self, buffer: &mut LineDataBuffer, data: &mut FollowData) -> FollowResult {
-        let result = self.root.follow(&mut self.stack, buffer, data)?;
-
-        match &result {
-            EncounteredEvent::Done | EncounteredEvent::Divert(..) => self.reset_stack(),
-            EncounteredEvent::BranchingChoice(..) => (),
-        }
+    ///
+    /// The follow starts from the last recorded position in the graph, which is nested
+    /// inside of a branch if the stitch was moved to a label in it. Positions are recorded
+    /// in the data, under the address of the stitch.
+    pub fn follow(&self, buffer: &mut LineDataBuffer, data: &mut FollowData) -> FollowResult {
+        let mut stack = take_stack(&self.root.address, data)?;
+        let num_lines = buffer.len();
+
+        let result = self.root.follow_from_stack(0, &mut stack, buffer, data)?;
+        self.record_stack(&result, stack, data)?;
+        self.set_line_addresses(&mut buffer[num_lines..]);
 
         Ok(result)
     }
 
     /// Follow a story while reading every line into a buffer.
     pub fn follow_with_choice(
-        &mut self,
+        &self,
         choice_index: usize,
         buffer: &mut LineDataBuffer,
         data: &mut FollowData,
     ) -> FollowResult {
-        let result =
-            self.root
-                .follow_with_choice(choice_index, 0, &mut self.stack, buffer, data)?;
+        let mut stack = take_stack(&self.root.address, data)?;
+        let num_lines = buffer.len();
 
-        match result {
-            EncounteredEvent::Done | EncounteredEvent::Divert(..) => self.reset_stack(),
-            _ => (),
-        }
+        let result = self
+            .root
+            .follow_with_choice(choice_index, 0, &mut stack, buffer, data)?;
+        self.record_stack(&result, stack, data)?;
+        self.set_line_addresses(&mut buffer[num_lines..]);
 
         Ok(result)
     }
 
-    /// Reset the current stack to the first line of the root node.
-    fn reset_stack(&mut self) {
-        self.stack = vec![0];
+    /// Whether or not the next item to follow in the stitch is a set of choices.
+    pub fn is_at_branching_point(&self, data: &FollowData) -> Result<bool, InternalError> {
+        let stack = get_stack(&self.root.address, data)?;
+
+        Ok(matches!(
+            self.root.get_item_at_stack(&stack),
+            Some(NodeItem::BranchingPoint(..))
+        ))
+    }
+
+    /// Get the `Stack` which points to a label in the graph, if it exists.
+    pub fn get_label_stack(&self, label: &str) -> Option<Stack> {
+        self.root
+            .get_labels()
+            .into_iter()
+            .find(|(name, _)| name == label)
+            .map(|(_, stack)| stack)
+    }
+
+    /// Set the current stack to the position of a label, from which the next follow starts.
+    pub fn move_to_label(
+        &self,
+        address: &Address,
+        data: &mut FollowData,
+    ) -> Result<(), InternalError> {
+        let stack = match address {
+            Address::Validated(AddressKind::Label { label, .. }) => self.get_label_stack(label),
+            _ => None,
+        }
+        .ok_or(StackError::BadAddress {
+            address: address.clone(),
+        })?;
+
+        set_stack(&self.root.address, stack, data)
+    }
+
+    /// Mark lines which were followed in the stitch with its address.
+    fn set_line_addresses(&self, lines: &mut [LineText]) {
+        lines
+            .iter_mut()
+            .for_each(|line| line.address = Some(self.root.address.clone()));
+    }
+
+    /// Record the position in the graph after a follow if the stitch will be returned to.
+    ///
+    /// Otherwise the stitch is followed from its first line the next time.
+    fn record_stack(
+        &self,
+        result: &EncounteredEvent,
+        stack: Stack,
+        data: &mut FollowData,
+    ) -> Result<(), InternalError> {
+        match result {
+            EncounteredEvent::BranchingChoice(..)
+            | EncounteredEvent::Thread { .. }
+            | EncounteredEvent::TunnelCall { .. }
+            | EncounteredEvent::Paused => set_stack(&self.root.address, stack, data),
+            EncounteredEvent::Done
+            | EncounteredEvent::Divert(..)
+            | EncounteredEvent::DivertWithArguments { .. }
+            | EncounteredEvent::Return(..)
+            | EncounteredEvent::TunnelReturn(..) => Ok(()),
+        }
     }
 }
 
@@ -121,86 +190,100 @@ pub fn parse_stitch_from_lines(
         }]);
     }
 
-    let mut parsed_lines = Vec::new();
-    let mut line_errors = Vec::new();
+    match parse_lines(lines) {
+        Ok(parsed_lines) => {
+            let root = parse_root_node(&parsed_lines, knot, stitch);
 
-    for result in lines
-        .into_iter()
-        .map(|(line, meta_data)| parse_line(line, meta_data))
-    {
-        match result {
-            Ok(parsed_line) => parsed_lines.push(parsed_line),
-            Err(line_error) => line_errors.push(KnotErrorKind::from(line_error)),
+            Ok(Stitch {
+                root,
+                parameters: Vec::new(),
+                meta_data,
+            })
         }
-    }
-
-    if line_errors.is_empty() {
-        let root = parse_root_node(&parsed_lines, knot, stitch);
-
-        Ok(Stitch {
-            root,
-            stack: vec![0],
-            meta_data,
-        })
-    } else {
-        Err(line_errors)
+        Err(line_errors) => Err(line_errors
+            .into_iter()
+            .map(|line_error| KnotErrorKind::from(line_error))
+            .collect()),
     }
 }
 
-/// Read a knot name from a non-parsed string which contains text markers for a knot.
+/// Read a knot name and its parameters from a string which contains text markers for a knot.
 ///
-/// The name is validated before returning.
-pub fn read_knot_name(line: &str) -> Result<String, KnotNameError> {
+/// Parameters are given in parenthesis after the name: `=== meet(character, mood) ===`.
+/// The name and parameters are validated before returning.
+pub fn read_knot_signature(line: &str) -> Result<(String, Vec<String>), KnotNameError> {
     if line.trim_start().starts_with(KNOT_MARKER) {
-        read_name_with_marker(line)
+        read_signature_with_marker(line)
     } else {
         Err(KnotNameError::Empty)
     }
 }
 
-/// Read a stitch name from a non-parsed string which contains text markers for a stitch.
+/// Read a stitch name and its parameters from a string which contains text markers for a stitch.
 ///
-/// The name is validated before returning.
-pub fn read_stitch_name(line: &str) -> Result<String, KnotNameError> {
+/// The name and parameters are validated before returning.
+pub fn read_stitch_signature(line: &str) -> Result<(String, Vec<String>), KnotNameError> {
     if line.trim_start().starts_with(STITCH_MARKER) && !line.trim_start().starts_with(KNOT_MARKER) {
-        read_name_with_marker(line)
+        read_signature_with_marker(line)
     } else {
         Err(KnotNameError::Empty)
     }
 }
 
-/// Read a name beginning with the given knot or stitch marker.
+/// Read a name and optional parameters beginning with the given knot or stitch marker.
 ///
-/// The name is validated before returning.
+/// The name and parameters are validated before returning.
 ///
 /// # Notes
 ///  *  Uses the [stitch marker][crate::consts::STITCH_MARKER] to trim extraneous markers
 ///     from the line before validating the name. Since the stitch marker is a subset
 ///     of the knot marker this will trim both types, but any other marker will not be
 ///     trimmed from the line.
-fn read_name_with_marker(line: &str) -> Result<String, KnotNameError> {
-    let trimmed_name = line
+fn read_signature_with_marker(line: &str) -> Result<(String, Vec<String>), KnotNameError> {
+    let signature = line
         .trim_start_matches(STITCH_MARKER)
         .trim_end_matches(STITCH_MARKER)
         .trim();
 
-    if let Some(c) = trimmed_name
-        .chars()
-        .find(|&c| !(c.is_alphanumeric() || c == '_'))
-    {
+    match signature.find('(') {
+        Some(i) if signature.ends_with(')') => {
+            let name = validate_name(signature.get(..i).unwrap().trim())?;
+            let inner = signature.get(i + 1..signature.len() - 1).unwrap();
+
+            let parameters = if inner.trim().is_empty() {
+                Vec::new()
+            } else {
+                inner
+                    .split(',')
+                    .map(|parameter| validate_name(parameter.trim()))
+                    .collect::<Result<Vec<_>, _>>()?
+            };
+
+            Ok((name, parameters))
+        }
+        _ => validate_name(signature).map(|name| (name, Vec::new())),
+    }
+}
+
+/// Validate a name of a knot, stitch or function parameter.
+///
+/// Names may only contain alphanumeric and underline characters and cannot be
+/// reserved keywords.
+pub fn validate_name(name: &str) -> Result<String, KnotNameError> {
+    if let Some(c) = name.chars().find(|&c| !(c.is_alphanumeric() || c == '_')) {
         if c.is_whitespace() {
             Err(KnotNameError::ContainsWhitespace)
         } else {
             Err(KnotNameError::ContainsInvalidCharacter(c))
         }
-    } else if trimmed_name.is_empty() {
+    } else if name.is_empty() {
         Err(KnotNameError::Empty)
-    } else if RESERVED_KEYWORDS.contains(&trimmed_name.to_uppercase().as_str()) {
+    } else if RESERVED_KEYWORDS.contains(&name.to_uppercase().as_str()) {
         Err(KnotNameError::ReservedKeyword {
-            keyword: trimmed_name.to_string(),
+            keyword: name.to_string(),
         })
     } else {
-        Ok(trimmed_name.to_string())
+        Ok(name.to_string())
     }
 }
 
@@ -212,7 +295,7 @@ mod tests {
         error::parse::line::LineError,
         follow::FollowDataBuilder,
         knot::{get_num_visited, Address},
-        line::{InternalLine, ParsedLineKind},
+        line::{parse_line, InternalLine, ParsedLineKind},
     };
 
     use std::str::FromStr;
@@ -226,8 +309,8 @@ mod tests {
 
             Ok(Stitch {
                 root,
-                stack: vec![0],
-                meta_data: MetaData { line_index: 0 },
+                parameters: Vec::new(),
+                meta_data: ().into(),
             })
         }
     }
@@ -279,7 +362,7 @@ mod tests {
     fn stitch_restarts_from_their_first_line_when_run_again() {
         let text = "Hello, World!";
 
-        let mut stitch = Stitch::from_str(text).unwrap();
+        let stitch = Stitch::from_str(text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -296,7 +379,7 @@ mod tests {
     fn following_stitch_increases_the_number_of_visits() {
         let text = "Hello, World!";
 
-        let mut stitch = Stitch::from_str(text).unwrap();
+        let stitch = Stitch::from_str(text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -311,7 +394,7 @@ mod tests {
     fn following_stitch_with_choice_does_not_increase_the_number_of_visits() {
         let text = "*   Choice";
 
-        let mut stitch = Stitch::from_str(text).unwrap();
+        let stitch = Stitch::from_str(text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -331,7 +414,7 @@ mod tests {
 -   Line
 ";
 
-        let mut stitch = Stitch::from_str(text).unwrap();
+        let stitch = Stitch::from_str(text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -360,7 +443,7 @@ mod tests {
             pre, name, after
         );
 
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -392,7 +475,7 @@ mod tests {
             text.push('\n');
         }
 
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
@@ -419,7 +502,7 @@ mod tests {
         let choice = "Choice 1";
         let text = format!("* {}", choice);
 
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = LineDataBuffer::new();
         let mut data = mock_follow_data(&stitch);
@@ -440,18 +523,20 @@ mod tests {
 * Choice 2
 ";
 
-        let mut stitch = Stitch::from_str(text).unwrap();
+        let stitch = Stitch::from_str(text).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_follow_data(&stitch);
 
+        let (knot, stitch_name) = stitch.root.address.get_knot_and_stitch().unwrap();
+
         stitch.follow(&mut buffer, &mut data).unwrap();
-        assert_eq!(&stitch.stack, &[0]);
+        assert_eq!(&data.stacks[knot][stitch_name], &[0]);
 
         stitch
             .follow_with_choice(0, &mut buffer, &mut data)
             .unwrap();
-        assert_eq!(&stitch.stack, &[0]);
+        assert!(!data.stacks[knot].contains_key(stitch_name));
     }
 
     #[test]
@@ -474,7 +559,7 @@ mod tests {
             text.push('\n');
         }
 
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = LineDataBuffer::new();
         let mut data = mock_follow_data(&stitch);
@@ -510,7 +595,7 @@ mod tests {
             text.push('\n');
         }
 
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
         let mut data = mock_follow_data(&stitch);
 
         let mut results_choice1 = LineDataBuffer::new();
@@ -548,7 +633,7 @@ Line 1
 -   Line 5
 Line 6
 ";
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = LineDataBuffer::new();
         let mut data = mock_follow_data(&stitch);
@@ -574,7 +659,7 @@ Line 6
 *   Choice 1
 *   Choice 2
 ";
-        let mut stitch = Stitch::from_str(&text).unwrap();
+        let stitch = Stitch::from_str(&text).unwrap();
 
         let mut buffer = LineDataBuffer::new();
         let mut data = mock_follow_data(&stitch);
@@ -589,25 +674,25 @@ Line 6
 
     #[test]
     fn read_knot_name_from_string_works_with_at_least_two_equal_signs() {
-        assert_eq!(&read_knot_name("== Knot").unwrap(), "Knot");
-        assert_eq!(&read_knot_name("=== Knot").unwrap(), "Knot");
-        assert_eq!(&read_knot_name("== Knot==").unwrap(), "Knot");
-        assert_eq!(&read_knot_name("==Knot==").unwrap(), "Knot");
+        assert_eq!(read_knot_signature("== Knot").unwrap().0, "Knot");
+        assert_eq!(read_knot_signature("=== Knot").unwrap().0, "Knot");
+        assert_eq!(read_knot_signature("== Knot==").unwrap().0, "Knot");
+        assert_eq!(read_knot_signature("==Knot==").unwrap().0, "Knot");
     }
 
     #[test]
     fn read_stitch_name_from_string_works_with_exactly_one_equal_sign() {
-        assert_eq!(&read_stitch_name("= Stitch").unwrap(), "Stitch");
-        assert_eq!(&read_stitch_name("=Stitch").unwrap(), "Stitch");
-        assert!(&read_stitch_name("== Stitch").is_err());
+        assert_eq!(read_stitch_signature("= Stitch").unwrap().0, "Stitch");
+        assert_eq!(read_stitch_signature("=Stitch").unwrap().0, "Stitch");
+        assert!(&read_stitch_signature("== Stitch").is_err());
     }
 
     #[test]
     fn knot_name_must_be_single_word() {
-        assert!(read_knot_name("== Knot name").is_err());
-        assert!(read_knot_name("== Knot name ==").is_err());
+        assert!(read_knot_signature("== Knot name").is_err());
+        assert!(read_knot_signature("== Knot name ==").is_err());
 
-        match read_knot_name("== knot name") {
+        match read_knot_signature("== knot name") {
             Err(KnotNameError::ContainsWhitespace) => (),
             Err(err) => panic!(
                 "Expected a `KnotNameError::ContainsWhitespace` error, got {:?}",
@@ -619,11 +704,11 @@ Line 6
 
     #[test]
     fn knot_name_cannot_be_empty() {
-        assert!(read_knot_name("==").is_err());
-        assert!(read_knot_name("== ").is_err());
-        assert!(read_knot_name("== a").is_ok());
+        assert!(read_knot_signature("==").is_err());
+        assert!(read_knot_signature("== ").is_err());
+        assert!(read_knot_signature("== a").is_ok());
 
-        match read_knot_name("== ") {
+        match read_knot_signature("== ") {
             Err(KnotNameError::Empty) => (),
             err => panic!(
                 "expected `KnotNameError::Empty` as kind error, but got {:?}",
@@ -634,20 +719,20 @@ Line 6
 
     #[test]
     fn knot_name_can_only_contain_alphanumeric_characters_and_underlines() {
-        assert!(read_knot_name("== knot").is_ok());
-        assert!(read_knot_name("== knot_name").is_ok());
-        assert!(read_knot_name("== knot_name_with_123").is_ok());
-        assert!(read_knot_name("== knot_name_with_абв").is_ok());
-        assert!(read_knot_name("== knot_name_with_αβγ").is_ok());
-        assert!(read_knot_name("== knot_name_with_ñßüåäö").is_ok());
-        assert!(read_knot_name("== knot_name_with_京").is_ok());
-
-        assert!(read_knot_name("== knot.name").is_err());
-        assert!(read_knot_name("== knot-name").is_err());
-        assert!(read_knot_name("== knot/name").is_err());
-        assert!(read_knot_name("== knot$name").is_err());
-
-        match read_knot_name("== 京knot.name") {
+        assert!(read_knot_signature("== knot").is_ok());
+        assert!(read_knot_signature("== knot_name").is_ok());
+        assert!(read_knot_signature("== knot_name_with_123").is_ok());
+        assert!(read_knot_signature("== knot_name_with_абв").is_ok());
+        assert!(read_knot_signature("== knot_name_with_αβγ").is_ok());
+        assert!(read_knot_signature("== knot_name_with_ñßüåäö").is_ok());
+        assert!(read_knot_signature("== knot_name_with_京").is_ok());
+
+        assert!(read_knot_signature("== knot.name").is_err());
+        assert!(read_knot_signature("== knot-name").is_err());
+        assert!(read_knot_signature("== knot/name").is_err());
+        assert!(read_knot_signature("== knot$name").is_err());
+
+        match read_knot_signature("== 京knot.name") {
             Err(KnotNameError::ContainsInvalidCharacter('.')) => (),
             Err(KnotNameError::ContainsInvalidCharacter(c)) => panic!(
                 "Expected a `KnotNameError::ContainsInvalidCharacter` error \
@@ -664,15 +749,46 @@ Line 6
 
     #[test]
     fn read_knot_name_from_string_returns_error_if_just_one_or_no_equal_signs() {
-        assert!(read_knot_name("= Knot name ==").is_err());
-        assert!(read_knot_name("=Knot name").is_err());
-        assert!(read_knot_name(" Knot name ==").is_err());
-        assert!(read_knot_name("Knot name==").is_err());
+        assert!(read_knot_signature("= Knot name ==").is_err());
+        assert!(read_knot_signature("=Knot name").is_err());
+        assert!(read_knot_signature(" Knot name ==").is_err());
+        assert!(read_knot_signature("Knot name==").is_err());
     }
 
     #[test]
     fn knot_and_stitch_names_may_not_be_from_the_reserved_list() {
-        assert!(read_knot_name("== else").is_err());
-        assert!(read_knot_name("== not").is_err());
+        assert!(read_knot_signature("== else").is_err());
+        assert!(read_knot_signature("== not").is_err());
+    }
+
+    #[test]
+    fn knot_and_stitch_signatures_may_have_parameters_in_parenthesis() {
+        assert_eq!(
+            read_knot_signature("=== meet(character, mood) ===").unwrap(),
+            (
+                "meet".to_string(),
+                vec!["character".to_string(), "mood".to_string()]
+            )
+        );
+
+        assert_eq!(
+            read_stitch_signature("= greet(name)").unwrap(),
+            ("greet".to_string(), vec!["name".to_string()])
+        );
+    }
+
+    #[test]
+    fn knot_and_stitch_signatures_without_parameters_have_empty_parameter_lists() {
+        assert!(read_knot_signature("== knot").unwrap().1.is_empty());
+        assert!(read_knot_signature("== knot() ==").unwrap().1.is_empty());
+        assert!(read_stitch_signature("= stitch").unwrap().1.is_empty());
+    }
+
+    #[test]
+    fn knot_parameters_are_validated_as_names() {
+        assert!(read_knot_signature("== meet(character name)").is_err());
+        assert!(read_knot_signature("== meet(character,)").is_err());
+        assert!(read_knot_signature("== meet(ref character)").is_err());
+        assert!(read_knot_signature("== meet(else)").is_err());
     }
 }
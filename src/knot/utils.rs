@@ -1,14 +1,15 @@
 //! Utilities for accessing `Knot` and `Stitch` data.
 
 use crate::{
-    error::{runtime::internal::StackError, InternalError},
+    error::{runtime::internal::StackError, InklingError, InternalError},
     follow::FollowData,
-    knot::{Address, KnotSet, Stitch},
+    knot::{Address, AddressKind, KnotSet, Stitch},
+    line::Variable,
+    node::Stack,
 };
 
 use std::collections::HashMap;
 
-#[allow(dead_code)]
 /// Return a reference to the `Stitch` at the target address.
 pub fn get_stitch<'a>(address: &Address, knots: &'a KnotSet) -> Result<&'a Stitch, InternalError> {
     let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
@@ -24,53 +25,169 @@ pub fn get_stitch<'a>(address: &Address, knots: &'a KnotSet) -> Result<&'a Stitc
         )
 }
 
-/// Return a mutable reference to the `Stitch` at the target address.
-pub fn get_mut_stitch<'a>(
-    address: &Address,
-    knots: &'a mut KnotSet,
-) -> Result<&'a mut Stitch, InternalError> {
+/// Take the last recorded position in the stitch at the target address.
+///
+/// The position is removed from the data. If none was recorded the stitch is followed
+/// from its beginning.
+pub fn take_stack(address: &Address, data: &mut FollowData) -> Result<Stack, InternalError> {
     let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
 
-    knots
+    let stack = data
+        .stacks
         .get_mut(knot_name)
-        .and_then(|knot| knot.stitches.get_mut(stitch_name))
-        .ok_or(
-            StackError::BadAddress {
-                address: address.clone(),
+        .and_then(|knot| knot.remove(stitch_name))
+        .unwrap_or_else(|| vec![0]);
+
+    Ok(stack)
+}
+
+/// Get the last recorded position in the stitch at the target address without removing it.
+pub fn get_stack(address: &Address, data: &FollowData) -> Result<Stack, InternalError> {
+    let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
+
+    let stack = data
+        .stacks
+        .get(knot_name)
+        .and_then(|knot| knot.get(stitch_name))
+        .cloned()
+        .unwrap_or_else(|| vec![0]);
+
+    Ok(stack)
+}
+
+/// Record the position in the stitch at the target address, from which the next follow starts.
+pub fn set_stack(
+    address: &Address,
+    stack: Stack,
+    data: &mut FollowData,
+) -> Result<(), InternalError> {
+    let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
+
+    data.stacks
+        .entry(knot_name.to_string())
+        .or_default()
+        .insert(stitch_name.to_string(), stack);
+
+    Ok(())
+}
+
+/// Return the address that a divert to the target address leads to.
+///
+/// Diverts to a global or temporary variable lead to the divert target which the variable
+/// holds. Other addresses are returned as they are.
+pub fn get_divert_target(address: &Address, data: &FollowData) -> Result<Address, InklingError> {
+    match address {
+        Address::Validated(AddressKind::GlobalVariable { name })
+        | Address::Validated(AddressKind::TemporaryVariable { name }) => {
+            match Variable::Address(address.clone()).as_value(data)? {
+                Variable::Divert(target) => Ok(target),
+                value => Err(InklingError::InvalidDivertTarget {
+                    name: name.clone(),
+                    value,
+                }),
             }
-            .into(),
-        )
+        }
+        _ => Ok(address.clone()),
+    }
 }
 
+/// Return the number of times that the knot, stitch or label at the address has been visited.
 pub fn get_num_visited(address: &Address, data: &FollowData) -> Result<u32, InternalError> {
+    let num_visited = match address {
+        Address::Validated(AddressKind::Label {
+            knot,
+            stitch,
+            label,
+        }) => data
+            .label_visit_counts
+            .get(knot)
+            .and_then(|knot| knot.get(stitch))
+            .and_then(|stitch| stitch.get(label).copied()),
+        _ => {
+            let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
+
+            data.knot_visit_counts
+                .get(knot_name)
+                .and_then(|knot| knot.get(stitch_name).copied())
+        }
+    };
+
+    num_visited.ok_or(
+        StackError::BadAddress {
+            address: address.clone(),
+        }
+        .into(),
+    )
+}
+
+/// Return the number of turns since the knot or stitch at the address was last visited.
+///
+/// If the address has not been visited this is -1. Visits to labels are not tracked
+/// by turn, so labels yield an error.
+pub fn get_turns_since(address: &Address, data: &FollowData) -> Result<i32, InternalError> {
+    if let Address::Validated(AddressKind::Label { .. }) = address {
+        return Err(StackError::BadAddress {
+            address: address.clone(),
+        }
+        .into());
+    }
+
+    get_num_visited(address, data)?;
+
     let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
 
-    data.knot_visit_counts
+    let turns_since = data
+        .knot_visit_turns
         .get(knot_name)
-        .and_then(|knot| knot.get(stitch_name).copied())
-        .ok_or(
-            StackError::BadAddress {
-                address: address.clone(),
-            }
-            .into(),
-        )
+        .and_then(|knot| knot.get(stitch_name))
+        .map(|turn| (data.turn_index - turn) as i32)
+        .unwrap_or(-1);
+
+    Ok(turns_since)
 }
 
+/// Increment the number of visits to the knot, stitch or label at the address.
+///
+/// Visits to knots and stitches also record the current turn index as their last visit.
 pub fn increment_num_visited(
     address: &Address,
     data: &mut FollowData,
 ) -> Result<(), InternalError> {
-    let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
+    let num_visited = match address {
+        Address::Validated(AddressKind::Label {
+            knot,
+            stitch,
+            label,
+        }) => data
+            .label_visit_counts
+            .get_mut(knot)
+            .and_then(|knot| knot.get_mut(stitch))
+            .and_then(|stitch| stitch.get_mut(label)),
+        _ => {
+            let (knot_name, stitch_name) = address.get_knot_and_stitch()?;
 
-    data.knot_visit_counts
-        .get_mut(knot_name)
-        .and_then(|knot| knot.get_mut(stitch_name).map(|count| *count += 1))
-        .ok_or(
-            StackError::BadAddress {
-                address: address.clone(),
+            let num_visited = data
+                .knot_visit_counts
+                .get_mut(knot_name)
+                .and_then(|knot| knot.get_mut(stitch_name));
+
+            if num_visited.is_some() {
+                data.knot_visit_turns
+                    .entry(knot_name.to_string())
+                    .or_default()
+                    .insert(stitch_name.to_string(), data.turn_index);
             }
-            .into(),
-        )
+
+            num_visited
+        }
+    };
+
+    num_visited.map(|count| *count += 1).ok_or(
+        StackError::BadAddress {
+            address: address.clone(),
+        }
+        .into(),
+    )
 }
 
 pub fn get_empty_knot_counts(knots: &KnotSet) -> HashMap<String, HashMap<String, u32>> {
@@ -87,3 +204,32 @@ pub fn get_empty_knot_counts(knots: &KnotSet) -> HashMap<String, HashMap<String,
         })
         .collect()
 }
+
+/// Return a map of all labels in the story, with their number of visits set to 0.
+///
+/// The labels are mapped by knot and stitch names.
+pub fn get_empty_label_counts(
+    knots: &KnotSet,
+) -> HashMap<String, HashMap<String, HashMap<String, u32>>> {
+    knots
+        .iter()
+        .map(|(knot_name, knot)| {
+            let stitches = knot
+                .stitches
+                .iter()
+                .map(|(stitch_name, stitch)| {
+                    let labels = stitch
+                        .root
+                        .get_labels()
+                        .into_iter()
+                        .map(|(label, _)| (label, 0))
+                        .collect();
+
+                    (stitch_name.clone(), labels)
+                })
+                .collect();
+
+            (knot_name.clone(), stitches)
+        })
+        .collect()
+}
@@ -47,9 +47,11 @@ mod story;
 mod utils;
 
 pub use error::InklingError;
-pub use line::Variable;
+pub use line::{InkList, ListItem, Variable};
 pub use log::Logger;
 pub use story::{
-    copy_lines_into_string, read_story_from_string, Choice, Line, LineBuffer, Location, Prompt,
-    Story,
+    compile_story_from_string, compile_story_with_loader, copy_lines_into_string,
+    read_story_from_string, read_story_with_loader, Checkpoint, Choice, CompiledStory,
+    FileStoryLoader, Line, LineBuffer, Location, Prompt, ReplayLog, ReplayStep, Story, StoryLoader,
+    StoryState, Transcript, TranscriptEntry, TranscriptEntryKind,
 };
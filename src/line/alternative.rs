@@ -28,11 +28,12 @@ use serde::{Deserialize, Serialize};
 ///
 /// Any selected `LineChunk`s can of course contain nested alternatives, and so on.
 pub struct Alternative {
-    /// Active list of item indices that will be used to select items.
+    /// Identifier of the alternative in the story.
     ///
-    /// The list should be in reverse item order, so that we can pop indices from
-    /// it -- popping yields the last item, after all.
-    pub active_inds: Vec<usize>,
+    /// The active list of item indices that the alternative selects items from is kept
+    /// under this identifier in the [follow data][crate::follow::FollowData::alternative_inds],
+    /// which lets the content of a story be shared between several states.
+    pub id: usize,
     /// Which kind of alternative this represents.
     pub kind: AlternativeKind,
     /// Set of content which the object will select and process from.
@@ -40,44 +41,91 @@ pub struct Alternative {
 }
 
 impl Alternative {
-    #[allow(unused_variables)] // `data` only used when the `random` feature is enabled
     /// Get the next item index in the alternative sequence.
-    pub fn get_next_index(&mut self, data: &mut FollowData) -> Option<usize> {
-        match self.kind {
-            AlternativeKind::OnceOnly => self.active_inds.pop(),
-            AlternativeKind::Sequence if self.active_inds.len() > 1 => self.active_inds.pop(),
-            AlternativeKind::Sequence => self.active_inds.get(0).cloned(),
-            AlternativeKind::Cycle => {
-                if self.active_inds.is_empty() {
-                    self.reset_active_list()
-                }
-
-                self.active_inds.pop()
-            }
-            AlternativeKind::Shuffle => {
-                if self.active_inds.is_empty() {
-                    self.reset_active_list()
-                }
+    pub fn get_next_index(&self, data: &mut FollowData) -> Option<usize> {
+        get_next_alternative_index(self.kind, self.id, self.items.len(), data)
+    }
+}
 
-                #[cfg(feature = "random")]
-                if self.is_first_item() {
-                    self.active_inds.shuffle(&mut data.rng.gen);
-                }
+/// Get the next item index for an alternative of given kind and identifier.
+///
+/// The active list of indices is taken from the data, or created if the alternative
+/// has not yet been processed, and put back after the index has been selected. This is
+/// shared between inline alternatives and multiline alternative blocks, which select
+/// between their branches in the same way.
+pub fn get_next_alternative_index(
+    kind: AlternativeKind,
+    id: usize,
+    num_items: usize,
+    data: &mut FollowData,
+) -> Option<usize> {
+    let mut active_inds = data
+        .alternative_inds
+        .remove(&id)
+        .unwrap_or_else(|| (0..num_items).rev().collect());
+
+    let index = get_next_index_from_list(kind, &mut active_inds, num_items, data);
+    data.alternative_inds.insert(id, active_inds);
+
+    index
+}
 
-                self.active_inds.pop()
+#[allow(unused_variables)] // `data` only used when the `random` feature is enabled
+/// Get the next item index from an active list of indices for an alternative of given kind.
+///
+/// The active list should be in reverse item order, so that we can pop indices from
+/// it -- popping yields the last item, after all.
+fn get_next_index_from_list(
+    kind: AlternativeKind,
+    active_inds: &mut Vec<usize>,
+    num_items: usize,
+    data: &mut FollowData,
+) -> Option<usize> {
+    let reset_active_list = |active_inds: &mut Vec<usize>| {
+        if active_inds.is_empty() {
+            *active_inds = (0..num_items).rev().collect();
+        }
+    };
+
+    match kind {
+        AlternativeKind::OnceOnly => active_inds.pop(),
+        AlternativeKind::Sequence if active_inds.len() > 1 => active_inds.pop(),
+        AlternativeKind::Sequence => active_inds.first().cloned(),
+        AlternativeKind::Cycle => {
+            reset_active_list(active_inds);
+            active_inds.pop()
+        }
+        AlternativeKind::Shuffle => {
+            reset_active_list(active_inds);
+
+            #[cfg(feature = "random")]
+            if active_inds.len() == num_items {
+                active_inds.shuffle(&mut data.rng.gen);
             }
+
+            active_inds.pop()
         }
-    }
+        AlternativeKind::ShuffleOnce => {
+            #[cfg(feature = "random")]
+            if active_inds.len() == num_items {
+                active_inds.shuffle(&mut data.rng.gen);
+            }
 
-    #[allow(dead_code)]
-    /// Return whether or not we are at the first item in the sequence.
-    fn is_first_item(&self) -> bool {
-        self.active_inds.len() == self.items.len()
-    }
+            active_inds.pop()
+        }
+        AlternativeKind::ShuffleStopping => {
+            // The final item is first in the reversed list and is kept in place
+            #[cfg(feature = "random")]
+            if active_inds.len() == num_items && num_items > 1 {
+                active_inds[1..].shuffle(&mut data.rng.gen);
+            }
 
-    /// Reset the active list by remaking the index list and reversing it.
-    fn reset_active_list(&mut self) {
-        self.active_inds = (0..self.items.len()).rev().collect();
+            if active_inds.len() > 1 {
+                active_inds.pop()
+            } else {
+                active_inds.first().cloned()
+            }
+        }
     }
 }
 
@@ -114,6 +162,39 @@ pub enum AlternativeKind {
     /// A set of three cards `[One, Two, Three]` will be shuffled then dealt one by one. Once
     /// the set is empty, the deck is reshuffled.
     Shuffle,
+    /// Shuffles the set once, then goes through it and produces nothing after the end.
+    ///
+    /// # Note
+    /// This is only a randomly shuffled sequence if the `random` feature is enabled. Otherwise,
+    /// it defaults to being a `OnceOnly` sequence.
+    ///
+    /// # Example
+    /// A set of three rumors `[One, Two, Three]` will be told in a random order, after which
+    /// nothing more is heard.
+    ShuffleOnce,
+    /// Shuffles all but the final item of the set, then goes through it and repeats
+    /// the final item.
+    ///
+    /// # Note
+    /// This is only a randomly shuffled sequence if the `random` feature is enabled. Otherwise,
+    /// it defaults to being a `Sequence`.
+    ///
+    /// # Example
+    /// A set of complaints `[One, Two, Three, Enough]` will be voiced in a random order,
+    /// followed by `Enough` forever after.
+    ShuffleStopping,
+}
+
+impl AlternativeKind {
+    /// Whether or not the kind selects its items in a shuffled order.
+    pub fn is_shuffle(&self) -> bool {
+        match self {
+            AlternativeKind::Shuffle
+            | AlternativeKind::ShuffleOnce
+            | AlternativeKind::ShuffleStopping => true,
+            AlternativeKind::Cycle | AlternativeKind::OnceOnly | AlternativeKind::Sequence => false,
+        }
+    }
 }
 
 impl ValidateContent for Alternative {
@@ -126,11 +207,8 @@ impl ValidateContent for Alternative {
         data: &ValidationData,
     ) {
         #[cfg(not(feature = "random"))]
-        match self.kind {
-            AlternativeKind::Shuffle => {
-                log.add_warning(Warning::ShuffleSequenceNoRandom, meta_data);
-            }
-            _ => (),
+        if self.kind.is_shuffle() {
+            log.add_warning(Warning::ShuffleSequenceNoRandom, meta_data);
         }
 
         self.items
@@ -155,9 +233,12 @@ impl AlternativeBuilder {
     }
 
     /// Finalize the `Alternative` and return it.
+    ///
+    /// # Notes
+    /// *   The identifier is set to 0. Identifiers are set when the story is compiled.
     pub fn build(self) -> Alternative {
         Alternative {
-            active_inds: (0..self.items.len()).rev().collect(),
+            id: 0,
             kind: self.kind,
             items: self.items,
         }
@@ -261,12 +342,12 @@ mod tests {
         let alternative = builder.build();
 
         assert_eq!(alternative.items, items);
-        assert_eq!(&alternative.active_inds, &[3, 2, 1, 0]);
+        assert_eq!(alternative.id, 0);
     }
 
     #[test]
     fn alternative_get_next_index_for_cycle_resets_list_after_yielding_all_inds() {
-        let mut alternative = create_alternative(AlternativeKind::Cycle, 3);
+        let alternative = create_alternative(AlternativeKind::Cycle, 3);
         let mut data = mock_data_with_single_stitch("", "", 0);
 
         assert_eq!(alternative.get_next_index(&mut data), Some(0));
@@ -280,7 +361,7 @@ mod tests {
 
     #[test]
     fn alternative_get_next_index_for_sequence_yields_final_index_forever_after_the_initial() {
-        let mut alternative = create_alternative(AlternativeKind::Sequence, 3);
+        let alternative = create_alternative(AlternativeKind::Sequence, 3);
         let mut data = mock_data_with_single_stitch("", "", 0);
 
         assert_eq!(alternative.get_next_index(&mut data), Some(0));
@@ -292,7 +373,7 @@ mod tests {
 
     #[test]
     fn alternative_get_next_index_for_once_only_yields_none_after_the_initial() {
-        let mut alternative = create_alternative(AlternativeKind::OnceOnly, 3);
+        let alternative = create_alternative(AlternativeKind::OnceOnly, 3);
         let mut data = mock_data_with_single_stitch("", "", 0);
 
         assert_eq!(alternative.get_next_index(&mut data), Some(0));
@@ -324,6 +405,24 @@ mod tests {
             assert_eq!(alternative.get_next_index(&mut data), Some(0));
         }
 
+        #[test]
+        fn shuffle_once_and_shuffle_stopping_follow_once_only_and_sequence_if_not_random() {
+            let mut data = mock_data_with_single_stitch_and_rng("", "", 0, StoryRng::default());
+
+            let mut alternative = create_alternative(AlternativeKind::ShuffleOnce, 2);
+
+            assert_eq!(alternative.get_next_index(&mut data), Some(0));
+            assert_eq!(alternative.get_next_index(&mut data), Some(1));
+            assert_eq!(alternative.get_next_index(&mut data), None);
+
+            let mut alternative = create_alternative(AlternativeKind::ShuffleStopping, 2);
+            alternative.id = 1;
+
+            assert_eq!(alternative.get_next_index(&mut data), Some(0));
+            assert_eq!(alternative.get_next_index(&mut data), Some(1));
+            assert_eq!(alternative.get_next_index(&mut data), Some(1));
+        }
+
         #[test]
         fn shuffle_alternative_yields_warning_during_validation_if_random_is_not_enabled() {
             let validation_data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
@@ -353,39 +452,39 @@ mod tests {
 
         #[test]
         fn alternative_get_next_index_for_shuffle_shuffles_active_index_list() {
-            let mut alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
+            let alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
             let mut data = mock_data_with_single_stitch_and_rng("", "", 0, StoryRng::default());
 
             // Create reverse list from 1, since we will pop the first (0) before the comparison
             let inds_unshuffled = (0..NUM_ITEMS).skip(1).rev().collect::<Vec<usize>>();
 
             alternative.get_next_index(&mut data);
-            assert!(alternative.active_inds != inds_unshuffled);
+            assert!(data.alternative_inds[&alternative.id] != inds_unshuffled);
         }
 
         #[test]
         fn alternative_get_next_index_for_shuffle_uses_shuffle_in_place_with_the_generator() {
-            let mut alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
+            let alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
 
             let mut rng = StoryRng::default();
             let mut data = mock_data_with_single_stitch_and_rng("", "", 0, rng.clone());
 
-            let mut active_inds = alternative.active_inds.clone();
+            let mut active_inds = (0..NUM_ITEMS).rev().collect::<Vec<_>>();
             active_inds.shuffle(&mut rng.gen);
 
             assert_eq!(alternative.get_next_index(&mut data), active_inds.pop());
-            assert_eq!(&alternative.active_inds, &active_inds);
+            assert_eq!(&data.alternative_inds[&alternative.id], &active_inds);
         }
 
         #[test]
         fn alternative_get_next_index_for_shuffle_resets_list_after_emptying() {
-            let mut alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
+            let alternative = create_alternative(AlternativeKind::Shuffle, NUM_ITEMS);
 
             let mut rng = StoryRng::default();
             let mut data = mock_data_with_single_stitch_and_rng("", "", 0, rng.clone());
 
             // Unshuffled list
-            let mut active_inds = alternative.active_inds.clone();
+            let mut active_inds = (0..NUM_ITEMS).rev().collect::<Vec<_>>();
 
             // First (internal) shuffle, go through all items
             for _ in 0..NUM_ITEMS {
@@ -397,7 +496,42 @@ mod tests {
             active_inds.shuffle(&mut rng.gen);
 
             assert_eq!(alternative.get_next_index(&mut data), active_inds.pop());
-            assert_eq!(&alternative.active_inds, &active_inds);
+            assert_eq!(&data.alternative_inds[&alternative.id], &active_inds);
+        }
+
+        #[test]
+        fn alternative_get_next_index_for_shuffle_once_yields_none_after_all_items() {
+            let alternative = create_alternative(AlternativeKind::ShuffleOnce, NUM_ITEMS);
+            let mut data = mock_data_with_single_stitch_and_rng("", "", 0, StoryRng::default());
+
+            let mut inds = (0..NUM_ITEMS)
+                .map(|_| alternative.get_next_index(&mut data).unwrap())
+                .collect::<Vec<_>>();
+
+            assert!(inds != (0..NUM_ITEMS).collect::<Vec<_>>());
+
+            inds.sort();
+            assert_eq!(inds, (0..NUM_ITEMS).collect::<Vec<_>>());
+
+            assert_eq!(alternative.get_next_index(&mut data), None);
+        }
+
+        #[test]
+        fn alternative_get_next_index_for_shuffle_stopping_yields_final_item_forever_after() {
+            let alternative = create_alternative(AlternativeKind::ShuffleStopping, NUM_ITEMS);
+            let mut data = mock_data_with_single_stitch_and_rng("", "", 0, StoryRng::default());
+
+            let mut inds = (0..NUM_ITEMS - 1)
+                .map(|_| alternative.get_next_index(&mut data).unwrap())
+                .collect::<Vec<_>>();
+
+            assert!(inds != (0..NUM_ITEMS - 1).collect::<Vec<_>>());
+
+            inds.sort();
+            assert_eq!(inds, (0..NUM_ITEMS - 1).collect::<Vec<_>>());
+
+            assert_eq!(alternative.get_next_index(&mut data), Some(NUM_ITEMS - 1));
+            assert_eq!(alternative.get_next_index(&mut data), Some(NUM_ITEMS - 1));
         }
 
         #[test]
@@ -0,0 +1,307 @@
+//! Assignment of new values to variables from within the story.
+
+use crate::{
+    error::{
+        parse::{
+            address::{InvalidAddressError, InvalidAddressErrorKind},
+            validate::{ExpressionKind, InvalidVariableExpression, ValidationError},
+        },
+        utils::MetaData,
+        InklingError, InternalError,
+    },
+    follow::FollowData,
+    knot::{Address, AddressKind},
+    line::{evaluate_expression, Expression},
+    log::Logger,
+    story::validate::{ValidateContent, ValidationData},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Assignment of an evaluated expression to a variable.
+///
+/// Corresponds to logic lines like `~ coins = coins + 4` in the script. Compound assignments
+/// (`~ coins += 4`) and increments (`~ coins++`) are expanded into this form when parsed,
+/// with the target variable as the head of the expression.
+///
+/// Assignments may also declare temporary variables (`~ temp x = 5`), which only live while
+/// the story is in the knot or stitch where they are declared.
+pub struct Assignment {
+    /// Address of variable to assign the value to.
+    pub target: Address,
+    /// Expression to evaluate for the new value.
+    pub expression: Expression,
+    /// Whether the assignment declares a temporary variable.
+    pub temporary: bool,
+}
+
+impl Assignment {
+    /// Evaluate the expression and assign its value to the target variable.
+    ///
+    /// If the assignment declares a temporary variable it is (re)set with the value
+    /// regardless of its previous type.
+    ///
+    /// # Errors
+    /// *   [`AssignedToConst`][crate::error::InklingError::AssignedToConst]: if the target
+    ///     is a constant variable.
+    /// *   [`VariableError`][crate::error::InklingError::VariableError]: if the expression
+    ///     could not be evaluated or evaluates to a different type than the target.
+    pub fn evaluate(&self, data: &mut FollowData) -> Result<(), InklingError> {
+        let value = evaluate_expression(&self.expression, data)?;
+
+        match &self.target {
+            Address::Validated(AddressKind::GlobalVariable { name }) => data
+                .variables
+                .get_mut(name)
+                .ok_or(InklingError::InvalidVariable {
+                    name: name.to_string(),
+                })
+                .and_then(|info| info.assign(value, name)),
+            Address::Validated(AddressKind::TemporaryVariable { name }) if self.temporary => {
+                data.temp_variables.insert(name.clone(), value);
+                Ok(())
+            }
+            Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                .temp_variables
+                .get_mut(name)
+                .ok_or(InklingError::InvalidVariable {
+                    name: name.to_string(),
+                })
+                .and_then(|variable| variable.assign(value).map_err(|err| err.into())),
+            other => Err(InternalError::UseOfUnvalidatedAddress {
+                address: other.clone(),
+            }
+            .into()),
+        }
+    }
+}
+
+impl Assignment {
+    /// Whether or not the assigned value or the type of the target is unknown until the
+    /// story is followed.
+    pub fn has_unknown_value(&self, data: &ValidationData) -> bool {
+        let unknown_target = match &self.target {
+            Address::Validated(AddressKind::TemporaryVariable { name }) if !self.temporary => {
+                data.unknown_temporary_variables.contains(name)
+            }
+            _ => false,
+        };
+
+        unknown_target || self.expression.has_unknown_value(data)
+    }
+}
+
+impl ValidateContent for Assignment {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        let num_errors = error.num_errors();
+
+        if self.temporary {
+            self.target = Address::Validated(AddressKind::TemporaryVariable {
+                name: self.target.to_string(),
+            });
+        } else {
+            self.target
+                .validate(error, log, current_location, meta_data, data);
+        }
+
+        if num_errors != error.num_errors() {
+            return;
+        }
+
+        match &self.target {
+            Address::Validated(AddressKind::GlobalVariable { .. })
+            | Address::Validated(AddressKind::TemporaryVariable { .. }) => (),
+            other => {
+                error.invalid_address_errors.push(InvalidAddressError {
+                    kind: InvalidAddressErrorKind::NotAVariable {
+                        address: other.clone(),
+                    },
+                    meta_data: meta_data.clone(),
+                });
+
+                return;
+            }
+        }
+
+        self.expression
+            .validate(error, log, current_location, meta_data, data);
+
+        if num_errors == error.num_errors() && !self.has_unknown_value(data) {
+            let mut follow_data = data.follow_data.clone();
+
+            if let Err(err) = self.evaluate(&mut follow_data) {
+                error.variable_errors.push(InvalidVariableExpression {
+                    expression_kind: ExpressionKind::Assignment,
+                    kind: err.into(),
+                    meta_data: meta_data.clone(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        follow::FollowDataBuilder,
+        line::{expression::Operand, Variable},
+        story::types::VariableInfo,
+    };
+
+    use std::collections::HashMap;
+
+    fn mock_follow_data(variables: &[(&str, Variable, bool)]) -> FollowData {
+        let variables = variables
+            .into_iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, (name, var, is_const))| {
+                let mut info = VariableInfo::new(var, i);
+                info.is_const = is_const;
+
+                (name.to_string(), info)
+            })
+            .collect();
+
+        FollowDataBuilder::new()
+            .with_knots(HashMap::new())
+            .with_variables(variables)
+            .build()
+    }
+
+    fn get_assignment(name: &str, value: Variable) -> Assignment {
+        Assignment {
+            target: Address::Validated(AddressKind::GlobalVariable {
+                name: name.to_string(),
+            }),
+            expression: Expression {
+                head: Operand::Variable(value),
+                tail: Vec::new(),
+            },
+            temporary: false,
+        }
+    }
+
+    fn get_temporary_assignment(name: &str, value: Variable, temporary: bool) -> Assignment {
+        Assignment {
+            target: Address::Validated(AddressKind::TemporaryVariable {
+                name: name.to_string(),
+            }),
+            expression: Expression::from(value),
+            temporary,
+        }
+    }
+
+    #[test]
+    fn evaluating_assignment_sets_new_value_to_variable() {
+        let mut data = mock_follow_data(&[("coins", Variable::Int(0), false)]);
+
+        get_assignment("coins", Variable::Int(5))
+            .evaluate(&mut data)
+            .unwrap();
+
+        assert_eq!(data.variables["coins"].variable, Variable::Int(5));
+    }
+
+    #[test]
+    fn evaluating_assignment_to_constant_variable_yields_error() {
+        let mut data = mock_follow_data(&[("coins", Variable::Int(0), true)]);
+
+        match get_assignment("coins", Variable::Int(5)).evaluate(&mut data) {
+            Err(InklingError::AssignedToConst { name }) => assert_eq!(&name, "coins"),
+            other => panic!(
+                "expected `InklingError::AssignedToConst` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn evaluating_assignment_of_different_type_yields_error() {
+        let mut data = mock_follow_data(&[("coins", Variable::Int(0), false)]);
+
+        match get_assignment("coins", Variable::Bool(true)).evaluate(&mut data) {
+            Err(InklingError::VariableError(..)) => (),
+            other => panic!("expected `InklingError::VariableError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn evaluating_assignment_to_unvalidated_address_yields_error() {
+        let mut data = mock_follow_data(&[("coins", Variable::Int(0), false)]);
+
+        let mut assignment = get_assignment("coins", Variable::Int(5));
+        assignment.target = Address::Raw("coins".to_string());
+
+        assert!(assignment.evaluate(&mut data).is_err());
+    }
+
+    #[test]
+    fn evaluating_temporary_declaration_sets_temporary_variable() {
+        let mut data = mock_follow_data(&[]);
+
+        get_temporary_assignment("x", Variable::Int(5), true)
+            .evaluate(&mut data)
+            .unwrap();
+
+        assert_eq!(data.temp_variables["x"], Variable::Int(5));
+        assert!(data.variables.is_empty());
+    }
+
+    #[test]
+    fn evaluating_temporary_declaration_may_change_type_of_earlier_declaration() {
+        let mut data = mock_follow_data(&[]);
+
+        get_temporary_assignment("x", Variable::Int(5), true)
+            .evaluate(&mut data)
+            .unwrap();
+        get_temporary_assignment("x", Variable::Bool(true), true)
+            .evaluate(&mut data)
+            .unwrap();
+
+        assert_eq!(data.temp_variables["x"], Variable::Bool(true));
+    }
+
+    #[test]
+    fn evaluating_assignment_to_temporary_variable_checks_type() {
+        let mut data = mock_follow_data(&[]);
+
+        get_temporary_assignment("x", Variable::Int(5), true)
+            .evaluate(&mut data)
+            .unwrap();
+
+        assert!(get_temporary_assignment("x", Variable::Int(10), false)
+            .evaluate(&mut data)
+            .is_ok());
+        assert_eq!(data.temp_variables["x"], Variable::Int(10));
+
+        assert!(get_temporary_assignment("x", Variable::Bool(true), false)
+            .evaluate(&mut data)
+            .is_err());
+    }
+
+    #[test]
+    fn evaluating_assignment_to_undeclared_temporary_variable_yields_error() {
+        let mut data = mock_follow_data(&[]);
+
+        match get_temporary_assignment("x", Variable::Int(5), false).evaluate(&mut data) {
+            Err(InklingError::InvalidVariable { name }) => assert_eq!(&name, "x"),
+            other => panic!(
+                "expected `InklingError::InvalidVariable` but got {:?}",
+                other
+            ),
+        }
+    }
+}
@@ -0,0 +1,641 @@
+//! Functions which are built into `Ink`.
+
+use crate::{
+    error::InklingError,
+    follow::FollowData,
+    knot::{get_num_visited, get_turns_since, Address, AddressKind},
+    line::{InkList, ListItem, Variable},
+};
+
+#[cfg(feature = "random")]
+use crate::story::rng::StoryRng;
+
+#[cfg(feature = "random")]
+use rand::{seq::SliceRandom, Rng};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Function which is built into `Ink` and can be called from any story.
+pub enum BuiltinFunction {
+    /// `CHOICE_COUNT()`: number of choices which have been accepted into the set
+    /// of choices that is being prepared.
+    ChoiceCount,
+    /// `FLOAT(x)`: number converted to a floating point number.
+    Float,
+    /// `FLOOR(x)`: number rounded down to the closest integer, keeping its type.
+    Floor,
+    /// `INT(x)`: number converted to an integer, truncating any decimals.
+    Int,
+    /// `LIST_ALL(list)`: all items of the lists that the list has items from.
+    ListAll,
+    /// `LIST_COUNT(list)`: number of items in the list.
+    ListCount,
+    /// `LIST_INVERT(list)`: all items of the lists that the list has items from,
+    /// which are not in the list.
+    ListInvert,
+    /// `LIST_MAX(list)`: item in the list with the highest value.
+    ListMax,
+    /// `LIST_MIN(list)`: item in the list with the lowest value.
+    ListMin,
+    /// `LIST_RANDOM(list)`: random item in the list.
+    ///
+    /// Without the `random` feature this is the first item in the list.
+    ListRandom,
+    /// `LIST_RANGE(list, min, max)`: items in the list with values between
+    /// the minimum and maximum, inclusive.
+    ListRange,
+    /// `LIST_VALUE(list)`: value of the item in the list with the highest value.
+    ListValue,
+    /// `POW(x, y)`: `x` raised to the power of `y`. Is an integer if both numbers are.
+    Pow,
+    /// `READ_COUNT(-> x)`: number of times that a knot, stitch or label has been visited.
+    ReadCount,
+    /// `RANDOM(min, max)`: random integer between the minimum and maximum, inclusive.
+    ///
+    /// Without the `random` feature this is the minimum.
+    Random,
+    /// `SEED_RANDOM(seed)`: seed the random number generator of the story.
+    ///
+    /// Does nothing without the `random` feature.
+    SeedRandom,
+    /// `TURNS()`: number of choices which have been made in the story.
+    Turns,
+    /// `TURNS_SINCE(-> x)`: number of choices which have been made since a knot or stitch
+    /// was last visited, or -1 if it has not been visited.
+    TurnsSince,
+}
+
+impl BuiltinFunction {
+    /// Get the built-in function with the given name, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "CHOICE_COUNT" => Some(BuiltinFunction::ChoiceCount),
+            "FLOAT" => Some(BuiltinFunction::Float),
+            "FLOOR" => Some(BuiltinFunction::Floor),
+            "INT" => Some(BuiltinFunction::Int),
+            "LIST_ALL" => Some(BuiltinFunction::ListAll),
+            "LIST_COUNT" => Some(BuiltinFunction::ListCount),
+            "LIST_INVERT" => Some(BuiltinFunction::ListInvert),
+            "LIST_MAX" => Some(BuiltinFunction::ListMax),
+            "LIST_MIN" => Some(BuiltinFunction::ListMin),
+            "LIST_RANDOM" => Some(BuiltinFunction::ListRandom),
+            "LIST_RANGE" => Some(BuiltinFunction::ListRange),
+            "LIST_VALUE" => Some(BuiltinFunction::ListValue),
+            "POW" => Some(BuiltinFunction::Pow),
+            "RANDOM" => Some(BuiltinFunction::Random),
+            "READ_COUNT" => Some(BuiltinFunction::ReadCount),
+            "SEED_RANDOM" => Some(BuiltinFunction::SeedRandom),
+            "TURNS" => Some(BuiltinFunction::Turns),
+            "TURNS_SINCE" => Some(BuiltinFunction::TurnsSince),
+            _ => None,
+        }
+    }
+
+    /// Get the number of arguments that the function is called with.
+    pub fn num_parameters(&self) -> usize {
+        match self {
+            BuiltinFunction::ListRange => 3,
+            BuiltinFunction::Pow | BuiltinFunction::Random => 2,
+            BuiltinFunction::ChoiceCount | BuiltinFunction::Turns => 0,
+            _ => 1,
+        }
+    }
+
+    #[allow(unused_variables)] // `data` only used when the `random` feature is enabled
+    /// Call the function with evaluated arguments and return its value.
+    ///
+    /// Assumes that the function is called with the correct number of arguments,
+    /// which is checked when the story is validated. Functions which do not return
+    /// a value evaluate to an empty string.
+    ///
+    /// # Errors
+    /// *   [`InvalidFunctionArgument`][crate::error::InklingError::InvalidFunctionArgument]:
+    ///     if an argument has the wrong type.
+    pub fn call(
+        &self,
+        name: &str,
+        arguments: &[Variable],
+        data: &mut FollowData,
+    ) -> Result<Variable, InklingError> {
+        let value = match self {
+            BuiltinFunction::ChoiceCount => Variable::Int(data.choice_count as i32),
+            BuiltinFunction::Float => Variable::Float(get_float(name, &arguments[0])?),
+            BuiltinFunction::Floor => match &arguments[0] {
+                Variable::Float(value) => Variable::Float(value.floor()),
+                other => Variable::Int(get_int(name, other)?),
+            },
+            BuiltinFunction::Int => match &arguments[0] {
+                Variable::Float(value) => Variable::Int(*value as i32),
+                other => Variable::Int(get_int(name, other)?),
+            },
+            BuiltinFunction::ListAll => get_list(name, &arguments[0])?.all(&data.lists).into(),
+            BuiltinFunction::ListCount => get_list(name, &arguments[0])?.len().into(),
+            BuiltinFunction::ListInvert => {
+                get_list(name, &arguments[0])?.invert(&data.lists).into()
+            }
+            BuiltinFunction::ListMax => {
+                let list = get_list(name, &arguments[0])?;
+                get_single_item_list(list, list.max_item()).into()
+            }
+            BuiltinFunction::ListMin => {
+                let list = get_list(name, &arguments[0])?;
+                get_single_item_list(list, list.min_item()).into()
+            }
+            BuiltinFunction::ListRandom => {
+                let list = get_list(name, &arguments[0])?;
+
+                #[cfg(feature = "random")]
+                let item = list.items().choose(&mut data.rng.gen);
+
+                #[cfg(not(feature = "random"))]
+                let item = list.min_item();
+
+                get_single_item_list(list, item).into()
+            }
+            BuiltinFunction::ListRange => {
+                let list = get_list(name, &arguments[0])?;
+                let min = get_range_bound(name, &arguments[1], InkList::min_item)?;
+                let max = get_range_bound(name, &arguments[2], InkList::max_item)?;
+
+                list.range(min, max).into()
+            }
+            BuiltinFunction::ListValue => get_list(name, &arguments[0])?
+                .max_item()
+                .map(|item| item.value)
+                .unwrap_or(0)
+                .into(),
+            BuiltinFunction::Pow => match (&arguments[0], &arguments[1]) {
+                (Variable::Int(base), Variable::Int(exponent)) => {
+                    Variable::Int((*base as f32).powi(*exponent) as i32)
+                }
+                (base, exponent) => {
+                    Variable::Float(get_float(name, base)?.powf(get_float(name, exponent)?))
+                }
+            },
+            BuiltinFunction::Random => {
+                let min = get_int(name, &arguments[0])?;
+                let max = get_int(name, &arguments[1])?;
+
+                if max < min {
+                    return Err(InklingError::InvalidFunctionArgument {
+                        name: name.to_string(),
+                        value: arguments[1].clone(),
+                    });
+                }
+
+                #[cfg(feature = "random")]
+                let value = data.rng.gen.gen_range(min, max + 1);
+
+                #[cfg(not(feature = "random"))]
+                let value = min;
+
+                Variable::Int(value)
+            }
+            BuiltinFunction::ReadCount => {
+                let address = get_address(name, &arguments[0])?;
+                Variable::Int(get_num_visited(address, data)? as i32)
+            }
+            BuiltinFunction::SeedRandom => {
+                let seed = get_int(name, &arguments[0])?;
+
+                #[cfg(feature = "random")]
+                {
+                    data.rng = StoryRng::with_seed(seed as u64);
+                }
+
+                Variable::String(String::new())
+            }
+            BuiltinFunction::Turns => Variable::Int(data.turn_index as i32),
+            BuiltinFunction::TurnsSince => match get_address(name, &arguments[0])? {
+                Address::Validated(AddressKind::Label { .. }) => {
+                    return Err(get_argument_error(name, &arguments[0]));
+                }
+                address => Variable::Int(get_turns_since(address, data)?),
+            },
+        };
+
+        Ok(value)
+    }
+}
+
+/// Get the list value of an argument.
+fn get_list<'a>(name: &str, argument: &'a Variable) -> Result<&'a InkList, InklingError> {
+    match argument {
+        Variable::List(list) => Ok(list),
+        other => Err(get_argument_error(name, other)),
+    }
+}
+
+/// Get the address of a divert target argument.
+fn get_address<'a>(name: &str, argument: &'a Variable) -> Result<&'a Address, InklingError> {
+    match argument {
+        Variable::Divert(address) => Ok(address),
+        other => Err(get_argument_error(name, other)),
+    }
+}
+
+/// Get the integer value of an argument.
+fn get_int(name: &str, argument: &Variable) -> Result<i32, InklingError> {
+    match argument {
+        Variable::Int(value) => Ok(*value),
+        other => Err(get_argument_error(name, other)),
+    }
+}
+
+/// Get the value of a numeric argument as a floating point number.
+fn get_float(name: &str, argument: &Variable) -> Result<f32, InklingError> {
+    match argument {
+        Variable::Float(value) => Ok(*value),
+        Variable::Int(value) => Ok(*value as f32),
+        other => Err(get_argument_error(name, other)),
+    }
+}
+
+/// Get the error for calling a function with an argument of the wrong type.
+fn get_argument_error(name: &str, argument: &Variable) -> InklingError {
+    InklingError::InvalidFunctionArgument {
+        name: name.to_string(),
+        value: argument.clone(),
+    }
+}
+
+/// Get a list with a single item, or an empty list, which can contain the same items as a list.
+fn get_single_item_list(list: &InkList, item: Option<&ListItem>) -> InkList {
+    let mut single = InkList::with_origins(list.origins().to_vec());
+
+    if let Some(item) = item {
+        single.insert(item.clone());
+    }
+
+    single
+}
+
+/// Get the bound of a range from a number or the value of an item in a list.
+fn get_range_bound(
+    name: &str,
+    argument: &Variable,
+    get_item: fn(&InkList) -> Option<&ListItem>,
+) -> Result<i32, InklingError> {
+    match argument {
+        Variable::Int(value) => Some(*value),
+        Variable::List(list) => get_item(list).map(|item| item.value),
+        _ => None,
+    }
+    .ok_or_else(|| get_argument_error(name, argument))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        follow::FollowDataBuilder,
+        story::types::{ListDefinition, ListDefinitionSet},
+    };
+
+    use std::collections::HashMap;
+
+    fn mock_follow_data() -> FollowData {
+        let mut lists = ListDefinitionSet::new();
+
+        lists.insert(
+            "colors".to_string(),
+            ListDefinition {
+                items: vec![
+                    ("red".to_string(), 1),
+                    ("green".to_string(), 2),
+                    ("blue".to_string(), 3),
+                ],
+                meta_data: ().into(),
+            },
+        );
+
+        FollowDataBuilder::new().with_lists(lists).build()
+    }
+
+    fn get_list(items: &[&str], data: &FollowData) -> Variable {
+        let definition = data.lists.get("colors").unwrap();
+
+        let mut list = InkList::with_origins(vec!["colors".to_string()]);
+
+        for item in items {
+            list.insert(definition.get_item("colors", item).unwrap());
+        }
+
+        Variable::List(list)
+    }
+
+    fn call(name: &str, arguments: &[Variable], data: &mut FollowData) -> Variable {
+        BuiltinFunction::from_name(name)
+            .unwrap()
+            .call(name, arguments, data)
+            .unwrap()
+    }
+
+    #[test]
+    fn built_in_functions_are_found_by_name() {
+        assert_eq!(
+            BuiltinFunction::from_name("LIST_COUNT"),
+            Some(BuiltinFunction::ListCount)
+        );
+        assert_eq!(BuiltinFunction::from_name("list_count"), None);
+        assert_eq!(BuiltinFunction::from_name("COUNT"), None);
+    }
+
+    #[test]
+    fn list_count_is_number_of_items() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red", "blue"], &data);
+
+        assert_eq!(call("LIST_COUNT", &[list], &mut data), Variable::Int(2));
+    }
+
+    #[test]
+    fn list_min_and_max_are_items_with_lowest_and_highest_values() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red", "green", "blue"], &data);
+
+        assert_eq!(
+            call("LIST_MIN", std::slice::from_ref(&list), &mut data),
+            get_list(&["red"], &data)
+        );
+        assert_eq!(
+            call("LIST_MAX", &[list], &mut data),
+            get_list(&["blue"], &data)
+        );
+    }
+
+    #[test]
+    fn list_all_and_invert_use_the_list_definitions() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["green"], &data);
+
+        assert_eq!(
+            call("LIST_ALL", std::slice::from_ref(&list), &mut data),
+            get_list(&["red", "green", "blue"], &data)
+        );
+        assert_eq!(
+            call("LIST_INVERT", &[list], &mut data),
+            get_list(&["red", "blue"], &data)
+        );
+    }
+
+    #[test]
+    fn list_range_bounds_can_be_numbers_or_items() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red", "green", "blue"], &data);
+        let green = get_list(&["green"], &data);
+
+        assert_eq!(
+            call(
+                "LIST_RANGE",
+                &[list.clone(), Variable::Int(2), Variable::Int(5)],
+                &mut data
+            ),
+            get_list(&["green", "blue"], &data)
+        );
+        assert_eq!(
+            call("LIST_RANGE", &[list, green.clone(), green], &mut data),
+            get_list(&["green"], &data)
+        );
+    }
+
+    #[test]
+    fn list_value_is_value_of_highest_item_or_zero_for_empty_lists() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red", "green"], &data);
+        let empty = get_list(&[], &data);
+
+        assert_eq!(call("LIST_VALUE", &[list], &mut data), Variable::Int(2));
+        assert_eq!(call("LIST_VALUE", &[empty], &mut data), Variable::Int(0));
+    }
+
+    #[test]
+    fn list_random_yields_an_item_from_the_list() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red", "blue"], &data);
+
+        match call("LIST_RANDOM", &[list], &mut data) {
+            Variable::List(item) => {
+                assert_eq!(item.len(), 1);
+                assert!(item.contains("red") || item.contains("blue"));
+            }
+            other => panic!("expected a list but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn calling_list_function_with_other_variable_yields_error() {
+        let mut data = mock_follow_data();
+
+        match BuiltinFunction::ListCount.call("LIST_COUNT", &[Variable::Int(1)], &mut data) {
+            Err(InklingError::InvalidFunctionArgument { .. }) => (),
+            other => panic!(
+                "expected `InklingError::InvalidFunctionArgument` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn int_truncates_floats_and_float_converts_integers() {
+        let mut data = mock_follow_data();
+
+        assert_eq!(
+            call("INT", &[Variable::Float(3.7)], &mut data),
+            Variable::Int(3)
+        );
+        assert_eq!(
+            call("INT", &[Variable::Float(-3.7)], &mut data),
+            Variable::Int(-3)
+        );
+        assert_eq!(
+            call("INT", &[Variable::Int(3)], &mut data),
+            Variable::Int(3)
+        );
+        assert_eq!(
+            call("FLOAT", &[Variable::Int(3)], &mut data),
+            Variable::Float(3.0)
+        );
+    }
+
+    #[test]
+    fn floor_rounds_down_and_keeps_the_type_of_the_number() {
+        let mut data = mock_follow_data();
+
+        assert_eq!(
+            call("FLOOR", &[Variable::Float(-1.5)], &mut data),
+            Variable::Float(-2.0)
+        );
+        assert_eq!(
+            call("FLOOR", &[Variable::Float(1.5)], &mut data),
+            Variable::Float(1.0)
+        );
+        assert_eq!(
+            call("FLOOR", &[Variable::Int(2)], &mut data),
+            Variable::Int(2)
+        );
+    }
+
+    #[test]
+    fn pow_is_integer_for_integers_and_float_otherwise() {
+        let mut data = mock_follow_data();
+
+        assert_eq!(
+            call("POW", &[Variable::Int(3), Variable::Int(2)], &mut data),
+            Variable::Int(9)
+        );
+        assert_eq!(
+            call("POW", &[Variable::Float(4.0), Variable::Int(2)], &mut data),
+            Variable::Float(16.0)
+        );
+        assert_eq!(
+            call("POW", &[Variable::Int(4), Variable::Float(0.5)], &mut data),
+            Variable::Float(2.0)
+        );
+    }
+
+    #[test]
+    fn random_yields_integer_between_minimum_and_maximum() {
+        let mut data = mock_follow_data();
+
+        for _ in 0..20 {
+            match call("RANDOM", &[Variable::Int(1), Variable::Int(3)], &mut data) {
+                Variable::Int(value) => assert!((1..=3).contains(&value)),
+                other => panic!("expected an integer but got {:?}", other),
+            }
+        }
+
+        assert_eq!(
+            call("RANDOM", &[Variable::Int(2), Variable::Int(2)], &mut data),
+            Variable::Int(2)
+        );
+    }
+
+    #[test]
+    fn random_with_maximum_below_minimum_yields_error() {
+        let mut data = mock_follow_data();
+
+        assert!(BuiltinFunction::Random
+            .call("RANDOM", &[Variable::Int(3), Variable::Int(1)], &mut data)
+            .is_err());
+    }
+
+    #[test]
+    fn numeric_functions_with_other_variables_yield_error() {
+        let mut data = mock_follow_data();
+        let list = get_list(&["red"], &data);
+
+        for (name, arguments) in &[
+            ("INT", vec![Variable::from("3")]),
+            ("FLOAT", vec![Variable::Bool(true)]),
+            ("RANDOM", vec![Variable::Float(1.0), Variable::Int(3)]),
+            ("POW", vec![list, Variable::Int(2)]),
+            ("SEED_RANDOM", vec![Variable::Float(1.0)]),
+        ] {
+            match BuiltinFunction::from_name(name)
+                .unwrap()
+                .call(name, arguments, &mut data)
+            {
+                Err(InklingError::InvalidFunctionArgument { .. }) => (),
+                other => panic!(
+                    "expected `InklingError::InvalidFunctionArgument` but got {:?}",
+                    other
+                ),
+            }
+        }
+    }
+
+    #[cfg(feature = "random")]
+    #[test]
+    fn seeding_random_makes_random_numbers_repeat() {
+        let mut data = mock_follow_data();
+
+        let mut roll = |data: &mut FollowData| {
+            (0..10)
+                .map(|_| call("RANDOM", &[Variable::Int(1), Variable::Int(100)], data))
+                .collect::<Vec<_>>()
+        };
+
+        call("SEED_RANDOM", &[Variable::Int(5)], &mut data);
+        let first = roll(&mut data);
+
+        call("SEED_RANDOM", &[Variable::Int(5)], &mut data);
+        let second = roll(&mut data);
+
+        assert_eq!(first, second);
+    }
+
+    fn mock_follow_data_with_turns() -> FollowData {
+        let mut visits = HashMap::new();
+        visits.insert("tripoli".to_string(), 2);
+        visits.insert("cairo".to_string(), 0);
+
+        let mut knot_visit_counts = HashMap::new();
+        knot_visit_counts.insert("africa".to_string(), visits);
+
+        let mut turns = HashMap::new();
+        turns.insert("tripoli".to_string(), 3);
+
+        let mut knot_visit_turns = HashMap::new();
+        knot_visit_turns.insert("africa".to_string(), turns);
+
+        FollowDataBuilder::new()
+            .with_knots(knot_visit_counts)
+            .with_turns(5, knot_visit_turns)
+            .build()
+    }
+
+    fn get_divert(knot: &str, stitch: &str) -> Variable {
+        Variable::Divert(Address::from_parts_unchecked(knot, Some(stitch)))
+    }
+
+    #[test]
+    fn turns_is_the_turn_index() {
+        let mut data = mock_follow_data_with_turns();
+
+        assert_eq!(call("TURNS", &[], &mut data), Variable::Int(5));
+    }
+
+    #[test]
+    fn turns_since_is_turns_since_last_visit_or_negative_one_if_not_visited() {
+        let mut data = mock_follow_data_with_turns();
+
+        assert_eq!(
+            call("TURNS_SINCE", &[get_divert("africa", "tripoli")], &mut data),
+            Variable::Int(2)
+        );
+        assert_eq!(
+            call("TURNS_SINCE", &[get_divert("africa", "cairo")], &mut data),
+            Variable::Int(-1)
+        );
+    }
+
+    #[test]
+    fn read_count_is_number_of_visits() {
+        let mut data = mock_follow_data_with_turns();
+
+        assert_eq!(
+            call("READ_COUNT", &[get_divert("africa", "tripoli")], &mut data),
+            Variable::Int(2)
+        );
+    }
+
+    #[test]
+    fn turn_functions_with_arguments_which_are_not_diverts_yield_error() {
+        let mut data = mock_follow_data_with_turns();
+
+        for name in &["TURNS_SINCE", "READ_COUNT"] {
+            match BuiltinFunction::from_name(name).unwrap().call(
+                name,
+                &[Variable::from("tripoli")],
+                &mut data,
+            ) {
+                Err(InklingError::InvalidFunctionArgument { .. }) => (),
+                other => panic!(
+                    "expected `InklingError::InvalidFunctionArgument` but got {:?}",
+                    other
+                ),
+            }
+        }
+    }
+}
@@ -3,40 +3,25 @@
 use crate::{
     error::{parse::validate::ValidationError, utils::MetaData},
     knot::Address,
-    line::{Condition, InternalLine},
+    line::{Condition, InternalLine, LineChunk},
     log::Logger,
     story::validate::{ValidateContent, ValidationData},
 };
 
-use std::sync::{Arc, Mutex};
-
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
-use std::ops::DerefMut;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// A single choice in a (usually) set of choices presented to the user.
 pub struct InternalChoice {
     /// Text presented to the user to represent the choice.
     ///
-    /// This is a reference counted object because of how we process the sets of encountered
-    /// choices. When encountered inside the node during a follow, all choices in a set are
-    /// collected and sent further up in the stack. They are then processed before displaying
-    /// to the user.
-    ///
-    /// This is different from how regular lines are processed to their final form, which
-    /// is done during the follow as the lines are encountered by the `Process` trait.
-    ///
-    /// In theory, we could rewrite the choice code to process them at collection, but
-    /// that would mess a bit with how the nodes are processing the data: changing their
-    /// responsibility from just finding the content, to checking conditions for which
-    /// choices will be available and so on.
-    ///
-    /// Instead, we use a pointer with internal mutability and send that further up the stack.
-    /// This means that any processing of choices further up will affect the data in the node,
-    /// meaning that for example alternative sequences will be updated if the choice was seen.
-    pub selection_text: Arc<Mutex<InternalLine>>,
+    /// Choices are collected when they are encountered inside the node during a follow
+    /// and processed before displaying them to the user. The state of alternatives
+    /// in this text is kept in the follow data, so the collected copies of the choice
+    /// advance the same sequences as the choice in the node.
+    pub selection_text: Box<InternalLine>,
     /// Text that will be added to the output line buffer if the choice is selected.
     ///
     /// This will be added to the buffer before the rest of the lines from the selected
@@ -55,20 +40,6 @@ pub struct InternalChoice {
     pub meta_data: MetaData,
 }
 
-impl PartialEq for InternalChoice {
-    fn eq(&self, rhs: &InternalChoice) -> bool {
-        let left_line = (*self.selection_text.lock().unwrap()).clone();
-        let right_line = (*rhs.selection_text.lock().unwrap()).clone();
-
-        left_line == right_line
-            && self.display_text == rhs.display_text
-            && self.condition == rhs.condition
-            && self.is_sticky == rhs.is_sticky
-            && self.is_fallback == rhs.is_fallback
-            && self.meta_data == rhs.meta_data
-    }
-}
-
 impl ValidateContent for InternalChoice {
     fn validate(
         &mut self,
@@ -80,13 +51,8 @@ impl ValidateContent for InternalChoice {
     ) {
         let num_address_errors = error.invalid_address_errors.len();
 
-        self.selection_text.lock().unwrap().deref_mut().validate(
-            error,
-            log,
-            current_location,
-            &self.meta_data,
-            data,
-        );
+        self.selection_text
+            .validate(error, log, current_location, &self.meta_data, data);
 
         // If address errors were found in the selection part of this line they may be repeated
         // in the display part. Since they are parsed from the same line we raise an error for
@@ -120,7 +86,7 @@ pub struct InternalChoiceBuilder {
     condition: Option<Condition>,
     is_fallback: bool,
     is_sticky: bool,
-    tags: Option<Vec<String>>,
+    tags: Option<Vec<LineChunk>>,
 }
 
 impl InternalChoiceBuilder {
@@ -151,7 +117,7 @@ impl InternalChoiceBuilder {
         let meta_data = self.display_text.meta_data.clone();
 
         InternalChoice {
-            selection_text: Arc::new(Mutex::new(self.selection_text)),
+            selection_text: Box::new(self.selection_text),
             display_text: self.display_text,
             condition: self.condition,
             is_sticky: self.is_sticky,
@@ -230,7 +196,14 @@ impl InternalChoiceBuilder {
     #[cfg(test)]
     /// Set tags to the choice.
     pub fn with_tags(mut self, tags: &[String]) -> Self {
-        self.tags.replace(tags.to_vec());
+        use crate::line::LineChunkBuilder;
+
+        let tags = tags
+            .iter()
+            .map(|tag| LineChunkBuilder::from_string(tag).build())
+            .collect();
+
+        self.tags.replace(tags);
         self
     }
 }
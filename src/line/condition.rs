@@ -36,7 +36,7 @@ use crate::{
         utils::MetaData,
     },
     knot::Address,
-    line::{Expression, Variable},
+    line::Expression,
     log::Logger,
     process::check_condition,
     story::validate::{ValidateContent, ValidationData},
@@ -103,8 +103,11 @@ pub enum StoryCondition {
     /// method for [`Variable`][crate::line::Variable]), then compare using that.
     ///
     /// Equal-to comparisons (`==`) can be made for all variable types. Less-than (`<`)
-    /// and greater-than (`>`) comparisons are only allowed for `Int` and `Float` variants.
-    /// An error is raised if another variant is used like that.
+    /// and greater-than (`>`) comparisons are only allowed for `Int`, `Float` and `List`
+    /// variants. An error is raised if another variant is used like that.
+    ///
+    /// Less-than-or-equal (`<=`) and greater-than-or-equal (`>=`) comparisons are parsed
+    /// as `IsTrueLike` expressions which evaluate the comparison.
     Comparison {
         /// Left hand side variable.
         lhs_variable: Expression,
@@ -117,6 +120,17 @@ pub enum StoryCondition {
         #[cfg_attr(feature = "serde_support", serde(with = "OrderingDerive"))]
         ordering: Ordering,
     },
+    /// Asserts that the left hand side list contains all items of the right hand side list,
+    /// from an `x has y` or `x ? y` statement.
+    ///
+    /// Empty lists never contain or are contained in other lists. The negated statements
+    /// `x hasnt y` and `x !? y` are parsed into this condition with a negation.
+    Contains {
+        /// Left hand side variable.
+        lhs_variable: Expression,
+        /// Right hand side variable.
+        rhs_variable: Expression,
+    },
     /// Assert that the variable value is "true".
     ///
     /// This is evaluated differently for different variable types.
@@ -124,13 +138,15 @@ pub enum StoryCondition {
     /// *   Boolean variables evaluate directly.
     /// *   Number variables (integers and floats) are `true` if they are non-zero.
     /// *   String variables are `true` if they have non-zero length.
+    /// *   List variables are `true` if they contain any item.
     ///
-    /// Variable `Address` variants will evaluate their value (see the `as_value` method
-    /// for [`Variable`][crate::line::Variable]), then as above.
+    /// The expression is evaluated to a single variable first, which for variable `Address`
+    /// variants is their value (see the `as_value` method for
+    /// [`Variable`][crate::line::Variable]), then as above.
     ///
     /// Variable `Divert` variants will never evaluate to `true` or `false`, but raise
     /// and error. They are not supposed to be used like this.
-    IsTrueLike { variable: Variable },
+    IsTrueLike { expression: Expression },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -142,30 +158,81 @@ pub enum AndOr {
 }
 
 impl Condition {
+    /// Whether or not the value of any expression in the condition is unknown until
+    /// the story is followed.
+    pub fn has_unknown_value(&self, data: &ValidationData) -> bool {
+        std::iter::once(&self.root)
+            .chain(self.items.iter().map(|item| match item {
+                AndOr::And(item) | AndOr::Or(item) => item,
+            }))
+            .any(|item| match &item.kind {
+                ConditionKind::True | ConditionKind::False => false,
+                ConditionKind::Nested(condition) => condition.has_unknown_value(data),
+                ConditionKind::Single(StoryCondition::Comparison {
+                    lhs_variable,
+                    rhs_variable,
+                    ..
+                })
+                | ConditionKind::Single(StoryCondition::Contains {
+                    lhs_variable,
+                    rhs_variable,
+                }) => lhs_variable.has_unknown_value(data) || rhs_variable.has_unknown_value(data),
+                ConditionKind::Single(StoryCondition::IsTrueLike { expression }) => {
+                    expression.has_unknown_value(data)
+                }
+            })
+    }
+
+    /// Whether or not evaluating the condition calls any functions.
+    pub fn has_function_calls(&self) -> bool {
+        std::iter::once(&self.root)
+            .chain(self.items.iter().map(|item| match item {
+                AndOr::And(item) | AndOr::Or(item) => item,
+            }))
+            .any(|item| match &item.kind {
+                ConditionKind::True | ConditionKind::False => false,
+                ConditionKind::Nested(condition) => condition.has_function_calls(),
+                ConditionKind::Single(StoryCondition::Comparison {
+                    lhs_variable,
+                    rhs_variable,
+                    ..
+                })
+                | ConditionKind::Single(StoryCondition::Contains {
+                    lhs_variable,
+                    rhs_variable,
+                }) => lhs_variable.has_function_calls() || rhs_variable.has_function_calls(),
+                ConditionKind::Single(StoryCondition::IsTrueLike { expression }) => {
+                    expression.has_function_calls()
+                }
+            })
+    }
+
     /// Evaluate the condition with the given evaluator closure.
     ///
     /// This closure will be called on every item in the `Condition` as all parts
     /// are walked through.
-    pub fn evaluate<F, E>(&self, evaluator: &F) -> Result<bool, E>
+    pub fn evaluate<F, E>(&self, evaluator: &mut F) -> Result<bool, E>
     where
-        F: Fn(&StoryCondition) -> Result<bool, E>,
+        F: FnMut(&StoryCondition) -> Result<bool, E>,
         E: Error,
     {
-        self.items
-            .iter()
-            .fold(inner_eval(&self.root, evaluator), |acc, next_condition| {
-                acc.and_then(|current| match next_condition {
-                    AndOr::And(item) => inner_eval(item, evaluator).map(|next| current && next),
-                    AndOr::Or(item) => inner_eval(item, evaluator).map(|next| current || next),
-                })
-            })
+        let mut result = inner_eval(&self.root, evaluator)?;
+
+        for next_condition in &self.items {
+            result = match next_condition {
+                AndOr::And(item) => inner_eval(item, evaluator).map(|next| result && next),
+                AndOr::Or(item) => inner_eval(item, evaluator).map(|next| result || next),
+            }?;
+        }
+
+        Ok(result)
     }
 }
 
 /// Match against and evaluate the items.
-fn inner_eval<F, E>(item: &ConditionItem, evaluator: &F) -> Result<bool, E>
+fn inner_eval<F, E>(item: &ConditionItem, evaluator: &mut F) -> Result<bool, E>
 where
-    F: Fn(&StoryCondition) -> Result<bool, E>,
+    F: FnMut(&StoryCondition) -> Result<bool, E>,
     E: Error,
 {
     let mut result = match &item.kind {
@@ -254,8 +321,8 @@ impl ValidateContent for Condition {
             }
         });
 
-        if num_errors == error.num_errors() {
-            if let Err(err) = check_condition(self, &data.follow_data) {
+        if num_errors == error.num_errors() && !self.has_unknown_value(data) {
+            if let Err(err) = check_condition(self, &mut data.follow_data.clone()) {
                 error.variable_errors.push(InvalidVariableExpression {
                     expression_kind: ExpressionKind::Condition,
                     kind: err.into(),
@@ -301,12 +368,16 @@ impl ValidateContent for StoryCondition {
                 ref mut lhs_variable,
                 ref mut rhs_variable,
                 ..
+            }
+            | StoryCondition::Contains {
+                ref mut lhs_variable,
+                ref mut rhs_variable,
             } => {
                 lhs_variable.validate(error, log, current_location, meta_data, data);
                 rhs_variable.validate(error, log, current_location, meta_data, data);
             }
-            StoryCondition::IsTrueLike { variable } => {
-                variable.validate(error, log, current_location, meta_data, data)
+            StoryCondition::IsTrueLike { expression } => {
+                expression.validate(error, log, current_location, meta_data, data)
             }
         }
     }
@@ -429,37 +500,37 @@ mod tests {
 
     #[test]
     fn condition_links_from_left_to_right() {
-        let f = |kind: &StoryCondition| match kind {
+        let mut f = |kind: &StoryCondition| match kind {
             _ => Err(MockError),
         };
 
         assert!(ConditionBuilder::from_kind(&True.into(), false)
             .build()
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
 
         assert!(!ConditionBuilder::from_kind(&False.into(), false)
             .build()
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
 
         assert!(ConditionBuilder::from_kind(&True.into(), false)
             .build()
             .with_and(True.into())
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
 
         assert!(!ConditionBuilder::from_kind(&True.into(), false)
             .build()
             .with_and(False.into())
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
 
         assert!(ConditionBuilder::from_kind(&False.into(), false)
             .build()
             .with_and(False.into())
             .with_or(True)
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
 
         assert!(!ConditionBuilder::from_kind(&False.into(), false)
@@ -467,19 +538,19 @@ mod tests {
             .with_and(False)
             .with_or(True)
             .with_and(False)
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
     }
 
     #[test]
     fn conditions_can_be_negated() {
-        let f = |kind: &StoryCondition| match kind {
+        let mut f = |kind: &StoryCondition| match kind {
             _ => Err(MockError),
         };
 
         assert!(ConditionBuilder::from_kind(&False.into(), true)
             .build()
-            .evaluate(&f)
+            .evaluate(&mut f)
             .unwrap());
     }
 }
@@ -7,12 +7,14 @@ use crate::{
         InklingError,
     },
     follow::FollowData,
-    knot::Address,
-    line::Variable,
+    knot::{Address, AddressKind},
+    line::{FunctionCall, Variable},
     log::Logger,
     story::validate::{ValidateContent, ValidationData},
 };
 
+use std::collections::HashMap;
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -39,56 +41,265 @@ impl Expression {
         self.tail
             .push((Operator::Subtract, Operand::Variable(variable)));
     }
+
+    /// Whether or not the value of the expression is unknown until the story is followed.
+    ///
+    /// This is the case for expressions which call functions or use temporary variables
+    /// that were assigned values from function calls. These expressions cannot be evaluated
+    /// when validating the story.
+    pub fn has_unknown_value(&self, data: &ValidationData) -> bool {
+        std::iter::once(&self.head)
+            .chain(self.tail.iter().map(|(_, operand)| operand))
+            .any(|operand| operand.has_unknown_value(data))
+    }
+
+    /// Whether or not the expression refers to global variables which are not constant.
+    ///
+    /// These expressions can be evaluated when validating the story, but only with the
+    /// initial values of the variables, which may change as the story is followed.
+    pub fn has_mutable_variables(&self, data: &ValidationData) -> bool {
+        std::iter::once(&self.head)
+            .chain(self.tail.iter().map(|(_, operand)| operand))
+            .any(|operand| operand.has_mutable_variables(data))
+    }
+
+    /// Whether or not evaluating the expression calls any functions.
+    pub fn has_function_calls(&self) -> bool {
+        std::iter::once(&self.head)
+            .chain(self.tail.iter().map(|(_, operand)| operand))
+            .any(|operand| operand.has_function_calls())
+    }
+
+    /// Get the names of all variables which the expression refers to by unvalidated addresses.
+    pub fn get_raw_addresses(&self) -> Vec<String> {
+        std::iter::once(&self.head)
+            .chain(self.tail.iter().map(|(_, operand)| operand))
+            .flat_map(|operand| operand.get_raw_addresses())
+            .collect()
+    }
+
+    /// Replace variables referred to by unvalidated addresses with the given values.
+    ///
+    /// Variables which are not in the set are left as they are.
+    pub fn replace_raw_addresses(&mut self, values: &HashMap<String, Variable>) {
+        std::iter::once(&mut self.head)
+            .chain(self.tail.iter_mut().map(|(_, operand)| operand))
+            .for_each(|operand| operand.replace_raw_addresses(values));
+    }
+}
+
+impl Operand {
+    /// Get the names of all variables which the operand refers to by unvalidated addresses.
+    fn get_raw_addresses(&self) -> Vec<String> {
+        match self {
+            Operand::FunctionCall(function_call) => function_call
+                .arguments
+                .iter()
+                .flat_map(|argument| argument.get_raw_addresses())
+                .collect(),
+            Operand::Nested(expression) => expression.get_raw_addresses(),
+            Operand::Not(operand) => operand.get_raw_addresses(),
+            Operand::Variable(Variable::Address(Address::Raw(name))) => vec![name.clone()],
+            Operand::Variable(..) => Vec::new(),
+        }
+    }
+
+    /// Replace variables referred to by unvalidated addresses with the given values.
+    fn replace_raw_addresses(&mut self, values: &HashMap<String, Variable>) {
+        match self {
+            Operand::FunctionCall(function_call) => function_call
+                .arguments
+                .iter_mut()
+                .for_each(|argument| argument.replace_raw_addresses(values)),
+            Operand::Nested(expression) => expression.replace_raw_addresses(values),
+            Operand::Not(operand) => operand.replace_raw_addresses(values),
+            Operand::Variable(variable) => {
+                if let Variable::Address(Address::Raw(name)) = variable {
+                    if let Some(value) = values.get(name) {
+                        *variable = value.clone();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether or not evaluating the operand calls any functions.
+    fn has_function_calls(&self) -> bool {
+        match self {
+            Operand::FunctionCall(..) => true,
+            Operand::Nested(expression) => expression.has_function_calls(),
+            Operand::Not(operand) => operand.has_function_calls(),
+            Operand::Variable(..) => false,
+        }
+    }
+
+    /// Whether or not the value of the operand is unknown until the story is followed.
+    fn has_unknown_value(&self, data: &ValidationData) -> bool {
+        match self {
+            Operand::FunctionCall(..) => true,
+            Operand::Nested(expression) => expression.has_unknown_value(data),
+            Operand::Not(operand) => operand.has_unknown_value(data),
+            Operand::Variable(Variable::Address(Address::Validated(
+                AddressKind::TemporaryVariable { name },
+            ))) => data.unknown_temporary_variables.contains(name),
+            Operand::Variable(..) => false,
+        }
+    }
+
+    /// Whether or not the operand refers to global variables which are not constant.
+    fn has_mutable_variables(&self, data: &ValidationData) -> bool {
+        match self {
+            Operand::FunctionCall(function_call) => function_call
+                .arguments
+                .iter()
+                .any(|argument| argument.has_mutable_variables(data)),
+            Operand::Nested(expression) => expression.has_mutable_variables(data),
+            Operand::Not(operand) => operand.has_mutable_variables(data),
+            Operand::Variable(Variable::Address(Address::Validated(
+                AddressKind::GlobalVariable { name },
+            ))) => data
+                .follow_data
+                .variables
+                .get(name)
+                .map(|info| !info.is_const)
+                .unwrap_or(true),
+            Operand::Variable(..) => false,
+        }
+    }
+}
+
+impl From<Variable> for Expression {
+    fn from(variable: Variable) -> Self {
+        Expression {
+            head: Operand::Variable(variable),
+            tail: Vec::new(),
+        }
+    }
+}
+
+impl From<FunctionCall> for Expression {
+    fn from(function_call: FunctionCall) -> Self {
+        Expression {
+            head: Operand::FunctionCall(function_call),
+            tail: Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Operand of an operation.
 pub enum Operand {
+    /// Call to a function, which evaluates to its returned value.
+    FunctionCall(FunctionCall),
     /// Nested inner expression from a parenthesis.
     Nested(Box<Expression>),
+    /// Logical negation of an operand: `not a` or `!a`.
+    Not(Box<Operand>),
     /// Variable with a value.
     Variable(Variable),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
-/// Mathematical operator applied to a term.
+/// Operator applied to a term.
 ///
 /// In strings these operators are assigned to values on the right of them.
+///
+/// Comparisons, containment checks and logical operators evaluate to `Bool` values.
+/// They have lower precedence than the mathematical operators and are grouped into
+/// nested expressions when parsed, so a single expression only ever links terms
+/// of the same precedence.
 pub enum Operator {
     Add,
     Subtract,
     Multiply,
     Divide,
     Remainder,
+    /// Intersection of two lists: `a ^ b`.
+    Intersect,
+    /// Equal to: `a == b`.
+    Equal,
+    /// Not equal to: `a != b`.
+    NotEqual,
+    /// Less than: `a < b`.
+    Less,
+    /// Less than or equal to: `a <= b`.
+    ///
+    /// A list is less than or equal to another if both its lowest and highest items
+    /// are less than or equal to those of the other list.
+    LessOrEqual,
+    /// Greater than: `a > b`.
+    Greater,
+    /// Greater than or equal to: `a >= b`.
+    ///
+    /// A list is greater than or equal to another if both its lowest and highest items
+    /// are greater than or equal to those of the other list.
+    GreaterOrEqual,
+    /// List or string containment: `a ? b` or `a has b`.
+    Contains,
+    /// Negated list or string containment: `a !? b` or `a hasnt b`.
+    NotContains,
+    /// Logical and: `a and b` or `a && b`.
+    And,
+    /// Logical or: `a or b` or `a || b`.
+    Or,
 }
 
 /// Evaluate an expression from start to finish, producing a single `Variable` value.
 pub fn evaluate_expression(
     expression: &Expression,
-    data: &FollowData,
+    data: &mut FollowData,
 ) -> Result<Variable, InklingError> {
-    expression
-        .tail
-        .iter()
-        .map(|(operation, operand)| (operation, get_value(operand, data)))
-        .fold(
-            get_value(&expression.head, data),
-            |acc, (operation, operand)| {
-                let lhs_variable = acc?;
-                let rhs_variable = operand?;
-
-                match operation {
-                    Operator::Add => lhs_variable.add(&rhs_variable),
-                    Operator::Subtract => lhs_variable.subtract(&rhs_variable),
-                    Operator::Multiply => lhs_variable.multiply(&rhs_variable),
-                    Operator::Divide => lhs_variable.divide(&rhs_variable),
-                    Operator::Remainder => lhs_variable.remainder(&rhs_variable),
-                }
-                .map_err(|err| err.into())
-            },
-        )
+    let mut value = get_value(&expression.head, data)?;
+
+    for (operation, operand) in &expression.tail {
+        let rhs_variable = get_value(operand, data)?;
+
+        value = match (operation, &value, &rhs_variable) {
+            // Adding a number to a list moves its items to the items with offset values
+            (Operator::Add, Variable::List(list), Variable::Int(offset)) => {
+                Ok(Variable::List(list.shift(*offset, &data.lists)))
+            }
+            (Operator::Subtract, Variable::List(list), Variable::Int(offset)) => {
+                Ok(Variable::List(list.shift(-offset, &data.lists)))
+            }
+            (Operator::LessOrEqual, Variable::List(list), Variable::List(other)) => {
+                Ok(Variable::Bool(list.less_or_equal(other)))
+            }
+            (Operator::GreaterOrEqual, Variable::List(list), Variable::List(other)) => {
+                Ok(Variable::Bool(list.greater_or_equal(other)))
+            }
+            (Operator::Add, ..) => value.add(&rhs_variable),
+            (Operator::Subtract, ..) => value.subtract(&rhs_variable),
+            (Operator::Multiply, ..) => value.multiply(&rhs_variable),
+            (Operator::Divide, ..) => value.divide(&rhs_variable),
+            (Operator::Remainder, ..) => value.remainder(&rhs_variable),
+            (Operator::Intersect, ..) => value.intersect(&rhs_variable),
+            (Operator::Equal, ..) => value.equal_to(&rhs_variable).map(Variable::Bool),
+            (Operator::NotEqual, ..) => value.equal_to(&rhs_variable).map(|eq| Variable::Bool(!eq)),
+            (Operator::Less, ..) => value.less_than(&rhs_variable).map(Variable::Bool),
+            (Operator::LessOrEqual, ..) => value
+                .greater_than(&rhs_variable)
+                .map(|gt| Variable::Bool(!gt)),
+            (Operator::Greater, ..) => value.greater_than(&rhs_variable).map(Variable::Bool),
+            (Operator::GreaterOrEqual, ..) => {
+                value.less_than(&rhs_variable).map(|lt| Variable::Bool(!lt))
+            }
+            (Operator::Contains, ..) => value.contains(&rhs_variable).map(Variable::Bool),
+            (Operator::NotContains, ..) => value
+                .contains(&rhs_variable)
+                .map(|has| Variable::Bool(!has)),
+            (Operator::And, ..) => Ok(Variable::Bool(
+                value.is_true_like()? && rhs_variable.is_true_like()?,
+            )),
+            (Operator::Or, ..) => Ok(Variable::Bool(
+                value.is_true_like()? || rhs_variable.is_true_like()?,
+            )),
+        }?;
+    }
+
+    Ok(value)
 }
 
 /// Nest inner operations based on order of precedence in operations.
@@ -116,17 +327,19 @@ pub fn apply_order_of_operations(expression: &Expression) -> Expression {
 }
 
 /// Evaluate a variable or inner expression to produce a single variable.
-fn get_value(operand: &Operand, data: &FollowData) -> Result<Variable, InklingError> {
+fn get_value(operand: &Operand, data: &mut FollowData) -> Result<Variable, InklingError> {
     match operand {
+        Operand::FunctionCall(function_call) => function_call.evaluate(data),
         Operand::Nested(expression) => evaluate_expression(expression, data),
+        Operand::Not(operand) => Ok(Variable::Bool(!get_value(operand, data)?.is_true_like()?)),
         Operand::Variable(variable) => variable.as_value(data),
     }
 }
 
-/// Split the expression items into groups, divided by addition and subtraction.
+/// Split the expression items into groups, divided by addition, subtraction and intersection.
 ///
-/// This groups multiplied, divided with and remainder or items, while added and subtracted
-/// items remain alone.
+/// This groups multiplied, divided with and remainder or items, while added, subtracted
+/// and intersected items remain alone.
 fn split_expression_into_groups_of_same_precedence(
     expression: &Expression,
 ) -> Vec<Vec<(Operator, Operand)>> {
@@ -138,7 +351,7 @@ fn split_expression_into_groups_of_same_precedence(
 
     for (operation, operand) in items {
         match operation {
-            Operator::Add | Operator::Subtract if !group.is_empty() => {
+            Operator::Add | Operator::Subtract | Operator::Intersect if !group.is_empty() => {
                 groups.push(group);
                 group = Vec::new();
             }
@@ -195,8 +408,8 @@ impl ValidateContent for Expression {
             operand.validate(error, log, current_location, meta_data, data)
         });
 
-        if num_errors == error.num_errors() {
-            if let Err(err) = evaluate_expression(self, &data.follow_data) {
+        if num_errors == error.num_errors() && !self.has_unknown_value(data) {
+            if let Err(err) = evaluate_expression(self, &mut data.follow_data.clone()) {
                 error.variable_errors.push(InvalidVariableExpression {
                     expression_kind: ExpressionKind::Expression,
                     kind: err.into(),
@@ -217,9 +430,15 @@ impl ValidateContent for Operand {
         data: &ValidationData,
     ) {
         match self {
+            Operand::FunctionCall(ref mut function_call) => {
+                function_call.validate(error, log, current_location, meta_data, data)
+            }
             Operand::Nested(ref mut expression) => {
                 expression.validate(error, log, current_location, meta_data, data)
             }
+            Operand::Not(ref mut operand) => {
+                operand.validate(error, log, current_location, meta_data, data)
+            }
             Operand::Variable(ref mut variable) => {
                 variable.validate(error, log, current_location, meta_data, data)
             }
@@ -231,18 +450,10 @@ impl ValidateContent for Operand {
 mod tests {
     use super::*;
 
-    use crate::{follow::FollowDataBuilder, knot::Address, story::types::VariableInfo};
-
-    use std::collections::HashMap;
-
-    impl From<Variable> for Expression {
-        fn from(variable: Variable) -> Self {
-            Expression {
-                head: Operand::Variable(variable),
-                tail: Vec::new(),
-            }
-        }
-    }
+    use crate::{
+        follow::FollowDataBuilder, knot::Address, line::parse_expression,
+        story::types::VariableInfo,
+    };
 
     fn get_simple_expression(head: Variable, tail: &[(Operator, Variable)]) -> Expression {
         let tail = tail
@@ -300,31 +511,31 @@ mod tests {
 
     #[test]
     fn expression_with_just_head_evaluates_to_head() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
         let expression = get_simple_expression(Variable::Int(5), &[]);
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(5)
         );
     }
 
     #[test]
     fn adding_two_variables_creates_summed_variable() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression =
             get_simple_expression(Variable::Int(1), &[(Operator::Add, Variable::Int(2))]);
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(3)
         );
     }
 
     #[test]
     fn all_operations_work_in_order() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         // 1 + 2 - (-2) * (-3) / 5 = -3
         let expression = get_simple_expression(
@@ -338,26 +549,43 @@ mod tests {
         );
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Float(-3.0)
         );
     }
 
     #[test]
     fn get_value_evaluates_variables_by_following_addresses_if_necessary() {
-        let data = mock_follow_data(&[], &[("counter", 1.into())]);
+        let mut data = mock_follow_data(&[], &[("counter", 1.into())]);
 
         let variable = Variable::Address(Address::variable_unchecked("counter"));
 
         assert_eq!(
-            get_value(&Operand::Variable(variable), &data).unwrap(),
+            get_value(&Operand::Variable(variable), &mut data).unwrap(),
             Variable::Int(1)
         );
     }
 
+    #[test]
+    fn raw_addresses_are_found_and_replaced_in_nested_operands_and_arguments() {
+        let mut expression = parse_expression("a + (b * POW(c, 2)) - d").unwrap();
+
+        assert_eq!(expression.get_raw_addresses(), &["a", "b", "c", "d"]);
+
+        let values = vec![("a", 1), ("c", 3)]
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), Variable::Int(value)))
+            .collect();
+
+        expression.replace_raw_addresses(&values);
+
+        assert_eq!(expression.get_raw_addresses(), &["b", "d"]);
+        assert_eq!(expression.head, Operand::Variable(Variable::Int(1)));
+    }
+
     #[test]
     fn nested_expression_evaluates_into_variable() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let nested_expression = get_simple_expression(
             Variable::Int(1),
@@ -367,8 +595,8 @@ mod tests {
         let nested = Operand::Nested(Box::new(nested_expression.clone()));
 
         assert_eq!(
-            evaluate_expression(&nested_expression, &data).unwrap(),
-            get_value(&nested, &data).unwrap()
+            evaluate_expression(&nested_expression, &mut data).unwrap(),
+            get_value(&nested, &mut data).unwrap()
         );
     }
 
@@ -468,4 +696,68 @@ mod tests {
 
         assert_eq!(ooo_expression.head, nested);
     }
+
+    #[test]
+    fn comparison_operators_evaluate_to_booleans() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let comparisons = &[
+            (Operator::Equal, false),
+            (Operator::NotEqual, true),
+            (Operator::Less, true),
+            (Operator::LessOrEqual, true),
+            (Operator::Greater, false),
+            (Operator::GreaterOrEqual, false),
+        ];
+
+        for (operator, result) in comparisons {
+            let expression = get_simple_expression(1.into(), &[(*operator, 2.into())]);
+
+            assert_eq!(
+                evaluate_expression(&expression, &mut data).unwrap(),
+                Variable::Bool(*result)
+            );
+        }
+    }
+
+    #[test]
+    fn logical_operators_evaluate_true_like_values() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let and = get_simple_expression(1.into(), &[(Operator::And, "".into())]);
+        let or = get_simple_expression(1.into(), &[(Operator::Or, "".into())]);
+
+        assert_eq!(
+            evaluate_expression(&and, &mut data).unwrap(),
+            Variable::Bool(false)
+        );
+        assert_eq!(
+            evaluate_expression(&or, &mut data).unwrap(),
+            Variable::Bool(true)
+        );
+    }
+
+    #[test]
+    fn not_operand_negates_true_like_value() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let expression = Expression {
+            head: Operand::Not(Box::new(Operand::Variable(0.into()))),
+            tail: Vec::new(),
+        };
+
+        assert_eq!(
+            evaluate_expression(&expression, &mut data).unwrap(),
+            Variable::Bool(true)
+        );
+    }
+
+    #[test]
+    fn comparing_invalid_types_yields_error() {
+        let mut data = mock_follow_data(&[], &[]);
+
+        let expression = get_simple_expression(1.into(), &[(Operator::Greater, "one".into())]);
+
+        assert!(evaluate_expression(&expression, &mut data).is_err());
+    }
 }
@@ -0,0 +1,432 @@
+//! Calls to functions from within the story.
+
+use crate::{
+    error::{
+        parse::validate::{InvalidFunctionCall, InvalidFunctionCallKind, ValidationError},
+        utils::MetaData,
+        InklingError,
+    },
+    follow::FollowData,
+    knot::{Address, AddressKind, FunctionParameter},
+    line::{
+        evaluate_expression, expression::Operand, Assignment, BuiltinFunction, Expression, Variable,
+    },
+    log::Logger,
+    story::validate::{ValidateContent, ValidationData},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, sync::Arc};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Call to a function with a set of arguments.
+///
+/// Corresponds to `name(a, b)` in expressions, conditions and logic lines. The arguments
+/// are evaluated before the function is called.
+pub struct FunctionCall {
+    /// Name of function to call.
+    pub name: String,
+    /// Expressions which evaluate to the arguments of the call.
+    pub arguments: Vec<Expression>,
+}
+
+impl FunctionCall {
+    /// Evaluate the arguments and call the function with them, returning its value.
+    ///
+    /// Functions which are built into `Ink` are called first. External functions which
+    /// have been bound are called with the arguments. Otherwise the function defined in
+    /// the story with the name is called, if it exists. Functions which do not return
+    /// a value evaluate to an empty string.
+    ///
+    /// # Errors
+    /// *   [`UnboundExternalFunctions`][crate::error::InklingError::UnboundExternalFunctions]:
+    ///     if no function has been bound to the external function and the story has no
+    ///     function with its name to fall back to.
+    /// *   [`ExternalFunctionError`][crate::error::InklingError::ExternalFunctionError]:
+    ///     if the bound function returned an error.
+    pub fn evaluate(&self, data: &mut FollowData) -> Result<Variable, InklingError> {
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|expression| evaluate_expression(expression, data))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(function) = BuiltinFunction::from_name(&self.name) {
+            return function.call(&self.name, &arguments, data);
+        }
+
+        let external_function = data
+            .external_functions
+            .get(&self.name)
+            .map(|info| info.function.clone());
+
+        let functions = Arc::clone(&data.functions);
+
+        match (external_function, functions.get(&self.name)) {
+            (Some(Some(function)), _) => {
+                function
+                    .call(&arguments)
+                    .map_err(|message| InklingError::ExternalFunctionError {
+                        name: self.name.clone(),
+                        message,
+                    })
+            }
+            (_, Some(function)) => {
+                let result = function.call(&self.name, arguments, data)?;
+                self.assign_ref_parameters(&function.parameters, result.parameters, data)?;
+
+                Ok(result
+                    .value
+                    .unwrap_or_else(|| Variable::String(String::new())))
+            }
+            (Some(None), None) => Err(InklingError::UnboundExternalFunctions {
+                names: vec![self.name.clone()],
+            }),
+            (None, None) => Err(InklingError::UnknownExternalFunction {
+                name: self.name.clone(),
+            }),
+        }
+    }
+
+    /// Assign the final values of parameters which are passed by reference to the variables
+    /// that were given as their arguments.
+    fn assign_ref_parameters(
+        &self,
+        parameters: &[FunctionParameter],
+        mut values: HashMap<String, Variable>,
+        data: &mut FollowData,
+    ) -> Result<(), InklingError> {
+        for (parameter, argument) in parameters.iter().zip(self.arguments.iter()) {
+            if let (true, Some(target), Some(value)) = (
+                parameter.is_ref,
+                get_argument_address(argument),
+                values.remove(&parameter.name),
+            ) {
+                Assignment {
+                    target: target.clone(),
+                    expression: Expression::from(value),
+                    temporary: false,
+                }
+                .evaluate(data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FunctionCall {
+    /// Check the types of arguments to a built-in function by calling it with them.
+    ///
+    /// Arguments with values which are not known until the story is followed are not checked.
+    /// Neither are arguments which refer to global variables that are not constant, since
+    /// their initial values may not be the values that the function is called with.
+    fn validate_builtin_arguments(
+        &self,
+        error: &mut ValidationError,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        let function = match BuiltinFunction::from_name(&self.name) {
+            Some(function) => function,
+            None => return,
+        };
+
+        if self.arguments.iter().any(|argument| {
+            argument.has_unknown_value(data) || argument.has_mutable_variables(data)
+        }) {
+            return;
+        }
+
+        let mut follow_data = data.follow_data.clone();
+
+        let result = self
+            .arguments
+            .iter()
+            .map(|expression| evaluate_expression(expression, &mut follow_data))
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|arguments| function.call(&self.name, &arguments, &mut follow_data));
+
+        if let Err(InklingError::InvalidFunctionArgument { value, .. }) = result {
+            error.function_call_errors.push(InvalidFunctionCall {
+                name: self.name.clone(),
+                kind: InvalidFunctionCallKind::InvalidArgument { value },
+                meta_data: meta_data.clone(),
+            });
+        }
+    }
+}
+
+/// Get the address of an argument if it is a single variable, as required by `ref` parameters.
+fn get_argument_address(argument: &Expression) -> Option<&Address> {
+    match argument {
+        Expression {
+            head: Operand::Variable(Variable::Address(address)),
+            tail,
+        } if tail.is_empty() => Some(address),
+        _ => None,
+    }
+}
+
+impl ValidateContent for FunctionCall {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        let num_errors = error.num_errors();
+
+        let num_parameters = BuiltinFunction::from_name(&self.name)
+            .map(|function| function.num_parameters())
+            .or_else(|| {
+                data.functions
+                    .get(&self.name)
+                    .map(|parameters| parameters.len())
+            })
+            .or_else(|| {
+                data.follow_data
+                    .external_functions
+                    .get(&self.name)
+                    .map(|info| info.parameters.len())
+            });
+
+        match num_parameters {
+            Some(num_parameters) if num_parameters != self.arguments.len() => {
+                error.function_call_errors.push(InvalidFunctionCall {
+                    name: self.name.clone(),
+                    kind: InvalidFunctionCallKind::WrongNumberOfArguments {
+                        expected: num_parameters,
+                        found: self.arguments.len(),
+                    },
+                    meta_data: meta_data.clone(),
+                });
+            }
+            Some(..) => (),
+            None => {
+                error.function_call_errors.push(InvalidFunctionCall {
+                    name: self.name.clone(),
+                    kind: InvalidFunctionCallKind::UnknownFunction,
+                    meta_data: meta_data.clone(),
+                });
+            }
+        }
+
+        self.arguments
+            .iter_mut()
+            .for_each(|argument| argument.validate(error, log, current_location, meta_data, data));
+
+        if num_errors == error.num_errors() {
+            self.validate_builtin_arguments(error, meta_data, data);
+        }
+
+        let ref_parameters = data
+            .functions
+            .get(&self.name)
+            .into_iter()
+            .flatten()
+            .zip(self.arguments.iter())
+            .filter(|(parameter, _)| parameter.is_ref);
+
+        for (parameter, argument) in ref_parameters {
+            match get_argument_address(argument) {
+                Some(Address::Validated(AddressKind::GlobalVariable { .. }))
+                | Some(Address::Validated(AddressKind::TemporaryVariable { .. })) => (),
+                _ => error.function_call_errors.push(InvalidFunctionCall {
+                    name: self.name.clone(),
+                    kind: InvalidFunctionCallKind::InvalidRefArgument {
+                        parameter: parameter.name.clone(),
+                    },
+                    meta_data: meta_data.clone(),
+                }),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{
+        follow::FollowDataBuilder,
+        story::types::{ExternalFunction, ExternalFunctionInfo},
+    };
+
+    use std::sync::Arc;
+
+    fn mock_follow_data(name: &str, function: Option<ExternalFunction>) -> FollowData {
+        let mut external_functions = HashMap::new();
+
+        external_functions.insert(
+            name.to_string(),
+            ExternalFunctionInfo {
+                parameters: vec!["a".to_string(), "b".to_string()],
+                function,
+                meta_data: ().into(),
+            },
+        );
+
+        FollowDataBuilder::new()
+            .with_external_functions(external_functions)
+            .build()
+    }
+
+    fn add_function() -> ExternalFunction {
+        ExternalFunction(Arc::new(|arguments: &[Variable]| {
+            arguments[0]
+                .add(&arguments[1])
+                .map_err(|err| err.to_string())
+        }))
+    }
+
+    fn get_call(name: &str, arguments: &[Variable]) -> FunctionCall {
+        FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.iter().cloned().map(Expression::from).collect(),
+        }
+    }
+
+    #[test]
+    fn calling_bound_function_returns_its_value_for_evaluated_arguments() {
+        let mut data = mock_follow_data("add", Some(add_function()));
+        let call = get_call("add", &[Variable::Int(1), Variable::Int(2)]);
+
+        assert_eq!(call.evaluate(&mut data).unwrap(), Variable::Int(3));
+    }
+
+    #[test]
+    fn calling_unbound_function_yields_error() {
+        let mut data = mock_follow_data("add", None);
+        let call = get_call("add", &[Variable::Int(1), Variable::Int(2)]);
+
+        match call.evaluate(&mut data) {
+            Err(InklingError::UnboundExternalFunctions { names }) => {
+                assert_eq!(names, vec!["add".to_string()]);
+            }
+            other => panic!(
+                "expected `InklingError::UnboundExternalFunctions` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn errors_from_bound_function_are_returned_with_function_name() {
+        let mut data = mock_follow_data("add", Some(add_function()));
+        let call = get_call("add", &[Variable::Int(1), Variable::Bool(true)]);
+
+        match call.evaluate(&mut data) {
+            Err(InklingError::ExternalFunctionError { name, .. }) => assert_eq!(&name, "add"),
+            other => panic!(
+                "expected `InklingError::ExternalFunctionError` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn validating_call_with_wrong_number_of_arguments_yields_error() {
+        let mut data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
+        data.follow_data = mock_follow_data("add", None);
+
+        let mut error = ValidationError::new();
+        let mut call = get_call("add", &[Variable::Int(1)]);
+
+        call.validate(
+            &mut error,
+            &mut Logger::default(),
+            &Address::Raw("".to_string()),
+            &().into(),
+            &data,
+        );
+
+        match &error.function_call_errors[..] {
+            [InvalidFunctionCall {
+                kind: InvalidFunctionCallKind::WrongNumberOfArguments { expected, found },
+                ..
+            }] => {
+                assert_eq!(*expected, 2);
+                assert_eq!(*found, 1);
+            }
+            other => panic!("expected a single argument count error but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validating_call_to_undeclared_function_yields_error() {
+        let data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
+
+        let mut error = ValidationError::new();
+        let mut call = get_call("add", &[Variable::Int(1), Variable::Int(2)]);
+
+        call.validate(
+            &mut error,
+            &mut Logger::default(),
+            &Address::Raw("".to_string()),
+            &().into(),
+            &data,
+        );
+
+        match &error.function_call_errors[..] {
+            [InvalidFunctionCall {
+                kind: InvalidFunctionCallKind::UnknownFunction,
+                ..
+            }] => (),
+            other => panic!(
+                "expected a single unknown function error but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn validating_call_to_built_in_function_with_invalid_argument_type_yields_error() {
+        let data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
+
+        let mut error = ValidationError::new();
+        let mut call = get_call("RANDOM", &[Variable::Int(1), Variable::from("six")]);
+
+        call.validate(
+            &mut error,
+            &mut Logger::default(),
+            &Address::Raw("".to_string()),
+            &().into(),
+            &data,
+        );
+
+        match &error.function_call_errors[..] {
+            [InvalidFunctionCall {
+                kind: InvalidFunctionCallKind::InvalidArgument { value },
+                ..
+            }] => assert_eq!(value, &Variable::from("six")),
+            other => panic!(
+                "expected a single invalid argument error but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn validating_call_to_built_in_function_with_valid_arguments_yields_no_error() {
+        let data = ValidationData::from_data(&HashMap::new(), &HashMap::new());
+
+        let mut error = ValidationError::new();
+        let mut call = get_call("POW", &[Variable::Int(2), Variable::Float(0.5)]);
+
+        call.validate(
+            &mut error,
+            &mut Logger::default(),
+            &Address::Raw("".to_string()),
+            &().into(),
+            &data,
+        );
+
+        assert!(error.is_empty());
+    }
+}
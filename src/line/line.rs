@@ -3,7 +3,7 @@
 use crate::{
     error::{parse::validate::ValidationError, utils::MetaData},
     knot::Address,
-    line::{Alternative, Condition, Expression},
+    line::{Alternative, Assignment, Condition, Expression},
     log::Logger,
     story::validate::{ValidateContent, ValidationData},
 };
@@ -20,9 +20,9 @@ use serde::{Deserialize, Serialize};
 pub struct InternalLine {
     /// Root chunk of line content, which may possibly be nested into even finer parts.
     pub chunk: LineChunk,
-    /// Tags associated with the line. Will be given to the user along with the processed
-    /// line content as the story is followed.
-    pub tags: Vec<String>,
+    /// Tags associated with the line. Their content is processed along with the line
+    /// and given to the user with the processed line content as the story is followed.
+    pub tags: Vec<LineChunk>,
     /// Whether or not the line is glued to the previous line. Glue prohibits new lines
     /// to be added between lines, which is otherwise the default behavior when following
     /// the story.
@@ -66,16 +66,42 @@ pub struct LineChunk {
 pub enum Content {
     /// Content that alternates every time it is visited in the story.
     Alternative(Alternative),
+    /// Assignment of a new value to a variable.
+    Assignment(Assignment),
     /// Divert to a new node in the story.
     Divert(Address),
+    /// Divert to a new node in the story with arguments for the parameters of its stitch.
+    DivertWithArguments {
+        address: Address,
+        arguments: Vec<Expression>,
+    },
     /// Null content.
     Empty,
     /// Expression to evaluate.
     Expression(Expression),
     /// Nested `LineChunk` to evaluate.
     Nested(LineChunk),
+    /// Return from a function with the value of an expression, if one is given.
+    Return(Option<Expression>),
+    /// Expression to evaluate for its side effects, discarding its value.
+    Statement(Expression),
     /// String of regular text content in the line.
     Text(String),
+    /// Start a thread at an address with arguments for the parameters of its stitch.
+    Thread {
+        address: Address,
+        arguments: Vec<Expression>,
+    },
+    /// Call a set of tunnels in order, then divert to an address if one is given.
+    ///
+    /// Every tunnel is given arguments for the parameters of its stitch. If no address
+    /// is given the story returns to the line after this when the tunnels have returned.
+    TunnelCall {
+        targets: Vec<(Address, Vec<Expression>)>,
+        divert: Option<Address>,
+    },
+    /// Return from the current tunnel, or divert to an address instead if one is given.
+    TunnelReturn(Option<Address>),
 }
 
 impl InternalLine {
@@ -110,6 +136,11 @@ impl InternalLine {
         buffer
     }
 
+    /// Whether or not processing the line assigns variables or calls functions.
+    pub fn has_logic(&self) -> bool {
+        self.chunk.has_logic() || self.tags.iter().any(|tag| tag.has_logic())
+    }
+
     #[cfg(test)]
     pub fn from_string(line: &str) -> Self {
         use builders::LineChunkBuilder;
@@ -119,6 +150,51 @@ impl InternalLine {
     }
 }
 
+impl LineChunk {
+    /// Whether or not processing the chunk assigns variables or calls functions.
+    pub fn has_logic(&self) -> bool {
+        self.condition
+            .as_ref()
+            .map(|condition| condition.has_function_calls())
+            .unwrap_or(false)
+            || self
+                .items
+                .iter()
+                .chain(self.else_items.iter())
+                .any(|item| item.has_logic())
+    }
+}
+
+impl Content {
+    /// Whether or not processing the content assigns variables or calls functions.
+    pub fn has_logic(&self) -> bool {
+        match self {
+            Content::Assignment(..) | Content::Statement(..) => true,
+            Content::Alternative(alternative) => {
+                alternative.items.iter().any(|chunk| chunk.has_logic())
+            }
+            Content::Expression(expression) | Content::Return(Some(expression)) => {
+                expression.has_function_calls()
+            }
+            Content::DivertWithArguments { arguments, .. } | Content::Thread { arguments, .. } => {
+                arguments
+                    .iter()
+                    .any(|argument| argument.has_function_calls())
+            }
+            Content::TunnelCall { targets, .. } => targets
+                .iter()
+                .flat_map(|(_, arguments)| arguments.iter())
+                .any(|argument| argument.has_function_calls()),
+            Content::Nested(chunk) => chunk.has_logic(),
+            Content::Divert(..)
+            | Content::Empty
+            | Content::Return(None)
+            | Content::Text(..)
+            | Content::TunnelReturn(..) => false,
+        }
+    }
+}
+
 impl ValidateContent for InternalLine {
     fn validate(
         &mut self,
@@ -130,6 +206,12 @@ impl ValidateContent for InternalLine {
     ) {
         self.chunk
             .validate(error, log, current_location, &self.meta_data, data);
+
+        let meta_data = &self.meta_data;
+
+        self.tags
+            .iter_mut()
+            .for_each(|tag| tag.validate(error, log, current_location, meta_data, data));
     }
 }
 
@@ -166,14 +248,47 @@ impl ValidateContent for Content {
             Content::Alternative(alternative) => {
                 alternative.validate(error, log, current_location, meta_data, data)
             }
-            Content::Divert(address) => {
-                address.validate(error, log, current_location, meta_data, data)
+            Content::Assignment(assignment) => {
+                assignment.validate(error, log, current_location, meta_data, data)
+            }
+            Content::Divert(address) | Content::TunnelReturn(Some(address)) => {
+                address.validate(error, log, current_location, meta_data, data);
+                address.validate_divert_target(0, error, meta_data, data);
+            }
+            Content::DivertWithArguments { address, arguments }
+            | Content::Thread { address, arguments } => {
+                address.validate(error, log, current_location, meta_data, data);
+                address.validate_divert_target(arguments.len(), error, meta_data, data);
+
+                arguments.iter_mut().for_each(|argument| {
+                    argument.validate(error, log, current_location, meta_data, data)
+                });
             }
-            Content::Empty | Content::Text(..) => (),
-            Content::Expression(expression) => {
+            Content::Empty | Content::Text(..) | Content::TunnelReturn(None) => (),
+            Content::Expression(expression) | Content::Statement(expression) => {
                 expression.validate(error, log, current_location, meta_data, data)
             }
             Content::Nested(chunk) => chunk.validate(error, log, current_location, meta_data, data),
+            Content::Return(expression) => {
+                if let Some(expression) = expression {
+                    expression.validate(error, log, current_location, meta_data, data)
+                }
+            }
+            Content::TunnelCall { targets, divert } => {
+                for (address, arguments) in targets.iter_mut() {
+                    address.validate(error, log, current_location, meta_data, data);
+                    address.validate_divert_target(arguments.len(), error, meta_data, data);
+
+                    arguments.iter_mut().for_each(|argument| {
+                        argument.validate(error, log, current_location, meta_data, data)
+                    });
+                }
+
+                if let Some(address) = divert {
+                    address.validate(error, log, current_location, meta_data, data);
+                    address.validate_divert_target(0, error, meta_data, data);
+                }
+            }
         }
     }
 }
@@ -187,7 +302,7 @@ pub mod builders {
     /// Builder for constructing an `InternalLine`.
     pub struct InternalLineBuilder {
         chunk: LineChunk,
-        tags: Vec<String>,
+        tags: Vec<LineChunk>,
         glue_begin: bool,
         glue_end: bool,
     }
@@ -210,7 +325,7 @@ pub mod builders {
                 tags: self.tags,
                 glue_begin: self.glue_begin,
                 glue_end: self.glue_end,
-                meta_data: MetaData { line_index: 0 },
+                meta_data: ().into(),
             }
         }
     }
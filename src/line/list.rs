@@ -0,0 +1,549 @@
+//! Values of list variables and the operations on them.
+
+use crate::story::types::ListDefinitionSet;
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::iter::FromIterator;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Value of a list variable.
+///
+/// Lists are defined in the story with `LIST name = a, (b), c`. The value of a list variable
+/// is a set of items from one or more of those definitions. Items are ordered by their value
+/// in the list which defines them.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_from_string, Variable};
+/// let content = "\
+/// LIST inventory = (sword), lamp, (rope)
+/// ";
+///
+/// let story = read_story_from_string(content).unwrap();
+///
+/// match story.get_variable("inventory").unwrap() {
+///     Variable::List(list) => {
+///         assert!(list.contains("sword"));
+///         assert!(!list.contains("lamp"));
+///         assert_eq!(list.len(), 2);
+///     }
+///     _ => unreachable!(),
+/// }
+/// ```
+pub struct InkList {
+    /// Items in the list, ordered by value.
+    items: Vec<ListItem>,
+    /// Names of the list definitions which items in this list come from.
+    ///
+    /// Kept when items are removed, to know which items the list can contain.
+    origins: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Item in a list.
+pub struct ListItem {
+    /// Value of the item in the list which defines it.
+    pub value: i32,
+    /// Name of the list which defines the item.
+    pub list: String,
+    /// Name of the item.
+    pub name: String,
+}
+
+impl ListItem {
+    /// Create an item from the name of its list and its own name.
+    ///
+    /// The value of the item is set from the definition of the list when it is
+    /// assigned to a variable with [`Story::set_variable`][crate::story::Story::set_variable()].
+    pub fn new<S: ToString>(list: S, name: S) -> Self {
+        ListItem {
+            value: 0,
+            list: list.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Get the full name of the item as `list.item`.
+    pub fn full_name(&self) -> String {
+        format!("{}.{}", self.list, self.name)
+    }
+
+    /// Whether the item has the given name, either as `item` or `list.item`.
+    fn has_name(&self, name: &str) -> bool {
+        self.name == name || self.full_name() == name
+    }
+}
+
+impl InkList {
+    /// Create an empty list.
+    pub fn new() -> Self {
+        InkList::default()
+    }
+
+    /// Create an empty list which can contain items from the given list definitions.
+    pub(crate) fn with_origins(origins: Vec<String>) -> Self {
+        InkList {
+            items: Vec::new(),
+            origins,
+        }
+    }
+
+    /// Get the items of the list, ordered by value.
+    pub fn items(&self) -> &[ListItem] {
+        &self.items
+    }
+
+    /// Get the names of the list definitions which items in this list can come from.
+    pub fn origins(&self) -> &[String] {
+        &self.origins
+    }
+
+    /// Get the number of items in the list.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Whether the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Whether the list contains an item with the given name.
+    ///
+    /// The name can be either just the item name or qualified with the list name:
+    /// `list.item`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.items.iter().any(|item| item.has_name(name))
+    }
+
+    /// Add an item to the list.
+    pub fn insert(&mut self, item: ListItem) {
+        if !self.origins.contains(&item.list) {
+            self.origins.push(item.list.clone());
+        }
+
+        if !self.items.contains(&item) {
+            self.items.push(item);
+            self.items.sort();
+        }
+    }
+
+    /// Remove the item with the given name from the list.
+    ///
+    /// The name can be either just the item name or qualified with the list name:
+    /// `list.item`. Returns the removed item if it was present.
+    pub fn remove(&mut self, name: &str) -> Option<ListItem> {
+        self.items
+            .iter()
+            .position(|item| item.has_name(name))
+            .map(|index| self.items.remove(index))
+    }
+
+    /// Get the item with the lowest value.
+    pub fn min_item(&self) -> Option<&ListItem> {
+        self.items.first()
+    }
+
+    /// Get the item with the highest value.
+    pub fn max_item(&self) -> Option<&ListItem> {
+        self.items.last()
+    }
+
+    /// Return the names of all items separated by commas, as they are printed in the story.
+    pub(crate) fn to_text(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| item.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Return a list with the items of both lists.
+    pub(crate) fn union(&self, other: &InkList) -> InkList {
+        let mut list = self.clone();
+        list.extend_origins(other);
+
+        for item in &other.items {
+            list.insert(item.clone());
+        }
+
+        list
+    }
+
+    /// Return a list with the items of this list which are not in the other list.
+    pub(crate) fn difference(&self, other: &InkList) -> InkList {
+        let mut list = self.clone();
+        list.items.retain(|item| !other.items.contains(item));
+
+        list
+    }
+
+    /// Return a list with the items which are in both lists.
+    pub(crate) fn intersection(&self, other: &InkList) -> InkList {
+        let mut list = self.clone();
+        list.extend_origins(other);
+        list.items.retain(|item| other.items.contains(item));
+
+        list
+    }
+
+    /// Whether all items of the other list are in this list.
+    ///
+    /// Empty lists neither contain nor are contained in other lists.
+    pub(crate) fn contains_all(&self, other: &InkList) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && other.items.iter().all(|item| self.items.contains(item))
+    }
+
+    /// Whether both lists contain the same items.
+    pub(crate) fn same_items(&self, other: &InkList) -> bool {
+        self.items == other.items
+    }
+
+    /// Whether all items in this list have a higher value than all items in the other list.
+    pub(crate) fn greater_than(&self, other: &InkList) -> bool {
+        match (self.min_item(), other.max_item()) {
+            (Some(min), Some(max)) => min.value > max.value,
+            _ => false,
+        }
+    }
+
+    /// Whether all items in this list have a lower value than all items in the other list.
+    pub(crate) fn less_than(&self, other: &InkList) -> bool {
+        match (self.max_item(), other.min_item()) {
+            (Some(max), Some(min)) => max.value < min.value,
+            _ => false,
+        }
+    }
+
+    /// Whether the lowest and highest items in this list have values that are at least
+    /// those of the lowest and highest items in the other list. Empty lists are never
+    /// greater than or equal to other lists.
+    pub(crate) fn greater_or_equal(&self, other: &InkList) -> bool {
+        match (
+            self.min_item(),
+            self.max_item(),
+            other.min_item(),
+            other.max_item(),
+        ) {
+            (Some(min), Some(max), Some(other_min), Some(other_max)) => {
+                min.value >= other_min.value && max.value >= other_max.value
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the lowest and highest items in this list have values that are at most
+    /// those of the lowest and highest items in the other list. Empty lists are never
+    /// less than or equal to other lists.
+    pub(crate) fn less_or_equal(&self, other: &InkList) -> bool {
+        match (
+            self.min_item(),
+            self.max_item(),
+            other.min_item(),
+            other.max_item(),
+        ) {
+            (Some(min), Some(max), Some(other_min), Some(other_max)) => {
+                min.value <= other_min.value && max.value <= other_max.value
+            }
+            _ => false,
+        }
+    }
+
+    /// Return a list with every item moved to the item in its list with a value that
+    /// is offset from it.
+    ///
+    /// Items which are moved past the start or end of their list are removed.
+    pub(crate) fn shift(&self, offset: i32, lists: &ListDefinitionSet) -> InkList {
+        let items = self
+            .items
+            .iter()
+            .filter_map(|item| {
+                lists.get(&item.list).and_then(|definition| {
+                    definition.get_item_with_value(&item.list, item.value + offset)
+                })
+            })
+            .collect();
+
+        InkList {
+            items,
+            origins: self.origins.clone(),
+        }
+        .sorted()
+    }
+
+    /// Return a list with all items of the lists that this list originates from.
+    pub(crate) fn all(&self, lists: &ListDefinitionSet) -> InkList {
+        let items = self
+            .origins
+            .iter()
+            .filter_map(|name| lists.get(name).map(|definition| definition.all_items(name)))
+            .flatten()
+            .collect();
+
+        InkList {
+            items,
+            origins: self.origins.clone(),
+        }
+        .sorted()
+    }
+
+    /// Return a list with all items of the lists that this list originates from which
+    /// are not in this list.
+    pub(crate) fn invert(&self, lists: &ListDefinitionSet) -> InkList {
+        self.all(lists).difference(self)
+    }
+
+    /// Return a list with the items that have values in the given, inclusive range.
+    pub(crate) fn range(&self, min: i32, max: i32) -> InkList {
+        let mut list = self.clone();
+        list.items
+            .retain(|item| item.value >= min && item.value <= max);
+
+        list
+    }
+
+    /// Set the values of all items from their list definitions and add their lists as origins.
+    ///
+    /// Returns the name of the first item which is not defined in the story as an error.
+    pub(crate) fn normalize(&mut self, lists: &ListDefinitionSet) -> Result<(), String> {
+        for item in self.items.iter_mut() {
+            item.value = lists
+                .get(&item.list)
+                .and_then(|definition| definition.get_value(&item.name))
+                .ok_or(item.full_name())?;
+        }
+
+        let origins = self
+            .items
+            .iter()
+            .map(|item| item.list.clone())
+            .collect::<Vec<_>>();
+
+        for origin in origins {
+            if !self.origins.contains(&origin) {
+                self.origins.push(origin);
+            }
+        }
+
+        self.items.sort();
+        self.items.dedup();
+
+        Ok(())
+    }
+
+    /// Add the origins of another list to the origins of this list.
+    fn extend_origins(&mut self, other: &InkList) {
+        for origin in &other.origins {
+            if !self.origins.contains(origin) {
+                self.origins.push(origin.clone());
+            }
+        }
+    }
+
+    /// Sort the items of the list by value.
+    fn sorted(mut self) -> Self {
+        self.items.sort();
+        self
+    }
+}
+
+impl FromIterator<ListItem> for InkList {
+    fn from_iter<I: IntoIterator<Item = ListItem>>(iter: I) -> Self {
+        let mut list = InkList::new();
+
+        for item in iter {
+            list.insert(item);
+        }
+
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::story::types::ListDefinition;
+
+    use std::collections::HashMap;
+
+    fn mock_lists(lists: &[(&str, &[&str])]) -> ListDefinitionSet {
+        lists
+            .iter()
+            .map(|(name, items)| {
+                let items = items
+                    .iter()
+                    .zip(1..)
+                    .map(|(item, value)| (item.to_string(), value))
+                    .collect();
+
+                (
+                    name.to_string(),
+                    ListDefinition {
+                        items,
+                        meta_data: ().into(),
+                    },
+                )
+            })
+            .collect::<HashMap<_, _>>()
+    }
+
+    fn get_list(list: &str, items: &[(&str, i32)]) -> InkList {
+        let mut ink_list = InkList::with_origins(vec![list.to_string()]);
+
+        for (name, value) in items {
+            ink_list.insert(ListItem {
+                value: *value,
+                list: list.to_string(),
+                name: name.to_string(),
+            });
+        }
+
+        ink_list
+    }
+
+    #[test]
+    fn inserted_items_are_ordered_by_value() {
+        let list = get_list("colors", &[("green", 2), ("red", 1), ("blue", 3)]);
+
+        assert_eq!(&list.to_text(), "red, green, blue");
+    }
+
+    #[test]
+    fn items_can_be_found_by_name_or_full_name() {
+        let list = get_list("colors", &[("red", 1)]);
+
+        assert!(list.contains("red"));
+        assert!(list.contains("colors.red"));
+        assert!(!list.contains("green"));
+        assert!(!list.contains("shapes.red"));
+    }
+
+    #[test]
+    fn union_difference_and_intersection_combine_items() {
+        let one = get_list("colors", &[("red", 1), ("green", 2)]);
+        let two = get_list("colors", &[("green", 2), ("blue", 3)]);
+
+        assert_eq!(&one.union(&two).to_text(), "red, green, blue");
+        assert_eq!(&one.difference(&two).to_text(), "red");
+        assert_eq!(&one.intersection(&two).to_text(), "green");
+    }
+
+    #[test]
+    fn lists_contain_other_lists_if_they_have_all_of_their_items() {
+        let list = get_list("colors", &[("red", 1), ("green", 2)]);
+
+        assert!(list.contains_all(&get_list("colors", &[("red", 1)])));
+        assert!(list.contains_all(&get_list("colors", &[("red", 1), ("green", 2)])));
+        assert!(!list.contains_all(&get_list("colors", &[("red", 1), ("blue", 3)])));
+    }
+
+    #[test]
+    fn empty_lists_are_never_contained() {
+        let list = get_list("colors", &[("red", 1)]);
+        let empty = get_list("colors", &[]);
+
+        assert!(!list.contains_all(&empty));
+        assert!(!empty.contains_all(&list));
+    }
+
+    #[test]
+    fn lists_are_ordered_by_the_values_of_their_items() {
+        let low = get_list("colors", &[("red", 1), ("green", 2)]);
+        let high = get_list("colors", &[("blue", 3)]);
+
+        assert!(high.greater_than(&low));
+        assert!(low.less_than(&high));
+        assert!(!low.greater_than(&high));
+        assert!(!low.less_than(&low));
+    }
+
+    #[test]
+    fn lists_are_greater_or_equal_if_both_their_lowest_and_highest_items_are() {
+        let low = get_list("colors", &[("red", 1), ("green", 2)]);
+        let wide = get_list("colors", &[("red", 1), ("blue", 3)]);
+        let high = get_list("colors", &[("green", 2), ("blue", 3)]);
+
+        assert!(high.greater_or_equal(&low));
+        assert!(high.greater_or_equal(&wide));
+        assert!(!wide.greater_or_equal(&high));
+        assert!(!low.greater_or_equal(&wide));
+
+        assert!(low.less_or_equal(&high));
+        assert!(wide.less_or_equal(&high));
+        assert!(!high.less_or_equal(&wide));
+        assert!(!wide.less_or_equal(&low));
+
+        assert!(wide.greater_or_equal(&wide));
+        assert!(wide.less_or_equal(&wide));
+    }
+
+    #[test]
+    fn empty_lists_are_never_greater_or_less_than_or_equal_to_lists() {
+        let list = get_list("colors", &[("red", 1)]);
+        let empty = get_list("colors", &[]);
+
+        assert!(!list.greater_or_equal(&empty));
+        assert!(!empty.greater_or_equal(&list));
+        assert!(!empty.greater_or_equal(&empty));
+
+        assert!(!list.less_or_equal(&empty));
+        assert!(!empty.less_or_equal(&list));
+        assert!(!empty.less_or_equal(&empty));
+    }
+
+    #[test]
+    fn shifting_items_moves_them_to_the_items_with_offset_values() {
+        let lists = mock_lists(&[("colors", &["red", "green", "blue"])]);
+        let list = get_list("colors", &[("red", 1), ("green", 2)]);
+
+        assert_eq!(&list.shift(1, &lists).to_text(), "green, blue");
+        assert_eq!(&list.shift(-1, &lists).to_text(), "red");
+    }
+
+    #[test]
+    fn all_and_invert_use_items_from_the_origin_lists() {
+        let lists = mock_lists(&[("colors", &["red", "green", "blue"])]);
+        let list = get_list("colors", &[("green", 2)]);
+
+        assert_eq!(&list.all(&lists).to_text(), "red, green, blue");
+        assert_eq!(&list.invert(&lists).to_text(), "red, blue");
+
+        let empty = get_list("colors", &[]);
+        assert_eq!(&empty.all(&lists).to_text(), "red, green, blue");
+    }
+
+    #[test]
+    fn normalizing_list_sets_item_values_from_their_definitions() {
+        let lists = mock_lists(&[("colors", &["red", "green", "blue"])]);
+
+        let mut list: InkList = vec![
+            ListItem::new("colors", "blue"),
+            ListItem::new("colors", "red"),
+        ]
+        .into_iter()
+        .collect();
+
+        list.normalize(&lists).unwrap();
+
+        assert_eq!(&list.to_text(), "red, blue");
+        assert_eq!(list.items()[1].value, 3);
+    }
+
+    #[test]
+    fn normalizing_list_with_unknown_item_yields_its_name() {
+        let lists = mock_lists(&[("colors", &["red"])]);
+
+        let mut list: InkList = vec![ListItem::new("colors", "purple")]
+            .into_iter()
+            .collect();
+
+        assert_eq!(list.normalize(&lists).unwrap_err(), "colors.purple");
+    }
+}
@@ -17,21 +17,37 @@
 //! is made and can have conditions for when they are presented at all.
 
 mod alternative;
+mod assignment;
+mod builtin;
 mod choice;
 pub(crate) mod condition;
 pub mod expression;
+mod function;
 pub(crate) mod line;
+mod list;
 pub(crate) mod parse;
 mod variable;
 
-pub(crate) use alternative::{Alternative, AlternativeBuilder, AlternativeKind};
+pub(crate) use alternative::{
+    get_next_alternative_index, Alternative, AlternativeBuilder, AlternativeKind,
+};
+pub(crate) use assignment::Assignment;
+pub(crate) use builtin::BuiltinFunction;
 pub(crate) use choice::{InternalChoice, InternalChoiceBuilder};
 pub(crate) use condition::{
     Condition, ConditionBuilder, ConditionItem, ConditionKind, StoryCondition,
 };
 pub(crate) use expression::{evaluate_expression, Expression};
+pub(crate) use function::FunctionCall;
 #[cfg(test)]
 pub(crate) use line::builders::LineChunkBuilder;
 pub(crate) use line::{Content, InternalLine, LineChunk};
-pub(crate) use parse::{parse_line, parse_variable, ParsedLineKind};
+pub use list::{InkList, ListItem};
+#[cfg(test)]
+pub(crate) use parse::parse_line;
+pub(crate) use parse::{
+    find_unescaped, parse_expression, parse_function_signature, parse_lines, parse_list_definition,
+    parse_tag, parse_variable, unescape, ParsedLineKind,
+};
+pub(crate) use variable::get_list_item;
 pub use variable::Variable;
@@ -1,7 +1,9 @@
 //! Parse `Alternative` line chunks.
 
 use crate::{
-    consts::{CYCLE_MARKER, ONCE_ONLY_MARKER, SEQUENCE_SEPARATOR, SHUFFLE_MARKER},
+    consts::{
+        CYCLE_MARKER, ONCE_ONLY_MARKER, SEQUENCE_KEYWORDS, SEQUENCE_SEPARATOR, SHUFFLE_MARKER,
+    },
     error::parse::line::LineErrorKind,
     line::{
         parse::{parse_chunk, split_line_at_separator_braces},
@@ -27,8 +29,35 @@ pub fn parse_alternative(content: &str) -> Result<Alternative, LineErrorKind> {
         .build())
 }
 
+/// Get the kind of alternating sequence that a keyword represents, if any.
+///
+/// Whitespace inside and around the keyword is not significant: ` shuffle  once `
+/// is the same keyword as `shuffle once`.
+pub fn get_sequence_keyword_kind(content: &str) -> Option<AlternativeKind> {
+    let keyword = content.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    SEQUENCE_KEYWORDS
+        .iter()
+        .find(|(name, _)| *name == keyword)
+        .map(|(_, kind)| *kind)
+}
+
+/// Split a leading sequence keyword and its colon from a string, if it begins with one.
+///
+/// Returns the kind of sequence and the string after the colon.
+pub fn split_sequence_keyword(content: &str) -> Option<(AlternativeKind, &str)> {
+    let i = content.find(':')?;
+    let (head, tail) = content.split_at(i);
+
+    get_sequence_keyword_kind(head).map(|kind| (kind, tail.get(1..).unwrap()))
+}
+
 /// Determine the alternating sequence kind and return the string without the marker.
 fn get_alternative_kind_and_cut_marker(content: &str) -> (&str, AlternativeKind) {
+    if let Some((kind, tail)) = split_sequence_keyword(content) {
+        return (tail, kind);
+    }
+
     match get_sequence_kind(content) {
         AlternativeKind::Sequence => (content, AlternativeKind::Sequence),
         kind => (content.get(1..).unwrap(), kind),
@@ -58,13 +87,13 @@ mod tests {
     fn list_of_strings_separated_by_vertical_lines_are_added_to_set() {
         let text = "One|Two|Three";
 
-        let mut alternative = parse_alternative(text).unwrap();
+        let alternative = parse_alternative(text).unwrap();
 
         assert_eq!(alternative.items.len(), 3);
 
-        assert_eq!(&get_processed_chunk(&mut alternative.items[0]), "One");
-        assert_eq!(&get_processed_chunk(&mut alternative.items[1]), "Two");
-        assert_eq!(&get_processed_chunk(&mut alternative.items[2]), "Three");
+        assert_eq!(&get_processed_chunk(&alternative.items[0]), "One");
+        assert_eq!(&get_processed_chunk(&alternative.items[1]), "Two");
+        assert_eq!(&get_processed_chunk(&alternative.items[2]), "Three");
     }
 
     #[test]
@@ -107,12 +136,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sequence_keywords_followed_by_a_colon_set_the_kind() {
+        let alternative = parse_alternative("shuffle once: One|Two|Three").unwrap();
+
+        assert_eq!(alternative.kind, AlternativeKind::ShuffleOnce);
+        assert_eq!(alternative.items.len(), 3);
+
+        let alternative = parse_alternative(" stopping :One|Two").unwrap();
+
+        assert_eq!(alternative.kind, AlternativeKind::Sequence);
+        assert_eq!(&get_processed_alternative(&alternative), "One");
+
+        assert_eq!(
+            parse_alternative("shuffle stopping: One|Two").unwrap().kind,
+            AlternativeKind::ShuffleStopping
+        );
+        assert_eq!(
+            parse_alternative("cycle: One|Two").unwrap().kind,
+            AlternativeKind::Cycle
+        );
+        assert_eq!(
+            parse_alternative("once: One|Two").unwrap().kind,
+            AlternativeKind::OnceOnly
+        );
+        assert_eq!(
+            parse_alternative("shuffle: One|Two").unwrap().kind,
+            AlternativeKind::Shuffle
+        );
+    }
+
+    #[test]
+    fn sequence_keywords_are_only_read_from_the_whole_head_before_the_colon() {
+        assert_eq!(
+            get_sequence_keyword_kind(" shuffle   once "),
+            Some(AlternativeKind::ShuffleOnce)
+        );
+        assert_eq!(get_sequence_keyword_kind("once upon"), None);
+        assert_eq!(get_sequence_keyword_kind("shuffled"), None);
+        assert!(split_sequence_keyword("x > 2: One|Two").is_none());
+    }
+
     #[test]
     fn whitespace_is_trimmed_from_the_beginning() {
         let text = " &One|Two|Three";
-        let mut alternative = parse_alternative(text).unwrap();
+        let alternative = parse_alternative(text).unwrap();
 
-        assert_eq!(&get_processed_alternative(&mut alternative), "One");
+        assert_eq!(&get_processed_alternative(&alternative), "One");
 
         match &alternative.kind {
             AlternativeKind::Cycle => (),
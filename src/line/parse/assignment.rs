@@ -0,0 +1,361 @@
+//! Parse variable assignments from logic lines.
+
+use crate::{
+    consts::{ASSIGNMENT_MARKER, RETURN_MARKER, TEMPORARY_VARIABLE_MARKER},
+    error::parse::{expression::ExpressionError, line::LineErrorKind},
+    knot::Address,
+    line::{
+        expression::{Operand, Operator},
+        parse::{
+            parse_expression, parse_function_call, split_line_at_separator_quotes, validate_address,
+        },
+        Assignment, Content, Expression, Variable,
+    },
+};
+
+/// Parse the content of a logic line starting with the assignment marker.
+///
+/// Logic lines either assign a value to a variable (see `parse_assignment`), return
+/// from a function (`~ return x + 1`) or call a function for its side effects:
+/// `~ play_sound("bell.wav")`. The value returned by such a call is discarded.
+pub fn parse_logic_line(content: &str) -> Result<Option<Content>, LineErrorKind> {
+    let line = match content.trim().strip_prefix(ASSIGNMENT_MARKER) {
+        Some(line) => line.trim(),
+        None => return Ok(None),
+    };
+
+    if let Some(result) = parse_return(line) {
+        return result.map(Some);
+    }
+
+    match parse_function_call(line) {
+        Some(result) => result
+            .map(|function_call| Some(Content::Statement(Expression::from(function_call))))
+            .map_err(|kind| {
+                ExpressionError {
+                    content: line.to_string(),
+                    kind,
+                }
+                .into()
+            }),
+        None => parse_assignment(content).map(|assignment| assignment.map(Content::Assignment)),
+    }
+}
+
+/// Parse a return from a function if the logic line content starts with the return keyword.
+///
+/// The returned value is optional: `~ return` returns from the function without a value.
+fn parse_return(line: &str) -> Option<Result<Content, LineErrorKind>> {
+    let tail = line.strip_prefix(RETURN_MARKER)?;
+
+    if tail.trim().is_empty() {
+        Some(Ok(Content::Return(None)))
+    } else if tail.starts_with(char::is_whitespace) {
+        let result = parse_expression(tail.trim())
+            .map(|expression| Content::Return(Some(expression)))
+            .map_err(|err| err.into());
+
+        Some(result)
+    } else {
+        None
+    }
+}
+
+/// Parse an `Assignment` from a line if it is a logic line starting with the assignment marker.
+///
+/// Assignments come in three forms: `~ x = expr`, `~ x += expr` (or `-=`) and `~ x++`
+/// (or `--`). The compound forms are expanded into regular assignments of `x + expr`
+/// or `x + 1` to the target variable.
+///
+/// Temporary variables are declared by a leading `temp` keyword: `~ temp x = expr`.
+/// Declarations must use the regular assignment form.
+pub fn parse_assignment(content: &str) -> Result<Option<Assignment>, LineErrorKind> {
+    let content = content.trim();
+
+    if !content.starts_with(ASSIGNMENT_MARKER) {
+        return Ok(None);
+    }
+
+    let mut line = content.get(ASSIGNMENT_MARKER.len_utf8()..).unwrap().trim();
+
+    let invalid_assignment = || LineErrorKind::InvalidAssignment {
+        content: content.to_string(),
+    };
+
+    let temporary = line
+        .strip_prefix(TEMPORARY_VARIABLE_MARKER)
+        .filter(|tail| tail.starts_with(char::is_whitespace))
+        .map(|tail| line = tail.trim_start())
+        .is_some();
+
+    let (name, operator, expression) = if line.ends_with("++") || line.ends_with("--") {
+        let (name, marker) = line.split_at(line.len() - 2);
+        let operator = get_compound_operator(marker).unwrap();
+
+        (
+            name.trim(),
+            Some(operator),
+            Expression::from(Variable::Int(1)),
+        )
+    } else {
+        let parts = split_line_at_separator_quotes(line, "=", Some(1))?;
+
+        if parts.len() != 2 {
+            return Err(invalid_assignment());
+        }
+
+        let lhs = parts[0].trim_end();
+
+        let (name, operator) = match lhs.char_indices().last() {
+            Some((i, c)) if c == '+' || c == '-' => (
+                lhs.get(..i).unwrap().trim(),
+                get_compound_operator(&lhs[i..]),
+            ),
+            _ => (lhs, None),
+        };
+
+        (name, operator, parse_expression(parts[1])?)
+    };
+
+    if name.is_empty() || (temporary && operator.is_some()) {
+        return Err(invalid_assignment());
+    }
+
+    let name = validate_address(name)?;
+
+    let expression = match operator {
+        Some(operator) => Expression {
+            head: Operand::Variable(Variable::Address(Address::Raw(name.clone()))),
+            tail: vec![(operator, Operand::Nested(Box::new(expression)))],
+        },
+        None => expression,
+    };
+
+    Ok(Some(Assignment {
+        target: Address::Raw(name),
+        expression,
+        temporary,
+    }))
+}
+
+/// Get the operator of a compound assignment or increment marker.
+fn get_compound_operator(marker: &str) -> Option<Operator> {
+    match marker.chars().next() {
+        Some('+') => Some(Operator::Add),
+        Some('-') => Some(Operator::Subtract),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_assignment(content: &str) -> Assignment {
+        parse_assignment(content).unwrap().unwrap()
+    }
+
+    fn raw_variable(name: &str) -> Operand {
+        Operand::Variable(Variable::Address(Address::Raw(name.to_string())))
+    }
+
+    #[test]
+    fn logic_line_with_function_call_parses_into_statement() {
+        match parse_logic_line("~ play_sound(\"bell.wav\")")
+            .unwrap()
+            .unwrap()
+        {
+            Content::Statement(expression) => assert_eq!(
+                expression,
+                parse_expression("play_sound(\"bell.wav\")").unwrap()
+            ),
+            other => panic!("expected `Content::Statement` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logic_line_with_assignment_parses_into_assignment() {
+        match parse_logic_line("~ coins = count_coins()")
+            .unwrap()
+            .unwrap()
+        {
+            Content::Assignment(..) => (),
+            other => panic!("expected `Content::Assignment` but got {:?}", other),
+        }
+
+        assert!(parse_logic_line("Hello, World!").unwrap().is_none());
+    }
+
+    #[test]
+    fn logic_line_with_return_keyword_parses_into_return_with_optional_value() {
+        match parse_logic_line("~ return x + 1").unwrap() {
+            Some(Content::Return(Some(expression))) => assert_eq!(expression.tail.len(), 1),
+            other => panic!(
+                "expected `Content::Return` with a value but got {:?}",
+                other
+            ),
+        }
+
+        match parse_logic_line("~ return").unwrap() {
+            Some(Content::Return(None)) => (),
+            other => panic!(
+                "expected `Content::Return` without a value but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn variables_starting_with_return_keyword_can_be_assigned_to() {
+        match parse_logic_line("~ returned = true").unwrap() {
+            Some(Content::Assignment(..)) => (),
+            other => panic!("expected `Content::Assignment` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_without_assignment_marker_are_not_assignments() {
+        assert!(parse_assignment("coins = 5").unwrap().is_none());
+        assert!(parse_assignment("Hello, World!").unwrap().is_none());
+    }
+
+    #[test]
+    fn simple_assignment_sets_target_and_expression() {
+        let assignment = get_assignment("~ coins = 5");
+
+        assert_eq!(assignment.target, Address::Raw("coins".to_string()));
+        assert_eq!(assignment.expression, parse_expression("5").unwrap());
+    }
+
+    #[test]
+    fn assignment_expression_may_contain_the_target() {
+        let assignment = get_assignment("~ coins = coins + 4");
+
+        assert_eq!(
+            assignment.expression,
+            parse_expression("coins + 4").unwrap()
+        );
+    }
+
+    #[test]
+    fn assignment_marker_may_be_followed_directly_by_the_target() {
+        let assignment = get_assignment("~coins=5");
+
+        assert_eq!(assignment.target, Address::Raw("coins".to_string()));
+    }
+
+    #[test]
+    fn equal_signs_inside_strings_do_not_split_the_assignment() {
+        let assignment = get_assignment("~ rank = \"= Capitaine =\"");
+
+        assert_eq!(
+            assignment.expression,
+            Expression::from(Variable::String("= Capitaine =".to_string()))
+        );
+    }
+
+    #[test]
+    fn compound_addition_adds_expression_to_target() {
+        let assignment = get_assignment("~ coins += 2 * 3");
+
+        assert_eq!(assignment.target, Address::Raw("coins".to_string()));
+        assert_eq!(assignment.expression.head, raw_variable("coins"));
+        assert_eq!(
+            assignment.expression.tail,
+            vec![(
+                Operator::Add,
+                Operand::Nested(Box::new(parse_expression("2 * 3").unwrap()))
+            )]
+        );
+    }
+
+    #[test]
+    fn compound_subtraction_subtracts_expression_from_target() {
+        let assignment = get_assignment("~ coins -= 2");
+
+        assert_eq!(assignment.expression.tail[0].0, Operator::Subtract);
+    }
+
+    #[test]
+    fn increments_add_or_subtract_one_from_target() {
+        let assignment = get_assignment("~ coins++");
+
+        assert_eq!(assignment.target, Address::Raw("coins".to_string()));
+        assert_eq!(assignment.expression.head, raw_variable("coins"));
+        assert_eq!(
+            assignment.expression.tail,
+            vec![(
+                Operator::Add,
+                Operand::Nested(Box::new(Expression::from(Variable::Int(1))))
+            )]
+        );
+
+        let assignment = get_assignment("~ coins --");
+        assert_eq!(assignment.expression.tail[0].0, Operator::Subtract);
+    }
+
+    #[test]
+    fn assignments_are_not_temporary_by_default() {
+        assert!(!get_assignment("~ coins = 5").temporary);
+        assert!(!get_assignment("~ temperature = 5").temporary);
+    }
+
+    #[test]
+    fn temp_keyword_declares_temporary_variable() {
+        let assignment = get_assignment("~ temp coins = 5");
+
+        assert!(assignment.temporary);
+        assert_eq!(assignment.target, Address::Raw("coins".to_string()));
+        assert_eq!(assignment.expression, parse_expression("5").unwrap());
+    }
+
+    #[test]
+    fn temporary_variables_cannot_be_declared_with_compound_assignments() {
+        assert!(parse_assignment("~ temp coins += 5").is_err());
+        assert!(parse_assignment("~ temp coins++").is_err());
+    }
+
+    #[test]
+    fn assignment_without_equal_sign_yields_error() {
+        match parse_assignment("~ coins") {
+            Err(LineErrorKind::InvalidAssignment { .. }) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidAssignment` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn assignment_without_target_yields_error() {
+        match parse_assignment("~ = 5") {
+            Err(LineErrorKind::InvalidAssignment { .. }) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidAssignment` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn assignment_to_invalid_target_name_yields_error() {
+        match parse_assignment("~ coi$ns = 5") {
+            Err(LineErrorKind::InvalidAddress { .. }) => (),
+            other => panic!(
+                "expected `LineErrorKind::InvalidAddress` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn assignment_with_invalid_expression_yields_error() {
+        match parse_assignment("~ coins = 5 +") {
+            Err(LineErrorKind::ExpressionError(..)) => (),
+            other => panic!(
+                "expected `LineErrorKind::ExpressionError` but got {:?}",
+                other
+            ),
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! Parse multiline conditional blocks as marked up `ParsedLineKind` objects.
+//!
+//! Blocks span several lines. They begin with an open brace, which may be followed
+//! by a condition or a value to switch on, and end with a closing brace. Inside
+//! the block every line of the format `- condition:` begins a new branch.
+//!
+//! ```plain
+//! {
+//!     - visited_paris:
+//!         "Bonjour!"
+//!     - else:
+//!         "Hello!"
+//! }
+//! ```
+//!
+//! Alternative blocks begin with a sequence keyword instead of a condition. Every line
+//! inside of them which begins with a dash begins a new branch.
+//!
+//! ```plain
+//! {stopping:
+//!     - "Bonjour!"
+//!     - "Bonjour encore!"
+//! }
+//! ```
+
+use crate::{
+    consts::{BLOCK_BEGIN_MARKER, BLOCK_END_MARKER, DIVERT_MARKER, ELSE_KEYWORD, GATHER_MARKER},
+    error::{parse::line::LineErrorKind, utils::MetaData},
+    line::{
+        parse::{
+            parse_condition, parse_expression, parse_internal_line, split_line_at_separator_braces,
+        },
+        Condition, ConditionBuilder, ConditionKind, ParsedLineKind, StoryCondition,
+    },
+};
+
+use std::cmp::Ordering;
+
+/// Get the header of a line that begins a multiline conditional block.
+///
+/// Blocks begin with a line that only contains an open brace, or an open brace
+/// followed by a condition (or switch value) and a colon: `{condition:`. The header
+/// is the string between the brace and colon, which is empty for the former case.
+pub fn get_block_header(content: &str) -> Option<&str> {
+    let content = content.trim();
+
+    if !content.starts_with(BLOCK_BEGIN_MARKER) || content.contains(BLOCK_END_MARKER) {
+        return None;
+    }
+
+    let header = content.get(BLOCK_BEGIN_MARKER.len_utf8()..).unwrap().trim();
+
+    if header.is_empty() {
+        Some(header)
+    } else if header.ends_with(':') {
+        Some(header.get(..header.len() - 1).unwrap().trim())
+    } else {
+        None
+    }
+}
+
+/// Check whether a line ends a multiline conditional block.
+pub fn is_block_end(content: &str) -> bool {
+    content.trim().len() == BLOCK_END_MARKER.len_utf8()
+        && content.trim().starts_with(BLOCK_END_MARKER)
+}
+
+/// Check whether a line inside a multiline conditional block begins a new branch.
+pub fn is_block_branch(content: &str) -> bool {
+    split_block_branch(content).is_some()
+}
+
+/// Parse a `ParsedLineKind::ConditionalStart` from the header of a block.
+///
+/// If the block switches on a value the header is not a condition: the conditions
+/// are then set for every branch.
+pub fn parse_block_start(header: &str, is_switch: bool) -> Result<ParsedLineKind, LineErrorKind> {
+    let condition = if header.is_empty() || is_switch {
+        None
+    } else {
+        Some(parse_condition(header)?)
+    };
+
+    Ok(ParsedLineKind::ConditionalStart { condition })
+}
+
+/// Parse a `ParsedLineKind::ConditionalBranch` from a line if it begins a new branch.
+///
+/// If the block switches on a value, the condition for the branch is that the value
+/// is equal to the one given for the branch. The `else` keyword marks a branch without
+/// a condition. Any content after the colon is parsed as the first line in the branch.
+pub fn parse_block_branch(
+    content: &str,
+    switch_value: Option<&str>,
+    meta_data: &MetaData,
+) -> Result<Option<ParsedLineKind>, LineErrorKind> {
+    split_block_branch(content)
+        .map(|(head, tail)| {
+            let condition = if head == ELSE_KEYWORD {
+                None
+            } else if let Some(value) = switch_value {
+                Some(get_switch_condition(value, head)?)
+            } else {
+                Some(parse_condition(head)?)
+            };
+
+            let line = if tail.is_empty() {
+                None
+            } else {
+                Some(parse_internal_line(tail, meta_data)?)
+            };
+
+            Ok(ParsedLineKind::ConditionalBranch { condition, line })
+        })
+        .transpose()
+}
+
+/// Parse a `ParsedLineKind::ConditionalBranch` from a line in an alternative block.
+///
+/// Every line which begins with a dash begins a new branch. The branches have no
+/// conditions and any content after the dash is parsed as the first line in the branch.
+pub fn parse_sequence_branch(
+    content: &str,
+    meta_data: &MetaData,
+) -> Result<Option<ParsedLineKind>, LineErrorKind> {
+    let content = content.trim_start();
+
+    if !content.starts_with(GATHER_MARKER) || content.starts_with(DIVERT_MARKER) {
+        return Ok(None);
+    }
+
+    let tail = content.get(GATHER_MARKER.len_utf8()..).unwrap().trim();
+
+    let line = if tail.is_empty() {
+        None
+    } else {
+        Some(parse_internal_line(tail, meta_data)?)
+    };
+
+    Ok(Some(ParsedLineKind::ConditionalBranch {
+        condition: None,
+        line,
+    }))
+}
+
+/// Split a branch line into its condition and remaining content.
+fn split_block_branch(content: &str) -> Option<(&str, &str)> {
+    let content = content.trim_start();
+
+    if !content.starts_with(GATHER_MARKER) {
+        return None;
+    }
+
+    let tail = content.get(GATHER_MARKER.len_utf8()..).unwrap();
+
+    match split_line_at_separator_braces(tail, ":", Some(1)) {
+        Ok(parts) if parts.len() == 2 && !parts[0].trim().is_empty() => {
+            Some((parts[0].trim(), parts[1].trim()))
+        }
+        _ => None,
+    }
+}
+
+/// Get the condition that a switch value is equal to the value of a branch.
+fn get_switch_condition(value: &str, case: &str) -> Result<Condition, LineErrorKind> {
+    let kind = ConditionKind::Single(StoryCondition::Comparison {
+        lhs_variable: parse_expression(value)?,
+        rhs_variable: parse_expression(case)?,
+        ordering: Ordering::Equal,
+    });
+
+    Ok(ConditionBuilder::from_kind(&kind, false).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_headers_are_read_from_lines_with_only_an_open_brace_and_condition() {
+        assert_eq!(get_block_header("{"), Some(""));
+        assert_eq!(get_block_header("  {  "), Some(""));
+        assert_eq!(get_block_header("{visited_paris:"), Some("visited_paris"));
+        assert_eq!(get_block_header("{ x > 2 :"), Some("x > 2"));
+    }
+
+    #[test]
+    fn lines_with_inline_conditions_or_text_are_not_block_headers() {
+        assert!(get_block_header("{visited_paris: Bonjour!}").is_none());
+        assert!(get_block_header("{visited_paris: Bonjour!").is_none());
+        assert!(get_block_header("Hello, World!").is_none());
+    }
+
+    #[test]
+    fn block_end_is_a_line_with_only_a_closing_brace() {
+        assert!(is_block_end("}"));
+        assert!(is_block_end("  }  "));
+        assert!(!is_block_end("}}"));
+        assert!(!is_block_end("{x}"));
+    }
+
+    #[test]
+    fn block_branches_begin_with_a_dash_and_end_with_a_colon() {
+        assert!(is_block_branch("- x > 2:"));
+        assert!(is_block_branch("-else:"));
+        assert!(is_block_branch("- x > 2: Text"));
+        assert!(!is_block_branch("- Text"));
+        assert!(!is_block_branch("-: Text"));
+        assert!(!is_block_branch("x > 2:"));
+    }
+
+    #[test]
+    fn block_start_with_header_has_condition_unless_it_is_a_switch() {
+        match parse_block_start("x > 2", false).unwrap() {
+            ParsedLineKind::ConditionalStart { condition } => {
+                assert_eq!(condition, Some(parse_condition("x > 2").unwrap()))
+            }
+            other => panic!("expected `ConditionalStart` but got {:?}", other),
+        }
+
+        match parse_block_start("x", true).unwrap() {
+            ParsedLineKind::ConditionalStart { condition } => assert!(condition.is_none()),
+            other => panic!("expected `ConditionalStart` but got {:?}", other),
+        }
+
+        match parse_block_start("", false).unwrap() {
+            ParsedLineKind::ConditionalStart { condition } => assert!(condition.is_none()),
+            other => panic!("expected `ConditionalStart` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn else_branches_have_no_condition() {
+        match parse_block_branch("- else:", None, &().into()).unwrap() {
+            Some(ParsedLineKind::ConditionalBranch { condition, line }) => {
+                assert!(condition.is_none());
+                assert!(line.is_none());
+            }
+            other => panic!("expected `ConditionalBranch` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branch_content_after_the_colon_is_parsed_as_a_line() {
+        match parse_block_branch("- x > 2: Hello, World!", None, &().into()).unwrap() {
+            Some(ParsedLineKind::ConditionalBranch { condition, line }) => {
+                assert_eq!(condition, Some(parse_condition("x > 2").unwrap()));
+                assert_eq!(
+                    line,
+                    Some(parse_internal_line("Hello, World!", &().into()).unwrap())
+                );
+            }
+            other => panic!("expected `ConditionalBranch` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branches_in_switch_blocks_compare_the_value_to_the_switch_value() {
+        match parse_block_branch("- 2:", Some("x"), &().into()).unwrap() {
+            Some(ParsedLineKind::ConditionalBranch { condition, .. }) => {
+                assert_eq!(condition, Some(parse_condition("x == 2").unwrap()));
+            }
+            other => panic!("expected `ConditionalBranch` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lines_which_are_not_branches_return_none() {
+        assert!(parse_block_branch("- Hello!", None, &().into())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn every_line_beginning_with_a_dash_is_a_branch_in_alternative_blocks() {
+        match parse_sequence_branch("  - Hello: World!", &().into()).unwrap() {
+            Some(ParsedLineKind::ConditionalBranch { condition, line }) => {
+                assert!(condition.is_none());
+                assert_eq!(
+                    line,
+                    Some(parse_internal_line("Hello: World!", &().into()).unwrap())
+                );
+            }
+            other => panic!("expected `ConditionalBranch` but got {:?}", other),
+        }
+
+        match parse_sequence_branch("-", &().into()).unwrap() {
+            Some(ParsedLineKind::ConditionalBranch { condition, line }) => {
+                assert!(condition.is_none());
+                assert!(line.is_none());
+            }
+            other => panic!("expected `ConditionalBranch` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn diverts_and_regular_lines_are_not_branches_in_alternative_blocks() {
+        assert!(parse_sequence_branch("-> knot", &().into())
+            .unwrap()
+            .is_none());
+        assert!(parse_sequence_branch("Hello!", &().into())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn invalid_branch_conditions_yield_errors() {
+        assert!(parse_block_branch("- x >:", None, &().into()).is_err());
+    }
+}
@@ -5,8 +5,8 @@ use crate::{
     error::{parse::line::LineErrorKind, utils::MetaData},
     line::{
         parse::{
-            parse_choice_condition, parse_internal_line, parse_markers_and_text,
-            split_at_divert_marker,
+            get_unescaped_indices, parse_choice_condition, parse_internal_line,
+            parse_markers_and_text, split_at_divert_marker, split_label_from_text,
         },
         Content, InternalChoice, InternalChoiceBuilder, InternalLine, ParsedLineKind,
     },
@@ -19,12 +19,17 @@ pub fn parse_choice(
 ) -> Result<Option<ParsedLineKind>, LineErrorKind> {
     parse_choice_markers_and_text(content)?
         .map(|(level, is_sticky, line)| {
-            parse_choice_data(line, meta_data)
-                .map(|mut choice_data| {
-                    choice_data.is_sticky = is_sticky;
-                    (level, choice_data)
-                })
-                .map(|(level, choice_data)| ParsedLineKind::Choice { level, choice_data })
+            let (label, line) = split_label_from_text(line);
+
+            parse_choice_data(line, meta_data).map(|mut choice_data| {
+                choice_data.is_sticky = is_sticky;
+
+                ParsedLineKind::Choice {
+                    level,
+                    label,
+                    choice_data,
+                }
+            })
         })
         .transpose()
 }
@@ -114,14 +119,14 @@ fn marker_exists_before_text(line: &str, marker: char) -> bool {
 /// These are demarcated by `[]` brackets. Content before the bracket is both selection
 /// and display text. Content inside the bracket is only for the selection and content
 /// after the bracket only for display.
+///
+/// Escaped brackets are kept as text.
 fn parse_choice_line_variants(line: &str) -> Result<(String, String), LineErrorKind> {
-    match (line.find('['), line.find(']')) {
-        (Some(i), Some(j)) if i < j => {
-            // Ensure that we don't have more brackets
-            if line.rfind('[').unwrap() != i || line.rfind(']').unwrap() != j {
-                return Err(LineErrorKind::UnmatchedBrackets);
-            }
+    let open_indices = get_unescaped_indices(line, "[");
+    let close_indices = get_unescaped_indices(line, "]");
 
+    match (open_indices.as_slice(), close_indices.as_slice()) {
+        (&[i], &[j]) if i < j => {
             let head = line.get(..i).unwrap();
             let inside = line.get(i + 1..j).unwrap();
             let tail = line.get(j + 1..).unwrap();
@@ -131,7 +136,7 @@ fn parse_choice_line_variants(line: &str) -> Result<(String, String), LineErrorK
 
             Ok((selection_text, display_text))
         }
-        (None, None) => Ok((line.to_string(), line.to_string())),
+        ([], []) => Ok((line.to_string(), line.to_string())),
         _ => Err(LineErrorKind::UnmatchedBrackets),
     }
 }
@@ -209,12 +214,52 @@ pub(crate) mod tests {
         assert_eq!(line, "Choice");
     }
 
+    #[test]
+    fn choices_may_be_labelled_with_a_name_in_parenthesis() {
+        match parse_choice("* (greet) Hello!", &().into())
+            .unwrap()
+            .unwrap()
+        {
+            ParsedLineKind::Choice {
+                label, choice_data, ..
+            } => {
+                assert_eq!(label, Some("greet".to_string()));
+                assert_eq!(
+                    choice_data.display_text,
+                    parse_internal_line("Hello!", &().into()).unwrap()
+                );
+            }
+            other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
+        }
+
+        match parse_choice("* (greet) {visited} Hello!", &().into())
+            .unwrap()
+            .unwrap()
+        {
+            ParsedLineKind::Choice {
+                label, choice_data, ..
+            } => {
+                assert_eq!(label, Some("greet".to_string()));
+                assert!(choice_data.condition.is_some());
+            }
+            other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn choices_are_not_labelled_by_default() {
+        match parse_choice("* Hello!", &().into()).unwrap().unwrap() {
+            ParsedLineKind::Choice { label, .. } => assert!(label.is_none()),
+            other => panic!("expected `ParsedLineKind::Choice` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn simple_lines_parse_into_choices_with_same_display_and_selection_texts() {
         let choice = parse_choice_data("Choice line", &().into()).unwrap();
         let comparison = parse_internal_line("Choice line", &().into()).unwrap();
 
-        assert_eq!(*choice.selection_text.lock().unwrap(), comparison);
+        assert_eq!(*choice.selection_text, comparison);
         assert_eq!(choice.display_text, comparison);
     }
 
@@ -222,7 +267,7 @@ pub(crate) mod tests {
     fn choices_can_be_parsed_with_alternatives_in_selection_text() {
         let choice = parse_choice_data("Hi! {One|Two}", &().into()).unwrap();
         assert_eq!(
-            *choice.selection_text.lock().unwrap(),
+            *choice.selection_text,
             parse_internal_line("Hi! {One|Two}", &().into()).unwrap(),
         );
     }
@@ -231,7 +276,7 @@ pub(crate) mod tests {
     fn braces_with_backslash_are_not_conditions() {
         let choice = parse_choice_data("\\{One|Two}", &().into()).unwrap();
         assert_eq!(
-            *choice.selection_text.lock().unwrap(),
+            *choice.selection_text,
             parse_internal_line("{One|Two}", &().into()).unwrap(),
         );
     }
@@ -240,7 +285,7 @@ pub(crate) mod tests {
     fn alternatives_can_be_within_brackets() {
         let choice = parse_choice_data("[{One|Two}]", &().into()).unwrap();
         assert_eq!(
-            *choice.selection_text.lock().unwrap(),
+            *choice.selection_text,
             parse_internal_line("{One|Two}", &().into()).unwrap(),
         );
     }
@@ -250,7 +295,7 @@ pub(crate) mod tests {
         let choice = parse_choice_data("Selection[] plus display", &().into()).unwrap();
 
         assert_eq!(
-            *choice.selection_text.lock().unwrap(),
+            *choice.selection_text,
             parse_internal_line("Selection", &().into()).unwrap()
         );
         assert_eq!(
@@ -261,7 +306,7 @@ pub(crate) mod tests {
         let choice = parse_choice_data("[Separate selection]And display", &().into()).unwrap();
 
         assert_eq!(
-            *choice.selection_text.lock().unwrap(),
+            *choice.selection_text,
             parse_internal_line("Separate selection", &().into()).unwrap()
         );
         assert_eq!(
@@ -270,6 +315,22 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn escaped_brackets_are_kept_as_text_in_choices() {
+        let choice = parse_choice_data("Open \\[box\\][ now]", &().into()).unwrap();
+
+        assert_eq!(
+            *choice.selection_text,
+            parse_internal_line("Open \\[box\\] now", &().into()).unwrap()
+        );
+        assert_eq!(
+            choice.display_text,
+            parse_internal_line("Open \\[box\\]", &().into()).unwrap()
+        );
+
+        assert!(parse_choice_data("Open \\[box]", &().into()).is_err());
+    }
+
     #[test]
     fn choice_with_no_selection_text_but_divert_is_fallback() {
         assert!(
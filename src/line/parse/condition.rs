@@ -8,12 +8,13 @@ use crate::{
         line::LineErrorKind,
     },
     line::{
+        expression::Operator,
         parse::{
-            parse_expression, parse_variable, split_line_at_separator_braces,
-            split_line_at_separator_parenthesis, split_line_into_groups_braces, LinePart,
+            expression::{find_operator, COMPARISON_OPERATORS, CONTAINS_OPERATORS},
+            parse_expression, split_line_at_separator_braces, split_line_at_separator_parenthesis,
+            split_line_into_groups_braces, LinePart,
         },
         Condition, ConditionBuilder, ConditionItem, ConditionKind, Expression, StoryCondition,
-        Variable,
     },
 };
 
@@ -87,7 +88,7 @@ pub fn parse_choice_condition(line: &mut String) -> Result<Option<Condition>, Li
 /// be `&&` or `||` respectively), since chained conditions need them. These splits will
 /// not be done within enclosed parenthesis: all grouped conditions inside those will be
 /// treated as whole.
-fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
+pub fn parse_condition(content: &str) -> Result<Condition, ConditionError> {
     let mut buffer = content.to_string();
 
     let mut items: Vec<(Link, ConditionItem)> = Vec::new();
@@ -336,56 +337,65 @@ fn get_split_index(content: &str, separator: &str) -> Result<usize, LineErrorKin
 
 /// Parse a `StoryCondition` from a line and return with whether it is negated.
 ///
-/// An extra negation comes from conditions with `!=` markers.
+/// An extra negation comes from conditions with `!=`, `hasnt` or `!?` markers. Conditions
+/// with `<=` and `>=` markers are parsed as expressions, which evaluate to whether the
+/// comparison holds, since they are not the negation of a `>` or `<` comparison for lists.
+///
+/// Comparisons have lower precedence than containment checks, which is why they are
+/// searched for first. Operators inside of parenthesis or strings belong to inner
+/// expressions and are not split at.
 ///
 /// # Notes
 /// *   Assumes that any preceeding `not` has been trimmed from the conditional. The
-///     negation will come purely from a `!=` marker.
+///     negation will come purely from the markers.
 fn parse_story_condition(line: &str) -> Result<(StoryCondition, bool), ConditionError> {
-    let ordering_search = line
-        .find("==")
-        .map(|i| (i, Ordering::Equal, 0, 2, false))
-        .or(line.find("!=").map(|i| (i, Ordering::Equal, 0, 2, true)))
-        .or(line.find("<=").map(|i| (i, Ordering::Less, 1, 2, false)))
-        .or(line
-            .find(">=")
-            .map(|i| (i, Ordering::Greater, -1, 2, false)))
-        .or(line.find("<").map(|i| (i, Ordering::Less, 0, 1, false)))
-        .or(line.find(">").map(|i| (i, Ordering::Greater, 0, 1, false)));
-
-    match ordering_search {
-        Some((index, ordering, adjustment, symbol_length, negate)) => {
-            let head = line.get(..index).unwrap().trim();
-            let tail = line.get(index + symbol_length..).unwrap().trim();
-
-            let lhs_variable = parse_comparison_expression(head)?;
-            let mut rhs_variable = parse_comparison_expression(tail)?;
-
-            if adjustment != 0 {
-                rhs_variable.add(Variable::Int(adjustment));
-            }
+    let split_at_operator = |index: usize, pattern: &str| {
+        let head = line.get(..index).unwrap().trim();
+        let tail = line.get(index + pattern.len()..).unwrap().trim();
 
-            Ok((
-                StoryCondition::Comparison {
-                    lhs_variable,
-                    rhs_variable,
-                    ordering,
-                },
-                negate,
-            ))
-        }
-        None => {
-            let variable = parse_condition_variable(line)?;
+        parse_comparison_expression(head)
+            .and_then(|lhs| parse_comparison_expression(tail).map(|rhs| (lhs, rhs)))
+    };
 
-            Ok((StoryCondition::IsTrueLike { variable }, false))
+    if let Some((index, pattern, operator)) = find_operator(line, COMPARISON_OPERATORS) {
+        if let Operator::LessOrEqual | Operator::GreaterOrEqual = operator {
+            let expression = parse_comparison_expression(line)?;
+
+            return Ok((StoryCondition::IsTrueLike { expression }, false));
         }
-    }
-}
 
-/// Parse a variable from a string and map any error to `ConditionError`
-fn parse_condition_variable(content: &str) -> Result<Variable, ConditionError> {
-    parse_variable(content)
-        .map_err(|err| ConditionError::from_kind(content, ConditionErrorKind::InvalidVariable(err)))
+        let (lhs_variable, rhs_variable) = split_at_operator(index, pattern)?;
+
+        let (ordering, negate) = match operator {
+            Operator::NotEqual => (Ordering::Equal, true),
+            Operator::Less => (Ordering::Less, false),
+            Operator::Greater => (Ordering::Greater, false),
+            _ => (Ordering::Equal, false),
+        };
+
+        Ok((
+            StoryCondition::Comparison {
+                lhs_variable,
+                rhs_variable,
+                ordering,
+            },
+            negate,
+        ))
+    } else if let Some((index, pattern, operator)) = find_operator(line, CONTAINS_OPERATORS) {
+        let (lhs_variable, rhs_variable) = split_at_operator(index, pattern)?;
+
+        Ok((
+            StoryCondition::Contains {
+                lhs_variable,
+                rhs_variable,
+            },
+            operator == Operator::NotContains,
+        ))
+    } else {
+        let expression = parse_comparison_expression(line)?;
+
+        Ok((StoryCondition::IsTrueLike { expression }, false))
+    }
 }
 
 /// Parse an expression from a string and map any error to `ConditionError`
@@ -437,10 +447,7 @@ mod tests {
 
     use crate::{
         knot::Address,
-        line::{
-            condition::AndOr,
-            expression::{Operand, Operator},
-        },
+        line::{condition::AndOr, expression::Operand, Variable},
     };
 
     #[test]
@@ -574,10 +581,10 @@ mod tests {
         let (condition, _) = parse_story_condition(&mut line).unwrap();
 
         match &condition {
-            StoryCondition::IsTrueLike { variable } => {
+            StoryCondition::IsTrueLike { expression } => {
                 assert_eq!(
-                    variable,
-                    &Variable::Address(Address::Raw("knot_name".to_string()))
+                    expression,
+                    &Expression::from(Variable::Address(Address::Raw("knot_name".to_string())))
                 );
             }
             other => panic!("expected `StoryCondition::IsTrueLike` but got {:?}", other),
@@ -605,13 +612,6 @@ mod tests {
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name >= 2".to_string();
-
-        match parse_story_condition(&mut line).unwrap().0 {
-            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Greater),
-            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
-        }
-
         let mut line = "knot_name < 2".to_string();
 
         match parse_story_condition(&mut line).unwrap().0 {
@@ -619,13 +619,6 @@ mod tests {
             other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
         }
 
-        let mut line = "knot_name <= 2".to_string();
-
-        match parse_story_condition(&mut line).unwrap().0 {
-            StoryCondition::Comparison { ordering, .. } => assert_eq!(ordering, Ordering::Less),
-            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
-        }
-
         let mut line = "knot_name == 2".to_string();
 
         match parse_story_condition(&mut line).unwrap().0 {
@@ -746,36 +739,85 @@ mod tests {
     }
 
     #[test]
-    fn parsing_story_conditions_with_larger_than_or_equal_condition_adjusts_rhs_value() {
-        let mut line = "knot >= 2".to_string();
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+    fn larger_than_or_equal_story_conditions_are_parsed_as_expressions() {
+        let (condition, negate) = parse_story_condition("knot >= 2").unwrap();
 
-        let mut equiv_line = "knot > 2 - 1".to_string();
-        let (mut equiv_condition, _) = parse_story_condition(&mut equiv_line).unwrap();
+        assert_eq!(
+            condition,
+            StoryCondition::IsTrueLike {
+                expression: parse_expression("knot >= 2").unwrap()
+            }
+        );
+        assert!(!negate);
+    }
 
-        // The function does `2 + (-1)` for the adjustment so we flip the term here
-        match equiv_condition {
-            StoryCondition::Comparison {
-                ref mut rhs_variable,
-                ..
-            } => {
-                rhs_variable.tail[0] = (Operator::Add, Operand::Variable((-1).into()));
+    #[test]
+    fn less_than_or_equal_story_conditions_are_parsed_as_expressions() {
+        let (condition, negate) = parse_story_condition("knot <= 2").unwrap();
+
+        assert_eq!(
+            condition,
+            StoryCondition::IsTrueLike {
+                expression: parse_expression("knot <= 2").unwrap()
             }
-            _ => unreachable!(),
-        }
+        );
+        assert!(!negate);
+    }
+
+    #[test]
+    fn has_and_question_mark_markers_parse_into_contains_conditions() {
+        let (condition, negate) = parse_story_condition("inventory has sword").unwrap();
 
-        assert_eq!(condition, equiv_condition);
+        assert_eq!(
+            condition,
+            StoryCondition::Contains {
+                lhs_variable: Variable::Address(Address::Raw("inventory".to_string())).into(),
+                rhs_variable: Variable::Address(Address::Raw("sword".to_string())).into(),
+            }
+        );
+        assert!(!negate);
+
+        assert_eq!(
+            parse_story_condition("inventory ? sword").unwrap(),
+            (condition, false)
+        );
     }
 
     #[test]
-    fn parsing_story_conditions_with_less_than_or_equal_condition_adjusts_rhs_value() {
-        let mut line = "knot <= 2".to_string();
-        let (condition, _) = parse_story_condition(&mut line).unwrap();
+    fn hasnt_and_negated_question_mark_markers_parse_into_negated_contains_conditions() {
+        let (condition, negate) = parse_story_condition("inventory hasnt sword").unwrap();
 
-        let mut equiv_line = "knot < 2 + 1".to_string();
-        let (equiv_condition, _) = parse_story_condition(&mut equiv_line).unwrap();
+        assert!(negate);
+        assert_eq!(
+            parse_story_condition("inventory !? sword").unwrap(),
+            (condition, true)
+        );
+    }
 
-        assert_eq!(condition, equiv_condition);
+    #[test]
+    fn contains_markers_inside_strings_are_ignored() {
+        match parse_story_condition("answer == \"who has it?\"").unwrap() {
+            (StoryCondition::Comparison { .. }, false) => (),
+            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn divert_markers_are_not_comparison_operators() {
+        match parse_story_condition("TURNS_SINCE(-> knot) > 2").unwrap() {
+            (
+                StoryCondition::Comparison {
+                    ordering: Ordering::Greater,
+                    lhs_variable,
+                    ..
+                },
+                false,
+            ) => match lhs_variable.head {
+                Operand::FunctionCall(ref call) => assert_eq!(&call.name, "TURNS_SINCE"),
+                other => panic!("expected a function call but got {:?}", other),
+            },
+            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
+        }
     }
 
     #[test]
@@ -792,10 +834,41 @@ mod tests {
         assert_eq!(condition, negated_condition);
     }
 
+    #[test]
+    fn negations_inside_story_conditions_are_parsed_as_expressions() {
+        let (condition, negate) = parse_story_condition("not superfluous").unwrap();
+
+        assert!(!negate);
+        assert_eq!(
+            condition,
+            StoryCondition::IsTrueLike {
+                expression: parse_expression("not superfluous").unwrap()
+            }
+        );
+
+        assert!(parse_story_condition("not superfluous > 3").is_ok());
+    }
+
+    #[test]
+    fn comparisons_inside_parenthesis_belong_to_the_inner_expression() {
+        let (condition, _) = parse_story_condition("POW(a > 2, 2) == (a < b)").unwrap();
+
+        match condition {
+            StoryCondition::Comparison {
+                lhs_variable,
+                rhs_variable,
+                ordering,
+            } => {
+                assert_eq!(ordering, Ordering::Equal);
+                assert_eq!(lhs_variable, parse_expression("POW(a > 2, 2)").unwrap());
+                assert_eq!(rhs_variable, parse_expression("(a < b)").unwrap());
+            }
+            other => panic!("expected `StoryCondition::Comparison` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn parsing_bad_conditions_give_error() {
-        assert!(parse_story_condition("not superfluous").is_err());
-        assert!(parse_story_condition("not superfluous > 3").is_err());
         assert!(parse_story_condition("no_value >").is_err());
         assert!(parse_story_condition("too_many_values > 3 2").is_err());
         assert!(parse_story_condition("").is_err());
@@ -807,10 +880,10 @@ mod tests {
         let condition = parse_choice_condition(&mut line).unwrap().unwrap();
 
         match &condition.story_condition() {
-            StoryCondition::IsTrueLike { variable } => {
+            StoryCondition::IsTrueLike { expression } => {
                 assert_eq!(
-                    variable,
-                    &Variable::Address(Address::Raw("knot_name".to_string()))
+                    expression,
+                    &Expression::from(Variable::Address(Address::Raw("knot_name".to_string())))
                 );
             }
             _ => panic!(),
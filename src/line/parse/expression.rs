@@ -1,38 +1,223 @@
 //! Parse `Expression` objects.
 
 use crate::{
+    consts::DIVERT_MARKER,
     error::parse::{
         expression::{ExpressionError, ExpressionErrorKind},
         line::LineErrorKind,
     },
     line::{
         expression::{apply_order_of_operations, Operand, Operator},
-        parse::{parse_variable, split_line_at_separator_parenthesis},
-        Expression,
+        parse::{
+            function::split_arguments, is_escaped, parse_function_call, parse_variable,
+            split_line_at_separator_parenthesis,
+        },
+        Expression, InkList, Variable,
     },
 };
 
 /// List of valid mathematical operators.
-pub const MATHEMATICAL_OPERATORS: &[char] = &['+', '-', '*', '/', '%'];
+pub const MATHEMATICAL_OPERATORS: &[char] = &['+', '-', '*', '/', '%', '^'];
 
-/// Parse a mathematical `Expression` from a string.
+/// Logical operators, which have the lowest precedence in expressions.
+const LOGICAL_OPERATORS: &[(&str, Operator)] = &[
+    (" and ", Operator::And),
+    ("&&", Operator::And),
+    (" or ", Operator::Or),
+    ("||", Operator::Or),
+];
+
+/// Comparison operators, which have precedence over logical operators.
+///
+/// Operators which start with the same characters are ordered longest first, so that
+/// eg. `<=` is found before `<` at the same position.
+pub(crate) const COMPARISON_OPERATORS: &[(&str, Operator)] = &[
+    ("==", Operator::Equal),
+    ("!=", Operator::NotEqual),
+    ("<=", Operator::LessOrEqual),
+    (">=", Operator::GreaterOrEqual),
+    ("<", Operator::Less),
+    (">", Operator::Greater),
+];
+
+/// Containment operators, which have precedence over comparisons but not over
+/// mathematical operators.
+pub(crate) const CONTAINS_OPERATORS: &[(&str, Operator)] = &[
+    ("!?", Operator::NotContains),
+    ("?", Operator::Contains),
+    (" hasnt ", Operator::NotContains),
+    (" has ", Operator::Contains),
+];
+
+/// Parse an `Expression` from a string.
 ///
-/// The expression may be a numerical expression or string concatenation.
+/// The expression may be a numerical expression, string concatenation, a comparison
+/// or a logical combination of these.
 ///
 /// Numerical expressions may use the standard mathematical operators and parenthesis.
 /// Terms within parenthesis will be grouped together into single units, and order
 /// of operator precedence is applied to group multiplication, division and remainder
 /// operations together before addition and subtraction.
 ///
-/// String concatenation should only use addition.
+/// String concatenation should only use addition. Lists are combined with addition,
+/// subtraction and intersection (`^`).
+///
+/// Comparisons (`==`, `!=`, `<`, `<=`, `>`, `>=`), containment checks (`?`, `!?`, `has`,
+/// `hasnt`) and logical operators (`and`, `&&`, `or`, `||`) evaluate to `true` or `false`.
+/// In order of lowest to highest precedence the terms are split at logical operators,
+/// comparisons, containment checks and finally mathematical operators. Negations (`not`
+/// or `!`) apply to the single operand after them: `not a == b` is `(not a) == b`.
+///
+/// Divert targets (`-> knot`) are single values which are not combined with
+/// mathematical operators.
 pub fn parse_expression(content: &str) -> Result<Expression, ExpressionError> {
+    parse_expression_kind(content).map_err(|kind| ExpressionError {
+        content: content.to_string(),
+        kind,
+    })
+}
+
+/// Parse an `Expression` from a string, splitting it by operator precedence.
+fn parse_expression_kind(content: &str) -> Result<Expression, ExpressionErrorKind> {
+    if let Some(terms) = split_line_at_operators(content, LOGICAL_OPERATORS) {
+        return parse_expression_from_operator_terms(terms);
+    }
+
+    if let Some(terms) = split_line_at_operators(content, COMPARISON_OPERATORS)
+        .or_else(|| split_line_at_operators(content, CONTAINS_OPERATORS))
+    {
+        return parse_expression_from_operator_terms(terms);
+    }
+
+    if content.trim_start().starts_with(DIVERT_MARKER) {
+        return parse_variable(content.trim())
+            .map(|variable| Expression::from(variable))
+            .map_err(|err| ExpressionErrorKind::InvalidVariable(err));
+    }
+
     split_line_into_operation_terms(content)
         .and_then(|operations| parse_expression_from_operation_terms(operations))
         .map(|expression| apply_order_of_operations(&expression))
-        .map_err(|kind| ExpressionError {
-            content: content.to_string(),
-            kind,
+}
+
+/// Parse terms which were split at operators of the same precedence into an `Expression`.
+///
+/// Every term is parsed as an expression of its own, which becomes a nested operand
+/// unless it is a single operand.
+fn parse_expression_from_operator_terms(
+    (head, tail): (&str, Vec<(Operator, &str)>),
+) -> Result<Expression, ExpressionErrorKind> {
+    let head = parse_expression_kind(head).map(get_operand_from_expression)?;
+
+    let tail = tail
+        .into_iter()
+        .map(|(operator, content)| {
+            parse_expression_kind(content)
+                .map(get_operand_from_expression)
+                .map(|operand| (operator, operand))
         })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Expression { head, tail })
+}
+
+/// Get the head of an expression with no tail, or nest the whole expression.
+fn get_operand_from_expression(expression: Expression) -> Operand {
+    if expression.tail.is_empty() {
+        expression.head
+    } else {
+        Operand::Nested(Box::new(expression))
+    }
+}
+
+/// Split off a leading `not` keyword or `!` from a line and return the remaining content.
+///
+/// A leading `!=` or `!?` is not a negation.
+fn split_off_negation(content: &str) -> Option<&str> {
+    let content = content.trim_start();
+
+    if let Some(tail) = content.strip_prefix("not") {
+        if tail.starts_with(|c: char| c.is_whitespace() || c == '(') {
+            return Some(tail);
+        }
+    }
+
+    content
+        .strip_prefix('!')
+        .filter(|tail| !tail.starts_with('=') && !tail.starts_with('?'))
+}
+
+/// Split a line at all given operators which are outside of parenthesis and strings.
+///
+/// Returns the head term along with the operators and terms that follow it, or `None`
+/// if no operator was found.
+fn split_line_at_operators<'a>(
+    content: &'a str,
+    operators: &[(&str, Operator)],
+) -> Option<(&'a str, Vec<(Operator, &'a str)>)> {
+    let mut splits = Vec::new();
+    let mut last_index = 0;
+
+    while let Some((index, pattern, operator)) =
+        find_operator(content.get(last_index..).unwrap(), operators)
+    {
+        let index = index + last_index;
+        splits.push((index, pattern.len(), operator));
+        last_index = index + pattern.len();
+    }
+
+    splits.first().map(|&(first_index, ..)| {
+        let head = content.get(..first_index).unwrap();
+
+        let tail = splits
+            .iter()
+            .enumerate()
+            .map(|(i, &(index, length, operator))| {
+                let end = splits
+                    .get(i + 1)
+                    .map(|&(next, ..)| next)
+                    .unwrap_or(content.len());
+
+                (operator, content.get(index + length..end).unwrap())
+            })
+            .collect();
+
+        (head, tail)
+    })
+}
+
+/// Find the first of the given operators which is outside of parenthesis and strings.
+///
+/// The `>` of divert markers (`-> knot`) is not an operator and is skipped.
+pub(crate) fn find_operator<'b>(
+    content: &str,
+    operators: &[(&'b str, Operator)],
+) -> Option<(usize, &'b str, Operator)> {
+    let mut depth = 0;
+    let mut in_string = false;
+
+    for (i, c) in content.char_indices() {
+        match c {
+            '"' if !is_escaped(content, i) => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth -= 1,
+            _ if !in_string && depth == 0 => {
+                let tail = content.get(i..).unwrap();
+                let is_divert_marker = c == '>' && content.get(..i).unwrap().ends_with('-');
+
+                let found = operators
+                    .iter()
+                    .find(|(pattern, _)| tail.starts_with(pattern) && !is_divert_marker);
+
+                if let Some(&(pattern, operator)) = found {
+                    return Some((i, pattern, operator));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
 }
 
 /// Parse a list of operation terms into a single `Expression`.
@@ -135,14 +320,29 @@ fn split_line_into_operation_terms(content: &str) -> Result<Vec<String>, Express
 
 /// Parse the `Operand` from an expression.
 ///
+/// Operands are nested expressions in parenthesis, function calls or single variables,
+/// which may be negated by a leading `not` or `!`.
+///
+/// Parenthesis which are empty or contain comma separated items are list values:
+/// `()` is an empty list and `(a, b)` is the union of its items.
+///
 /// Assumes that the given string is trimmed of whitespace from both ends.
 fn parse_operand(content: &str) -> Result<Operand, ExpressionErrorKind> {
-    if content.starts_with('(') && content.ends_with(')') && content.len() > 1 {
+    if let Some(tail) = split_off_negation(content) {
+        parse_operand(tail.trim()).map(|operand| Operand::Not(Box::new(operand)))
+    } else if content.starts_with('(') && content.ends_with(')') && content.len() > 1 {
         let inner = content.get(1..content.bytes().len() - 1).unwrap();
+        let items = split_arguments(inner);
 
-        parse_expression(inner)
-            .map(|expression| Operand::Nested(Box::new(expression)))
-            .map_err(|err| err.kind)
+        if items.is_empty() {
+            Ok(Operand::Variable(Variable::List(InkList::new())))
+        } else if items.len() > 1 {
+            parse_list_union(&items)
+        } else {
+            parse_expression_kind(inner).map(|expression| Operand::Nested(Box::new(expression)))
+        }
+    } else if let Some(function_call) = parse_function_call(content) {
+        function_call.map(|function_call| Operand::FunctionCall(function_call))
     } else {
         parse_variable(content)
             .map(|variable| Operand::Variable(variable))
@@ -150,6 +350,26 @@ fn parse_operand(content: &str) -> Result<Operand, ExpressionErrorKind> {
     }
 }
 
+/// Parse the union of comma separated list items into a nested expression.
+fn parse_list_union(items: &[&str]) -> Result<Operand, ExpressionErrorKind> {
+    let operands = items
+        .iter()
+        .map(|item| parse_operand(item.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (head, tail) = operands.split_first().unwrap();
+
+    let expression = Expression {
+        head: head.clone(),
+        tail: tail
+            .iter()
+            .map(|operand| (Operator::Add, operand.clone()))
+            .collect(),
+    };
+
+    Ok(Operand::Nested(Box::new(expression)))
+}
+
 /// Split off the initial operator and return its type.
 ///
 /// Assumes to be called on lines for which operators were definitely found. This should
@@ -161,6 +381,7 @@ fn split_off_operator(buffer: &mut String) -> Option<Operator> {
         '*' => Some(Operator::Multiply),
         '/' => Some(Operator::Divide),
         '%' => Some(Operator::Remainder),
+        '^' => Some(Operator::Intersect),
         _ => None,
     });
 
@@ -173,7 +394,7 @@ fn split_off_operator(buffer: &mut String) -> Option<Operator> {
 
 /// Split the string corresponding to the next whole operation from the buffer.
 ///
-/// Splits occur when mathematical operators '+', '-', '*', '/' and '%' are encountered
+/// Splits occur when mathematical operators '+', '-', '*', '/', '%' and '^' are encountered
 /// outside of parenthesis and strings (marked by '""' marks).
 ///
 /// For an input buffer of `a + (b * c) - d` this returns `a `, leaving the buffer as
@@ -195,7 +416,13 @@ fn read_next_operation_string(buffer: &mut String) -> Result<String, ExpressionE
 
         if buffer
             .get(..index + 1)
-            .map(|s| s.matches(|c| c == '"').count() % 2 == 0)
+            .map(|s| {
+                s.match_indices('"')
+                    .filter(|&(i, _)| !is_escaped(s, i))
+                    .count()
+                    % 2
+                    == 0
+            })
             .unwrap_or(true)
             || index >= tail.bytes().len()
         {
@@ -233,6 +460,7 @@ fn get_closest_split_index(content: &str) -> Result<usize, LineErrorKind> {
         .and_then(|current_min| get_split_index(&content, "*").map(|next| current_min.min(next)))
         .and_then(|current_min| get_split_index(&content, "/").map(|next| current_min.min(next)))
         .and_then(|current_min| get_split_index(&content, "%").map(|next| current_min.min(next)))
+        .and_then(|current_min| get_split_index(&content, "^").map(|next| current_min.min(next)))
 }
 
 /// Return the lowest index for the given separator keyword in the line.
@@ -302,62 +530,62 @@ mod tests {
 
     #[test]
     fn many_operations_created_nested_structure_based_on_operator_precedence() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + 2 - 2 * 3 + 1 / 5 + 5").unwrap();
         let equiv_expression = parse_expression("1 + 2 - (2 * 3) + (1 / 5) + 5").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
-            evaluate_expression(&equiv_expression, &data).unwrap()
+            evaluate_expression(&expression, &mut data).unwrap(),
+            evaluate_expression(&equiv_expression, &mut data).unwrap()
         );
     }
 
     #[test]
     fn whitespace_does_not_matter() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + 2 - 2 * 3 + 1 / 5 + 5").unwrap();
         let equiv_expression = parse_expression("1+2-(2*3)+(1/5)+5").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
-            evaluate_expression(&equiv_expression, &data).unwrap()
+            evaluate_expression(&expression, &mut data).unwrap(),
+            evaluate_expression(&equiv_expression, &mut data).unwrap()
         );
     }
 
     #[test]
     fn nested_parenthesis_are_evaluated_correctly() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("1 + ((2 * (4 + 6)) * (3 - 5))").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(-39),
         );
     }
 
     #[test]
     fn parenthesis_can_nest_several_levels_at_once() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("((((1 + 2))))").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::Int(3),
         );
     }
 
     #[test]
     fn strings_can_be_inside_expressions() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let expression = parse_expression("\"str\" + \"ing\"").unwrap();
 
         assert_eq!(
-            evaluate_expression(&expression, &data).unwrap(),
+            evaluate_expression(&expression, &mut data).unwrap(),
             Variable::String("string".to_string())
         );
     }
@@ -498,6 +726,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn divert_targets_are_parsed_as_single_variables() {
+        assert_eq!(
+            parse_expression("-> knot.stitch").unwrap(),
+            Expression::from(Variable::Divert(Address::Raw("knot.stitch".to_string())))
+        );
+    }
+
     #[test]
     fn variables_may_be_multibyte_characters() {
         assert_eq!(
@@ -522,6 +758,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn operators_inside_strings_with_escaped_quotes_are_not_split() {
+        assert_eq!(
+            split_line_into_operation_terms("\"a \\\" + b\" + c").unwrap(),
+            &["\"a \\\" + b\" ", "+ c"]
+        );
+    }
+
     #[test]
     fn ummatched_string_quotes_keeps_all_content_from_opening_quote_as_one() {
         assert_eq!(
@@ -539,4 +783,115 @@ mod tests {
             &["\"one\" ", "+ word", "-with", "-dash\""]
         );
     }
+
+    #[test]
+    fn comparisons_split_expressions_into_nested_terms() {
+        let expression = parse_expression("a + 1 == b * 2").unwrap();
+
+        assert_eq!(
+            expression.head,
+            get_operand_from_expression(parse_expression("a + 1").unwrap())
+        );
+        assert_eq!(
+            expression.tail,
+            &[(
+                Operator::Equal,
+                get_operand_from_expression(parse_expression("b * 2").unwrap())
+            )]
+        );
+    }
+
+    #[test]
+    fn logical_operators_have_lower_precedence_than_comparisons() {
+        let expression = parse_expression("a > 1 and b or c").unwrap();
+
+        assert_eq!(
+            expression.head,
+            Operand::Nested(Box::new(parse_expression("a > 1").unwrap()))
+        );
+        assert_eq!(expression.tail[0].0, Operator::And);
+        assert_eq!(expression.tail[1].0, Operator::Or);
+
+        assert_eq!(
+            parse_expression("a && b || c").unwrap(),
+            parse_expression("a and b or c").unwrap()
+        );
+    }
+
+    #[test]
+    fn all_comparison_and_containment_operators_are_parsed() {
+        let operators = &[
+            ("==", Operator::Equal),
+            ("!=", Operator::NotEqual),
+            ("<", Operator::Less),
+            ("<=", Operator::LessOrEqual),
+            (">", Operator::Greater),
+            (">=", Operator::GreaterOrEqual),
+            ("?", Operator::Contains),
+            ("!?", Operator::NotContains),
+            ("has", Operator::Contains),
+            ("hasnt", Operator::NotContains),
+        ];
+
+        for (marker, operator) in operators {
+            let expression = parse_expression(&format!("a {} b", marker)).unwrap();
+            assert_eq!(expression.tail[0].0, *operator);
+        }
+    }
+
+    #[test]
+    fn negation_applies_to_the_operand_after_it() {
+        let expression = parse_expression("not a == b").unwrap();
+
+        assert_eq!(
+            expression.head,
+            Operand::Not(Box::new(Operand::Variable(Variable::Address(
+                Address::Raw("a".to_string())
+            ))))
+        );
+        assert_eq!(expression.tail.len(), 1);
+
+        assert_eq!(
+            parse_expression("not (a == b)").unwrap().head,
+            Operand::Not(Box::new(Operand::Nested(Box::new(
+                parse_expression("a == b").unwrap()
+            ))))
+        );
+
+        assert_eq!(
+            parse_expression("!a").unwrap(),
+            parse_expression("not a").unwrap()
+        );
+    }
+
+    #[test]
+    fn operators_inside_strings_and_parenthesis_are_not_split_at() {
+        let expression = parse_expression("\"a == b\" == f(c < d)").unwrap();
+
+        assert_eq!(expression.tail.len(), 1);
+        assert_eq!(
+            expression.head,
+            Operand::Variable(Variable::String("a == b".to_string()))
+        );
+    }
+
+    #[test]
+    fn divert_targets_can_be_compared() {
+        let expression = parse_expression("next == -> knot").unwrap();
+
+        assert_eq!(
+            expression.tail,
+            &[(
+                Operator::Equal,
+                Operand::Variable(Variable::Divert(Address::Raw("knot".to_string())))
+            )]
+        );
+    }
+
+    #[test]
+    fn comparisons_without_right_hand_side_yield_error() {
+        assert!(parse_expression("a ==").is_err());
+        assert!(parse_expression("a and").is_err());
+        assert!(parse_expression("not").is_ok());
+    }
 }
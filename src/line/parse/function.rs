@@ -0,0 +1,227 @@
+//! Parse `FunctionCall` objects and function signatures.
+
+use crate::{
+    error::parse::expression::ExpressionErrorKind,
+    line::{
+        parse::{is_escaped, parse_expression},
+        FunctionCall,
+    },
+};
+
+/// Parse a `FunctionCall` from a string if it is on the form `name(a, b)`.
+///
+/// Returns `None` if the string is not a function call. The arguments are parsed
+/// as expressions.
+///
+/// Assumes that the given string is trimmed of whitespace from both ends.
+pub fn parse_function_call(content: &str) -> Option<Result<FunctionCall, ExpressionErrorKind>> {
+    let (name, inner) = split_name_and_parenthesis(content)?;
+
+    let arguments = split_arguments(inner)
+        .into_iter()
+        .map(|argument| parse_expression(argument).map_err(|err| err.kind))
+        .collect::<Result<Vec<_>, _>>();
+
+    Some(arguments.map(|arguments| FunctionCall {
+        name: name.to_string(),
+        arguments,
+    }))
+}
+
+/// Parse the name and parameter names from a function signature on the form `name(a, b)`.
+///
+/// Returns `None` if the signature is invalid.
+pub fn parse_function_signature(content: &str) -> Option<(String, Vec<String>)> {
+    let (name, inner) = split_name_and_parenthesis(content.trim())?;
+
+    let parameters = split_arguments(inner)
+        .into_iter()
+        .map(|parameter| parameter.trim())
+        .map(|parameter| {
+            if is_valid_name(parameter) {
+                Some(parameter.to_string())
+            } else {
+                None
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some((name.to_string(), parameters))
+}
+
+/// Split a string on the form `name(inner)` into the name and the inner string.
+///
+/// The opening parenthesis after the name has to be closed at the end of the string.
+fn split_name_and_parenthesis(content: &str) -> Option<(&str, &str)> {
+    let i = content.find('(')?;
+    let name = content.get(..i).unwrap().trim_end();
+
+    if !is_valid_name(name) || !content.ends_with(')') {
+        return None;
+    }
+
+    let inner = content.get(i + 1..content.len() - 1).unwrap();
+
+    if get_closing_parenthesis_index(inner) != Some(inner.len()) {
+        return None;
+    }
+
+    Some((name, inner))
+}
+
+/// Get the index of the parenthesis which closes an already opened parenthesis.
+///
+/// Parenthesis inside of strings are ignored. Returns the length of the string if the
+/// parenthesis is not closed inside of it and `None` if other parenthesis are unmatched.
+fn get_closing_parenthesis_index(content: &str) -> Option<usize> {
+    let mut level = 0;
+    let mut in_string = false;
+
+    for (i, c) in content.char_indices() {
+        match c {
+            '"' if !is_escaped(content, i) => in_string = !in_string,
+            '(' if !in_string => level += 1,
+            ')' if !in_string && level == 0 => return Some(i),
+            ')' if !in_string => level -= 1,
+            _ => (),
+        }
+    }
+
+    if level == 0 {
+        Some(content.len())
+    } else {
+        None
+    }
+}
+
+/// Split the arguments of a function call at commas outside of parenthesis and strings.
+///
+/// Assumes that all parenthesis in the string are matched.
+pub(super) fn split_arguments(content: &str) -> Vec<&str> {
+    if content.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let mut arguments = Vec::new();
+    let mut level = 0;
+    let mut in_string = false;
+    let mut start = 0;
+
+    for (i, c) in content.char_indices() {
+        match c {
+            '"' if !is_escaped(content, i) => in_string = !in_string,
+            '(' if !in_string => level += 1,
+            ')' if !in_string => level -= 1,
+            ',' if !in_string && level == 0 => {
+                arguments.push(content.get(start..i).unwrap());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+
+    arguments.push(content.get(start..).unwrap());
+
+    arguments
+}
+
+/// Check whether a string is a valid name of a function.
+pub(super) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(|c: char| c.is_numeric())
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{knot::Address, line::Variable};
+
+    fn get_function_call(content: &str) -> FunctionCall {
+        parse_function_call(content).unwrap().unwrap()
+    }
+
+    #[test]
+    fn function_call_is_name_followed_by_arguments_in_parenthesis() {
+        let call = get_function_call("play_sound(\"bell.wav\", 2)");
+
+        assert_eq!(&call.name, "play_sound");
+        assert_eq!(
+            call.arguments,
+            vec![
+                Variable::String("bell.wav".to_string()).into(),
+                Variable::Int(2).into()
+            ]
+        );
+    }
+
+    #[test]
+    fn function_call_can_have_no_arguments() {
+        let call = get_function_call("get_time()");
+
+        assert_eq!(&call.name, "get_time");
+        assert!(call.arguments.is_empty());
+    }
+
+    #[test]
+    fn arguments_may_be_expressions_with_nested_parenthesis_and_commas_in_strings() {
+        let call = get_function_call("f((a + 1) * 2, \"one, two\", g(b, c))");
+
+        assert_eq!(call.arguments.len(), 3);
+        assert_eq!(
+            call.arguments[1],
+            Variable::String("one, two".to_string()).into()
+        );
+        assert_eq!(
+            call.arguments[2],
+            FunctionCall {
+                name: "g".to_string(),
+                arguments: vec![
+                    Variable::Address(Address::Raw("b".to_string())).into(),
+                    Variable::Address(Address::Raw("c".to_string())).into()
+                ]
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn strings_which_are_not_function_calls_return_none() {
+        assert!(parse_function_call("variable").is_none());
+        assert!(parse_function_call("(a + b)").is_none());
+        assert!(parse_function_call("f(a) + g(b)").is_none());
+        assert!(parse_function_call("\"f(a)\"").is_none());
+        assert!(parse_function_call("1f(a)").is_none());
+    }
+
+    #[test]
+    fn invalid_arguments_yield_error() {
+        assert!(parse_function_call("f(a, )").unwrap().is_err());
+        assert!(parse_function_call("f(a +)").unwrap().is_err());
+    }
+
+    #[test]
+    fn function_signature_parses_into_name_and_parameter_names() {
+        assert_eq!(
+            parse_function_signature(" play_sound(name, volume) ").unwrap(),
+            (
+                "play_sound".to_string(),
+                vec!["name".to_string(), "volume".to_string()]
+            )
+        );
+
+        assert_eq!(
+            parse_function_signature("get_time()").unwrap(),
+            ("get_time".to_string(), Vec::new())
+        );
+    }
+
+    #[test]
+    fn function_signatures_with_invalid_names_return_none() {
+        assert!(parse_function_signature("play sound(name)").is_none());
+        assert!(parse_function_signature("play_sound(a + b)").is_none());
+        assert!(parse_function_signature("play_sound").is_none());
+        assert!(parse_function_signature("play_sound(a, )").is_none());
+    }
+}
@@ -4,7 +4,10 @@ use crate::{
     consts::GATHER_MARKER,
     error::{parse::line::LineErrorKind, utils::MetaData},
     line::{
-        parse::{parse_internal_line, parse_markers_and_text, split_at_divert_marker},
+        parse::{
+            parse_internal_line, parse_markers_and_text, split_at_divert_marker,
+            split_label_from_text,
+        },
         ParsedLineKind,
     },
 };
@@ -17,9 +20,20 @@ pub fn parse_gather(
     let (line_without_divert, line_from_divert) = split_at_divert_marker(content);
 
     parse_markers_and_text(line_without_divert, GATHER_MARKER)
-        .map(|(level, remaining_text)| (level, format!("{}{}", remaining_text, line_from_divert)))
-        .map(|(level, line)| {
-            parse_internal_line(&line, meta_data).map(|line| ParsedLineKind::Gather { level, line })
+        .map(|(level, remaining_text)| {
+            let (label, remaining_text) = split_label_from_text(remaining_text);
+            (
+                level,
+                label,
+                format!("{}{}", remaining_text, line_from_divert),
+            )
+        })
+        .map(|(level, label, line)| {
+            parse_internal_line(&line, meta_data).map(|line| ParsedLineKind::Gather {
+                level,
+                label,
+                line,
+            })
         })
         .transpose()
 }
@@ -96,6 +110,36 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn gathers_may_be_labelled_with_a_name_in_parenthesis() {
+        match parse_line("- (meeting) Hello, World!", &().into()).unwrap() {
+            ParsedLineKind::Gather { label, line, .. } => {
+                assert_eq!(label, Some("meeting".to_string()));
+                assert_eq!(line, InternalLine::from_string("Hello, World!"));
+            }
+            other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
+        }
+
+        match parse_line("-(meeting)", &().into()).unwrap() {
+            ParsedLineKind::Gather { label, line, .. } => {
+                assert_eq!(label, Some("meeting".to_string()));
+                assert_eq!(line.chunk.items.len(), 0);
+            }
+            other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parenthesis_without_valid_name_in_gather_is_regular_text() {
+        match parse_line("- (an aside) Hello, World!", &().into()).unwrap() {
+            ParsedLineKind::Gather { label, line, .. } => {
+                assert!(label.is_none());
+                assert_eq!(line, InternalLine::from_string("(an aside) Hello, World!"));
+            }
+            other => panic!("expected `ParsedLineKind::Gather` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn diverts_can_come_directly_after_gathers() {
         match parse_line("- -> world", &().into()).unwrap() {
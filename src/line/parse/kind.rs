@@ -2,10 +2,17 @@
 
 use crate::{
     consts::DIVERT_MARKER,
-    error::{parse::line::LineError, utils::MetaData},
+    error::{
+        parse::line::{LineError, LineErrorKind},
+        utils::MetaData,
+    },
     line::{
-        parse::{parse_choice, parse_gather, parse_internal_line},
-        InternalChoice, InternalLine,
+        parse::{
+            find_unescaped, get_block_header, get_sequence_keyword_kind, is_block_branch,
+            is_block_end, parse_block_branch, parse_block_start, parse_choice, parse_gather,
+            parse_internal_line, parse_logic_line, parse_sequence_branch, parse_thread, unescape,
+        },
+        AlternativeKind, Condition, Content, InternalChoice, InternalLine, LineChunk,
     },
 };
 
@@ -20,29 +27,71 @@ pub enum ParsedLineKind {
     Choice {
         /// Nested level of choice.
         level: u32,
+        /// Label of choice, if set.
+        label: Option<String>,
         /// Parsed data of choice.
         choice_data: InternalChoice,
     },
     Gather {
         /// Nested level of gather.
         level: u32,
+        /// Label of gather point, if set.
+        label: Option<String>,
         /// Parsed line of gather point.
         line: InternalLine,
     },
     /// Regular line of content.
     Line(InternalLine),
+    /// Beginning of a multiline conditional block.
+    ConditionalStart {
+        /// Condition for the first branch, if it was set along with the block marker.
+        condition: Option<Condition>,
+    },
+    /// Beginning of a new branch in a multiline conditional block.
+    ConditionalBranch {
+        /// Condition for the branch to be followed, or `None` for `else` branches.
+        condition: Option<Condition>,
+        /// Content which was set on the same line as the condition.
+        line: Option<InternalLine>,
+    },
+    /// Beginning of a multiline alternative block, in which each branch is an item.
+    ///
+    /// Branches of these blocks are marked with `ConditionalBranch` objects without
+    /// conditions and the block is ended by `ConditionalEnd`.
+    SequenceStart {
+        /// Kind of alternating sequence which selects the branch to follow.
+        kind: AlternativeKind,
+    },
+    /// End of a multiline conditional or alternative block.
+    ConditionalEnd,
+}
+
+/// Kind of a block which is open while lines are parsed.
+enum OpenBlock<'a> {
+    /// Conditional block, with the value to switch on if it is a switch block.
+    Conditional { switch_value: Option<&'a str> },
+    /// Alternative block, in which every line beginning with a dash is a new branch.
+    Sequence,
 }
 
 #[cfg(test)]
 impl ParsedLineKind {
     /// Construct a `ParsedLineKind::Choice` object with given level and choice data.
     pub fn choice(level: u32, choice_data: InternalChoice) -> Self {
-        ParsedLineKind::Choice { level, choice_data }
+        ParsedLineKind::Choice {
+            level,
+            label: None,
+            choice_data,
+        }
     }
 
     /// Construct a `ParsedLineKind::Gather` object with given level and line.
     pub fn gather(level: u32, line: InternalLine) -> Self {
-        ParsedLineKind::Gather { level, line }
+        ParsedLineKind::Gather {
+            level,
+            label: None,
+            line,
+        }
     }
 
     /// Construct a `ParsedLineKind::Line` object with given line.
@@ -57,16 +106,117 @@ pub fn parse_line(content: &str, meta_data: &MetaData) -> Result<ParsedLineKind,
         choice
     } else if let Some(gather) = parse_gather(content, meta_data).transpose() {
         gather
+    } else if let Some(logic) = parse_logic_line(content).transpose() {
+        logic.map(|item| get_line_from_single_item(item, meta_data))
+    } else if let Some(thread) = parse_thread(content).transpose() {
+        thread.map(|item| get_line_from_single_item(item, meta_data))
     } else {
         parse_internal_line(content, meta_data).map(|line| ParsedLineKind::Line(line))
     }
     .map_err(|kind| LineError {
-        line: content.to_string(),
+        line: unescape(content),
         kind,
         meta_data: meta_data.clone(),
     })
 }
 
+/// Create a line of content from a single item, such as a logic line or thread.
+fn get_line_from_single_item(item: Content, meta_data: &MetaData) -> ParsedLineKind {
+    let chunk = LineChunk {
+        condition: None,
+        items: vec![item],
+        else_items: Vec::new(),
+    };
+
+    let mut line = InternalLine::from_chunk(chunk);
+    line.meta_data = meta_data.clone();
+
+    ParsedLineKind::Line(line)
+}
+
+/// Parse a set of lines into `ParsedLineKind` objects.
+///
+/// Multiline conditional blocks span several lines, which means that their lines cannot
+/// be parsed without knowing whether they are inside of a block. This function keeps
+/// track of that while parsing the lines one by one.
+///
+/// If the first line inside a block which begins with a header is a branch, the block
+/// switches on the value of the header instead of using it as a condition. Blocks whose
+/// header is a sequence keyword (`{stopping:`) are alternative blocks.
+///
+/// All encountered errors are returned together.
+pub fn parse_lines(lines: &[(&str, MetaData)]) -> Result<Vec<ParsedLineKind>, Vec<LineError>> {
+    let mut parsed_lines = Vec::new();
+    let mut line_errors = Vec::new();
+
+    // Kind and line index of every currently open block, innermost last
+    let mut open_blocks: Vec<(OpenBlock, usize)> = Vec::new();
+
+    for (i, (content, meta_data)) in lines.iter().enumerate() {
+        let get_line_error = |kind| LineError {
+            line: unescape(content),
+            kind,
+            meta_data: meta_data.clone(),
+        };
+
+        let result =
+            if let Some(kind) = get_block_header(content).and_then(get_sequence_keyword_kind) {
+                open_blocks.push((OpenBlock::Sequence, i));
+
+                Ok(ParsedLineKind::SequenceStart { kind })
+            } else if let Some(header) = get_block_header(content) {
+                let is_switch = !header.is_empty()
+                    && lines
+                        .get(i + 1)
+                        .map(|(next, _)| is_block_branch(next))
+                        .unwrap_or(false);
+
+                let switch_value = if is_switch { Some(header) } else { None };
+                open_blocks.push((OpenBlock::Conditional { switch_value }, i));
+
+                parse_block_start(header, is_switch).map_err(get_line_error)
+            } else if is_block_end(content) {
+                open_blocks
+                    .pop()
+                    .map(|_| ParsedLineKind::ConditionalEnd)
+                    .ok_or(get_line_error(LineErrorKind::UnmatchedBraces))
+            } else if let Some((block, _)) = open_blocks.last() {
+                match block {
+                    OpenBlock::Conditional { switch_value } => {
+                        parse_block_branch(content, *switch_value, meta_data)
+                    }
+                    OpenBlock::Sequence => parse_sequence_branch(content, meta_data),
+                }
+                .map_err(get_line_error)
+                .transpose()
+                .unwrap_or_else(|| parse_line(content, meta_data))
+            } else {
+                parse_line(content, meta_data)
+            };
+
+        match result {
+            Ok(parsed_line) => parsed_lines.push(parsed_line),
+            Err(line_error) => line_errors.push(line_error),
+        }
+    }
+
+    for (_, i) in open_blocks {
+        let (content, meta_data) = &lines[i];
+
+        line_errors.push(LineError {
+            line: unescape(content),
+            kind: LineErrorKind::UnmatchedBraces,
+            meta_data: meta_data.clone(),
+        });
+    }
+
+    if line_errors.is_empty() {
+        Ok(parsed_lines)
+    } else {
+        Err(line_errors)
+    }
+}
+
 /// Count leading markers and return the number and a string without them.
 pub fn parse_markers_and_text(line: &str, marker: char) -> Option<(u32, &str)> {
     if line.trim_start().starts_with(marker) {
@@ -89,9 +239,31 @@ fn split_markers_from_text(line: &str, marker: char) -> (&str, &str) {
     }
 }
 
+/// Split a leading label from the text of a choice or gather and return both parts.
+///
+/// Labels are names enclosed in parenthesis directly after the markers: `- (label) Text`.
+/// If the parenthesis does not contain a valid name it is not a label and the text
+/// is returned as is.
+pub fn split_label_from_text(line: &str) -> (Option<String>, &str) {
+    let trimmed = line.trim_start();
+
+    if trimmed.starts_with('(') {
+        if let Some(i) = trimmed.find(')') {
+            let name = trimmed.get(1..i).unwrap().trim();
+
+            if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                let tail = trimmed.get(i + 1..).unwrap().trim_start();
+                return (Some(name.to_string()), tail);
+            }
+        }
+    }
+
+    (None, line)
+}
+
 /// Split a string at the divert marker and return both parts.
 pub fn split_at_divert_marker(content: &str) -> (&str, &str) {
-    if let Some(i) = content.find(DIVERT_MARKER) {
+    if let Some(i) = find_unescaped(content, DIVERT_MARKER) {
         content.split_at(i)
     } else {
         (content, "")
@@ -102,6 +274,8 @@ pub fn split_at_divert_marker(content: &str) -> (&str, &str) {
 pub mod tests {
     use super::*;
 
+    use crate::line::Content;
+
     #[test]
     fn simple_line_parses_to_line() {
         let line = parse_line("Hello, World!", &().into()).unwrap();
@@ -110,6 +284,154 @@ pub mod tests {
         assert_eq!(line, ParsedLineKind::Line(comparison));
     }
 
+    fn parse_lines_from_str(content: &str) -> Result<Vec<ParsedLineKind>, Vec<LineError>> {
+        let lines = content
+            .lines()
+            .map(|line| (line, ().into()))
+            .collect::<Vec<(&str, MetaData)>>();
+
+        parse_lines(&lines)
+    }
+
+    #[test]
+    fn lines_in_blocks_are_parsed_into_block_starts_branches_and_ends() {
+        let lines = parse_lines_from_str(
+            "{x > 2:
+Line
+- else:
+Line
+}",
+        )
+        .unwrap();
+
+        match &lines[..] {
+            [ParsedLineKind::ConditionalStart {
+                condition: Some(..),
+            }, ParsedLineKind::Line(..), ParsedLineKind::ConditionalBranch {
+                condition: None, ..
+            }, ParsedLineKind::Line(..), ParsedLineKind::ConditionalEnd] => (),
+            other => panic!("expected a conditional block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn branch_markers_outside_of_blocks_are_gathers() {
+        let lines = parse_lines_from_str("- else:").unwrap();
+
+        match &lines[..] {
+            [ParsedLineKind::Gather { .. }] => (),
+            other => panic!("expected a single `Gather` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn blocks_with_header_followed_by_branch_are_switch_blocks() {
+        let lines = parse_lines_from_str(
+            "{x:
+- 2: Line
+}",
+        )
+        .unwrap();
+
+        match &lines[..] {
+            [ParsedLineKind::ConditionalStart { condition: None }, ParsedLineKind::ConditionalBranch {
+                condition: Some(..),
+                line: Some(..),
+            }, ParsedLineKind::ConditionalEnd] => (),
+            other => panic!("expected a switch block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn blocks_with_sequence_keyword_headers_are_alternative_blocks() {
+        let lines = parse_lines_from_str(
+            "{shuffle once:
+- Line: 1
+Line
+-
+}",
+        )
+        .unwrap();
+
+        match &lines[..] {
+            [ParsedLineKind::SequenceStart {
+                kind: AlternativeKind::ShuffleOnce,
+            }, ParsedLineKind::ConditionalBranch {
+                condition: None,
+                line: Some(..),
+            }, ParsedLineKind::Line(..), ParsedLineKind::ConditionalBranch {
+                condition: None,
+                line: None,
+            }, ParsedLineKind::ConditionalEnd] => (),
+            other => panic!("expected an alternative block but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unclosed_blocks_yield_unmatched_braces_errors() {
+        let errors = parse_lines_from_str(
+            "{
+- x > 2:
+Line",
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(&errors[0].line, "{");
+
+        match errors[0].kind {
+            LineErrorKind::UnmatchedBraces => (),
+            ref other => panic!(
+                "expected `LineErrorKind::UnmatchedBraces` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn block_ends_without_open_block_yield_unmatched_braces_errors() {
+        let errors = parse_lines_from_str(
+            "Line
+}",
+        )
+        .unwrap_err();
+
+        assert_eq!(errors.len(), 1);
+
+        match errors[0].kind {
+            LineErrorKind::UnmatchedBraces => (),
+            ref other => panic!(
+                "expected `LineErrorKind::UnmatchedBraces` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn line_with_assignment_marker_parses_to_line_with_assignment() {
+        match parse_line("~ coins = 5", &().into()).unwrap() {
+            ParsedLineKind::Line(line) => match &line.chunk.items[..] {
+                [Content::Assignment(..)] => (),
+                other => panic!(
+                    "expected a single `Content::Assignment` but got {:?}",
+                    other
+                ),
+            },
+            other => panic!("expected `ParsedLineKind::Line` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn logic_line_with_function_call_parses_to_line_with_statement() {
+        match parse_line("~ play_sound()", &().into()).unwrap() {
+            ParsedLineKind::Line(line) => match &line.chunk.items[..] {
+                [Content::Statement(..)] => (),
+                other => panic!("expected a single `Content::Statement` but got {:?}", other),
+            },
+            other => panic!("expected `ParsedLineKind::Line` but got {:?}", other),
+        }
+    }
+
     #[test]
     fn line_with_choice_markers_parses_to_choice() {
         let line = parse_line("* Hello, World!", &().into()).unwrap();
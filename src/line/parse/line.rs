@@ -1,15 +1,17 @@
 //! Parse `InternalLine` and `LineChunk` objects.
 
 use crate::{
-    consts::{DIVERT_MARKER, GLUE_MARKER, TAG_MARKER},
+    consts::{DIVERT_MARKER, GLUE_MARKER, TAG_MARKER, THREAD_MARKER},
     error::{parse::line::LineErrorKind, utils::MetaData},
     knot::Address,
     line::{
         parse::{
-            parse_alternative, parse_expression, parse_line_condition,
+            function::split_arguments,
+            is_escaped, parse_alternative, parse_expression, parse_line_condition,
+            split_sequence_keyword, unescape,
             utils::{split_line_at_separator_braces, split_line_into_groups_braces, LinePart},
         },
-        Content, InternalLine, LineChunk,
+        Content, Expression, InternalLine, LineChunk,
     },
 };
 
@@ -31,15 +33,15 @@ pub fn parse_internal_line(
 ) -> Result<InternalLine, LineErrorKind> {
     let mut buffer = content.to_string();
 
-    let tags = parse_tags(&mut buffer);
+    let tags = parse_tags(&mut buffer)?;
     let divert = split_off_end_divert(&mut buffer)?;
 
     let (glue_begin, glue_end) = parse_line_glue(&mut buffer, divert.is_some());
 
     let mut chunk = parse_chunk(&buffer)?;
 
-    if let Some(address) = divert {
-        chunk.items.push(Content::Divert(Address::Raw(address)));
+    if let Some(item) = divert {
+        chunk.items.push(item);
     }
 
     Ok(InternalLine {
@@ -79,13 +81,13 @@ fn get_text_items(content: &str) -> Result<Vec<Content>, LineErrorKind> {
     let divert = split_off_end_divert(&mut buffer)?;
 
     if !buffer.trim().is_empty() {
-        items.push(Content::Text(buffer));
+        items.push(Content::Text(unescape(&buffer)));
     } else {
         items.push(Content::Empty);
     }
 
-    if let Some(address) = divert {
-        items.push(Content::Divert(Address::Raw(address)));
+    if let Some(item) = divert {
+        items.push(item);
     }
 
     Ok(items)
@@ -120,12 +122,19 @@ fn parse_embraced_line(content: &str) -> Result<Content, LineErrorKind> {
 }
 
 /// Determine which kind of variable content is in an embraced string.
+///
+/// Content with an `||` operator is an expression if it parses as one, otherwise it is
+/// a set of alternatives with an empty item.
 fn determine_kind(content: &str) -> Result<VariableText, LineErrorKind> {
     if content.trim().is_empty() {
         Err(LineErrorKind::EmptyExpression)
+    } else if split_sequence_keyword(content).is_some() {
+        Ok(VariableText::Alternative)
     } else if split_line_at_separator_braces(content, ":", Some(1))?.len() > 1 {
         Ok(VariableText::Conditional)
-    } else if split_line_at_separator_braces(content, "|", Some(1))?.len() > 1 {
+    } else if split_line_at_separator_braces(content, "|", Some(1))?.len() > 1
+        && !(content.contains("||") && parse_expression(content).is_ok())
+    {
         Ok(VariableText::Alternative)
     } else {
         Ok(VariableText::Expression)
@@ -138,7 +147,10 @@ fn determine_kind(content: &str) -> Result<VariableText, LineErrorKind> {
 /// in are not (currently) removed.
 fn parse_line_glue(line: &mut String, has_divert: bool) -> (bool, bool) {
     let glue_left = line.trim_start().starts_with(GLUE_MARKER);
-    let glue_right = line.trim_end().ends_with(GLUE_MARKER);
+    let glue_right = line.trim_end().ends_with(GLUE_MARKER) && {
+        let trimmed = line.trim_end();
+        !is_escaped(trimmed, trimmed.len() - GLUE_MARKER.len())
+    };
 
     if glue_left {
         *line = line
@@ -155,36 +167,161 @@ fn parse_line_glue(line: &mut String, has_divert: bool) -> (bool, bool) {
 }
 
 /// Split any found tags off the given line and return them separately.
-fn parse_tags(line: &mut String) -> Vec<String> {
-    match line.find(TAG_MARKER) {
-        Some(i) => {
-            let part = line.split_off(i);
-
-            part.trim_matches(TAG_MARKER)
-                .split(TAG_MARKER)
-                .map(|tag| tag.trim().to_string())
-                .collect::<Vec<_>>()
-        }
-        None => Vec::new(),
-    }
+///
+/// Tag markers inside of braces or which are escaped do not begin tags. Empty tags
+/// are skipped.
+fn parse_tags(line: &mut String) -> Result<Vec<LineChunk>, LineErrorKind> {
+    let splits = split_line_at_separator_braces(line, &TAG_MARKER.to_string(), None)?;
+
+    let (head, tags) = match splits.split_first() {
+        Some((head, tags)) if !tags.is_empty() => (head, tags),
+        _ => return Ok(Vec::new()),
+    };
+
+    let tags = tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(parse_tag)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    line.truncate(head.len());
+
+    Ok(tags)
+}
+
+/// Parse a `LineChunk` from the content of a tag.
+///
+/// Tags can contain the same variable content as lines: printed expressions, alternatives
+/// and conditions, which are processed along with the line. Text in them is kept as is,
+/// including any divert markers, except for escape markers which are removed.
+pub fn parse_tag(content: &str) -> Result<LineChunk, LineErrorKind> {
+    let items = split_line_into_groups_braces(content)?
+        .into_iter()
+        .map(|group| match group {
+            LinePart::Text(part) => Ok(Content::Text(unescape(part))),
+            LinePart::Embraced(text) => parse_embraced_line(text),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(LineChunk {
+        condition: None,
+        items,
+        else_items: Vec::new(),
+    })
 }
 
 /// Split diverts off the given line and return it separately if found.
-fn split_off_end_divert(line: &mut String) -> Result<Option<String>, LineErrorKind> {
+///
+/// Several divert markers denote tunnels: `-> tunnel ->` calls a tunnel and returns
+/// to the line after, `-> one -> two -> knot` calls the tunnels in order and then diverts
+/// to the last address. Tunnels may be given arguments: `-> tunnel(a, b) ->`. A tunnel
+/// return is marked by two divert markers: `->->`, which may be followed by an address
+/// to divert to instead of returning: `->-> knot`.
+fn split_off_end_divert(line: &mut String) -> Result<Option<Content>, LineErrorKind> {
     let splits = split_line_at_separator_braces(&line, DIVERT_MARKER, None)?;
 
-    match splits.len() {
-        0 | 1 => Ok(None),
-        2 => {
-            let head_length = splits.get(0).unwrap().len();
+    let (head, addresses) = match splits.split_first() {
+        Some((head, addresses)) if !addresses.is_empty() => (head, addresses),
+        _ => return Ok(None),
+    };
+
+    let addresses = join_splits_inside_parenthesis(addresses, DIVERT_MARKER);
+    let addresses = addresses.iter().map(|s| s.trim()).collect::<Vec<_>>();
+
+    let item = match addresses.as_slice() {
+        [address] => parse_divert(address)?,
+        ["", ""] => Content::TunnelReturn(None),
+        ["", address] => Content::TunnelReturn(Some(Address::Raw(validate_address(address)?))),
+        [targets @ .., last] => {
+            let targets = targets
+                .iter()
+                .map(|target| parse_divert_target(target))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let divert = match *last {
+                "" => None,
+                address => Some(Address::Raw(validate_address(address)?)),
+            };
+
+            Content::TunnelCall { targets, divert }
+        }
+        [] => unreachable!("the list of addresses was checked to be non-empty"),
+    };
+
+    line.truncate(head.len());
+    line.push(' ');
 
-            let address = validate_address(splits[1].trim())?;
-            line.truncate(head_length);
-            line.push(' ');
+    Ok(Some(item))
+}
+
+/// Join consecutive parts of a line which were split at a separator inside of parenthesis.
+///
+/// Diverts may be given divert targets as arguments, `-> knot(-> other)`, which should not
+/// be split into separate addresses.
+fn join_splits_inside_parenthesis(splits: &[&str], separator: &str) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut level = 0;
+
+    for split in splits {
+        match joined.last_mut() {
+            Some(last) if level > 0 => {
+                last.push_str(separator);
+                last.push_str(split);
+            }
+            _ => {
+                joined.push(split.to_string());
+                level = 0;
+            }
+        }
+
+        level += split.matches('(').count() as i32 - split.matches(')').count() as i32;
+    }
+
+    joined
+}
+
+/// Parse a divert to an address, which may be given arguments: `-> knot(a, b)`.
+///
+/// The arguments are parsed as expressions.
+fn parse_divert(content: &str) -> Result<Content, LineErrorKind> {
+    match content.find('(') {
+        Some(i) if content.ends_with(')') => {
+            let address = validate_address(content.get(..i).unwrap().trim_end())?;
+
+            let arguments = split_arguments(content.get(i + 1..content.len() - 1).unwrap())
+                .into_iter()
+                .map(parse_expression)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(Content::DivertWithArguments {
+                address: Address::Raw(address),
+                arguments,
+            })
+        }
+        _ => Ok(Content::Divert(Address::Raw(validate_address(content)?))),
+    }
+}
+
+/// Parse the address of a tunnel or thread along with its arguments, if any are given.
+fn parse_divert_target(content: &str) -> Result<(Address, Vec<Expression>), LineErrorKind> {
+    match parse_divert(content)? {
+        Content::Divert(address) => Ok((address, Vec::new())),
+        Content::DivertWithArguments { address, arguments } => Ok((address, arguments)),
+        _ => unreachable!("diverts are parsed into divert content"),
+    }
+}
 
-            Ok(Some(address))
+/// Parse a thread from a line if it begins with a thread marker: `<- address`.
+///
+/// Threads may be given arguments: `<- address(a, b)`.
+pub fn parse_thread(content: &str) -> Result<Option<Content>, LineErrorKind> {
+    match content.trim().strip_prefix(THREAD_MARKER) {
+        Some(target) => {
+            let (address, arguments) = parse_divert_target(target.trim())?;
+            Ok(Some(Content::Thread { address, arguments }))
         }
-        _ => Err(LineErrorKind::FoundTunnel),
+        None => Ok(None),
     }
 }
 
@@ -198,15 +335,16 @@ pub fn validate_address(line: &str) -> Result<String, LineErrorKind> {
             .splitn(2, |c: char| c.is_whitespace())
             .skip(1)
             .next()
-            .unwrap()
-            .to_string();
+            .unwrap();
 
-        Err(LineErrorKind::ExpectedEndOfLine { tail })
+        Err(LineErrorKind::ExpectedEndOfLine {
+            tail: unescape(tail),
+        })
     } else if line.is_empty() {
         Err(LineErrorKind::EmptyDivert)
     } else if line.contains(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')) {
         Err(LineErrorKind::InvalidAddress {
-            address: line.to_string(),
+            address: unescape(line),
         })
     } else {
         Ok(line.to_string())
@@ -220,7 +358,7 @@ mod tests {
     use crate::{
         knot::Address,
         line::{expression::Operand, Variable},
-        process::line::tests::get_processed_chunk,
+        process::line::tests::{get_processed_chunk_with_data, mock_data_with_single_stitch},
     };
 
     #[test]
@@ -247,7 +385,7 @@ mod tests {
 
     #[test]
     fn braces_denote_alternative_sequences_in_chunks() {
-        let mut chunk = parse_chunk("{One|Two}").unwrap();
+        let chunk = parse_chunk("{One|Two}").unwrap();
 
         assert_eq!(chunk.items.len(), 1);
 
@@ -256,8 +394,10 @@ mod tests {
             other => panic!("expected `Content::Alternative` but got {:?}", other),
         }
 
-        assert_eq!(&get_processed_chunk(&mut chunk), "One");
-        assert_eq!(&get_processed_chunk(&mut chunk), "Two");
+        let mut data = mock_data_with_single_stitch("", "", 0);
+
+        assert_eq!(&get_processed_chunk_with_data(&chunk, &mut data), "One");
+        assert_eq!(&get_processed_chunk_with_data(&chunk, &mut data), "Two");
     }
 
     #[test]
@@ -329,6 +469,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diverts_may_have_arguments_which_are_parsed_as_expressions() {
+        let chunk = parse_chunk("-> meet(\"Anna\", 1 + 2)").unwrap();
+
+        assert_eq!(
+            chunk.items.last().unwrap(),
+            &Content::DivertWithArguments {
+                address: Address::Raw("meet".to_string()),
+                arguments: vec![
+                    parse_expression("\"Anna\"").unwrap(),
+                    parse_expression("1 + 2").unwrap()
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn divert_arguments_may_be_divert_targets() {
+        let chunk = parse_chunk("-> meet(-> anna)").unwrap();
+
+        assert_eq!(
+            chunk.items.last().unwrap(),
+            &Content::DivertWithArguments {
+                address: Address::Raw("meet".to_string()),
+                arguments: vec![parse_expression("-> anna").unwrap()],
+            }
+        );
+    }
+
+    #[test]
+    fn divert_markers_before_the_last_parenthesis_still_split_off_the_divert() {
+        let chunk = parse_chunk("Hello :( -> world").unwrap();
+
+        assert_eq!(
+            chunk.items.last().unwrap(),
+            &Content::Divert(Address::Raw("world".to_string()))
+        );
+    }
+
+    #[test]
+    fn diverts_with_arguments_require_valid_addresses() {
+        assert!(parse_chunk("-> meet them(\"Anna\")").is_err());
+        assert!(parse_chunk("-> meet-them(\"Anna\")").is_err());
+        assert!(parse_chunk("-> (\"Anna\")").is_err());
+    }
+
     #[test]
     fn divert_marker_adds_whitespace_to_the_left_of_it() {
         let chunk = parse_chunk("hello-> world").unwrap();
@@ -344,10 +530,118 @@ mod tests {
     }
 
     #[test]
-    fn multiple_diverts_in_a_chunk_yields_error() {
-        match parse_chunk("-> hello -> world") {
-            Err(LineErrorKind::FoundTunnel) => (),
-            other => panic!("expected `LineErrorKind::FoundTunnel` but got {:?}", other),
+    fn divert_with_trailing_divert_marker_is_a_tunnel_call() {
+        let chunk = parse_chunk("Hello-> tunnel ->").unwrap();
+
+        assert_eq!(chunk.items[0], Content::Text("Hello ".to_string()));
+        assert_eq!(
+            chunk.items[1],
+            Content::TunnelCall {
+                targets: vec![(Address::Raw("tunnel".to_string()), Vec::new())],
+                divert: None,
+            }
+        );
+    }
+
+    #[test]
+    fn tunnel_calls_may_have_arguments() {
+        let chunk = parse_chunk("-> tunnel(2, \"Anna\") -> other ->").unwrap();
+
+        match &chunk.items[1] {
+            Content::TunnelCall { targets, divert } => {
+                assert_eq!(targets[0].0, Address::Raw("tunnel".to_string()));
+                assert_eq!(targets[0].1.len(), 2);
+                assert_eq!(targets[1], (Address::Raw("other".to_string()), Vec::new()));
+                assert!(divert.is_none());
+            }
+            other => panic!("expected `Content::TunnelCall` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn chained_tunnels_may_end_with_a_divert() {
+        let chunk = parse_chunk("-> one -> two -> knot").unwrap();
+
+        assert_eq!(
+            chunk.items[1],
+            Content::TunnelCall {
+                targets: vec![
+                    (Address::Raw("one".to_string()), Vec::new()),
+                    (Address::Raw("two".to_string()), Vec::new())
+                ],
+                divert: Some(Address::Raw("knot".to_string())),
+            }
+        );
+
+        let chunk = parse_chunk("-> one -> two ->").unwrap();
+
+        assert_eq!(
+            chunk.items[1],
+            Content::TunnelCall {
+                targets: vec![
+                    (Address::Raw("one".to_string()), Vec::new()),
+                    (Address::Raw("two".to_string()), Vec::new())
+                ],
+                divert: None,
+            }
+        );
+    }
+
+    #[test]
+    fn two_divert_markers_without_address_is_a_tunnel_return() {
+        let chunk = parse_chunk("Leaving->->").unwrap();
+
+        assert_eq!(chunk.items[0], Content::Text("Leaving ".to_string()));
+        assert_eq!(chunk.items[1], Content::TunnelReturn(None));
+    }
+
+    #[test]
+    fn tunnel_return_followed_by_an_address_diverts_to_it() {
+        let chunk = parse_chunk("->-> knot").unwrap();
+
+        assert_eq!(
+            chunk.items[1],
+            Content::TunnelReturn(Some(Address::Raw("knot".to_string())))
+        );
+    }
+
+    #[test]
+    fn thread_marker_parses_into_thread_with_address() {
+        assert_eq!(
+            parse_thread("<- conversation").unwrap(),
+            Some(Content::Thread {
+                address: Address::Raw("conversation".to_string()),
+                arguments: Vec::new()
+            })
+        );
+
+        assert_eq!(parse_thread("Not a thread <- conversation").unwrap(), None);
+    }
+
+    #[test]
+    fn threads_may_have_arguments() {
+        match parse_thread("<- conversation(\"Anna\", 2)").unwrap() {
+            Some(Content::Thread { address, arguments }) => {
+                assert_eq!(address, Address::Raw("conversation".to_string()));
+                assert_eq!(arguments.len(), 2);
+            }
+            other => panic!("expected `Content::Thread` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn thread_without_address_yields_error() {
+        match parse_thread("<-  ") {
+            Err(LineErrorKind::EmptyDivert) => (),
+            other => panic!("expected `LineErrorKind::EmptyDivert` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn empty_tunnel_address_yields_error() {
+        match parse_chunk("-> one -> -> two") {
+            Err(LineErrorKind::EmptyDivert) => (),
+            other => panic!("expected `LineErrorKind::EmptyDivert` but got {:?}", other),
         }
     }
 
@@ -430,8 +724,8 @@ mod tests {
         let line = parse_internal_line("Hello, World! # tag one # tag two", &().into()).unwrap();
 
         assert_eq!(line.tags.len(), 2);
-        assert_eq!(&line.tags[0], "tag one");
-        assert_eq!(&line.tags[1], "tag two");
+        assert_eq!(line.tags[0], parse_tag("tag one").unwrap());
+        assert_eq!(line.tags[1], parse_tag("tag two").unwrap());
 
         assert_eq!(line.chunk.items.len(), 1);
         assert_eq!(
@@ -440,6 +734,75 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tags_are_parsed_with_variable_content() {
+        let tag = parse_tag("mood: {mood}").unwrap();
+
+        assert_eq!(tag.items.len(), 2);
+        assert_eq!(tag.items[0], Content::Text("mood: ".to_string()));
+        assert_eq!(tag.items[1], parse_embraced_line("mood").unwrap());
+
+        let tag = parse_tag("{a|b}_{x > 2: high|low}").unwrap();
+
+        assert_eq!(tag.items.len(), 3);
+        assert!(matches!(tag.items[0], Content::Alternative(..)));
+        assert!(matches!(tag.items[2], Content::Nested(..)));
+    }
+
+    #[test]
+    fn divert_markers_in_tags_are_kept_as_text() {
+        let tag = parse_tag("path: a -> b").unwrap();
+
+        assert_eq!(tag.items, &[Content::Text("path: a -> b".to_string())]);
+    }
+
+    #[test]
+    fn tags_with_invalid_variable_content_yield_errors() {
+        assert!(parse_internal_line("Hello # tag {}", &().into()).is_err());
+    }
+
+    #[test]
+    fn escaped_markers_are_kept_as_text_without_escape_markers() {
+        let line = parse_internal_line(
+            "Issue \\#12: a \\-> b \\{c\\} \\| d \\\\ \\<> # tag",
+            &().into(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            line.chunk.items,
+            &[Content::Text(
+                "Issue #12: a -> b {c} | d \\ <> ".to_string()
+            )]
+        );
+        assert_eq!(line.tags.len(), 1);
+        assert!(!line.glue_end);
+    }
+
+    #[test]
+    fn markers_after_escaped_backslashes_are_not_escaped() {
+        let line = parse_internal_line("Path C:\\\\ -> knot", &().into()).unwrap();
+
+        assert_eq!(
+            line.chunk.items,
+            &[
+                Content::Text("Path C:\\  ".to_string()),
+                Content::Divert(Address::Raw("knot".to_string()))
+            ]
+        );
+    }
+
+    #[test]
+    fn escaped_tag_markers_are_kept_in_tags() {
+        let line = parse_internal_line("Hello # color: \\#ff0000 # mood", &().into()).unwrap();
+
+        assert_eq!(line.tags.len(), 2);
+        assert_eq!(
+            line.tags[0].items,
+            &[Content::Text("color: #ff0000".to_string())]
+        );
+    }
+
     #[test]
     fn parse_embraced_line_as_alternative() {
         match parse_embraced_line("One | Two").unwrap() {
@@ -542,6 +905,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn expression_beginning_with_sequence_keyword_and_colon_is_alternative() {
+        assert_eq!(
+            determine_kind("shuffle once: one | two").unwrap(),
+            VariableText::Alternative
+        );
+        assert_eq!(
+            determine_kind("stopping: one").unwrap(),
+            VariableText::Alternative
+        );
+        assert_eq!(
+            determine_kind("stoppingly: one").unwrap(),
+            VariableText::Conditional
+        );
+    }
+
     #[test]
     fn expression_with_mathematical_operators_is_expression() {
         assert_eq!(determine_kind("+").unwrap(), VariableText::Expression);
@@ -0,0 +1,125 @@
+//! Parse list definitions.
+
+use crate::line::parse::function::{is_valid_name, split_arguments};
+
+/// Convenience type for a parsed list definition: its name and items.
+///
+/// Every item has a name, value and whether it is selected in the initial value of the list.
+pub type ListDefinitionItems = (String, Vec<(String, i32, bool)>);
+
+/// Parse the name and items of a list definition on the form `name = a, (b), c = 5`.
+///
+/// Items are returned with their value and whether they are selected in the initial value
+/// of the list variable, which is marked by enclosing the item in parenthesis. Values are
+/// counted up from 1, or from the previous item if it was given an explicit value.
+///
+/// Returns `None` if the definition is invalid.
+pub fn parse_list_definition(content: &str) -> Option<ListDefinitionItems> {
+    let i = content.find('=')?;
+    let (head, tail) = content.split_at(i);
+
+    let name = head.trim();
+
+    if !is_valid_name(name) {
+        return None;
+    }
+
+    let mut value = 0;
+
+    let items = split_arguments(tail.get(1..).unwrap())
+        .into_iter()
+        .map(|item| {
+            let (item, is_selected) = split_off_selection(item.trim());
+            let (item_name, explicit_value) = split_off_value(item)?;
+
+            value = explicit_value.unwrap_or(value + 1);
+
+            Some((item_name.to_string(), value, is_selected))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    if items.is_empty() {
+        None
+    } else {
+        Some((name.to_string(), items))
+    }
+}
+
+/// Split off enclosing parenthesis from an item and return whether they were present.
+fn split_off_selection(item: &str) -> (&str, bool) {
+    if item.starts_with('(') && item.ends_with(')') {
+        (item.get(1..item.len() - 1).unwrap().trim(), true)
+    } else {
+        (item, false)
+    }
+}
+
+/// Split an item on the form `name = 5` into its name and explicit value, if present.
+fn split_off_value(item: &str) -> Option<(&str, Option<i32>)> {
+    let (name, value) = match item.find('=') {
+        Some(i) => {
+            let value = item.get(i + 1..).unwrap().trim().parse::<i32>().ok()?;
+            (item.get(..i).unwrap().trim(), Some(value))
+        }
+        None => (item, None),
+    };
+
+    if is_valid_name(name) {
+        Some((name, value))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_items(content: &str) -> Vec<(String, i32, bool)> {
+        parse_list_definition(content).unwrap().1
+    }
+
+    #[test]
+    fn list_definition_has_name_and_items_separated_by_commas() {
+        let (name, items) = parse_list_definition("colors = red, green, blue").unwrap();
+
+        assert_eq!(&name, "colors");
+        assert_eq!(
+            items,
+            vec![
+                ("red".to_string(), 1, false),
+                ("green".to_string(), 2, false),
+                ("blue".to_string(), 3, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_in_parenthesis_are_selected() {
+        let items = get_items("colors = red, (green), ( blue )");
+
+        assert!(!items[0].2);
+        assert!(items[1].2);
+        assert!(items[2].2);
+        assert_eq!(&items[2].0, "blue");
+    }
+
+    #[test]
+    fn items_can_be_given_values_which_following_items_count_up_from() {
+        let items = get_items("volume = quiet = 2, (loud = 10), deafening");
+
+        assert_eq!(items[0].1, 2);
+        assert_eq!(items[1].1, 10);
+        assert_eq!(items[2].1, 11);
+    }
+
+    #[test]
+    fn invalid_list_definitions_yield_none() {
+        assert!(parse_list_definition("colors").is_none());
+        assert!(parse_list_definition("colors = ").is_none());
+        assert!(parse_list_definition("colors = red, , blue").is_none());
+        assert!(parse_list_definition("colors = red green").is_none());
+        assert!(parse_list_definition("colors = red = high").is_none());
+        assert!(parse_list_definition("two words = red").is_none());
+    }
+}
@@ -12,25 +12,42 @@
 //! Thus `ParsedLineKind` is a temporary object, used only while parsing an `Ink` story.
 
 mod alternative;
+mod assignment;
+mod block;
 mod choice;
 mod condition;
 pub(self) mod expression;
+mod function;
 mod gather;
 mod kind;
 mod line;
+mod list;
 mod utils;
 mod variable;
 
-pub(self) use alternative::parse_alternative;
+pub(self) use alternative::{get_sequence_keyword_kind, parse_alternative, split_sequence_keyword};
+pub(self) use assignment::parse_logic_line;
+pub(self) use block::{
+    get_block_header, is_block_branch, is_block_end, parse_block_branch, parse_block_start,
+    parse_sequence_branch,
+};
 pub(self) use choice::parse_choice;
-pub(self) use condition::{parse_choice_condition, parse_line_condition};
-pub(self) use expression::parse_expression;
+pub(self) use condition::{parse_choice_condition, parse_condition, parse_line_condition};
+pub use expression::parse_expression;
+pub(self) use function::parse_function_call;
+pub use function::parse_function_signature;
 pub(self) use gather::parse_gather;
-pub use kind::{parse_line, ParsedLineKind};
-pub(self) use kind::{parse_markers_and_text, split_at_divert_marker};
-pub use line::{parse_chunk, parse_internal_line, validate_address};
+#[cfg(test)]
+pub use kind::parse_line;
+pub use kind::{parse_lines, ParsedLineKind};
+pub(self) use kind::{parse_markers_and_text, split_at_divert_marker, split_label_from_text};
+pub(self) use line::parse_thread;
+pub use line::{parse_chunk, parse_internal_line, parse_tag, validate_address};
+pub use list::parse_list_definition;
+pub use utils::{find_unescaped, unescape};
 pub(self) use utils::{
-    split_line_at_separator_braces, split_line_at_separator_parenthesis,
+    get_unescaped_indices, is_escaped, split_line_at_separator_braces,
+    split_line_at_separator_parenthesis, split_line_at_separator_quotes,
     split_line_into_groups_braces, LinePart,
 };
 pub use variable::parse_variable;
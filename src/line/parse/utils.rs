@@ -1,6 +1,6 @@
 //! Utilities for parsing of lines.
 
-use crate::error::parse::line::LineErrorKind;
+use crate::{consts::ESCAPE_MARKER, error::parse::line::LineErrorKind};
 
 use std::{iter::once, ops::Range};
 
@@ -38,7 +38,6 @@ pub fn split_line_at_separator_parenthesis<'a>(
     split_line_at_separator(content, separator, max_splits, '(', ')')
 }
 
-#[allow(dead_code)]
 /// Return line split at a separator, ignoring separators inside double quotes.
 ///
 /// Wrapper around `split_line_at_separator` with double quotes as open and close.
@@ -56,7 +55,8 @@ pub fn split_line_at_separator_quotes<'a>(
 /// *   Should work for strings with multibyte characters, since we search for braces
 ///     based on their byte indices, not char index position.
 /// *   Will not work if the separator itself includes curly '{}' braces.
-/// *   Separators can be escaped with a leading backslash '\' (has to be removed later).
+/// *   Separators can be escaped with a leading backslash '\' (has to be removed later
+///     with `unescape`).
 fn split_line_at_separator<'a>(
     content: &'a str,
     separator: &str,
@@ -84,6 +84,56 @@ fn split_line_at_separator<'a>(
         .collect())
 }
 
+/// Check whether the character at a byte index in a string is escaped.
+///
+/// A character is escaped if it is preceeded by an odd number of escape markers,
+/// since every pair of markers is an escaped, literal marker.
+pub fn is_escaped(content: &str, index: usize) -> bool {
+    content
+        .get(..index)
+        .map(|head| {
+            head.bytes()
+                .rev()
+                .take_while(|&byte| byte == ESCAPE_MARKER as u8)
+                .count()
+                % 2
+                == 1
+        })
+        .unwrap_or(false)
+}
+
+/// Get the byte indices of all matches of a pattern which are not escaped.
+pub fn get_unescaped_indices(content: &str, pattern: &str) -> Vec<usize> {
+    content
+        .match_indices(pattern)
+        .map(|(i, _)| i)
+        .filter(|&i| !is_escaped(content, i))
+        .collect()
+}
+
+/// Find the byte index of the first match of a pattern which is not escaped.
+pub fn find_unescaped(content: &str, pattern: &str) -> Option<usize> {
+    get_unescaped_indices(content, pattern).first().cloned()
+}
+
+/// Remove escape markers from a string, keeping the characters which they escape.
+///
+/// A trailing escape marker which does not escape any character is kept.
+pub fn unescape(content: &str) -> String {
+    let mut buffer = String::with_capacity(content.len());
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        if c == ESCAPE_MARKER {
+            buffer.push(chars.next().unwrap_or(ESCAPE_MARKER));
+        } else {
+            buffer.push(c);
+        }
+    }
+
+    buffer
+}
+
 /// Split a line into parts of pure text and text enclosed in braces.
 ///
 /// Wrapper around `split_line_into_groups` with curly braces as open and close.
@@ -153,16 +203,9 @@ fn get_separator_indices(
     outside_brace_ranges: &[Range<usize>],
     separator: &str,
 ) -> Vec<usize> {
-    let backslash_preceeding_indices = content
-        .match_indices('\\')
-        .map(|(i, _)| i + 1)
-        .collect::<Vec<_>>();
-
-    content
-        .match_indices(separator)
-        .map(|(i, _)| i)
+    get_unescaped_indices(content, separator)
+        .into_iter()
         .filter(|i| outside_brace_ranges.iter().any(|range| range.contains(i)))
-        .filter(|i| !backslash_preceeding_indices.contains(i))
         .collect::<Vec<_>>()
 }
 
@@ -226,7 +269,7 @@ fn get_brace_level_zero_ranges(
 /// zero and one every time the character is encountered.
 ///
 /// # Notes
-/// *   Braces can be preceeded with backslashes ('\') in which case they do not
+/// *   Braces can be escaped with backslashes ('\') in which case they do not
 ///     count as nesting braces.
 /// *   Opening and closing characters must be single-byte characters.
 ///
@@ -244,10 +287,13 @@ fn get_brace_level_of_line(
 ) -> Result<Vec<u8>, LineErrorKind> {
     content
         .bytes()
-        .scan((None, 0), |(prev, brace_level), byte| {
-            if byte == open as u8 && prev.map(|c| c != b'\\').unwrap_or(true) {
+        .enumerate()
+        .scan(0, |brace_level, (i, byte)| {
+            let is_marker = !is_escaped(content, i);
+
+            if byte == open as u8 && is_marker {
                 *brace_level += 1;
-            } else if byte == close as u8 && prev.map(|c| c != b'\\').unwrap_or(true) {
+            } else if byte == close as u8 && is_marker {
                 if *brace_level > 0 {
                     *brace_level -= 1;
                 } else {
@@ -255,8 +301,6 @@ fn get_brace_level_of_line(
                 }
             }
 
-            prev.replace(byte);
-
             if open == close {
                 *brace_level = *brace_level % 2;
             }
@@ -597,4 +641,46 @@ mod tests {
             &[1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 0, 0, 1, 1, 1, 1, 0]
         );
     }
+
+    #[test]
+    fn escaped_backslashes_do_not_escape_braces() {
+        assert_eq!(
+            &get_brace_level_of_line("\\\\{a}", '{', '}').unwrap(),
+            &[0, 0, 1, 1, 0],
+        );
+    }
+
+    #[test]
+    fn separators_after_escaped_backslashes_are_not_escaped() {
+        assert_eq!(
+            split_line_at_separator("One\\\\|Two", "|", None, '{', '}').unwrap(),
+            &["One\\\\", "Two"]
+        );
+    }
+
+    #[test]
+    fn characters_are_escaped_by_an_odd_number_of_preceeding_backslashes() {
+        assert!(!is_escaped("#", 0));
+        assert!(is_escaped("\\#", 1));
+        assert!(!is_escaped("\\\\#", 2));
+        assert!(is_escaped("\\\\\\#", 3));
+    }
+
+    #[test]
+    fn escaped_matches_of_patterns_are_not_found() {
+        assert_eq!(
+            find_unescaped("https:\\//example.com // Link", "//"),
+            Some(21)
+        );
+        assert_eq!(find_unescaped("\\# Not a tag", "#"), None);
+        assert_eq!(get_unescaped_indices("[a\\]]", "]"), &[4]);
+    }
+
+    #[test]
+    fn unescaping_removes_escape_markers_and_keeps_escaped_characters() {
+        assert_eq!(&unescape("\\#1 \\[draft\\]"), "#1 [draft]");
+        assert_eq!(&unescape("C:\\\\Games"), "C:\\Games");
+        assert_eq!(&unescape("Hello\\"), "Hello\\");
+        assert_eq!(&unescape("Hello, World!"), "Hello, World!");
+    }
 }
@@ -4,7 +4,10 @@ use crate::{
     consts::DIVERT_MARKER,
     error::parse::variable::{VariableError, VariableErrorKind},
     knot::Address,
-    line::{parse::validate_address, Variable},
+    line::{
+        parse::{get_unescaped_indices, unescape, validate_address},
+        Variable,
+    },
 };
 
 /// Parse a `Variable` from a line.
@@ -15,10 +18,10 @@ pub fn parse_variable(content: &str) -> Result<Variable, VariableError> {
         Ok(Variable::Bool(true))
     } else if content.to_lowercase() == "false" {
         Ok(Variable::Bool(false))
-    } else if content.starts_with('"') && content.ends_with('"') && content.len() > 2 {
-        Ok(Variable::String(
-            content.get(1..content.len() - 1).unwrap().to_string(),
-        ))
+    } else if is_single_string(content) {
+        Ok(Variable::String(unescape(
+            content.get(1..content.len() - 1).unwrap(),
+        )))
     } else if content.starts_with(DIVERT_MARKER) {
         let inner = content.get(DIVERT_MARKER.len()..).unwrap().trim();
 
@@ -40,6 +43,17 @@ pub fn parse_variable(content: &str) -> Result<Variable, VariableError> {
     })
 }
 
+/// Check whether a string is a single string value within quotation marks.
+///
+/// Content like `"one" + "two"` begins and ends with quotation marks but is not.
+/// Escaped quotation marks inside of the string are part of it: `"\"Hello\""`.
+fn is_single_string(content: &str) -> bool {
+    content.len() > 2
+        && content.starts_with('"')
+        && content.ends_with('"')
+        && get_unescaped_indices(content, "\"") == [0, content.len() - 1]
+}
+
 /// Parse a variable number from a string.
 fn parse_number(content: &str) -> Result<Variable, VariableErrorKind> {
     if content.contains('.') {
@@ -116,6 +130,11 @@ mod tests {
         assert!(parse_variable("knot other_knot").is_err());
     }
 
+    #[test]
+    fn several_strings_are_not_a_single_string_variable() {
+        assert!(parse_variable("\"one\" + \"two\"").is_err());
+    }
+
     #[test]
     fn parse_string_variable_within_quotation_marks() {
         assert_eq!(
@@ -129,6 +148,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn escaped_characters_in_strings_are_unescaped() {
+        assert_eq!(
+            parse_variable("\"She said \\\"Hi\\\"\"").unwrap(),
+            Variable::String("She said \"Hi\"".to_string())
+        );
+
+        assert_eq!(
+            parse_variable("\"C:\\\\Games\"").unwrap(),
+            Variable::String("C:\\Games".to_string())
+        );
+
+        assert!(parse_variable("\"word\\\"").is_err());
+    }
+
     #[test]
     fn parsing_single_quotation_mark_string_is_error() {
         assert!(parse_variable("\"").is_err());
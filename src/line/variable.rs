@@ -9,8 +9,10 @@ use crate::{
     },
     follow::FollowData,
     knot::{get_num_visited, Address, AddressKind},
+    line::{InkList, ListItem},
     log::Logger,
     story::{
+        types::ListDefinitionSet,
         validate::{ValidateContent, ValidationData},
         Location,
     },
@@ -70,6 +72,10 @@ pub enum Variable {
     ///
     /// Will print to that number.
     Int(i32),
+    /// Set of items from lists defined in the story.
+    ///
+    /// Will print the names of its items, separated by commas.
+    List(InkList),
     /// Text string.
     String(String),
     /// Divert to another address.
@@ -139,6 +145,7 @@ impl Variable {
             Variable::Float(value) => Some(format!("{}", value)),
             Variable::Int(value) => Some(format!("{}", value)),
             Variable::String(string) => Some(format!("{}", string)),
+            Variable::List(list) => Some(list.to_text()),
             Variable::Divert(_) | Variable::Address(_) => None,
         }
     }
@@ -201,6 +208,10 @@ impl Variable {
     /// # use inkling::{read_story_from_string, Location, Variable};
     /// let content = "\
     /// VAR location = -> mirandas_den.dream
+    ///
+    /// === mirandas_den ===
+    /// = dream
+    /// You dream of Miranda.
     /// ";
     ///
     /// let story = read_story_from_string(content).unwrap();
@@ -210,6 +221,7 @@ impl Variable {
     ///     Some(Location {
     ///         knot: "mirandas_den".to_string(),
     ///         stitch: Some("dream".to_string()),
+    ///         label: None,
     ///     })
     /// );
     ///
@@ -231,7 +243,8 @@ impl Variable {
     pub(crate) fn to_string_internal(&self, data: &FollowData) -> Result<String, InklingError> {
         match &self {
             Variable::Address(address) => match address {
-                Address::Validated(AddressKind::Location { .. }) => {
+                Address::Validated(AddressKind::Location { .. })
+                | Address::Validated(AddressKind::Label { .. }) => {
                     let num_visited = get_num_visited(address, data)?;
                     Ok(format!("{}", num_visited))
                 }
@@ -242,6 +255,13 @@ impl Variable {
                         name: name.to_string(),
                     })
                     .and_then(|variable_info| variable_info.variable.to_string_internal(data)),
+                Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                    .temp_variables
+                    .get(name)
+                    .ok_or(InklingError::InvalidVariable {
+                        name: name.to_string(),
+                    })
+                    .and_then(|variable| variable.to_string_internal(data)),
                 other => Err(InternalError::UseOfUnvalidatedAddress {
                     address: other.clone(),
                 }
@@ -254,6 +274,7 @@ impl Variable {
             }),
             Variable::Float(value) => Ok(format!("{}", value)),
             Variable::Int(value) => Ok(format!("{}", value)),
+            Variable::List(list) => Ok(list.to_text()),
             Variable::String(content) => Ok(content.clone()),
         }
     }
@@ -268,6 +289,7 @@ impl Variable {
             Variable::Bool(value) => format!("{}", value),
             Variable::Float(value) => format!("{}", value),
             Variable::Int(value) => format!("{}", value),
+            Variable::List(list) => format!("({})", list.to_text()),
             Variable::String(string) => format!("\"{}\"", string),
             Variable::Divert(address) => format!("-> {}", address.to_string()),
         }
@@ -278,14 +300,16 @@ impl Variable {
     /// If the variable is a number, boolean, string or divert a clone of the value is returned.
     ///
     /// If the variable is an address to another variable, we follow the address to that variable
-    /// and return the value of that. This evaluates nested variables to the end.
+    /// and return the value of that. This evaluates nested variables to the end. Temporary
+    /// variables are looked up in the currently visited knot or stitch.
     ///
     /// If the address is to a location in the story, the number of times that location has
     /// been visited is returned as an integer variable.
     pub(crate) fn as_value(&self, data: &FollowData) -> Result<Variable, InklingError> {
         match &self {
             Variable::Address(address) => match address {
-                Address::Validated(AddressKind::Location { .. }) => {
+                Address::Validated(AddressKind::Location { .. })
+                | Address::Validated(AddressKind::Label { .. }) => {
                     let num_visited = get_num_visited(address, data)?;
                     Ok(Variable::Int(num_visited as i32))
                 }
@@ -296,6 +320,13 @@ impl Variable {
                         name: name.to_string(),
                     })
                     .and_then(|info| info.variable.as_value(&data)),
+                Address::Validated(AddressKind::TemporaryVariable { name }) => data
+                    .temp_variables
+                    .get(name)
+                    .ok_or(InklingError::InvalidVariable {
+                        name: name.to_string(),
+                    })
+                    .and_then(|variable| variable.as_value(&data)),
                 other => Err(InternalError::UseOfUnvalidatedAddress {
                     address: other.clone(),
                 }
@@ -356,6 +387,7 @@ impl Variable {
             (Divert(..), Divert(..)) => (),
             (Float(..), Float(..)) => (),
             (Int(..), Int(..)) => (),
+            (List(..), List(..)) => (),
             (String(..), String(..)) => (),
             _ => {
                 return Err(VariableError::from_kind(
@@ -367,7 +399,16 @@ impl Variable {
             }
         }
 
-        *self = inferred_value;
+        match (self, inferred_value) {
+            // Empty lists keep the origins of the previous value, to know which items
+            // they can contain
+            (List(list), List(new_list))
+                if new_list.is_empty() && new_list.origins().is_empty() =>
+            {
+                *list = InkList::with_origins(list.origins().to_vec());
+            }
+            (variable, value) => *variable = value,
+        }
 
         Ok(())
     }
@@ -376,7 +417,7 @@ impl Variable {
     ///
     /// This operation is valid for integer, floating point and string variables.
     /// Integer and floating point variables simply adds the numbers together. String
-    /// variables concatenate their strings.
+    /// variables concatenate their strings. List variables return the union of their items.
     ///
     /// Integer and floating point values can be added to one another. If so, the integer
     /// is cast into a floating point number before the operation and the variable is returned
@@ -426,6 +467,7 @@ impl Variable {
             (Float(val1), Int(val2)) => Ok(Float(val1 + *val2 as f32)),
             (Float(val1), Float(val2)) => Ok(Float(val1 + val2)),
             (String(s1), String(s2)) => Ok(String(format!("{}{}", s1, s2))),
+            (List(list1), List(list2)) => Ok(List(list1.union(list2))),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidOperation {
@@ -438,7 +480,8 @@ impl Variable {
 
     /// Subtract the value of a variable from that of another.
     ///
-    /// This operation is valid for integer and floating point variables.
+    /// This operation is valid for integer, floating point and list variables. Subtracting
+    /// a list from another removes its items from it.
     ///
     /// Integer and floating point values can be subtracted from one another. If so, the integer
     /// is cast into a floating point number before the operation and the variable is returned
@@ -474,6 +517,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok(Float(*val1 as f32 - val2)),
             (Float(val1), Int(val2)) => Ok(Float(val1 - *val2 as f32)),
             (Float(val1), Float(val2)) => Ok(Float(val1 - val2)),
+            (List(list1), List(list2)) => Ok(List(list1.difference(list2))),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidOperation {
@@ -484,6 +528,82 @@ impl Variable {
         }
     }
 
+    /// Return the items which are in both of two list variables.
+    ///
+    /// This operation is only valid for list variables.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{InkList, ListItem, Variable};
+    /// let list1: InkList = vec![ListItem::new("colors", "red"), ListItem::new("colors", "blue")]
+    ///     .into_iter()
+    ///     .collect();
+    /// let list2: InkList = vec![ListItem::new("colors", "blue")].into_iter().collect();
+    ///
+    /// assert_eq!(
+    ///     Variable::from(list1).intersect(&Variable::from(list2.clone())).unwrap(),
+    ///     Variable::from(list2)
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidOperation`][crate::error::variable::VariableErrorKind::InvalidOperation]:
+    ///     if the variables cannot perform this operation.
+    pub fn intersect(&self, other: &Variable) -> Result<Variable, VariableError> {
+        use Variable::*;
+
+        match (&self, other) {
+            (List(list1), List(list2)) => Ok(List(list1.intersection(list2))),
+            _ => Err(VariableError::from_kind(
+                self.clone(),
+                VariableErrorKind::InvalidOperation {
+                    other: other.clone(),
+                    operator: '^',
+                },
+            )),
+        }
+    }
+
+    /// Assert whether a list variable contains all items of another, or a string contains
+    /// another string.
+    ///
+    /// This operation is only valid for list and string variables. Empty lists never contain
+    /// and are never contained in other lists.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{InkList, ListItem, Variable};
+    /// let list: InkList = vec![ListItem::new("colors", "red"), ListItem::new("colors", "blue")]
+    ///     .into_iter()
+    ///     .collect();
+    /// let red: InkList = vec![ListItem::new("colors", "red")].into_iter().collect();
+    ///
+    /// assert!(Variable::from(list.clone()).contains(&Variable::from(red)).unwrap());
+    /// assert!(!Variable::from(list).contains(&Variable::from(InkList::new())).unwrap());
+    ///
+    /// assert!(Variable::from("Hello, World!").contains(&Variable::from("World")).unwrap());
+    /// assert!(!Variable::from("Hello, World!").contains(&Variable::from("world")).unwrap());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidOperation`][crate::error::variable::VariableErrorKind::InvalidOperation]:
+    ///     if the variables cannot perform this operation.
+    pub fn contains(&self, other: &Variable) -> Result<bool, VariableError> {
+        use Variable::*;
+
+        match (&self, other) {
+            (List(list1), List(list2)) => Ok(list1.contains_all(list2)),
+            (String(string1), String(string2)) => Ok(string1.contains(string2.as_str())),
+            _ => Err(VariableError::from_kind(
+                self.clone(),
+                VariableErrorKind::InvalidOperation {
+                    other: other.clone(),
+                    operator: '?',
+                },
+            )),
+        }
+    }
+
     /// Multiply the value of a variable with that of another.
     ///
     /// This operation is valid for integer and floating point variables.
@@ -683,6 +803,7 @@ impl Variable {
             (Bool(val1), Bool(val2)) => Ok(val1.eq(val2)),
             (Address(val1), Address(val2)) => Ok(val1.eq(val2)),
             (Divert(val1), Divert(val2)) => Ok(val1.eq(val2)),
+            (List(val1), List(val2)) => Ok(val1.same_items(val2)),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidComparison {
@@ -695,10 +816,13 @@ impl Variable {
 
     /// Assert whether a numeric variable value is greater than that of another.
     ///
-    /// This operation is only valid for `Int` and `Float` variants. Those variants can
-    /// be compared to each other. If an integer is compared to a floating point number
+    /// This operation is only valid for `Int`, `Float` and `List` variants. Numeric variants
+    /// can be compared to each other. If an integer is compared to a floating point number
     /// the integer will be cast to a float, then the comparison is made.
     ///
+    /// A list is greater than another if all its items have higher values than all
+    /// items of the other list. Empty lists are never greater than other lists.
+    ///
     /// # Examples
     /// ## Valid comparisons between numbers
     /// ```
@@ -728,6 +852,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok((*val1 as f32).gt(val2)),
             (Float(val1), Int(val2)) => Ok(val1.gt(&(*val2 as f32))),
             (Float(val1), Float(val2)) => Ok(val1.gt(val2)),
+            (List(val1), List(val2)) => Ok(val1.greater_than(val2)),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidComparison {
@@ -740,10 +865,13 @@ impl Variable {
 
     /// Assert whether a numeric variable value is less than that of another.
     ///
-    /// This operation is only valid for `Int` and `Float` variants. Those variants can
-    /// be compared to each other. If an integer is compared to a floating point number
+    /// This operation is only valid for `Int`, `Float` and `List` variants. Numeric variants
+    /// can be compared to each other. If an integer is compared to a floating point number
     /// the integer will be cast to a float, then the comparison is made.
     ///
+    /// A list is less than another if all its items have lower values than all
+    /// items of the other list. Empty lists are never less than other lists.
+    ///
     /// # Examples
     /// ## Valid comparisons between numbers
     /// ```
@@ -773,6 +901,7 @@ impl Variable {
             (Int(val1), Float(val2)) => Ok((*val1 as f32).lt(val2)),
             (Float(val1), Int(val2)) => Ok(val1.lt(&(*val2 as f32))),
             (Float(val1), Float(val2)) => Ok(val1.lt(val2)),
+            (List(val1), List(val2)) => Ok(val1.less_than(val2)),
             _ => Err(VariableError::from_kind(
                 self.clone(),
                 VariableErrorKind::InvalidComparison {
@@ -783,6 +912,31 @@ impl Variable {
         }
     }
 
+    /// Assert whether the variable value is "true".
+    ///
+    /// Boolean variables evaluate directly. Numbers are `true` if they are non-zero,
+    /// strings if they have non-zero length and lists if they contain any item.
+    ///
+    /// # Errors
+    /// *   [`InvalidComparison`][crate::error::variable::VariableErrorKind::InvalidComparison]:
+    ///     if the variable is a divert, which is neither `true` nor `false`.
+    pub(crate) fn is_true_like(&self) -> Result<bool, VariableError> {
+        match self {
+            Variable::Bool(value) => Ok(*value),
+            Variable::Float(value) => Ok(*value != 0.0),
+            Variable::Int(value) => Ok(*value != 0),
+            Variable::String(s) => Ok(!s.is_empty()),
+            Variable::List(list) => Ok(!list.is_empty()),
+            Variable::Divert(..) | Variable::Address(..) => Err(VariableError::from_kind(
+                self.clone(),
+                VariableErrorKind::InvalidComparison {
+                    other: Variable::Bool(true),
+                    comparison: Ordering::Equal,
+                },
+            )),
+        }
+    }
+
     /// Get string representation of the variant.
     pub(crate) fn variant_string(&self) -> &str {
         match &self {
@@ -791,6 +945,7 @@ impl Variable {
             Variable::Divert(..) => "DivertTarget",
             Variable::Float(..) => "Float",
             Variable::Int(..) => "Int",
+            Variable::List(..) => "List",
             Variable::String(..) => "String",
         }
     }
@@ -829,6 +984,12 @@ impl From<&String> for Variable {
     }
 }
 
+impl From<InkList> for Variable {
+    fn from(list: InkList) -> Self {
+        Variable::List(list)
+    }
+}
+
 impl From<String> for Variable {
     fn from(string: String) -> Self {
         Variable::String(string.clone())
@@ -845,13 +1006,60 @@ impl ValidateContent for Variable {
         data: &ValidationData,
     ) {
         match self {
+            Variable::Address(address @ Address::Raw(..)) => {
+                let mut validated = address.clone();
+                let mut address_error = ValidationError::new();
+
+                validated.validate(&mut address_error, log, current_location, meta_data, data);
+
+                // Names which are not an address may be items of a defined list
+                if address_error.is_empty() {
+                    *address = validated;
+                } else if let Some(item) =
+                    get_list_item(&address.to_string(), &data.follow_data.lists)
+                {
+                    *self = Variable::List(std::iter::once(item).collect());
+                } else {
+                    address.validate(error, log, current_location, meta_data, data);
+                }
+            }
             Variable::Address(address) | Variable::Divert(address) => {
                 address.validate(error, log, current_location, meta_data, data);
             }
-            Variable::Bool(..) | Variable::Float(..) | Variable::Int(..) | Variable::String(..) => {
-                ()
-            }
+            Variable::Bool(..)
+            | Variable::Float(..)
+            | Variable::Int(..)
+            | Variable::List(..)
+            | Variable::String(..) => (),
+        }
+    }
+}
+
+/// Find the list item with the given name in the list definitions of the story.
+///
+/// The name can be either just the item name, which has to be unique among all lists,
+/// or qualified with the list name: `list.item`.
+pub(crate) fn get_list_item(name: &str, lists: &ListDefinitionSet) -> Option<ListItem> {
+    let mut matches = match name.find('.') {
+        Some(i) => {
+            let (list_name, item_name) = (&name[..i], &name[i + 1..]);
+
+            lists
+                .get(list_name)
+                .and_then(|definition| definition.get_item(list_name, item_name))
+                .into_iter()
+                .collect::<Vec<_>>()
         }
+        None => lists
+            .iter()
+            .filter_map(|(list_name, definition)| definition.get_item(list_name, name))
+            .collect(),
+    };
+
+    if matches.len() == 1 {
+        matches.pop()
+    } else {
+        None
     }
 }
 
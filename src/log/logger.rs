@@ -21,7 +21,7 @@ use serde::{Deserialize, Serialize};
 /// # let content = "Story content.";
 /// let story = read_story_from_string(content).unwrap();
 ///
-/// for msg in story.log.iter() {
+/// for msg in story.get_log().iter() {
 ///     eprintln!("{}", msg);
 /// }
 /// ```
@@ -67,7 +67,7 @@ impl Logger {
     /// Iterate over the logged messages.
     ///
     /// The iterator visits the messages in the order of their line numbers.
-    pub fn iter(&self) -> LoggerIter {
+    pub fn iter(&self) -> LoggerIter<'_> {
         LoggerIter {
             todo_comments: self.todo_comments.iter().peekable(),
             warnings: self.warnings.iter().peekable(),
@@ -42,6 +42,8 @@ pub enum MessageKind {
 pub enum Warning {
     /// Found a shuffle sequence but the `random` feature is not enabled.
     ShuffleSequenceNoRandom,
+    /// Found the beginning of a multiline comment which was never closed.
+    UnclosedMultilineComment,
 }
 
 impl fmt::Display for LogMessage {
@@ -74,9 +76,14 @@ impl fmt::Display for Warning {
             ShuffleSequenceNoRandom => write!(
                 f,
                 "found a shuffle sequence but the `random` feature is not enabled: \
-                 changed it to a cycle sequence (fix: compile `inkling` with the \
+                 its items are selected in order (fix: compile `inkling` with the \
                  `random` feature)"
             ),
+            UnclosedMultilineComment => write!(
+                f,
+                "found a multiline comment which was not closed: all content after it \
+                 was treated as a comment"
+            ),
         }
     }
 }
@@ -1,14 +1,14 @@
 //! Processing nested story content by following, or walking through, it.
 
 use crate::{
-    error::{runtime::internal::IncorrectNodeStackError, InternalError},
+    error::{runtime::internal::IncorrectNodeStackError, InklingError, InternalError},
     follow::{ChoiceInfo, EncounteredEvent, FollowData, FollowResult, LineDataBuffer},
     knot::increment_num_visited,
-    node::{Branch, NodeItem, RootNode},
-    process::process_line,
+    node::{AlternativeBlock, Branch, ConditionalBranch, NodeItem, RootNode},
+    process::{check_condition, process_line},
 };
 
-use std::{fmt, slice::IterMut};
+use std::fmt;
 
 /// Represents the current stack of choices made from the tree root.
 ///
@@ -64,45 +64,99 @@ pub trait Follow: FollowInternal {
     ///
     ///     Ensure that the stack is maintained before calling this method.
     fn follow(
-        &mut self,
+        &self,
         stack: &mut Stack,
         buffer: &mut LineDataBuffer,
         data: &mut FollowData,
     ) -> FollowResult {
-        let at_index = stack
-            .last_mut()
+        let stack_index = stack
+            .len()
+            .checked_sub(1)
             .ok_or(InternalError::from(IncorrectNodeStackError::EmptyStack))?;
 
-        if *at_index > self.get_num_items() {
+        if stack[stack_index] > self.get_num_items() {
             return Err(InternalError::from(IncorrectNodeStackError::OutOfBounds {
-                stack_index: stack.len() - 1,
+                stack_index,
                 stack: stack.clone(),
                 num_items: self.get_num_items(),
             })
             .into());
-        } else if *at_index == 0 {
+        } else if stack[stack_index] == 0 {
+            // Pause before the node is counted as visited, since it is entered again
+            if self
+                .get_item(0)
+                .is_some_and(|item| pauses_before(item, data))
+            {
+                return Ok(EncounteredEvent::Paused);
+            }
+
             self.increment_num_visited(data)?;
         }
 
-        for item in self.iter_mut_items().skip(*at_index) {
-            *at_index += 1;
+        while let Some(item) = self.get_item(stack[stack_index]) {
+            if stack[stack_index] > 0 && pauses_before(item, data) {
+                return Ok(EncounteredEvent::Paused);
+            }
+
+            stack[stack_index] += 1;
 
             match item {
                 NodeItem::Line(line) => {
                     let result =
-                        process_line(line, buffer, data).map_err(|err| InternalError::from(err))?;
-
-                    if let EncounteredEvent::Divert(..) = result {
-                        return Ok(result);
+                        process_line(line, buffer, data).map_err(|err| InklingError::from(err))?;
+
+                    match result {
+                        EncounteredEvent::Done
+                            if data.pause_after_lines && data.call_stack.is_empty() =>
+                        {
+                            return Ok(EncounteredEvent::Paused)
+                        }
+                        EncounteredEvent::Done => (),
+                        _ => return Ok(result),
                     }
                 }
+                NodeItem::Label(address) => {
+                    increment_num_visited(address, data)?;
+                }
                 NodeItem::BranchingPoint(branches) => {
-                    *at_index -= 1;
+                    stack[stack_index] -= 1;
 
-                    let branching_choice_set = get_choices_from_branching_set(branches);
+                    let branching_choice_set = get_choices_from_branching_set(branches, data);
 
                     return Ok(EncounteredEvent::BranchingChoice(branching_choice_set));
                 }
+                NodeItem::Conditional(branches) => {
+                    if let Some(branch_index) = get_fulfilled_branch_index(branches, data)? {
+                        // The stack points to the block while its branch is followed, which
+                        // lets us return to it if a choice is encountered in the branch
+                        stack[stack_index] -= 1;
+                        stack.extend_from_slice(&[branch_index, 0]);
+
+                        match branches[branch_index].follow(stack, buffer, data)? {
+                            EncounteredEvent::Done => {
+                                stack.truncate(stack_index + 1);
+                                stack[stack_index] += 1;
+                            }
+                            other => return Ok(other),
+                        }
+                    }
+                }
+                NodeItem::Alternative(block) => {
+                    if let Some(branch_index) = block.get_next_index(data) {
+                        // Like for conditional blocks, the stack points to the block
+                        // while its selected branch is followed
+                        stack[stack_index] -= 1;
+                        stack.extend_from_slice(&[branch_index, 0]);
+
+                        match block.branches[branch_index].follow(stack, buffer, data)? {
+                            EncounteredEvent::Done => {
+                                stack.truncate(stack_index + 1);
+                                stack[stack_index] += 1;
+                            }
+                            other => return Ok(other),
+                        }
+                    }
+                }
             }
         }
 
@@ -129,7 +183,7 @@ pub trait Follow: FollowInternal {
     /// Finally, when we return from a deeper level due to running out of content in that node,
     /// we keep `follow`ing the content in the current node until its end.
     fn follow_with_choice(
-        &mut self,
+        &self,
         selection: usize,
         stack_index: usize,
         stack: &mut Stack,
@@ -139,7 +193,7 @@ pub trait Follow: FollowInternal {
         let result = if let Some(next_branch) = self.get_next_level_branch(stack_index, stack)? {
             next_branch.follow_with_choice(selection, stack_index + 2, stack, buffer, data)
         } else {
-            let selected_branch = self.get_selected_branch(selection, stack_index, stack)?;
+            let selected_branch = self.get_selected_branch(selection, stack_index, stack, data)?;
 
             stack.extend_from_slice(&[selection, 0]);
 
@@ -156,10 +210,44 @@ pub trait Follow: FollowInternal {
             other => Ok(other),
         }
     }
+
+    /// Follow the content in the tree from a position that may be nested in branches.
+    ///
+    /// Will move through the tree using the `Stack` until the deepest level is reached,
+    /// without making any choices along the way. From there the content is followed
+    /// as usual, and when a nested branch runs out of content the follow continues
+    /// at the level above it, like when resuming with a choice.
+    ///
+    /// This is used to start a follow at a label inside of a branch.
+    fn follow_from_stack(
+        &self,
+        stack_index: usize,
+        stack: &mut Stack,
+        buffer: &mut LineDataBuffer,
+        data: &mut FollowData,
+    ) -> FollowResult {
+        let result = match self.get_next_level_branch(stack_index, stack)? {
+            Some(next_branch) => {
+                next_branch.follow_from_stack(stack_index + 2, stack, buffer, data)
+            }
+            None => return self.follow(stack, buffer, data),
+        }?;
+
+        match result {
+            EncounteredEvent::Done => {
+                stack.truncate(stack_index + 1);
+                stack.last_mut().map(|i| *i += 1);
+
+                self.follow(stack, buffer, data)
+            }
+            other => Ok(other),
+        }
+    }
 }
 
 impl Follow for RootNode {}
 impl Follow for Branch {}
+impl Follow for ConditionalBranch {}
 
 /// Internal utilities required to implement `Follow`.
 ///
@@ -167,55 +255,85 @@ impl Follow for Branch {}
 /// when importing `Follow`.
 pub trait FollowInternal: fmt::Debug {
     fn get_next_level_branch(
-        &mut self,
+        &self,
         stack_index: usize,
         stack: &Stack,
-    ) -> Result<Option<&mut Branch>, InternalError> {
+    ) -> Result<Option<&dyn Follow>, InternalError> {
         if stack_index < stack.len() - 1 {
-            self.get_branches_at_stack_index(stack_index, stack)
-                .and_then(|branches| {
-                    let branch_index = stack.get(stack_index + 1).ok_or(
-                        IncorrectNodeStackError::MissingBranchIndex {
-                            stack_index,
-                            stack: stack.clone(),
-                        },
-                    )?;
-
-                    Ok((branch_index, branches))
-                })
-                .and_then(|(branch_index, branches)| {
-                    let num_items = branches.len();
-
-                    Some(
-                        branches.get_mut(*branch_index).ok_or(
-                            IncorrectNodeStackError::OutOfBounds {
-                                stack_index: stack_index + 1,
-                                stack: stack.clone(),
-                                num_items,
-                            }
-                            .into(),
-                        ),
-                    )
-                    .transpose()
-                })
+            let branch_index =
+                *stack
+                    .get(stack_index + 1)
+                    .ok_or(IncorrectNodeStackError::MissingBranchIndex {
+                        stack_index,
+                        stack: stack.clone(),
+                    })?;
+
+            let num_items = self.get_num_items();
+
+            let item = stack
+                .get(stack_index)
+                .and_then(|i| self.get_item(*i))
+                .ok_or(IncorrectNodeStackError::OutOfBounds {
+                    stack_index,
+                    stack: stack.clone(),
+                    num_items,
+                })?;
+
+            let (branch, num_branches): (Option<&dyn Follow>, usize) = match item {
+                NodeItem::BranchingPoint(branches) => {
+                    let num_branches = branches.len();
+                    let branch = branches
+                        .get(branch_index)
+                        .map(|branch| branch as &dyn Follow);
+
+                    (branch, num_branches)
+                }
+                NodeItem::Conditional(branches)
+                | NodeItem::Alternative(AlternativeBlock { branches, .. }) => {
+                    let num_branches = branches.len();
+                    let branch = branches
+                        .get(branch_index)
+                        .map(|branch| branch as &dyn Follow);
+
+                    (branch, num_branches)
+                }
+                NodeItem::Line(..) | NodeItem::Label(..) => {
+                    return Err(IncorrectNodeStackError::ExpectedBranchingPoint {
+                        stack_index,
+                        stack: stack.clone(),
+                    }
+                    .into());
+                }
+            };
+
+            branch
+                .ok_or(
+                    IncorrectNodeStackError::OutOfBounds {
+                        stack_index: stack_index + 1,
+                        stack: stack.clone(),
+                        num_items: num_branches,
+                    }
+                    .into(),
+                )
+                .map(Some)
         } else {
             Ok(None)
         }
     }
 
     fn get_selected_branch(
-        &mut self,
+        &self,
         branch_index: usize,
         stack_index: usize,
         stack: &Stack,
-    ) -> Result<&mut Branch, InternalError> {
+        data: &FollowData,
+    ) -> Result<&Branch, InternalError> {
         self.get_branches_at_stack_index(stack_index, stack)
-            .map_err(|err| err.into())
             .and_then(|branches| {
-                let branch_choices = get_choices_from_branching_set(branches);
+                let branch_choices = get_choices_from_branching_set(branches, data);
 
                 branches
-                    .get_mut(branch_index)
+                    .get(branch_index)
                     .ok_or(InternalError::IncorrectChoiceIndex {
                         selection: branch_index,
                         available_choices: branch_choices,
@@ -226,15 +344,15 @@ pub trait FollowInternal: fmt::Debug {
     }
 
     fn get_branches_at_stack_index(
-        &mut self,
+        &self,
         stack_index: usize,
         stack: &Stack,
-    ) -> Result<&mut Vec<Branch>, InternalError> {
+    ) -> Result<&[Branch], InternalError> {
         let num_items = self.get_num_items();
 
         stack
             .get(stack_index)
-            .and_then(move |i| self.get_item_mut(*i))
+            .and_then(|i| self.get_item(*i))
             .ok_or(
                 IncorrectNodeStackError::OutOfBounds {
                     stack_index,
@@ -244,8 +362,11 @@ pub trait FollowInternal: fmt::Debug {
                 .into(),
             )
             .and_then(|item| match item {
-                NodeItem::BranchingPoint(branches) => Ok(branches),
-                NodeItem::Line(..) => Err(IncorrectNodeStackError::ExpectedBranchingPoint {
+                NodeItem::BranchingPoint(branches) => Ok(branches.as_slice()),
+                NodeItem::Line(..)
+                | NodeItem::Conditional(..)
+                | NodeItem::Alternative(..)
+                | NodeItem::Label(..) => Err(IncorrectNodeStackError::ExpectedBranchingPoint {
                     stack_index,
                     stack: stack.clone(),
                 }
@@ -254,10 +375,8 @@ pub trait FollowInternal: fmt::Debug {
     }
 
     fn get_item(&self, index: usize) -> Option<&NodeItem>;
-    fn get_item_mut(&mut self, index: usize) -> Option<&mut NodeItem>;
     fn get_num_items(&self) -> usize;
-    fn increment_num_visited(&mut self, data: &mut FollowData) -> Result<(), InternalError>;
-    fn iter_mut_items(&mut self) -> IterMut<NodeItem>;
+    fn increment_num_visited(&self, data: &mut FollowData) -> Result<(), InternalError>;
 }
 
 impl FollowInternal for RootNode {
@@ -265,21 +384,13 @@ impl FollowInternal for RootNode {
         self.items.get(index)
     }
 
-    fn get_item_mut(&mut self, index: usize) -> Option<&mut NodeItem> {
-        self.items.get_mut(index)
-    }
-
     fn get_num_items(&self) -> usize {
         self.items.len()
     }
 
-    fn increment_num_visited(&mut self, data: &mut FollowData) -> Result<(), InternalError> {
+    fn increment_num_visited(&self, data: &mut FollowData) -> Result<(), InternalError> {
         increment_num_visited(&self.address, data)
     }
-
-    fn iter_mut_items(&mut self) -> IterMut<NodeItem> {
-        self.items.iter_mut()
-    }
 }
 
 impl FollowInternal for Branch {
@@ -287,30 +398,75 @@ impl FollowInternal for Branch {
         self.items.get(index)
     }
 
-    fn get_item_mut(&mut self, index: usize) -> Option<&mut NodeItem> {
-        self.items.get_mut(index)
+    fn get_num_items(&self) -> usize {
+        self.items.len()
+    }
+
+    fn increment_num_visited(&self, data: &mut FollowData) -> Result<(), InternalError> {
+        *data.branch_visit_counts.entry(self.id).or_insert(0) += 1;
+
+        Ok(())
+    }
+}
+
+impl FollowInternal for ConditionalBranch {
+    fn get_item(&self, index: usize) -> Option<&NodeItem> {
+        self.items.get(index)
     }
 
     fn get_num_items(&self) -> usize {
         self.items.len()
     }
 
-    fn increment_num_visited(&mut self, _: &mut FollowData) -> Result<(), InternalError> {
-        self.num_visited += 1;
-
+    fn increment_num_visited(&self, _: &mut FollowData) -> Result<(), InternalError> {
         Ok(())
     }
+}
+
+/// Whether or not the follow pauses before an item, since it contains logic which should
+/// only be evaluated once the story is continued past the previous line.
+///
+/// Items in functions are never paused before.
+fn pauses_before(item: &NodeItem, data: &FollowData) -> bool {
+    data.pause_before_logic && data.call_stack.is_empty() && item.has_logic()
+}
 
-    fn iter_mut_items(&mut self) -> IterMut<NodeItem> {
-        self.items.iter_mut()
+/// Get the index of the first branch in a conditional block whose condition is fulfilled.
+///
+/// Branches without a condition are always fulfilled. If no branch is fulfilled,
+/// `None` is returned and the block is skipped.
+fn get_fulfilled_branch_index(
+    branches: &[ConditionalBranch],
+    data: &mut FollowData,
+) -> Result<Option<usize>, InklingError> {
+    for (i, branch) in branches.iter().enumerate() {
+        let fulfilled = match &branch.condition {
+            Some(condition) => check_condition(condition, data)?,
+            None => true,
+        };
+
+        if fulfilled {
+            return Ok(Some(i));
+        }
     }
+
+    Ok(None)
 }
 
 /// Collect the `ChoiceInfo` from a given set of branches.
-fn get_choices_from_branching_set(branches: &[Branch]) -> Vec<ChoiceInfo> {
+fn get_choices_from_branching_set(branches: &[Branch], data: &FollowData) -> Vec<ChoiceInfo> {
     branches
         .iter()
-        .map(|branch| ChoiceInfo::from_choice(&branch.choice, branch.num_visited))
+        .enumerate()
+        .map(|(i, branch)| {
+            let num_visited = data
+                .branch_visit_counts
+                .get(&branch.id)
+                .copied()
+                .unwrap_or(0);
+
+            ChoiceInfo::from_choice(&branch.choice, num_visited, i)
+        })
         .collect::<Vec<_>>()
 }
 
@@ -321,9 +477,11 @@ mod tests {
     use crate::{
         error::InklingError,
         follow::FollowDataBuilder,
-        knot::{get_num_visited, Address},
-        line::{InternalChoice, LineChunkBuilder},
-        node::builders::{BranchBuilder, BranchingPointBuilder, RootNodeBuilder},
+        knot::{get_num_visited, Address, AddressKind},
+        line::{Condition, ConditionBuilder, ConditionKind, InternalChoice, LineChunkBuilder},
+        node::builders::{
+            BranchBuilder, BranchingPointBuilder, ConditionalBranchBuilder, RootNodeBuilder,
+        },
     };
 
     use std::collections::HashMap;
@@ -344,7 +502,7 @@ mod tests {
 
     #[test]
     fn stack_that_points_to_line_instead_of_branching_choice_returns_error() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .build();
 
@@ -363,7 +521,7 @@ mod tests {
 
     #[test]
     fn out_of_bounds_stack_indices_return_stack_error() {
-        let mut node = RootNodeBuilder::empty().build();
+        let node = RootNodeBuilder::empty().build();
 
         let mut buffer = Vec::new();
         let mut stack = vec![0];
@@ -380,7 +538,7 @@ mod tests {
 
     #[test]
     fn out_of_bounds_stack_indices_return_stack_error_when_checking_branches() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(BranchingPointBuilder::new().build())
             .build();
 
@@ -401,7 +559,7 @@ mod tests {
     fn branch_choices_are_collected_when_supplying_an_incorrect_index_for_a_choice() {
         let internal_choice = InternalChoice::from_string("Choice");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(BranchBuilder::from_choice(internal_choice.clone()).build())
@@ -429,7 +587,7 @@ mod tests {
 
     #[test]
     fn following_items_in_a_node_adds_lines_to_buffer() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_text_line_chunk("Line 2")
             .build();
@@ -450,7 +608,7 @@ mod tests {
 
     #[test]
     fn following_into_a_node_increments_number_of_visits() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .build();
 
@@ -467,7 +625,7 @@ mod tests {
 
     #[test]
     fn following_items_updates_stack() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_text_line_chunk("Line 2")
             .build();
@@ -482,7 +640,7 @@ mod tests {
 
     #[test]
     fn following_items_starts_from_stack() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_text_line_chunk("Line 2")
             .build();
@@ -499,7 +657,7 @@ mod tests {
 
     #[test]
     fn follow_always_uses_last_position_in_stack() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_text_line_chunk("Line 2")
             .with_text_line_chunk("Line 3")
@@ -519,7 +677,7 @@ mod tests {
 
     #[test]
     fn following_into_a_node_does_not_increment_number_of_visits_if_stack_is_non_zero() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_text_line_chunk("Line 2")
             .build();
@@ -536,7 +694,7 @@ mod tests {
 
     #[test]
     fn following_into_line_with_divert_immediately_returns_it() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_line_chunk(
                 LineChunkBuilder::new()
@@ -571,7 +729,7 @@ mod tests {
             .with_branch(BranchBuilder::from_choice(choice2.clone()).build())
             .build();
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(branching_choice_set)
             .build();
 
@@ -602,7 +760,7 @@ mod tests {
             .with_branch(BranchBuilder::from_choice(choice2.clone()).build())
             .build();
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_branching_choice(branching_choice_set)
             .build();
@@ -645,7 +803,7 @@ mod tests {
             .with_branch(nested_branch) // Stack: [1, 2]
             .build();
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .with_branching_choice(root_branching_choice) // Stack: [1]
             .with_text_line_chunk("Line 5")
@@ -666,7 +824,7 @@ mod tests {
     fn after_finishing_with_a_branch_lower_nodes_return_to_their_content() {
         let choice = InternalChoice::from_string("Choice");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(BranchBuilder::from_choice(choice).build())
@@ -702,6 +860,15 @@ mod tests {
             )
             .build();
 
+        match &mut node.items[0] {
+            NodeItem::BranchingPoint(branches) => {
+                for (id, branch) in branches.iter_mut().enumerate() {
+                    branch.id = id;
+                }
+            }
+            _ => unreachable!(),
+        }
+
         let mut buffer = Vec::new();
         let mut stack = vec![0];
         let mut data = mock_follow_data(&node);
@@ -709,21 +876,16 @@ mod tests {
         node.follow_with_choice(1, 0, &mut stack, &mut buffer, &mut data)
             .unwrap();
 
-        match &node.items[0] {
-            NodeItem::BranchingPoint(branches) => {
-                assert_eq!(branches[0].num_visited, 0);
-                assert_eq!(branches[1].num_visited, 1);
-                assert_eq!(branches[2].num_visited, 0);
-            }
-            _ => unreachable!(),
-        }
+        assert_eq!(data.branch_visit_counts.get(&0), None);
+        assert_eq!(data.branch_visit_counts.get(&1), Some(&1));
+        assert_eq!(data.branch_visit_counts.get(&2), None);
     }
 
     #[test]
     fn encountered_choices_return_with_their_number_of_visits_counter() {
         let choice = InternalChoice::from_string("Choice");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(BranchBuilder::from_choice(choice.clone()).build())
@@ -756,7 +918,7 @@ mod tests {
     fn selected_branches_adds_line_text_to_line_buffer() {
         let choice = InternalChoice::from_string("Choice");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(BranchBuilder::from_choice(choice.clone()).build())
@@ -778,7 +940,7 @@ mod tests {
     fn diverts_found_after_selections_are_returned() {
         let choice = InternalChoice::from_string("Choice -> divert");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(BranchBuilder::from_choice(choice.clone()).build())
@@ -813,7 +975,7 @@ mod tests {
             )
             .build();
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(branch_set)
             .build();
 
@@ -834,7 +996,7 @@ mod tests {
     fn after_a_followed_choice_returns_the_caller_nodes_always_follow_into_their_next_lines() {
         let choice = InternalChoice::from_string("Choice");
 
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_branching_choice(
                 BranchingPointBuilder::new()
                     .with_branch(
@@ -885,7 +1047,7 @@ mod tests {
 
     #[test]
     fn following_with_stack_that_has_too_large_index_raises_error() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .build();
 
@@ -909,7 +1071,7 @@ mod tests {
 
     #[test]
     fn following_with_empty_stack_raises_error() {
-        let mut node = RootNodeBuilder::empty()
+        let node = RootNodeBuilder::empty()
             .with_text_line_chunk("Line 1")
             .build();
 
@@ -930,4 +1092,187 @@ mod tests {
             ),
         }
     }
+
+    #[test]
+    fn following_from_nested_stack_starts_inside_branch_and_returns_to_lower_levels() {
+        let choice = InternalChoice::from_string("Choice");
+
+        let node = RootNodeBuilder::empty()
+            .with_branching_choice(
+                BranchingPointBuilder::new()
+                    .with_branch(
+                        BranchBuilder::from_choice(choice.clone())
+                            .with_text_line_chunk("Line 1")
+                            .build(),
+                    )
+                    .with_branch(
+                        BranchBuilder::from_choice(choice.clone())
+                            .with_text_line_chunk("Line 2")
+                            .with_text_line_chunk("Line 3")
+                            .build(),
+                    )
+                    .build(),
+            )
+            .with_text_line_chunk("Line 4")
+            .build();
+
+        let mut buffer = Vec::new();
+        let mut stack = vec![0, 1, 2];
+        let mut data = mock_follow_data(&node);
+
+        assert_eq!(
+            node.follow_from_stack(0, &mut stack, &mut buffer, &mut data)
+                .unwrap(),
+            EncounteredEvent::Done
+        );
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(&buffer[0].text, "Line 3");
+        assert_eq!(&buffer[1].text, "Line 4");
+    }
+
+    #[test]
+    fn following_past_label_increments_its_number_of_visits() {
+        let label = Address::Validated(AddressKind::Label {
+            knot: "".to_string(),
+            stitch: "".to_string(),
+            label: "label".to_string(),
+        });
+
+        let node = RootNodeBuilder::empty()
+            .with_item(NodeItem::Label(label.clone()))
+            .with_text_line_chunk("Line 1")
+            .build();
+
+        let mut label_counts = HashMap::new();
+        label_counts.insert("label".to_string(), 0);
+
+        let mut stitch_label_counts = HashMap::new();
+        stitch_label_counts.insert("".to_string(), label_counts);
+
+        let mut label_visit_counts = HashMap::new();
+        label_visit_counts.insert("".to_string(), stitch_label_counts);
+
+        let mut data = mock_follow_data(&node);
+        data.label_visit_counts = label_visit_counts;
+
+        let mut buffer = Vec::new();
+
+        node.follow(&mut vec![0], &mut buffer, &mut data).unwrap();
+        node.follow(&mut vec![0], &mut buffer, &mut data).unwrap();
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(get_num_visited(&label, &data).unwrap(), 2);
+    }
+
+    fn get_condition(value: bool) -> Condition {
+        let kind = if value {
+            ConditionKind::True
+        } else {
+            ConditionKind::False
+        };
+
+        ConditionBuilder::from_kind(&kind, false).build()
+    }
+
+    #[test]
+    fn following_conditional_follows_first_branch_with_fulfilled_condition() {
+        let node = RootNodeBuilder::empty()
+            .with_item(NodeItem::Conditional(vec![
+                ConditionalBranchBuilder::from_condition(Some(get_condition(false)))
+                    .with_text_line_chunk("Line 1")
+                    .build(),
+                ConditionalBranchBuilder::from_condition(Some(get_condition(true)))
+                    .with_text_line_chunk("Line 2")
+                    .build(),
+                ConditionalBranchBuilder::from_condition(None)
+                    .with_text_line_chunk("Line 3")
+                    .build(),
+            ]))
+            .with_text_line_chunk("Line 4")
+            .build();
+
+        let mut buffer = Vec::new();
+        let mut stack = vec![0];
+        let mut data = mock_follow_data(&node);
+
+        assert_eq!(
+            node.follow(&mut stack, &mut buffer, &mut data).unwrap(),
+            EncounteredEvent::Done
+        );
+
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(&buffer[0].text, "Line 2");
+        assert_eq!(&buffer[1].text, "Line 4");
+        assert_eq!(stack, vec![2]);
+    }
+
+    #[test]
+    fn following_conditional_without_fulfilled_branch_skips_it() {
+        let node = RootNodeBuilder::empty()
+            .with_item(NodeItem::Conditional(vec![
+                ConditionalBranchBuilder::from_condition(Some(get_condition(false)))
+                    .with_text_line_chunk("Line 1")
+                    .build(),
+            ]))
+            .with_text_line_chunk("Line 2")
+            .build();
+
+        let mut buffer = Vec::new();
+        let mut stack = vec![0];
+        let mut data = mock_follow_data(&node);
+
+        node.follow(&mut stack, &mut buffer, &mut data).unwrap();
+
+        assert_eq!(buffer.len(), 1);
+        assert_eq!(&buffer[0].text, "Line 2");
+    }
+
+    #[test]
+    fn choices_in_conditional_branches_keep_the_block_in_the_stack() {
+        let choice = InternalChoice::from_string("Choice");
+
+        let node = RootNodeBuilder::empty()
+            .with_item(NodeItem::Conditional(vec![
+                ConditionalBranchBuilder::from_condition(Some(get_condition(true)))
+                    .with_text_line_chunk("Line 1")
+                    .with_item(
+                        BranchingPointBuilder::new()
+                            .with_branch(
+                                BranchBuilder::from_choice(choice.clone())
+                                    .with_text_line_chunk("Line 2")
+                                    .build(),
+                            )
+                            .build(),
+                    )
+                    .with_text_line_chunk("Line 3")
+                    .build(),
+            ]))
+            .with_text_line_chunk("Line 4")
+            .build();
+
+        let mut buffer = Vec::new();
+        let mut stack = vec![0];
+        let mut data = mock_follow_data(&node);
+
+        match node.follow(&mut stack, &mut buffer, &mut data).unwrap() {
+            EncounteredEvent::BranchingChoice(..) => (),
+            other => panic!("expected a branching choice but got {:?}", other),
+        }
+
+        assert_eq!(stack, vec![0, 0, 1]);
+
+        assert_eq!(
+            node.follow_with_choice(0, 0, &mut stack, &mut buffer, &mut data)
+                .unwrap(),
+            EncounteredEvent::Done
+        );
+
+        assert_eq!(buffer.len(), 5);
+        assert_eq!(&buffer[0].text, "Line 1");
+        assert_eq!(&buffer[1].text, "Choice");
+        assert_eq!(&buffer[2].text, "Line 2");
+        assert_eq!(&buffer[3].text, "Line 3");
+        assert_eq!(&buffer[4].text, "Line 4");
+    }
 }
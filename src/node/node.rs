@@ -2,12 +2,17 @@
 
 use crate::{
     error::{parse::validate::ValidationError, utils::MetaData},
-    knot::Address,
-    line::{InternalChoice, InternalLine},
+    follow::FollowData,
+    knot::{Address, AddressKind},
+    line::{get_next_alternative_index, AlternativeKind, Condition, InternalChoice, InternalLine},
     log::Logger,
+    node::Stack,
     story::validate::{ValidateContent, ValidationData},
 };
 
+#[cfg(not(feature = "random"))]
+use crate::log::Warning;
+
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
 
@@ -34,8 +39,60 @@ pub struct Branch {
     pub choice: InternalChoice,
     /// Content grouped under this branch.
     pub items: Vec<NodeItem>,
-    /// Number of times the node has been visited in the story.
-    pub num_visited: u32,
+    /// Identifier of the branch in the story.
+    ///
+    /// The number of times that the branch has been visited is kept under this identifier
+    /// in the [follow data][crate::follow::FollowData::branch_visit_counts].
+    pub id: usize,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Branch of a multiline conditional block in a `Stitch`.
+///
+/// When the block is encountered the first branch with a fulfilled condition is followed.
+/// Branches without a condition (`else` branches) are always followed if reached.
+pub struct ConditionalBranch {
+    /// Condition which must be fulfilled for the branch to be followed.
+    pub condition: Option<Condition>,
+    /// Content grouped under this branch.
+    pub items: Vec<NodeItem>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Multiline alternative block in a `Stitch`.
+///
+/// When the block is encountered one of its branches is selected and followed, like
+/// an item is selected when an inline `Alternative` is processed. The branches have
+/// no conditions.
+pub struct AlternativeBlock {
+    /// Identifier of the block in the story, see `Alternative`.
+    pub id: usize,
+    /// Which kind of alternative sequence the block represents.
+    pub kind: AlternativeKind,
+    /// Set of branches which the block will select from.
+    pub branches: Vec<ConditionalBranch>,
+}
+
+impl AlternativeBlock {
+    /// Construct the block from its kind and branches.
+    ///
+    /// The identifier is set to 0. Identifiers are set when the story is compiled.
+    pub fn from_branches(kind: AlternativeKind, branches: Vec<ConditionalBranch>) -> Self {
+        AlternativeBlock {
+            id: 0,
+            kind,
+            branches,
+        }
+    }
+
+    /// Get the index of the next branch to follow, if any.
+    pub fn get_next_index(&self, data: &mut FollowData) -> Option<usize> {
+        get_next_alternative_index(self.kind, self.id, self.branches.len(), data)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -43,9 +100,112 @@ pub struct Branch {
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
 /// Every item that a `Stitch` contains can be either some text producing asset
 /// or a branching point which the user must select an option from to continue.
+///
+/// Labelled choices and gathers are marked by a `Label` item, which produces no text
+/// but is counted when passed and can be diverted to. Multiline conditional blocks
+/// are `Conditional` items, which branch without the user having to select a choice.
+/// Multiline alternative blocks are `Alternative` items, which do the same.
 pub enum NodeItem {
     Line(InternalLine),
     BranchingPoint(Vec<Branch>),
+    Conditional(Vec<ConditionalBranch>),
+    Alternative(AlternativeBlock),
+    Label(Address),
+}
+
+impl NodeItem {
+    /// Get the name of a label item.
+    fn get_label_name(&self) -> Option<&str> {
+        match self {
+            NodeItem::Label(Address::Raw(name))
+            | NodeItem::Label(Address::Validated(AddressKind::Label { label: name, .. })) => {
+                Some(name)
+            }
+            _ => None,
+        }
+    }
+
+    /// Get the items of a branch in a branching item.
+    fn get_branch_items(&self, index: usize) -> Option<&[NodeItem]> {
+        match self {
+            NodeItem::BranchingPoint(branches) => {
+                branches.get(index).map(|branch| branch.items.as_slice())
+            }
+            NodeItem::Conditional(branches)
+            | NodeItem::Alternative(AlternativeBlock { branches, .. }) => {
+                branches.get(index).map(|branch| branch.items.as_slice())
+            }
+            NodeItem::Line(..) | NodeItem::Label(..) => None,
+        }
+    }
+
+    /// Whether or not following the item assigns variables or calls functions before
+    /// reaching any of its content.
+    ///
+    /// The content of branches is checked when it is followed, so only the conditions
+    /// of conditional blocks are checked.
+    pub fn has_logic(&self) -> bool {
+        match self {
+            NodeItem::Line(line) => line.has_logic(),
+            NodeItem::Conditional(branches) => branches
+                .iter()
+                .filter_map(|branch| branch.condition.as_ref())
+                .any(|condition| condition.has_function_calls()),
+            NodeItem::BranchingPoint(..) | NodeItem::Alternative(..) | NodeItem::Label(..) => false,
+        }
+    }
+}
+
+impl RootNode {
+    /// Get the names of all labels in the tree along with the `Stack` that points to them.
+    pub fn get_labels(&self) -> Vec<(String, Stack)> {
+        let mut labels = Vec::new();
+        collect_labels(&self.items, &mut Vec::new(), &mut labels);
+
+        labels
+    }
+
+    /// Get the item that a `Stack` points to in the tree, if any.
+    pub fn get_item_at_stack(&self, stack: &[usize]) -> Option<&NodeItem> {
+        let (index, levels) = stack.split_last()?;
+        let mut items = self.items.as_slice();
+
+        for level in levels.chunks(2) {
+            items = items.get(level[0])?.get_branch_items(*level.get(1)?)?;
+        }
+
+        items.get(*index)
+    }
+}
+
+/// Recursively collect all labels and their `Stack`s from a set of items.
+fn collect_labels(items: &[NodeItem], stack: &mut Stack, labels: &mut Vec<(String, Stack)>) {
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            NodeItem::BranchingPoint(branches) => {
+                for (j, branch) in branches.iter().enumerate() {
+                    stack.extend_from_slice(&[i, j]);
+                    collect_labels(&branch.items, stack, labels);
+                    stack.truncate(stack.len() - 2);
+                }
+            }
+            NodeItem::Conditional(branches)
+            | NodeItem::Alternative(AlternativeBlock { branches, .. }) => {
+                for (j, branch) in branches.iter().enumerate() {
+                    stack.extend_from_slice(&[i, j]);
+                    collect_labels(&branch.items, stack, labels);
+                    stack.truncate(stack.len() - 2);
+                }
+            }
+            NodeItem::Label(..) => {
+                let mut label_stack = stack.clone();
+                label_stack.push(i);
+
+                labels.push((item.get_label_name().unwrap().to_string(), label_stack));
+            }
+            NodeItem::Line(..) => (),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -58,6 +218,20 @@ impl NodeItem {
         }
     }
 
+    pub fn is_conditional(&self) -> bool {
+        match self {
+            NodeItem::Conditional(..) => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_alternative(&self) -> bool {
+        match self {
+            NodeItem::Alternative(..) => true,
+            _ => false,
+        }
+    }
+
     pub fn is_line(&self) -> bool {
         match self {
             NodeItem::Line(..) => true,
@@ -111,6 +285,25 @@ impl ValidateContent for Branch {
     }
 }
 
+impl ValidateContent for ConditionalBranch {
+    fn validate(
+        &mut self,
+        error: &mut ValidationError,
+        log: &mut Logger,
+        current_location: &Address,
+        meta_data: &MetaData,
+        data: &ValidationData,
+    ) {
+        if let Some(condition) = self.condition.as_mut() {
+            condition.validate(error, log, current_location, meta_data, data);
+        }
+
+        self.items
+            .iter_mut()
+            .for_each(|item| item.validate(error, log, current_location, meta_data, data))
+    }
+}
+
 impl ValidateContent for NodeItem {
     fn validate(
         &mut self,
@@ -124,7 +317,32 @@ impl ValidateContent for NodeItem {
             NodeItem::BranchingPoint(branches) => branches
                 .iter_mut()
                 .for_each(|item| item.validate(error, log, current_location, meta_data, data)),
+            NodeItem::Conditional(branches) => branches
+                .iter_mut()
+                .for_each(|item| item.validate(error, log, current_location, meta_data, data)),
+            NodeItem::Alternative(block) => {
+                #[cfg(not(feature = "random"))]
+                if block.kind.is_shuffle() {
+                    log.add_warning(Warning::ShuffleSequenceNoRandom, meta_data);
+                }
+
+                block
+                    .branches
+                    .iter_mut()
+                    .for_each(|item| item.validate(error, log, current_location, meta_data, data))
+            }
             NodeItem::Line(line) => line.validate(error, log, current_location, meta_data, data),
+            NodeItem::Label(address) => {
+                if let (Address::Raw(label), Ok((knot, stitch))) =
+                    (&address, current_location.get_knot_and_stitch())
+                {
+                    *address = Address::Validated(AddressKind::Label {
+                        knot: knot.to_string(),
+                        stitch: stitch.to_string(),
+                        label: label.clone(),
+                    });
+                }
+            }
         };
     }
 }
@@ -136,16 +354,43 @@ pub mod builders {
     //! the `test` profile is activated. These functions are not meant to be used internally
     //! except by tests, since they do not perform any validation of the content.
 
-    use super::{Branch, NodeItem, RootNode};
+    use super::{AlternativeBlock, Branch, ConditionalBranch, NodeItem, RootNode};
 
     use crate::{
         knot::{Address, AddressKind},
-        line::{InternalChoice, InternalLine},
+        line::{AlternativeKind, Condition, InternalChoice, InternalLine},
     };
 
     #[cfg(test)]
     use crate::line::LineChunk;
 
+    /// Common interface for adding content to the nodes that are being built.
+    pub trait NodeBuilder {
+        fn add_item(&mut self, item: NodeItem);
+
+        fn add_branching_choice(&mut self, branching_set: Vec<Branch>) {
+            self.add_item(NodeItem::BranchingPoint(branching_set));
+        }
+
+        fn add_conditional(&mut self, branches: Vec<ConditionalBranch>) {
+            self.add_item(NodeItem::Conditional(branches));
+        }
+
+        fn add_alternative(&mut self, kind: AlternativeKind, branches: Vec<ConditionalBranch>) {
+            self.add_item(NodeItem::Alternative(AlternativeBlock::from_branches(
+                kind, branches,
+            )));
+        }
+
+        fn add_label(&mut self, label: &str) {
+            self.add_item(NodeItem::Label(Address::Raw(label.to_string())));
+        }
+
+        fn add_line(&mut self, line: InternalLine) {
+            self.add_item(NodeItem::Line(line));
+        }
+    }
+
     /// Builder for a `RootNote`.
     ///
     /// # Notes
@@ -175,18 +420,6 @@ pub mod builders {
             }
         }
 
-        pub fn add_branching_choice(&mut self, branching_set: Vec<Branch>) {
-            self.add_item(NodeItem::BranchingPoint(branching_set));
-        }
-
-        pub fn add_item(&mut self, item: NodeItem) {
-            self.items.push(item);
-        }
-
-        pub fn add_line(&mut self, line: InternalLine) {
-            self.add_item(NodeItem::Line(line));
-        }
-
         #[cfg(test)]
         pub fn empty() -> Self {
             Self::from_address("", "")
@@ -214,6 +447,12 @@ pub mod builders {
         }
     }
 
+    impl NodeBuilder for RootNodeBuilder {
+        fn add_item(&mut self, item: NodeItem) {
+            self.items.push(item);
+        }
+    }
+
     /// Builder for a `Branch`.
     ///
     /// Is created from the `InternalChoice` that spawns the branch in the parsed lines
@@ -221,7 +460,7 @@ pub mod builders {
     ///
     /// # Notes
     ///  *  Adds the line from its choice as the first in its item list.
-    ///  *  Sets the number of visits to 0.
+    ///  *  Sets the identifier to 0. Identifiers are set when the story is compiled.
     pub struct BranchBuilder {
         choice: InternalChoice,
         items: Vec<NodeItem>,
@@ -241,20 +480,52 @@ pub mod builders {
             Branch {
                 choice: self.choice,
                 items: self.items,
-                num_visited: 0,
+                id: 0,
             }
         }
 
-        pub fn add_branching_choice(&mut self, branching_set: Vec<Branch>) {
-            self.add_item(NodeItem::BranchingPoint(branching_set));
+        #[cfg(test)]
+        pub fn with_item(mut self, item: NodeItem) -> Self {
+            self.items.push(item);
+            self
         }
 
-        pub fn add_item(&mut self, item: NodeItem) {
+        #[cfg(test)]
+        pub fn with_branching_choice(self, branching_choice_set: NodeItem) -> Self {
+            self.with_item(branching_choice_set)
+        }
+
+        #[cfg(test)]
+        pub fn with_text_line_chunk(self, content: &str) -> Self {
+            self.with_item(NodeItem::Line(InternalLine::from_string(content)))
+        }
+    }
+
+    impl NodeBuilder for BranchBuilder {
+        fn add_item(&mut self, item: NodeItem) {
             self.items.push(item);
         }
+    }
+
+    /// Builder for a `ConditionalBranch`.
+    pub struct ConditionalBranchBuilder {
+        condition: Option<Condition>,
+        items: Vec<NodeItem>,
+    }
+
+    impl ConditionalBranchBuilder {
+        pub fn from_condition(condition: Option<Condition>) -> Self {
+            ConditionalBranchBuilder {
+                condition,
+                items: Vec::new(),
+            }
+        }
 
-        pub fn add_line(&mut self, line: InternalLine) {
-            self.add_item(NodeItem::Line(line));
+        pub fn build(self) -> ConditionalBranch {
+            ConditionalBranch {
+                condition: self.condition,
+                items: self.items,
+            }
         }
 
         #[cfg(test)]
@@ -263,17 +534,18 @@ pub mod builders {
             self
         }
 
-        #[cfg(test)]
-        pub fn with_branching_choice(self, branching_choice_set: NodeItem) -> Self {
-            self.with_item(branching_choice_set)
-        }
-
         #[cfg(test)]
         pub fn with_text_line_chunk(self, content: &str) -> Self {
             self.with_item(NodeItem::Line(InternalLine::from_string(content)))
         }
     }
 
+    impl NodeBuilder for ConditionalBranchBuilder {
+        fn add_item(&mut self, item: NodeItem) {
+            self.items.push(item);
+        }
+    }
+
     #[cfg(test)]
     pub struct BranchingPointBuilder {
         items: Vec<Branch>,
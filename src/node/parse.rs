@@ -4,13 +4,14 @@
 //! takes these individual lines and groups them into node trees.
 //!
 //! This hinges on the [`ParsedLineKind`][crate::line::ParsedLineKind] object, which
-//! contains the nesting level of branching and gather points.
+//! contains the nesting level of branching and gather points, and marks where multiline
+//! conditional and alternative blocks and their branches begin and end.
 
 use crate::{
-    line::{InternalLine, ParsedLineKind},
+    line::{Condition, InternalLine, ParsedLineKind},
     node::{
-        builders::{BranchBuilder, RootNodeBuilder},
-        Branch, RootNode,
+        builders::{BranchBuilder, ConditionalBranchBuilder, NodeBuilder, RootNodeBuilder},
+        Branch, ConditionalBranch, RootNode,
     },
 };
 
@@ -21,6 +22,13 @@ use crate::{
 pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> RootNode {
     let mut builder = RootNodeBuilder::from_address(knot, stitch);
 
+    parse_items(&mut builder, lines);
+
+    builder.build()
+}
+
+/// Parse a set of lines from beginning to end into items of the given builder.
+fn parse_items<B: NodeBuilder>(builder: &mut B, lines: &[ParsedLineKind]) {
     let mut index = 0;
 
     while index < lines.len() {
@@ -36,7 +44,11 @@ pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> Ro
 
                 builder.add_branching_choice(branches);
 
-                if let Some(line) = gather {
+                if let Some((label, line)) = gather {
+                    if let Some(label) = label {
+                        builder.add_label(&label);
+                    }
+
                     builder.add_line(line);
 
                     // `parse_choice_set_with_gather` advances the index to the next line
@@ -45,13 +57,101 @@ pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> Ro
                     index -= 1;
                 }
             }
-            ParsedLineKind::Gather { line, .. } => {
+            ParsedLineKind::Gather { label, line, .. } => {
+                if let Some(label) = label {
+                    builder.add_label(label);
+                }
+
                 builder.add_line(line.clone());
             }
+            ParsedLineKind::ConditionalStart { .. } => {
+                let branches = parse_conditional_block(&mut index, lines);
+
+                builder.add_conditional(branches);
+            }
+            ParsedLineKind::SequenceStart { kind } => {
+                let branches = parse_conditional_block(&mut index, lines);
+
+                builder.add_alternative(*kind, branches);
+            }
+            // Blocks are parsed as a whole from their start, so these are never encountered here
+            ParsedLineKind::ConditionalBranch { .. } | ParsedLineKind::ConditionalEnd => (),
         };
 
         index += 1;
     }
+}
+
+/// Parse the branches of a multiline conditional or alternative block.
+///
+/// If the block was opened with a condition its first branch begins directly,
+/// otherwise every branch begins with a `ParsedLineKind::ConditionalBranch`. The content
+/// of every branch is parsed like a root node, so choices and gathers in them are
+/// nested in the branch.
+///
+/// The `index` should point to the start of the block. When the function returns it
+/// will point to the line which ended it.
+fn parse_conditional_block(index: &mut usize, lines: &[ParsedLineKind]) -> Vec<ConditionalBranch> {
+    let mut branches = Vec::new();
+
+    let mut current_branch = match &lines[*index] {
+        ParsedLineKind::ConditionalStart {
+            condition: Some(condition),
+        } => Some((Some(condition.clone()), None, *index + 1)),
+        _ => None,
+    };
+
+    let mut nested_level = 0;
+    *index += 1;
+
+    while *index < lines.len() {
+        match &lines[*index] {
+            ParsedLineKind::ConditionalStart { .. } | ParsedLineKind::SequenceStart { .. } => {
+                nested_level += 1
+            }
+            ParsedLineKind::ConditionalEnd if nested_level > 0 => nested_level -= 1,
+            ParsedLineKind::ConditionalEnd => break,
+            ParsedLineKind::ConditionalBranch { condition, line } if nested_level == 0 => {
+                if let Some((condition, line, start)) = current_branch.take() {
+                    branches.push(parse_conditional_branch(
+                        condition,
+                        line,
+                        &lines[start..*index],
+                    ));
+                }
+
+                current_branch.replace((condition.clone(), line.clone(), *index + 1));
+            }
+            _ => (),
+        }
+
+        *index += 1;
+    }
+
+    if let Some((condition, line, start)) = current_branch {
+        branches.push(parse_conditional_branch(
+            condition,
+            line,
+            &lines[start..*index],
+        ));
+    }
+
+    branches
+}
+
+/// Parse a single `ConditionalBranch` from its condition and lines.
+fn parse_conditional_branch(
+    condition: Option<Condition>,
+    line: Option<InternalLine>,
+    lines: &[ParsedLineKind],
+) -> ConditionalBranch {
+    let mut builder = ConditionalBranchBuilder::from_condition(condition);
+
+    if let Some(line) = line {
+        builder.add_line(line);
+    }
+
+    parse_items(&mut builder, lines);
 
     builder.build()
 }
@@ -59,8 +159,8 @@ pub fn parse_root_node(lines: &[ParsedLineKind], knot: &str, stitch: &str) -> Ro
 /// Parse a set of branching points and the gather it ended with.
 ///
 /// After parsing a group of choices, check whether it ended because of a `Gather`.
-/// If so, return the `NodeItem::Line` object from that gather so that it can be appended
-/// *after* the node, not inside it.
+/// If so, return the `NodeItem::Line` object and possible label from that gather so that
+/// they can be appended *after* the node, not inside it.
 ///
 /// When the function returns the `index` will point to the line directly after
 /// the last line of content belonging to this branch, or if a `gather` point was found,
@@ -69,13 +169,13 @@ fn parse_branching_choice_set_and_gather(
     index: &mut usize,
     current_level: u32,
     lines: &[ParsedLineKind],
-) -> (Vec<Branch>, Option<InternalLine>) {
+) -> (Vec<Branch>, Option<(Option<String>, InternalLine)>) {
     let node = parse_branching_choice_set(index, current_level, lines);
     let mut gather = None;
 
-    if let Some(ParsedLineKind::Gather { level, line }) = lines.get(*index) {
+    if let Some(ParsedLineKind::Gather { level, label, line }) = lines.get(*index) {
         if *level == current_level {
-            gather.replace(line.clone());
+            gather.replace((label.clone(), line.clone()));
             *index += 1;
         }
     }
@@ -120,14 +220,16 @@ fn parse_branch_at_given_level(
 
     let head = &lines[*index];
 
-    let choice = match head {
+    let (choice, label) = match head {
         ParsedLineKind::Choice { level, .. } if *level < current_level => {
             return None;
         }
         ParsedLineKind::Gather { level, .. } if *level <= current_level => {
             return None;
         }
-        ParsedLineKind::Choice { choice_data, .. } => choice_data.clone(),
+        ParsedLineKind::Choice {
+            choice_data, label, ..
+        } => (choice_data.clone(), label),
         _ => panic!(
             "could not correctly parse a `Branch` item: \
              expected first line to be a `ParsedLine::Choice` object, but was {:?}",
@@ -137,6 +239,10 @@ fn parse_branch_at_given_level(
 
     let mut builder = BranchBuilder::from_choice(choice);
 
+    if let Some(label) = label {
+        builder.add_label(label);
+    }
+
     // This skips to the next index, where the branch's content or a new branch point will appear
     *index += 1;
 
@@ -154,7 +260,11 @@ fn parse_branch_at_given_level(
 
                 builder.add_branching_choice(branching_set);
 
-                if let Some(line) = gather {
+                if let Some((label, line)) = gather {
+                    if let Some(label) = label {
+                        builder.add_label(&label);
+                    }
+
                     builder.add_line(line);
                 }
 
@@ -171,6 +281,17 @@ fn parse_branch_at_given_level(
                     break;
                 }
             }
+            ParsedLineKind::ConditionalStart { .. } => {
+                let branches = parse_conditional_block(index, lines);
+
+                builder.add_conditional(branches);
+            }
+            ParsedLineKind::SequenceStart { kind } => {
+                let branches = parse_conditional_block(index, lines);
+
+                builder.add_alternative(*kind, branches);
+            }
+            ParsedLineKind::ConditionalBranch { .. } | ParsedLineKind::ConditionalEnd => (),
         }
 
         *index += 1;
@@ -183,7 +304,11 @@ fn parse_branch_at_given_level(
 mod tests {
     use super::*;
 
-    use crate::{knot::Address, line::InternalChoice, node::NodeItem};
+    use crate::{
+        knot::Address,
+        line::{AlternativeKind, ConditionBuilder, ConditionKind, InternalChoice},
+        node::NodeItem,
+    };
 
     pub fn get_empty_choice(level: u32) -> ParsedLineKind {
         ParsedLineKind::choice(level, InternalChoice::from_string(""))
@@ -239,6 +364,7 @@ mod tests {
 
         let input = ParsedLineKind::Choice {
             level: 1,
+            label: None,
             choice_data: choice.clone(),
         };
 
@@ -603,6 +729,337 @@ mod tests {
         }
     }
 
+    #[test]
+    fn labelled_gathers_add_a_label_item_before_the_gather_line() {
+        let choice1 = get_empty_choice(1);
+        let gather1 = ParsedLineKind::Gather {
+            level: 1,
+            label: Some("meeting".to_string()),
+            line: InternalLine::from_string(""),
+        };
+
+        let root = parse_root_node(&[gather1.clone(), choice1, gather1], "", "");
+
+        assert_eq!(root.items.len(), 5);
+        assert_eq!(
+            root.items[0],
+            NodeItem::Label(Address::Raw("meeting".to_string()))
+        );
+        assert!(root.items[1].is_line());
+        assert!(root.items[2].is_branching_choice());
+        assert_eq!(
+            root.items[3],
+            NodeItem::Label(Address::Raw("meeting".to_string()))
+        );
+        assert!(root.items[4].is_line());
+    }
+
+    #[test]
+    fn labelled_choices_add_a_label_item_after_the_choice_line_in_the_branch() {
+        let choice = ParsedLineKind::Choice {
+            level: 1,
+            label: Some("greet".to_string()),
+            choice_data: InternalChoice::from_string(""),
+        };
+
+        let mut index = 0;
+        let branch = parse_branch_at_given_level(&mut index, 1, &[choice]).unwrap();
+
+        assert_eq!(branch.items.len(), 2);
+        assert!(branch.items[0].is_line());
+        assert_eq!(
+            branch.items[1],
+            NodeItem::Label(Address::Raw("greet".to_string()))
+        );
+    }
+
+    #[test]
+    fn labels_in_tree_are_found_with_stacks_pointing_to_them() {
+        let choice1 = ParsedLineKind::Choice {
+            level: 1,
+            label: Some("greet".to_string()),
+            choice_data: InternalChoice::from_string(""),
+        };
+        let gather1 = ParsedLineKind::Gather {
+            level: 1,
+            label: Some("meeting".to_string()),
+            line: InternalLine::from_string(""),
+        };
+
+        let lines = vec![get_parsed_line(""), get_empty_choice(1), choice1, gather1];
+
+        let root = parse_root_node(&lines, "", "");
+
+        assert_eq!(
+            root.get_labels(),
+            vec![
+                ("greet".to_string(), vec![1, 1, 1]),
+                ("meeting".to_string(), vec![2]),
+            ]
+        );
+    }
+
+    fn get_condition(value: bool) -> Condition {
+        let kind = if value {
+            ConditionKind::True
+        } else {
+            ConditionKind::False
+        };
+
+        ConditionBuilder::from_kind(&kind, false).build()
+    }
+
+    fn get_conditional_start(condition: Option<bool>) -> ParsedLineKind {
+        ParsedLineKind::ConditionalStart {
+            condition: condition.map(get_condition),
+        }
+    }
+
+    fn get_conditional_branch(condition: Option<bool>) -> ParsedLineKind {
+        ParsedLineKind::ConditionalBranch {
+            condition: condition.map(get_condition),
+            line: None,
+        }
+    }
+
+    #[test]
+    fn conditional_blocks_are_parsed_into_a_single_item_with_branches() {
+        let lines = vec![
+            get_parsed_line("Line 1"),
+            get_conditional_start(Some(true)),
+            get_parsed_line("Line 2"),
+            get_parsed_line("Line 3"),
+            get_conditional_branch(None),
+            get_parsed_line("Line 4"),
+            ParsedLineKind::ConditionalEnd,
+            get_parsed_line("Line 5"),
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        assert_eq!(root.items.len(), 3);
+        assert!(root.items[0].is_line());
+        assert!(root.items[2].is_line());
+
+        match &root.items[1] {
+            NodeItem::Conditional(branches) => {
+                assert_eq!(branches.len(), 2);
+
+                assert_eq!(branches[0].condition, Some(get_condition(true)));
+                assert_eq!(branches[0].items.len(), 2);
+
+                assert!(branches[1].condition.is_none());
+                assert_eq!(branches[1].items.len(), 1);
+            }
+            other => panic!("expected `NodeItem::Conditional` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conditional_blocks_without_start_condition_begin_at_the_first_branch() {
+        let lines = vec![
+            get_conditional_start(None),
+            get_conditional_branch(Some(true)),
+            get_parsed_line("Line 1"),
+            get_conditional_branch(Some(false)),
+            get_parsed_line("Line 2"),
+            ParsedLineKind::ConditionalEnd,
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        match &root.items[..] {
+            [NodeItem::Conditional(branches)] => {
+                assert_eq!(branches.len(), 2);
+                assert_eq!(branches[0].condition, Some(get_condition(true)));
+                assert_eq!(branches[1].condition, Some(get_condition(false)));
+            }
+            other => panic!(
+                "expected a single `NodeItem::Conditional` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn content_on_the_branch_line_is_the_first_line_in_the_branch() {
+        let lines = vec![
+            get_conditional_start(None),
+            ParsedLineKind::ConditionalBranch {
+                condition: None,
+                line: Some(InternalLine::from_string("Line 1")),
+            },
+            get_parsed_line("Line 2"),
+            ParsedLineKind::ConditionalEnd,
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        match &root.items[..] {
+            [NodeItem::Conditional(branches)] => {
+                assert_eq!(
+                    branches[0].items[0],
+                    NodeItem::Line(InternalLine::from_string("Line 1"))
+                );
+                assert_eq!(branches[0].items.len(), 2);
+            }
+            other => panic!(
+                "expected a single `NodeItem::Conditional` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn nested_conditional_blocks_are_parsed_inside_their_branch() {
+        let lines = vec![
+            get_conditional_start(Some(true)),
+            get_conditional_start(Some(true)),
+            get_parsed_line("Line 1"),
+            get_conditional_branch(None),
+            get_parsed_line("Line 2"),
+            ParsedLineKind::ConditionalEnd,
+            get_conditional_branch(None),
+            get_parsed_line("Line 3"),
+            ParsedLineKind::ConditionalEnd,
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        match &root.items[..] {
+            [NodeItem::Conditional(branches)] => {
+                assert_eq!(branches.len(), 2);
+                assert_eq!(branches[0].items.len(), 1);
+                assert!(branches[0].items[0].is_conditional());
+                assert_eq!(branches[1].items.len(), 1);
+            }
+            other => panic!(
+                "expected a single `NodeItem::Conditional` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn choices_and_gathers_in_conditional_branches_are_nested_in_the_branch() {
+        let lines = vec![
+            get_empty_choice(1),
+            get_conditional_start(Some(true)),
+            get_empty_choice(1),
+            get_empty_choice(1),
+            get_empty_gather(1),
+            ParsedLineKind::ConditionalEnd,
+            get_empty_gather(1),
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        assert_eq!(root.items.len(), 2);
+
+        match &root.items[0] {
+            NodeItem::BranchingPoint(choice_branches) => {
+                assert_eq!(choice_branches.len(), 1);
+                assert_eq!(choice_branches[0].items.len(), 2);
+
+                match &choice_branches[0].items[1] {
+                    NodeItem::Conditional(branches) => {
+                        assert_eq!(branches[0].items.len(), 2);
+                        assert!(branches[0].items[0].is_branching_choice());
+                        assert!(branches[0].items[1].is_line());
+                    }
+                    other => panic!("expected `NodeItem::Conditional` but got {:?}", other),
+                }
+            }
+            other => panic!("expected `NodeItem::BranchingPoint` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn alternative_blocks_are_parsed_into_a_single_item_with_branches_of_their_kind() {
+        let lines = vec![
+            ParsedLineKind::SequenceStart {
+                kind: AlternativeKind::Cycle,
+            },
+            get_conditional_branch(None),
+            get_parsed_line("Line 1"),
+            get_parsed_line("Line 2"),
+            get_conditional_branch(None),
+            get_conditional_start(Some(true)),
+            get_parsed_line("Line 3"),
+            ParsedLineKind::ConditionalEnd,
+            ParsedLineKind::ConditionalEnd,
+            get_parsed_line("Line 4"),
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        assert_eq!(root.items.len(), 2);
+        assert!(root.items[1].is_line());
+
+        match &root.items[0] {
+            NodeItem::Alternative(block) => {
+                assert_eq!(block.kind, AlternativeKind::Cycle);
+
+                assert_eq!(block.branches.len(), 2);
+                assert_eq!(block.branches[0].items.len(), 2);
+                assert_eq!(block.branches[1].items.len(), 1);
+                assert!(block.branches[1].items[0].is_conditional());
+            }
+            other => panic!("expected `NodeItem::Alternative` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn alternative_blocks_in_choice_branches_are_nested_in_the_branch() {
+        let lines = vec![
+            get_empty_choice(1),
+            ParsedLineKind::SequenceStart {
+                kind: AlternativeKind::Sequence,
+            },
+            get_conditional_branch(None),
+            get_parsed_line("Line 1"),
+            ParsedLineKind::ConditionalEnd,
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        match &root.items[..] {
+            [NodeItem::BranchingPoint(choice_branches)] => {
+                assert_eq!(choice_branches[0].items.len(), 2);
+                assert!(choice_branches[0].items[1].is_alternative());
+            }
+            other => panic!(
+                "expected a single `NodeItem::BranchingPoint` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn labels_in_conditional_branches_are_found() {
+        let gather = ParsedLineKind::Gather {
+            level: 1,
+            label: Some("meeting".to_string()),
+            line: InternalLine::from_string(""),
+        };
+
+        let lines = vec![
+            get_parsed_line(""),
+            get_conditional_start(Some(true)),
+            get_conditional_branch(None),
+            get_parsed_line(""),
+            gather,
+            ParsedLineKind::ConditionalEnd,
+        ];
+
+        let root = parse_root_node(&lines, "", "");
+
+        assert_eq!(
+            root.get_labels(),
+            vec![("meeting".to_string(), vec![1, 1, 1])]
+        );
+    }
+
     #[test]
     fn address_of_root_node_is_set_from_knot_and_stitch_names() {
         let root_node = parse_root_node(&[], "tripoli", "cinema");
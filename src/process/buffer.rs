@@ -9,22 +9,36 @@ use crate::{
 pub fn process_buffer(into_buffer: &mut LineBuffer, from_buffer: LineDataBuffer) {
     let mut iter = from_buffer
         .into_iter()
-        .filter(|line| !line.text.trim().is_empty())
+        .filter(|line| !is_empty_line(line))
         .peekable();
 
-    while let Some(mut line) = iter.next() {
-        let (glue, whitespace) = check_for_whitespace_and_glue(&line, iter.peek());
+    while let Some(line) = iter.next() {
+        let next_line = iter.peek();
+        into_buffer.push(process_line_text(line, next_line));
+    }
+}
+
+/// Process a single internal line to a user-ready state.
+///
+/// The next non-empty line is required to check whether it is glued to this line.
+/// If there is no next line, the line ends with a newline character.
+pub fn process_line_text(mut line: LineText, next_line: Option<&LineText>) -> Line {
+    let (glue, whitespace) = check_for_whitespace_and_glue(&line, next_line);
 
-        trim_extra_whitespace(&mut line);
-        add_line_ending(&mut line, glue, whitespace);
+    trim_extra_whitespace(&mut line);
+    add_line_ending(&mut line, glue, whitespace);
 
-        into_buffer.push(Line {
-            text: line.text,
-            tags: line.tags,
-        });
+    Line {
+        text: line.text,
+        tags: line.tags,
     }
 }
 
+/// Check whether a line contains no text, in which case it is not shown to the user.
+pub fn is_empty_line(line: &LineText) -> bool {
+    line.text.trim().is_empty()
+}
+
 /// Check whether the line is glued to the next and if so whether it ends with a blank space.
 fn check_for_whitespace_and_glue(line: &LineText, next_line: Option<&LineText>) -> (bool, bool) {
     let glue = next_line
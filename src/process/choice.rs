@@ -1,15 +1,14 @@
 //! Process and filter choices to present to the user.
 
 use crate::{
-    error::{InklingError, InternalError},
+    error::InklingError,
     follow::{ChoiceInfo, FollowData},
     line::InternalLine,
     process::{check_condition, process_line},
     story::Choice,
 };
 
-use std::ops::DerefMut;
-use std::sync::{Arc, Mutex};
+use std::mem;
 
 /// Prepare a list of choices to display to the user.
 ///
@@ -67,24 +66,38 @@ fn zip_choices_with_filter_values(
     choices
         .iter()
         .zip(checked_choices.into_iter())
-        .enumerate()
-        .map(|(i, (ChoiceInfo { choice_data, .. }, keep))| {
-            let (text, tags) = if keep {
-                process_choice_text_and_tags(choice_data.selection_text.clone(), data)
-            } else {
-                // If we are filtering the choice we do not want it's processed selection
-                // text to update their state. Instead, we clone the data and process that.
-
-                let independent_text = choice_data.selection_text.lock().unwrap().clone();
-                process_choice_text_and_tags(Arc::new(Mutex::new(independent_text)), data)
-            }?;
+        .map(|(choice, keep)| {
+            let ChoiceInfo {
+                choice_data,
+                index,
+                thread,
+                ..
+            } = choice;
+
+            let (text, tags) = with_thread_variables(choice, data, |data| {
+                if keep {
+                    process_choice_text_and_tags(&choice_data.selection_text, data)
+                } else {
+                    // If we are filtering the choice we do not want it's processed selection
+                    // text to update the state of its alternatives. Instead, we restore that
+                    // state after processing it.
+
+                    let alternative_inds = data.alternative_inds.clone();
+                    let result = process_choice_text_and_tags(&choice_data.selection_text, data);
+                    data.alternative_inds = alternative_inds;
+
+                    result
+                }
+            })?;
 
             Ok((
                 keep,
                 Choice {
                     text,
                     tags,
-                    index: i,
+                    index: *index,
+                    thread: thread.clone(),
+                    meta_data: choice_data.meta_data.clone(),
                 },
             ))
         })
@@ -93,54 +106,84 @@ fn zip_choices_with_filter_values(
 
 /// Process a line into a string and return it with its tags.
 fn process_choice_text_and_tags(
-    choice_line: Arc<Mutex<InternalLine>>,
+    choice_line: &InternalLine,
     data: &mut FollowData,
 ) -> Result<(String, Vec<String>), InklingError> {
     let mut data_buffer = Vec::new();
 
-    let mut line = choice_line.lock().unwrap();
-    let mut line = line.deref_mut();
-
-    process_line(&mut line, &mut data_buffer, data).map_err(|err| InternalError::from(err))?;
+    process_line(choice_line, &mut data_buffer, data).map_err(|err| InklingError::from(err))?;
 
     let mut buffer = String::new();
+    let mut tags = Vec::new();
 
     for data in data_buffer.into_iter() {
         buffer.push_str(&data.text);
+        tags.extend(data.tags);
     }
 
-    Ok((buffer.trim().to_string(), line.tags.clone()))
+    Ok((buffer.trim().to_string(), tags))
 }
 
 /// Return a list of whether choices fulfil their conditions.
+///
+/// The [choice count][crate::follow::FollowData::choice_count] is reset, then incremented
+/// for every choice that is kept. Conditions of later choices can thus check how many
+/// choices were accepted before them.
 fn check_choices_for_conditions(
     choices: &[ChoiceInfo],
-    data: &FollowData,
+    data: &mut FollowData,
     keep_only_fallback: bool,
 ) -> Result<Vec<bool>, InklingError> {
     let mut checked_conditions = Vec::new();
+    data.choice_count = 0;
+
+    for choice in choices.iter() {
+        let ChoiceInfo {
+            num_visited,
+            choice_data,
+            ..
+        } = choice;
 
-    for ChoiceInfo {
-        num_visited,
-        choice_data,
-    } in choices.iter()
-    {
-        let mut keep = choice_data
-            .condition
-            .as_ref()
-            .map(|condition| check_condition(condition, data).unwrap())
-            .unwrap_or(true);
+        let mut keep = match &choice_data.condition {
+            Some(condition) => {
+                with_thread_variables(choice, data, |data| check_condition(condition, data))?
+            }
+            None => true,
+        };
 
         keep = keep
             && (choice_data.is_sticky || *num_visited == 0)
             && (choice_data.is_fallback == keep_only_fallback);
 
+        if keep {
+            data.choice_count += 1;
+        }
+
         checked_conditions.push(keep);
     }
 
     Ok(checked_conditions)
 }
 
+/// Evaluate a function with the temporary variables of the thread that a choice was
+/// encountered in, if any.
+fn with_thread_variables<T, F>(choice: &ChoiceInfo, data: &mut FollowData, f: F) -> T
+where
+    F: FnOnce(&mut FollowData) -> T,
+{
+    match &choice.thread {
+        Some(thread) => {
+            let temp_variables =
+                mem::replace(&mut data.temp_variables, thread.temp_variables.clone());
+            let result = f(data);
+            data.temp_variables = temp_variables;
+
+            result
+        }
+        None => f(data),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,17 +193,15 @@ mod tests {
         follow::FollowDataBuilder,
         line::{
             line::builders::InternalLineBuilder, AlternativeBuilder, Condition, ConditionBuilder,
-            InternalChoice, InternalChoiceBuilder, LineChunkBuilder, StoryCondition, Variable,
+            Expression, InternalChoice, InternalChoiceBuilder, LineChunkBuilder, StoryCondition,
+            Variable,
         },
     };
 
     use std::collections::HashMap;
 
     fn create_choice_extra(num_visited: u32, choice_data: InternalChoice) -> ChoiceInfo {
-        ChoiceInfo {
-            num_visited,
-            choice_data,
-        }
+        ChoiceInfo::from_choice(&choice_data, num_visited, 0)
     }
 
     fn get_empty_data() -> FollowData {
@@ -214,7 +255,9 @@ mod tests {
     }
 
     fn get_true_like_condition(variable: Variable, negate: bool) -> Condition {
-        let kind = StoryCondition::IsTrueLike { variable };
+        let kind = StoryCondition::IsTrueLike {
+            expression: Expression::from(variable),
+        };
 
         ConditionBuilder::from_kind(&kind.into(), negate).build()
     }
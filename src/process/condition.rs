@@ -1,19 +1,16 @@
 //! Checking of `Condition`s which determine whether content will be displayed.
 
 use crate::{
-    error::{
-        variable::{VariableError, VariableErrorKind},
-        InklingError,
-    },
+    error::InklingError,
     follow::FollowData,
-    line::{expression::evaluate_expression, Condition, StoryCondition, Variable},
+    line::{expression::evaluate_expression, Condition, StoryCondition},
 };
 
 use std::cmp::Ordering;
 
 /// Check whether a single condition is fulfilled.
-pub fn check_condition(condition: &Condition, data: &FollowData) -> Result<bool, InklingError> {
-    let evaluator = |kind: &StoryCondition| match kind {
+pub fn check_condition(condition: &Condition, data: &mut FollowData) -> Result<bool, InklingError> {
+    let mut evaluator = |kind: &StoryCondition| match kind {
         StoryCondition::Comparison {
             lhs_variable,
             rhs_variable,
@@ -29,24 +26,21 @@ pub fn check_condition(condition: &Condition, data: &FollowData) -> Result<bool,
             }
         }
         .map_err(|err| err.into()),
-        StoryCondition::IsTrueLike { variable } => match variable.as_value(data)? {
-            Variable::Bool(value) => Ok(value),
-            Variable::Float(value) => Ok(value != 0.0),
-            Variable::Int(value) => Ok(value != 0),
-            Variable::String(s) => Ok(s.len() > 0),
-            Variable::Divert(..) => Err(VariableError::from_kind(
-                variable.clone(),
-                VariableErrorKind::InvalidComparison {
-                    other: Variable::Bool(true),
-                    comparison: Ordering::Equal,
-                },
-            )
-            .into()),
-            Variable::Address(..) => unreachable!("`as_value` will not return an `Address`"),
-        },
+        StoryCondition::Contains {
+            lhs_variable,
+            rhs_variable,
+        } => {
+            let lhs = evaluate_expression(lhs_variable, data)?;
+            let rhs = evaluate_expression(rhs_variable, data)?;
+
+            lhs.contains(&rhs).map_err(|err| err.into())
+        }
+        StoryCondition::IsTrueLike { expression } => evaluate_expression(expression, data)?
+            .is_true_like()
+            .map_err(|err| err.into()),
     };
 
-    condition.evaluate(&evaluator)
+    condition.evaluate(&mut evaluator)
 }
 
 #[cfg(test)]
@@ -58,7 +52,7 @@ mod tests {
         knot::Address,
         line::{
             expression::{Expression, Operand},
-            ConditionBuilder,
+            ConditionBuilder, Variable,
         },
         story::types::VariableInfo,
     };
@@ -90,7 +84,9 @@ mod tests {
     }
 
     fn get_true_like_condition(variable: Variable, negate: bool) -> Condition {
-        let kind = StoryCondition::IsTrueLike { variable };
+        let kind = StoryCondition::IsTrueLike {
+            expression: Expression::from(variable),
+        };
 
         ConditionBuilder::from_kind(&kind.into(), negate).build()
     }
@@ -118,7 +114,7 @@ mod tests {
 
     #[test]
     fn conditions_can_compare_variable_values() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let integer_condition = get_variable_comparison_condition(
             Variable::from(5),
@@ -134,62 +130,62 @@ mod tests {
             false,
         );
 
-        assert!(check_condition(&integer_condition, &data).unwrap());
-        assert!(!check_condition(&string_condition, &data).unwrap());
+        assert!(check_condition(&integer_condition, &mut data).unwrap());
+        assert!(!check_condition(&string_condition, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_boolean_and_true() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let true_condition = get_true_like_condition(Variable::from(true), false);
         let false_condition = get_true_like_condition(Variable::from(false), false);
 
-        assert!(check_condition(&true_condition, &data).unwrap());
-        assert!(!check_condition(&false_condition, &data).unwrap());
+        assert!(check_condition(&true_condition, &mut data).unwrap());
+        assert!(!check_condition(&false_condition, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_numeric_and_non_zero() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let int_equal = get_true_like_condition(Variable::from(0), false);
         let int_greater = get_true_like_condition(Variable::from(1), false);
         let int_less = get_true_like_condition(Variable::from(-1), false);
 
-        assert!(check_condition(&int_greater, &data).unwrap());
-        assert!(check_condition(&int_less, &data).unwrap());
-        assert!(!check_condition(&int_equal, &data).unwrap());
+        assert!(check_condition(&int_greater, &mut data).unwrap());
+        assert!(check_condition(&int_less, &mut data).unwrap());
+        assert!(!check_condition(&int_equal, &mut data).unwrap());
 
         let float_equal = get_true_like_condition(Variable::from(0.0), false);
         let float_greater = get_true_like_condition(Variable::from(0.1), false);
         let float_less = get_true_like_condition(Variable::from(-0.1), false);
 
-        assert!(check_condition(&float_greater, &data).unwrap());
-        assert!(check_condition(&float_less, &data).unwrap());
-        assert!(!check_condition(&float_equal, &data).unwrap());
+        assert!(check_condition(&float_greater, &mut data).unwrap());
+        assert!(check_condition(&float_less, &mut data).unwrap());
+        assert!(!check_condition(&float_equal, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_conditions_return_true_if_variable_is_string_with_non_zero_length() {
-        let data = mock_follow_data(&[], &[]);
+        let mut data = mock_follow_data(&[], &[]);
 
         let string_word = get_true_like_condition(Variable::from("non-empty"), false);
         let string_char = get_true_like_condition(Variable::from("c"), false);
         let string_empty = get_true_like_condition(Variable::from(""), false);
 
-        assert!(check_condition(&string_word, &data).unwrap());
-        assert!(check_condition(&string_char, &data).unwrap());
-        assert!(!check_condition(&string_empty, &data).unwrap());
+        assert!(check_condition(&string_word, &mut data).unwrap());
+        assert!(check_condition(&string_char, &mut data).unwrap());
+        assert!(!check_condition(&string_empty, &mut data).unwrap());
     }
 
     #[test]
     fn is_true_like_condition_yields_error_if_variable_is_divert() {
-        let data = mock_follow_data(&[("tripoli", "cinema", 1)], &[]);
+        let mut data = mock_follow_data(&[("tripoli", "cinema", 1)], &[]);
 
         let variable = Variable::Divert(Address::from_parts_unchecked("tripoli", Some("cinema")));
         let divert = get_true_like_condition(variable, false);
 
-        assert!(check_condition(&divert, &data).is_err());
+        assert!(check_condition(&divert, &mut data).is_err());
     }
 }
@@ -3,58 +3,96 @@
 use crate::{
     error::runtime::internal::{ProcessError, ProcessErrorKind},
     follow::{EncounteredEvent, FollowData, LineDataBuffer, LineText},
+    knot::get_divert_target,
     line::{evaluate_expression, Alternative, Content, InternalLine, LineChunk},
     process::check_condition,
 };
 
+use std::mem;
+
 /// Process and add the content of an `InternalLine` to a buffer.
 pub fn process_line(
-    line: &mut InternalLine,
+    line: &InternalLine,
     buffer: &mut LineDataBuffer,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
     let mut text_buffer = String::new();
 
-    let result = process_chunk(&mut line.chunk, &mut text_buffer, data);
+    let result = process_chunk(&line.chunk, &mut text_buffer, data);
+    let mut tags = process_tags(&line.tags, data)?;
 
-    let line_text = LineText {
-        text: text_buffer,
-        glue_begin: line.glue_begin,
-        glue_end: line.glue_end,
-        tags: line.tags.clone(),
-    };
+    // Lines printed by called functions are separated by newlines in the text
+    let texts = text_buffer.split('\n').collect::<Vec<_>>();
+    let last_index = texts.len() - 1;
+
+    for (i, text) in texts.into_iter().enumerate() {
+        let line_text = LineText {
+            text: text.to_string(),
+            glue_begin: i == 0 && line.glue_begin,
+            glue_end: i == last_index && line.glue_end,
+            tags: if i == 0 {
+                mem::take(&mut tags)
+            } else {
+                Vec::new()
+            },
+            meta_data: line.meta_data.clone(),
+            address: None,
+        };
 
-    buffer.push(line_text);
+        buffer.push(line_text);
+    }
 
     result
 }
 
+/// Process the content of tags into strings.
+///
+/// Like lines of text, tags are trimmed of extra whitespace after processing.
+/// Any events encountered in them are ignored.
+pub fn process_tags(
+    tags: &[LineChunk],
+    data: &mut FollowData,
+) -> Result<Vec<String>, ProcessError> {
+    tags.iter()
+        .map(|tag| {
+            let mut buffer = String::new();
+            process_chunk(tag, &mut buffer, data)?;
+
+            Ok(buffer.split_whitespace().collect::<Vec<_>>().join(" "))
+        })
+        .collect()
+}
+
 /// Process and add the content of a `LineChunk` to a string buffer.
 ///
 /// If a condition is set to the chunk, it will be evaluated. If it evaluates to true,
 /// the items in the `items` field will be processed. If not, the items in the `else_items`
 /// field will be.
 fn process_chunk(
-    chunk: &mut LineChunk,
+    chunk: &LineChunk,
     buffer: &mut String,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
     let items = match &chunk.condition {
         Some(ref condition) => {
-            if check_condition(condition, data)? {
-                chunk.items.iter_mut()
+            let fulfilled = check_condition(condition, data)?;
+            push_function_text(buffer, data);
+
+            if fulfilled {
+                chunk.items.iter()
             } else {
-                chunk.else_items.iter_mut()
+                chunk.else_items.iter()
             }
         }
-        None => chunk.items.iter_mut(),
+        None => chunk.items.iter(),
     };
 
     for item in items {
         let result = process_content(item, buffer, data)?;
 
-        if let EncounteredEvent::Divert(..) = result {
-            return Ok(result);
+        match result {
+            EncounteredEvent::Done => (),
+            _ => return Ok(result),
         }
     }
 
@@ -63,44 +101,110 @@ fn process_chunk(
 
 /// Process and add the content of a `Content` item to a string buffer.
 fn process_content(
-    item: &mut Content,
+    item: &Content,
     buffer: &mut String,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
     match item {
         Content::Alternative(alternative) => process_alternative(alternative, buffer, data),
-        Content::Divert(address) => Ok(EncounteredEvent::Divert(address.clone())),
+        Content::Assignment(assignment) => {
+            assignment.evaluate(data)?;
+            push_function_text(buffer, data);
+            Ok(EncounteredEvent::Done)
+        }
+        Content::Divert(address) => Ok(EncounteredEvent::Divert(get_divert_target(address, data)?)),
+        Content::DivertWithArguments { address, arguments } => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| evaluate_expression(argument, data))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(EncounteredEvent::DivertWithArguments {
+                address: get_divert_target(address, data)?,
+                arguments,
+            })
+        }
         Content::Empty => {
             buffer.push(' ');
             Ok(EncounteredEvent::Done)
         }
         Content::Expression(expression) => {
             let variable = evaluate_expression(&expression, data)?;
+            push_function_text(buffer, data);
             buffer.push_str(&variable.to_string_internal(data)?);
             Ok(EncounteredEvent::Done)
         }
         Content::Nested(chunk) => process_chunk(chunk, buffer, data),
+        Content::Return(expression) => {
+            let value = expression
+                .as_ref()
+                .map(|expression| evaluate_expression(expression, data))
+                .transpose()?;
+            push_function_text(buffer, data);
+            Ok(EncounteredEvent::Return(value))
+        }
+        Content::Statement(expression) => {
+            evaluate_expression(&expression, data)?;
+            push_function_text(buffer, data);
+            Ok(EncounteredEvent::Done)
+        }
         Content::Text(string) => {
             buffer.push_str(string);
             Ok(EncounteredEvent::Done)
         }
+        Content::Thread { address, arguments } => {
+            let arguments = arguments
+                .iter()
+                .map(|argument| evaluate_expression(argument, data))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Ok(EncounteredEvent::Thread {
+                address: get_divert_target(address, data)?,
+                arguments,
+            })
+        }
+        Content::TunnelCall { targets, divert } => Ok(EncounteredEvent::TunnelCall {
+            targets: targets
+                .iter()
+                .map(|(address, arguments)| {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| evaluate_expression(argument, data))
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    Ok((get_divert_target(address, data)?, arguments))
+                })
+                .collect::<Result<_, ProcessError>>()?,
+            divert: divert
+                .as_ref()
+                .map(|address| get_divert_target(address, data))
+                .transpose()?,
+        }),
+        Content::TunnelReturn(divert) => Ok(EncounteredEvent::TunnelReturn(
+            divert
+                .as_ref()
+                .map(|address| get_divert_target(address, data))
+                .transpose()?,
+        )),
     }
 }
 
+/// Add the text that was printed by functions called while processing content to a buffer.
+fn push_function_text(buffer: &mut String, data: &mut FollowData) {
+    buffer.push_str(&mem::take(&mut data.function_text));
+}
+
 /// Process and add the content of an `Alternative` to a string buffer.
 fn process_alternative(
-    alternative: &mut Alternative,
+    alternative: &Alternative,
     buffer: &mut String,
     data: &mut FollowData,
 ) -> Result<EncounteredEvent, ProcessError> {
     match alternative.get_next_index(data) {
         Some(index) => {
-            let item = alternative
-                .items
-                .get_mut(index)
-                .ok_or_else(|| ProcessError {
-                    kind: ProcessErrorKind::InvalidAlternativeIndex,
-                })?;
+            let item = alternative.items.get(index).ok_or_else(|| ProcessError {
+                kind: ProcessErrorKind::InvalidAlternativeIndex,
+            })?;
 
             process_chunk(item, buffer, data)
         }
@@ -116,14 +220,16 @@ pub mod tests {
         follow::FollowDataBuilder,
         knot::Address,
         line::{
-            expression::Operand, parse::parse_internal_line, AlternativeBuilder, ConditionBuilder,
-            ConditionKind, Expression, LineChunkBuilder, Variable,
+            expression::Operand,
+            parse::{parse_internal_line, parse_tag},
+            AlternativeBuilder, ConditionBuilder, ConditionKind, Expression, LineChunkBuilder,
+            Variable,
         },
     };
 
     use std::collections::HashMap;
 
-    pub fn get_processed_alternative(alternative: &mut Alternative) -> String {
+    pub fn get_processed_alternative(alternative: &Alternative) -> String {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
@@ -132,11 +238,16 @@ pub mod tests {
         buffer
     }
 
-    pub fn get_processed_chunk(chunk: &mut LineChunk) -> String {
-        let mut buffer = String::new();
+    pub fn get_processed_chunk(chunk: &LineChunk) -> String {
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        process_chunk(chunk, &mut buffer, &mut data).unwrap();
+        get_processed_chunk_with_data(chunk, &mut data)
+    }
+
+    pub fn get_processed_chunk_with_data(chunk: &LineChunk, data: &mut FollowData) -> String {
+        let mut buffer = String::new();
+
+        process_chunk(chunk, &mut buffer, data).unwrap();
 
         buffer
     }
@@ -162,7 +273,7 @@ pub mod tests {
         let mut buffer = Vec::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        process_line(&mut line, &mut buffer, &mut data).unwrap();
+        process_line(&line, &mut buffer, &mut data).unwrap();
 
         let result = &buffer[0];
         assert!(result.glue_begin);
@@ -171,16 +282,36 @@ pub mod tests {
 
     #[test]
     fn full_line_processing_retains_tags() {
-        let mut line = parse_internal_line("A test string", &().into()).unwrap();
-        line.tags = vec!["tag 1".to_string(), "tag 2".to_string()];
+        let line = parse_internal_line("A test string # tag 1 # tag 2", &().into()).unwrap();
 
         let mut buffer = Vec::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        process_line(&mut line, &mut buffer, &mut data).unwrap();
+        process_line(&line, &mut buffer, &mut data).unwrap();
 
         let result = &buffer[0];
-        assert_eq!(result.tags, line.tags);
+        assert_eq!(result.tags, &["tag 1", "tag 2"]);
+    }
+
+    #[test]
+    fn tags_are_processed_along_with_the_line() {
+        let line = parse_internal_line("A test string # mood: {calm|angry}", &().into()).unwrap();
+        let mut data = mock_data_with_single_stitch("", "", 0);
+
+        let mut buffer = Vec::new();
+        process_line(&line, &mut buffer, &mut data).unwrap();
+        process_line(&line, &mut buffer, &mut data).unwrap();
+
+        assert_eq!(buffer[0].tags, &["mood: calm"]);
+        assert_eq!(buffer[1].tags, &["mood: angry"]);
+    }
+
+    #[test]
+    fn processed_tags_are_trimmed_of_extra_whitespace() {
+        let tags = vec![parse_tag(" weather: {true: rain} ").unwrap()];
+        let mut data = mock_data_with_single_stitch("", "", 0);
+
+        assert_eq!(process_tags(&tags, &mut data).unwrap(), &["weather: rain"]);
     }
 
     #[test]
@@ -188,8 +319,8 @@ pub mod tests {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        let mut item = Content::Text("Hello, World!".to_string());
-        process_content(&mut item, &mut buffer, &mut data).unwrap();
+        let item = Content::Text("Hello, World!".to_string());
+        process_content(&item, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "Hello, World!");
     }
@@ -204,9 +335,9 @@ pub mod tests {
             tail: Vec::new(),
         };
 
-        let mut item = Content::Expression(expression);
+        let item = Content::Expression(expression);
 
-        process_content(&mut item, &mut buffer, &mut data).unwrap();
+        process_content(&item, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "5");
     }
@@ -223,9 +354,9 @@ pub mod tests {
             tail: Vec::new(),
         };
 
-        let mut item = Content::Expression(expression);
+        let item = Content::Expression(expression);
 
-        assert!(process_content(&mut item, &mut buffer, &mut data).is_err());
+        assert!(process_content(&item, &mut buffer, &mut data).is_err());
     }
 
     #[test]
@@ -233,8 +364,8 @@ pub mod tests {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        let mut item = Content::Empty;
-        process_content(&mut item, &mut buffer, &mut data).unwrap();
+        let item = Content::Empty;
+        process_content(&item, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, " ");
     }
@@ -245,8 +376,8 @@ pub mod tests {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        let mut line = LineChunkBuilder::from_string(content).build();
-        process_chunk(&mut line, &mut buffer, &mut data).unwrap();
+        let line = LineChunkBuilder::from_string(content).build();
+        process_chunk(&line, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, content);
     }
@@ -256,12 +387,12 @@ pub mod tests {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        let mut chunk = LineChunkBuilder::new()
+        let chunk = LineChunkBuilder::new()
             .with_text("Line 1")
             .with_text("Line 2")
             .build();
 
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "Line 1Line 2");
     }
@@ -279,14 +410,14 @@ pub mod tests {
 
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "Displayed if true.");
 
         chunk.condition.replace(false_condition);
 
         buffer.clear();
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
         assert_eq!(&buffer, "");
     }
 
@@ -303,20 +434,20 @@ pub mod tests {
 
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "Displayed if true.");
 
         chunk.condition.replace(false_condition);
 
         buffer.clear();
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
         assert_eq!(&buffer, "Displayed if false.");
     }
 
     #[test]
     fn chunks_without_condition_always_processes_the_true_content() {
-        let mut chunk = LineChunk {
+        let chunk = LineChunk {
             condition: None,
             items: vec![Content::Text("Displayed if true.".to_string())],
             else_items: vec![Content::Text("Displayed if false.".to_string())],
@@ -324,7 +455,7 @@ pub mod tests {
 
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
-        process_chunk(&mut chunk, &mut buffer, &mut data).unwrap();
+        process_chunk(&chunk, &mut buffer, &mut data).unwrap();
 
         assert_eq!(&buffer, "Displayed if true.");
     }
@@ -334,14 +465,14 @@ pub mod tests {
         let mut buffer = String::new();
         let mut data = mock_data_with_single_stitch("", "", 0);
 
-        let mut chunk = LineChunkBuilder::new()
+        let chunk = LineChunkBuilder::new()
             .with_text("Line 1")
             .with_divert("divert")
             .with_text("Line 2")
             .build();
 
         assert_eq!(
-            process_chunk(&mut chunk, &mut buffer, &mut data).unwrap(),
+            process_chunk(&chunk, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Divert(Address::Raw("divert".to_string()))
         );
 
@@ -350,7 +481,7 @@ pub mod tests {
 
     #[test]
     fn diverts_in_alternates_shortcut_when_finally_processed() {
-        let mut alternative = AlternativeBuilder::sequence()
+        let alternative = AlternativeBuilder::sequence()
             .with_line(LineChunkBuilder::from_string("Line 1").build())
             .with_line(LineChunkBuilder::new().with_divert("divert").build())
             .with_line(LineChunkBuilder::from_string("Line 2").build())
@@ -360,20 +491,20 @@ pub mod tests {
         let mut data = mock_data_with_single_stitch("", "", 0);
 
         assert_eq!(
-            process_alternative(&mut alternative, &mut buffer, &mut data).unwrap(),
+            process_alternative(&alternative, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Done
         );
         assert_eq!(&buffer, "Line 1");
         buffer.clear();
 
         assert_eq!(
-            process_alternative(&mut alternative, &mut buffer, &mut data).unwrap(),
+            process_alternative(&alternative, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Divert(Address::Raw("divert".to_string()))
         );
         buffer.clear();
 
         assert_eq!(
-            process_alternative(&mut alternative, &mut buffer, &mut data).unwrap(),
+            process_alternative(&alternative, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Done
         );
         assert_eq!(&buffer, "Line 2");
@@ -391,7 +522,7 @@ pub mod tests {
             .with_line(LineChunkBuilder::from_string("Alternative line 2").build())
             .build();
 
-        let mut line = LineChunkBuilder::new()
+        let line = LineChunkBuilder::new()
             .with_text("Line 1")
             .with_item(Content::Alternative(alternative))
             .with_text("Line 2")
@@ -401,7 +532,7 @@ pub mod tests {
         let mut data = mock_data_with_single_stitch("", "", 0);
 
         assert_eq!(
-            process_chunk(&mut line, &mut buffer, &mut data).unwrap(),
+            process_chunk(&line, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Done
         );
 
@@ -409,7 +540,7 @@ pub mod tests {
         buffer.clear();
 
         assert_eq!(
-            process_chunk(&mut line, &mut buffer, &mut data).unwrap(),
+            process_chunk(&line, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Divert(Address::Raw("divert".to_string()))
         );
 
@@ -417,7 +548,7 @@ pub mod tests {
         buffer.clear();
 
         assert_eq!(
-            process_chunk(&mut line, &mut buffer, &mut data).unwrap(),
+            process_chunk(&line, &mut buffer, &mut data).unwrap(),
             EncounteredEvent::Done
         );
 
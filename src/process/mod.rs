@@ -5,7 +5,7 @@ pub(crate) mod choice;
 mod condition;
 pub(crate) mod line;
 
-pub use buffer::process_buffer;
+pub use buffer::{is_empty_line, process_buffer, process_line_text};
 pub use choice::{get_fallback_choices, prepare_choices_for_user};
 pub use condition::check_condition;
-pub use line::process_line;
+pub use line::{process_line, process_tags};
@@ -0,0 +1,414 @@
+//! Compiled content of a story, which is shared by all states that play it.
+
+use crate::{
+    consts::ROOT_KNOT_NAME,
+    error::ReadError,
+    follow::FollowData,
+    knot::{
+        get_empty_function_counts, get_empty_function_label_counts, get_empty_knot_counts,
+        get_empty_label_counts, Address, FunctionSet, KnotSet,
+    },
+    line::{Content, InternalLine, LineChunk},
+    log::Logger,
+    node::NodeItem,
+    story::{
+        loader::StoryLoader,
+        parse::{read_story_content_from_string, read_story_content_with_loader},
+        replay::{hash_bytes, FNV_OFFSET_BASIS},
+        rng::StoryRng,
+        state::StoryState,
+        types::{ExternalFunctionSet, ListDefinitionSet, VariableSet},
+        validate::validate_story_content,
+    },
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, sync::Arc};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Immutable content of a story which has been read and validated.
+///
+/// The compiled story contains the knots, functions and lists of a story, but none of the
+/// state of a playthrough. Wrap it in an `Arc` to share it between any number of
+/// [`Story`][crate::story::Story] objects, each of which plays through it with its own
+/// [`StoryState`][crate::story::StoryState].
+pub struct CompiledStory {
+    /// Collection of `Knot`s which make up the story.
+    pub(crate) knots: KnotSet,
+    /// Functions defined in the story.
+    pub(crate) functions: Arc<FunctionSet>,
+    /// Lists defined in the story.
+    pub(crate) lists: Arc<ListDefinitionSet>,
+    /// External functions declared in the story, without bound functions.
+    pub(crate) external_functions: ExternalFunctionSet,
+    /// Global variables with their initial values.
+    pub(crate) variables: VariableSet,
+    /// Global tags associated with the story.
+    pub(crate) tags: Vec<String>,
+    /// Log of warnings and to-do comments encountered when parsing the story from the script.
+    pub log: Logger,
+}
+
+impl CompiledStory {
+    /// Create a new state which starts from the beginning of the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::compile_story_from_string;
+    /// let compiled = compile_story_from_string("A cold wind blew through the valley.").unwrap();
+    /// let state = compiled.new_state();
+    /// ```
+    pub fn new_state(&self) -> StoryState {
+        let mut knot_visit_counts = get_empty_knot_counts(&self.knots);
+        knot_visit_counts.extend(get_empty_function_counts(&self.functions));
+
+        let mut label_visit_counts = get_empty_label_counts(&self.knots);
+        label_visit_counts.extend(get_empty_function_label_counts(&self.functions));
+
+        let data = FollowData {
+            knot_visit_counts,
+            knot_visit_turns: HashMap::new(),
+            turn_index: 0,
+            choice_count: 0,
+            label_visit_counts,
+            branch_visit_counts: HashMap::new(),
+            stacks: HashMap::new(),
+            alternative_inds: HashMap::new(),
+            variables: self.variables.clone(),
+            lists: Arc::clone(&self.lists),
+            temp_variables: HashMap::new(),
+            external_functions: self.external_functions.clone(),
+            functions: Arc::clone(&self.functions),
+            call_stack: Vec::new(),
+            function_text: String::new(),
+            tunnel_stack: Vec::new(),
+            rng: StoryRng::default(),
+            pause_after_lines: false,
+            pause_before_logic: false,
+        };
+
+        let root_address = Address::from_root_knot(ROOT_KNOT_NAME, &self.knots).expect(
+            "After successfully creating all knots, the root knot name that was returned from \
+             `read_knots_from_string` is not present in the set of created knots. \
+             This simply should not be possible",
+        );
+
+        StoryState::new(root_address, data)
+    }
+}
+
+/// Read and compile a story by parsing an input string.
+///
+/// # Examples
+/// ```
+/// # use inkling::{compile_story_from_string, Story};
+/// use std::sync::Arc;
+///
+/// let content = "\
+/// He drifted off, and when he opened his eyes the woman was still there.
+/// Now she was talking to the old man seated next to her—the farmer from two stations back.
+/// ";
+///
+/// let compiled = Arc::new(compile_story_from_string(content).unwrap());
+///
+/// let first = Story::new(Arc::clone(&compiled));
+/// let second = Story::new(Arc::clone(&compiled));
+/// ```
+pub fn compile_story_from_string(string: &str) -> Result<CompiledStory, ReadError> {
+    let mut log = Logger::default();
+    let (knots, functions, variables, lists, external_functions, tags) =
+        read_story_content_from_string(string, &mut log)?;
+
+    compile_story(
+        knots,
+        functions,
+        variables,
+        lists,
+        external_functions,
+        tags,
+        log,
+    )
+}
+
+/// Read and compile a story from a root file and all files that it includes.
+///
+/// See [`read_story_with_loader`][crate::story::read_story_with_loader] for how files
+/// are included.
+pub fn compile_story_with_loader(
+    root: &str,
+    loader: &dyn StoryLoader,
+) -> Result<CompiledStory, ReadError> {
+    let mut log = Logger::default();
+    let (knots, functions, variables, lists, external_functions, tags) =
+        read_story_content_with_loader(root, loader, &mut log)?;
+
+    compile_story(
+        knots,
+        functions,
+        variables,
+        lists,
+        external_functions,
+        tags,
+        log,
+    )
+}
+
+/// Validate parsed story content and create the `CompiledStory` from it.
+fn compile_story(
+    mut knots: KnotSet,
+    mut functions: FunctionSet,
+    variables: VariableSet,
+    lists: ListDefinitionSet,
+    external_functions: ExternalFunctionSet,
+    tags: Vec<String>,
+    mut log: Logger,
+) -> Result<CompiledStory, ReadError> {
+    let mut data = FollowData {
+        knot_visit_counts: get_empty_knot_counts(&knots),
+        knot_visit_turns: HashMap::new(),
+        turn_index: 0,
+        choice_count: 0,
+        label_visit_counts: get_empty_label_counts(&knots),
+        branch_visit_counts: HashMap::new(),
+        stacks: HashMap::new(),
+        alternative_inds: HashMap::new(),
+        variables,
+        lists: Arc::new(lists),
+        temp_variables: HashMap::new(),
+        external_functions,
+        functions: Arc::default(),
+        call_stack: Vec::new(),
+        function_text: String::new(),
+        tunnel_stack: Vec::new(),
+        rng: StoryRng::default(),
+        pause_after_lines: false,
+        pause_before_logic: false,
+    };
+
+    validate_story_content(&mut knots, &mut functions, &mut data, &mut log)?;
+
+    assign_content_ids(&mut knots, &mut functions);
+
+    Ok(CompiledStory {
+        knots,
+        functions: Arc::new(functions),
+        lists: data.lists,
+        external_functions: data.external_functions,
+        variables: data.variables,
+        tags,
+        log,
+    })
+}
+
+/// Number all branches and alternatives in the story.
+///
+/// The state of a playthrough refers to this content by its id. Ids are derived from
+/// the path of the knot, stitch or function which contains the content and its index
+/// in it. They are the same every time a story is compiled from the same content, and
+/// editing the content of one stitch does not change the ids in the others.
+fn assign_content_ids(knots: &mut KnotSet, functions: &mut FunctionSet) {
+    for (knot_name, knot) in knots.iter_mut() {
+        let mut ids = IdGenerator::new(knot_name);
+
+        knot.tags
+            .iter_mut()
+            .for_each(|chunk| assign_chunk_ids(chunk, &mut ids));
+
+        for (stitch_name, stitch) in knot.stitches.iter_mut() {
+            let mut ids = IdGenerator::new(&format!("{}.{}", knot_name, stitch_name));
+            assign_node_ids(&mut stitch.root.items, &mut ids);
+        }
+    }
+
+    for (name, function) in functions.iter_mut() {
+        let mut ids = IdGenerator::new(name);
+        assign_node_ids(&mut function.stitch.root.items, &mut ids);
+    }
+}
+
+/// Generator of ids for the content of a single knot, stitch or function.
+struct IdGenerator {
+    /// Hash of the path to the knot, stitch or function.
+    path_hash: u64,
+    /// Index of the next content in it.
+    index: u64,
+}
+
+impl IdGenerator {
+    /// Create a generator for the content at a path.
+    fn new(path: &str) -> Self {
+        IdGenerator {
+            path_hash: hash_bytes(FNV_OFFSET_BASIS, path.bytes()),
+            index: 0,
+        }
+    }
+
+    /// Return the id of the next content and increment the index.
+    fn next_id(&mut self) -> usize {
+        let id = hash_bytes(self.path_hash, self.index.to_le_bytes());
+        self.index += 1;
+
+        id as usize
+    }
+}
+
+/// Number the branches and alternatives in a set of node items.
+fn assign_node_ids(items: &mut [NodeItem], ids: &mut IdGenerator) {
+    for item in items.iter_mut() {
+        match item {
+            NodeItem::Line(line) => assign_line_ids(line, ids),
+            NodeItem::BranchingPoint(branches) => {
+                for branch in branches.iter_mut() {
+                    branch.id = ids.next_id();
+
+                    assign_line_ids(&mut branch.choice.selection_text, ids);
+                    assign_line_ids(&mut branch.choice.display_text, ids);
+                    assign_node_ids(&mut branch.items, ids);
+                }
+            }
+            NodeItem::Conditional(branches) => {
+                for branch in branches.iter_mut() {
+                    assign_node_ids(&mut branch.items, ids);
+                }
+            }
+            NodeItem::Alternative(block) => {
+                block.id = ids.next_id();
+
+                for branch in block.branches.iter_mut() {
+                    assign_node_ids(&mut branch.items, ids);
+                }
+            }
+            NodeItem::Label(..) => (),
+        }
+    }
+}
+
+/// Number the alternatives in a line and its tags.
+fn assign_line_ids(line: &mut InternalLine, ids: &mut IdGenerator) {
+    assign_chunk_ids(&mut line.chunk, ids);
+
+    line.tags
+        .iter_mut()
+        .for_each(|chunk| assign_chunk_ids(chunk, ids));
+}
+
+/// Number the alternatives in a chunk of line content.
+fn assign_chunk_ids(chunk: &mut LineChunk, ids: &mut IdGenerator) {
+    for content in chunk.items.iter_mut().chain(chunk.else_items.iter_mut()) {
+        match content {
+            Content::Alternative(alternative) => {
+                alternative.id = ids.next_id();
+
+                alternative
+                    .items
+                    .iter_mut()
+                    .for_each(|item| assign_chunk_ids(item, ids));
+            }
+            Content::Nested(nested) => assign_chunk_ids(nested, ids),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::node::Branch;
+
+    fn get_branches(items: &[NodeItem]) -> &[Branch] {
+        items
+            .iter()
+            .find_map(|item| match item {
+                NodeItem::BranchingPoint(branches) => Some(branches.as_slice()),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn compiling_story_assigns_unique_ids_to_branches_and_alternatives() {
+        let content = "\
+{&One|Two}
+*   A {~Three|Four} choice
+    {Five|Six}
+*   Other choice
+";
+
+        let compiled = compile_story_from_string(content).unwrap();
+        let stitch = &compiled.knots[ROOT_KNOT_NAME].stitches[ROOT_KNOT_NAME];
+
+        let branches = get_branches(&stitch.root.items);
+
+        assert_ne!(branches[0].id, branches[1].id);
+    }
+
+    #[test]
+    fn compiling_the_same_story_twice_assigns_the_same_ids() {
+        let content = "\
+-> a_knot
+
+=== a_knot ===
+*   A {&One|Two} choice
+*   Other choice
+
+=== b_knot ===
+*   A {~Three|Four} choice
+*   Other choice
+
+=== function f ===
+{Five|Six}
+";
+
+        let first = compile_story_from_string(content).unwrap();
+        let second = compile_story_from_string(content).unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn editing_a_knot_does_not_change_the_ids_in_other_knots() {
+        let content = "\
+-> a_knot
+
+=== a_knot ===
+*   A choice
+*   Other choice
+
+=== b_knot ===
+*   A {~Three|Four} choice
+*   Other choice
+";
+
+        let edited = "\
+-> a_knot
+
+=== a_knot ===
+{&One|Two}
+*   A new choice
+*   A choice
+*   Other choice
+
+=== b_knot ===
+*   A {~Three|Four} choice
+*   Other choice
+";
+
+        let get_ids = |content: &str, knot: &str| {
+            let story = compile_story_from_string(content).unwrap();
+            let knot = &story.knots[knot];
+            let stitch = &knot.stitches[&knot.default_stitch];
+
+            get_branches(&stitch.root.items)
+                .iter()
+                .map(|branch| branch.id)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(get_ids(content, "b_knot"), get_ids(edited, "b_knot"));
+    }
+}
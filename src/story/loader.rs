@@ -0,0 +1,106 @@
+//! Loading of story content which is divided into several files.
+//!
+//! Stories can include other files by name with `INCLUDE` lines in their prelude:
+//!
+//! ```plain
+//! INCLUDE château.ink
+//! INCLUDE gloomwood.ink
+//! ```
+//!
+//! The names are resolved to the content of the files by a [`StoryLoader`][StoryLoader].
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Resolve the names of included files to their content.
+///
+/// Implemented for [`FileStoryLoader`][FileStoryLoader], which reads the files from
+/// a directory, and for `HashMap<String, String>` which maps names to content in memory.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_with_loader, StoryLoader};
+/// use std::collections::HashMap;
+///
+/// let mut files = HashMap::new();
+///
+/// files.insert("main.ink".to_string(), "INCLUDE château.ink\n-> château".to_string());
+/// files.insert("château.ink".to_string(), "== château ==\nThe château is cold.".to_string());
+///
+/// let story = read_story_with_loader("main.ink", &files).unwrap();
+/// ```
+pub trait StoryLoader {
+    /// Load the content of a file with the given name.
+    fn load(&self, name: &str) -> io::Result<String>;
+}
+
+#[derive(Clone, Debug)]
+/// Loader which reads included files from a directory.
+///
+/// Names of included files are resolved relative to the directory.
+pub struct FileStoryLoader {
+    /// Directory to read files from.
+    directory: PathBuf,
+}
+
+impl FileStoryLoader {
+    /// Create a loader which reads files from the given directory.
+    pub fn new<P: AsRef<Path>>(directory: P) -> Self {
+        FileStoryLoader {
+            directory: directory.as_ref().to_path_buf(),
+        }
+    }
+}
+
+impl StoryLoader for FileStoryLoader {
+    fn load(&self, name: &str) -> io::Result<String> {
+        fs::read_to_string(self.directory.join(name))
+    }
+}
+
+impl StoryLoader for HashMap<String, String> {
+    fn load(&self, name: &str) -> io::Result<String> {
+        self.get(name).cloned().ok_or(io::Error::new(
+            io::ErrorKind::NotFound,
+            "no file with that name was added to the loader",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_map_loader_returns_content_of_file_with_name() {
+        let mut files = HashMap::new();
+        files.insert("main.ink".to_string(), "Line one".to_string());
+
+        assert_eq!(&files.load("main.ink").unwrap(), "Line one");
+    }
+
+    #[test]
+    fn hash_map_loader_yields_not_found_error_for_unknown_names() {
+        let files: HashMap<String, String> = HashMap::new();
+
+        assert_eq!(
+            files.load("main.ink").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn file_loader_reads_files_relative_to_its_directory() {
+        let directory = std::env::temp_dir().join("inkling_file_story_loader_test");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("main.ink"), "Line one").unwrap();
+
+        let loader = FileStoryLoader::new(&directory);
+
+        assert_eq!(&loader.load("main.ink").unwrap(), "Line one");
+        assert!(loader.load("missing.ink").is_err());
+    }
+}
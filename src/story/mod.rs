@@ -2,7 +2,9 @@
 //!
 //! The important internal part of this module is the [`Story`][crate::story::Story]
 //! object, which contains an entire story. The user will be interacting with this
-//! during runtime.
+//! during runtime. It plays through the immutable content of a
+//! [`CompiledStory`][crate::story::CompiledStory] and keeps its playthrough in a
+//! [`StoryState`][crate::story::StoryState].
 //!
 //! Similar (but not identical) to the [node objects][crate::node] of knots, the story
 //! has methods which are run to follow the content. The external syntax is slightly
@@ -11,14 +13,24 @@
 //! Most of the rest of this module deals with processing internal data into a form
 //! presented to the user, or validating the content of the story as it is being accessed.
 
+mod compiled;
+mod loader;
 pub(crate) mod parse;
+mod replay;
 pub(crate) mod rng;
+mod state;
 mod story;
+mod transcript;
 pub(crate) mod types;
 mod utils;
 pub(crate) mod validate;
 
+pub use compiled::{compile_story_from_string, compile_story_with_loader, CompiledStory};
+pub use loader::{FileStoryLoader, StoryLoader};
 pub use parse::read_story_content_from_string;
-pub use story::{read_story_from_string, Story};
+pub use replay::{ReplayLog, ReplayStep};
+pub use state::{Checkpoint, StoryState};
+pub use story::{read_story_from_string, read_story_with_loader, Story};
+pub use transcript::{Transcript, TranscriptEntry, TranscriptEntryKind};
 pub use types::{Choice, Line, LineBuffer, Location, Prompt};
 pub use utils::copy_lines_into_string;
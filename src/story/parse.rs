@@ -8,37 +8,116 @@
 use crate::{
     consts::{
         CONST_MARKER, EXTERNAL_FUNCTION_MARKER, INCLUDE_MARKER, KNOT_MARKER, LINE_COMMENT_MARKER,
-        ROOT_KNOT_NAME, STITCH_MARKER, TAG_MARKER, TODO_COMMENT_MARKER, VARIABLE_MARKER,
+        LIST_MARKER, MULTILINE_COMMENT_BEGIN_MARKER, MULTILINE_COMMENT_END_MARKER, ROOT_KNOT_NAME,
+        STITCH_MARKER, TAG_MARKER, TODO_COMMENT_MARKER, VARIABLE_MARKER,
     },
     error::{
         parse::{
+            include::{IncludeError, IncludeErrorKind},
             knot::{KnotError, KnotErrorKind, KnotNameError},
+            line::LineError,
             prelude::{PreludeError, PreludeErrorKind},
             ParseError,
         },
         utils::MetaData,
         ReadError,
     },
-    knot::{parse_stitch_from_lines, read_knot_name, read_stitch_name, Knot, KnotSet, Stitch},
-    line::parse_variable,
-    log::Logger,
-    story::types::{VariableInfo, VariableSet},
+    follow::FollowData,
+    knot::{
+        parse_stitch_from_lines, read_function_signature, read_knot_signature,
+        read_stitch_signature, Function, FunctionSet, Knot, KnotSet, Stitch,
+    },
+    line::{
+        evaluate_expression, find_unescaped, get_list_item, parse_expression,
+        parse_function_signature, parse_list_definition, parse_tag, parse_variable, unescape,
+        Expression, InkList, LineChunk, ListItem, Variable,
+    },
+    log::{Logger, Warning},
+    story::{
+        loader::StoryLoader,
+        rng::StoryRng,
+        types::{
+            ExternalFunctionInfo, ExternalFunctionSet, ListDefinition, ListDefinitionSet,
+            VariableInfo, VariableSet,
+        },
+    },
 };
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-/// Read an Ink story from a string and return knots along with the metadata.
+/// Read an Ink story from a string and return knots and functions along with the metadata.
 pub fn read_story_content_from_string(
     content: &str,
     log: &mut Logger,
-) -> Result<(KnotSet, VariableSet, Vec<String>), ReadError> {
-    let mut content_lines = process_file_content_into_lines_and_metadata(content, log);
+) -> Result<
+    (
+        KnotSet,
+        FunctionSet,
+        VariableSet,
+        ListDefinitionSet,
+        ExternalFunctionSet,
+        Vec<String>,
+    ),
+    ReadError,
+> {
+    let content = remove_multiline_comments(content, None, log);
+
+    let mut content_lines = process_file_content_into_lines_and_metadata(&content, None, log);
     prune_empty_lines(&mut content_lines);
 
-    let (root_knot, variables, tags, prelude_errors) =
+    parse_story_content_from_lines(content_lines)
+}
+
+/// Read an Ink story from a root file and all files included by it.
+///
+/// The files are loaded by name using the given loader. The content of each file is
+/// merged into a single story before it is parsed. Returns knots and functions along with
+/// the metadata.
+pub fn read_story_content_with_loader(
+    root: &str,
+    loader: &dyn StoryLoader,
+    log: &mut Logger,
+) -> Result<
+    (
+        KnotSet,
+        FunctionSet,
+        VariableSet,
+        ListDefinitionSet,
+        ExternalFunctionSet,
+        Vec<String>,
+    ),
+    ReadError,
+> {
+    let mut files = HashMap::new();
+    load_file_and_included_files(root, None, loader, &mut Vec::new(), &mut files, log)?;
+
+    let (mut content_lines, knot_lines) = merge_included_lines(root, &files, &mut HashSet::new());
+    content_lines.extend(knot_lines);
+
+    parse_story_content_from_lines(content_lines)
+}
+
+/// Parse the knots, functions, variables and global tags of a story from its processed lines.
+fn parse_story_content_from_lines(
+    mut content_lines: Vec<(&str, MetaData)>,
+) -> Result<
+    (
+        KnotSet,
+        FunctionSet,
+        VariableSet,
+        ListDefinitionSet,
+        ExternalFunctionSet,
+        Vec<String>,
+    ),
+    ReadError,
+> {
+    let (root_knot, variables, lists, external_functions, tags, prelude_errors) =
         split_off_and_parse_prelude(&mut content_lines)?;
 
-    let (mut knots, mut knot_errors) = parse_knots_from_lines(content_lines);
+    let (mut knots, functions, mut knot_errors) = parse_knots_from_lines(content_lines);
 
     match root_knot {
         Ok(knot) => {
@@ -48,7 +127,7 @@ pub fn read_story_content_from_string(
     }
 
     if knot_errors.is_empty() && prelude_errors.is_empty() {
-        Ok((knots, variables, tags))
+        Ok((knots, functions, variables, lists, external_functions, tags))
     } else {
         Err(ParseError {
             knot_errors,
@@ -58,24 +137,239 @@ pub fn read_story_content_from_string(
     }
 }
 
+/// Load the processed lines of a file and all files included by it into a set.
+///
+/// Every file is only loaded once. The names of files which are currently being
+/// included are kept in a stack to detect if a file includes itself.
+fn load_file_and_included_files(
+    name: &str,
+    meta_data: Option<&MetaData>,
+    loader: &dyn StoryLoader,
+    include_stack: &mut Vec<String>,
+    files: &mut HashMap<String, Vec<(String, MetaData)>>,
+    log: &mut Logger,
+) -> Result<(), IncludeError> {
+    if let Some(i) = include_stack.iter().position(|included| included == name) {
+        let mut cycle = include_stack[i..].to_vec();
+        cycle.push(name.to_string());
+
+        return Err(IncludeError {
+            name: name.to_string(),
+            kind: IncludeErrorKind::IncludeCycle { cycle },
+            meta_data: meta_data.cloned(),
+        });
+    }
+
+    if files.contains_key(name) {
+        return Ok(());
+    }
+
+    let content = loader.load(name).map_err(|err| IncludeError {
+        name: name.to_string(),
+        kind: IncludeErrorKind::CouldNotLoad {
+            message: err.to_string(),
+        },
+        meta_data: meta_data.cloned(),
+    })?;
+
+    let content = remove_multiline_comments(&content, Some(name), log);
+
+    let mut lines = process_file_content_into_lines_and_metadata(&content, Some(name), log);
+    prune_empty_lines(&mut lines);
+
+    let lines = lines
+        .into_iter()
+        .map(|(line, meta_data)| (line.to_string(), meta_data))
+        .collect::<Vec<_>>();
+
+    let included_files = lines
+        .iter()
+        .take_while(|(line, _)| !line.starts_with(KNOT_MARKER))
+        .filter_map(|(line, meta_data)| {
+            get_included_file_name(line).map(|name| (name.to_string(), meta_data.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    files.insert(name.to_string(), lines);
+
+    include_stack.push(name.to_string());
+
+    for (included_name, meta_data) in included_files {
+        load_file_and_included_files(
+            &included_name,
+            Some(&meta_data),
+            loader,
+            include_stack,
+            files,
+            log,
+        )?;
+    }
+
+    include_stack.pop();
+
+    Ok(())
+}
+
+/// Merge the lines of a file with the lines of all files included by it.
+///
+/// `INCLUDE` lines are replaced by the lines of the included file which come before its
+/// first knot. The knots of included files are added after the knots of the including file.
+/// Returns the lines before the first knot and the knot lines separately.
+///
+/// Files which have already been merged are not merged again.
+fn merge_included_lines<'a>(
+    name: &str,
+    files: &'a HashMap<String, Vec<(String, MetaData)>>,
+    merged_files: &mut HashSet<String>,
+) -> (Vec<(&'a str, MetaData)>, Vec<(&'a str, MetaData)>) {
+    merged_files.insert(name.to_string());
+
+    let mut knot_lines = files
+        .get(name)
+        .unwrap()
+        .iter()
+        .map(|(line, meta_data)| (line.as_str(), meta_data.clone()))
+        .collect::<Vec<_>>();
+
+    let mut prelude_lines = Vec::new();
+
+    for (line, meta_data) in split_off_prelude_lines(&mut knot_lines) {
+        match get_included_file_name(line) {
+            Some(included_name) if !merged_files.contains(included_name) => {
+                let (included_prelude, included_knots) =
+                    merge_included_lines(included_name, files, merged_files);
+
+                prelude_lines.extend(included_prelude);
+                knot_lines.extend(included_knots);
+            }
+            Some(_) => (),
+            None => prelude_lines.push((line, meta_data)),
+        }
+    }
+
+    (prelude_lines, knot_lines)
+}
+
+/// Get the name of an included file from an `INCLUDE` line.
+fn get_included_file_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+
+    if line.starts_with(&format!("{} ", INCLUDE_MARKER)) {
+        line.get(INCLUDE_MARKER.len()..)
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+    } else {
+        None
+    }
+}
+
+/// Get the `MetaData` of a line, with the name of its file if it is known.
+fn get_line_meta_data(line_index: usize, file_name: Option<&str>) -> MetaData {
+    match file_name {
+        Some(name) => MetaData::from_file(name, line_index),
+        None => MetaData::from(line_index),
+    }
+}
+
+/// Remove multiline comments from the content of a `.ink` file.
+///
+/// Comments may begin and end anywhere in a line. The line breaks inside of them are kept
+/// to ensure that the line numbers of all content after them are unchanged. TODO comments
+/// which begin the comment or a line inside of it are logged.
+///
+/// Comment markers after a line comment marker are part of that comment and ignored.
+/// If a comment is not closed, the rest of the content is removed and a warning is logged.
+fn remove_multiline_comments(content: &str, file_name: Option<&str>, log: &mut Logger) -> String {
+    let mut buffer = String::with_capacity(content.len());
+    let mut open_comment: Option<MetaData> = None;
+
+    for (line, line_index) in content.lines().zip(0..) {
+        let meta_data = get_line_meta_data(line_index, file_name);
+        let mut remaining = line;
+
+        loop {
+            if open_comment.is_some() {
+                match find_unescaped(remaining, MULTILINE_COMMENT_END_MARKER) {
+                    Some(i) => {
+                        log_todo_in_comment(remaining.get(..i).unwrap(), log, &meta_data);
+
+                        remaining = remaining
+                            .get(i + MULTILINE_COMMENT_END_MARKER.len()..)
+                            .unwrap();
+                        open_comment = None;
+                    }
+                    None => {
+                        log_todo_in_comment(remaining, log, &meta_data);
+                        break;
+                    }
+                }
+            } else {
+                let comment_start = find_unescaped(remaining, MULTILINE_COMMENT_BEGIN_MARKER)
+                    .filter(|&i| match find_unescaped(remaining, LINE_COMMENT_MARKER) {
+                        Some(j) => i < j,
+                        None => true,
+                    });
+
+                match comment_start {
+                    Some(i) => {
+                        buffer.push_str(remaining.get(..i).unwrap());
+
+                        remaining = remaining
+                            .get(i + MULTILINE_COMMENT_BEGIN_MARKER.len()..)
+                            .unwrap();
+                        open_comment.replace(meta_data.clone());
+                    }
+                    None => {
+                        buffer.push_str(remaining);
+                        break;
+                    }
+                }
+            }
+        }
+
+        buffer.push('\n');
+    }
+
+    if let Some(meta_data) = open_comment {
+        log.add_warning(Warning::UnclosedMultilineComment, &meta_data);
+    }
+
+    buffer
+}
+
+/// Log a TODO comment if one begins a line inside of a multiline comment.
+///
+/// Decorative asterisks before the comment are allowed: ` * TODO: Comment`.
+fn log_todo_in_comment(comment: &str, log: &mut Logger, meta_data: &MetaData) {
+    let comment = comment.trim_start_matches(|c: char| c.is_whitespace() || c == '*');
+
+    if comment.starts_with(TODO_COMMENT_MARKER) {
+        log.add_todo(comment, meta_data);
+    }
+}
+
 /// Split the content from a `.ink` file into lines, trim them and add MetaData.
 ///
 /// This also removes comments from the lines, leaving only the actual content that will
 /// be used into story.
 fn process_file_content_into_lines_and_metadata<'a>(
     content: &'a str,
+    file_name: Option<&str>,
     log: &mut Logger,
 ) -> Vec<(&'a str, MetaData)> {
     content
         .lines()
         .zip(0..)
-        .map(|(line, line_index)| (line, MetaData::from(line_index)))
+        .map(|(line, line_index)| (line, get_line_meta_data(line_index, file_name)))
         .map(|(line, meta_data)| (trim_comment(line, log, &meta_data).trim(), meta_data))
         .collect()
 }
 
 /// Split off lines until the first named knot then parse its content and root knot.
 ///
+/// Global variables and constants may be declared anywhere in the story, not only in
+/// the prelude. Their lines are split off from all of the content before the prelude.
+///
 /// After this function has been called, the given set of lines starts at the first named
 /// knot.
 ///
@@ -89,11 +383,14 @@ fn split_off_and_parse_prelude(
     (
         Result<Knot, KnotError>,
         VariableSet,
+        ListDefinitionSet,
+        ExternalFunctionSet,
         Vec<String>,
         Vec<PreludeError>,
     ),
     ReadError,
 > {
+    let variable_lines = split_off_variable_lines(lines);
     let prelude_and_root = split_off_prelude_lines(lines);
     let (prelude_lines, root_lines) = split_prelude_into_metadata_and_text(&prelude_and_root);
 
@@ -101,45 +398,94 @@ fn split_off_and_parse_prelude(
         .first()
         .or(lines.first())
         .or(prelude_lines.last())
+        .or(variable_lines.last())
         .map(|(_, meta_data)| meta_data.clone())
         .ok_or(ReadError::Empty)?;
 
     let tags = parse_global_tags(&prelude_lines);
-    let (variables, prelude_errors) = parse_global_variables(&prelude_lines);
+    let (lists, list_variables, list_errors) = parse_global_lists(&prelude_lines);
+    let (mut variables, mut prelude_errors) = parse_global_variables(&variable_lines, &lists);
+    prelude_errors.extend(list_errors);
+    prelude_errors.extend(merge_list_variables(&mut variables, list_variables));
+    let (external_functions, external_function_errors) = parse_external_functions(&prelude_lines);
+    prelude_errors.extend(external_function_errors);
+
     let root_knot = parse_root_knot_from_lines(root_lines, root_meta_data);
 
-    Ok((root_knot, variables, tags, prelude_errors))
+    Ok((
+        root_knot,
+        variables,
+        lists,
+        external_functions,
+        tags,
+        prelude_errors,
+    ))
 }
 
-/// Parse all knots from a set of lines and return along with any encountered errors.
-fn parse_knots_from_lines(lines: Vec<(&str, MetaData)>) -> (KnotSet, Vec<KnotError>) {
+/// Parse all knots and functions from a set of lines and return along with any encountered errors.
+///
+/// Knots and functions share the same name space, since both can be referred to by name.
+fn parse_knots_from_lines(lines: Vec<(&str, MetaData)>) -> (KnotSet, FunctionSet, Vec<KnotError>) {
     let knot_line_sets = divide_lines_at_marker(lines, KNOT_MARKER);
 
     let mut knots = HashMap::new();
+    let mut functions = HashMap::new();
     let mut knot_errors = Vec::new();
 
     for lines in knot_line_sets.into_iter().filter(|lines| !lines.is_empty()) {
-        match get_knot_from_lines(lines) {
-            Ok((knot_name, knot_data)) => {
-                if !knots.contains_key(&knot_name) {
-                    knots.insert(knot_name, knot_data);
-                } else {
-                    let prev_meta_data = knots.get(&knot_name).unwrap().meta_data.clone();
-
-                    knot_errors.push(KnotError {
-                        knot_meta_data: knot_data.meta_data.clone(),
-                        line_errors: vec![KnotErrorKind::DuplicateKnotName {
-                            name: knot_name,
-                            prev_meta_data,
-                        }],
-                    });
+        if read_function_signature(lines[0].0).is_some() {
+            match get_function_from_lines(lines) {
+                Ok((name, function)) => {
+                    match get_duplicate_name_error(&name, &function.meta_data, &knots, &functions) {
+                        Some(error) => knot_errors.push(error),
+                        None => {
+                            functions.insert(name, function);
+                        }
+                    }
+                }
+                Err(error) => knot_errors.push(error),
+            }
+        } else {
+            match get_knot_from_lines(lines) {
+                Ok((knot_name, knot_data)) => {
+                    match get_duplicate_name_error(
+                        &knot_name,
+                        &knot_data.meta_data,
+                        &knots,
+                        &functions,
+                    ) {
+                        Some(error) => knot_errors.push(error),
+                        None => {
+                            knots.insert(knot_name, knot_data);
+                        }
+                    }
                 }
+                Err(error) => knot_errors.push(error),
             }
-            Err(error) => knot_errors.push(error),
         }
     }
 
-    (knots, knot_errors)
+    (knots, functions, knot_errors)
+}
+
+/// Return an error if a knot or function with the given name has already been parsed.
+fn get_duplicate_name_error(
+    name: &str,
+    meta_data: &MetaData,
+    knots: &KnotSet,
+    functions: &FunctionSet,
+) -> Option<KnotError> {
+    knots
+        .get(name)
+        .map(|knot| &knot.meta_data)
+        .or(functions.get(name).map(|function| &function.meta_data))
+        .map(|prev_meta_data| KnotError {
+            knot_meta_data: meta_data.clone(),
+            line_errors: vec![KnotErrorKind::DuplicateKnotName {
+                name: name.to_string(),
+                prev_meta_data: prev_meta_data.clone(),
+            }],
+        })
 }
 
 /// Parse the root knot from a set of lines.
@@ -179,26 +525,35 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
 
     let mut line_errors = Vec::new();
 
-    let knot_name = match read_knot_name(head_line) {
-        Ok(name) => name,
+    let (knot_name, knot_parameters) = match read_knot_signature(head_line) {
+        Ok(signature) => signature,
         Err(kind) => {
             let (invalid_name, error) = get_invalid_name_error(head_line, kind, &knot_meta_data);
 
             line_errors.push(error);
 
-            invalid_name
+            (invalid_name, Vec::new())
         }
     };
 
-    let tags = get_knot_tags(&mut tail);
+    let (tags, tag_errors) = get_knot_tags(&mut tail);
+    line_errors.extend(tag_errors);
 
     if tail.is_empty() {
         line_errors.push(KnotErrorKind::EmptyKnot);
     }
 
-    let (default_stitch, stitches, stitch_errors) = get_stitches_from_lines(tail, &knot_name);
+    let (default_stitch, mut stitches, stitch_errors) = get_stitches_from_lines(tail, &knot_name);
     line_errors.extend(stitch_errors);
 
+    // Parameters of the knot are bound when it is entered through its default stitch
+    if let Some(stitch) = default_stitch
+        .as_ref()
+        .and_then(|name| stitches.get_mut(name))
+    {
+        stitch.parameters.splice(0..0, knot_parameters);
+    }
+
     if default_stitch.is_some() && line_errors.is_empty() {
         Ok((
             knot_name,
@@ -217,6 +572,63 @@ fn get_knot_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Knot), K
     }
 }
 
+/// Parse a single `Function` from a set of lines.
+///
+/// The content of a function is parsed as a single stitch. Returns the function and its name.
+///
+/// Assumes that the set of lines is non-empty and that the first line is the header of
+/// a function, which we assert before calling this function.
+fn get_function_from_lines(lines: Vec<(&str, MetaData)>) -> Result<(String, Function), KnotError> {
+    let (head, tail) = lines
+        .split_first()
+        .map(|(head, tail)| (head, tail.to_vec()))
+        .unwrap();
+
+    let (head_line, meta_data) = head;
+
+    let mut line_errors = Vec::new();
+
+    let (name, parameters) = match read_function_signature(head_line).unwrap() {
+        Ok(signature) => signature,
+        Err(kind) => {
+            let (invalid_name, error) = get_invalid_name_error(head_line, kind, &meta_data);
+
+            line_errors.push(error);
+
+            (invalid_name, Vec::new())
+        }
+    };
+
+    if tail.is_empty() {
+        line_errors.push(KnotErrorKind::EmptyKnot);
+    }
+
+    match parse_stitch_from_lines(&tail, &name, ROOT_KNOT_NAME, meta_data.clone()) {
+        Ok(stitch) if line_errors.is_empty() => Ok((
+            name,
+            Function {
+                parameters,
+                stitch,
+                meta_data: meta_data.clone(),
+            },
+        )),
+        Ok(_) => Err(KnotError {
+            knot_meta_data: meta_data.clone(),
+            line_errors,
+        }),
+        Err(errors) => {
+            if !tail.is_empty() {
+                line_errors.extend(errors);
+            }
+
+            Err(KnotError {
+                knot_meta_data: meta_data.clone(),
+                line_errors,
+            })
+        }
+    }
+}
+
 /// Parse all stitches from a set of lines and return along with encountered errors.
 fn get_stitches_from_lines(
     lines: Vec<(&str, MetaData)>,
@@ -277,22 +689,22 @@ fn get_stitch_from_lines(
 
     let (first_line, meta_data) = lines[0].clone();
 
-    let stitch_name = match get_stitch_name(first_line, &meta_data) {
-        Ok(name) => {
-            if name.is_some() {
-                lines.remove(0);
-            }
-
-            get_stitch_identifier(name, stitch_index)
+    let (stitch_name, parameters) = match get_stitch_signature(first_line, &meta_data) {
+        Ok(Some((name, parameters))) => {
+            lines.remove(0);
+            (get_stitch_identifier(Some(name), stitch_index), parameters)
         }
+        Ok(None) => (get_stitch_identifier(None, stitch_index), Vec::new()),
         Err(kind) => {
             line_errors.push(kind);
-            "$INVALID_NAME$".to_string()
+            ("$INVALID_NAME$".to_string(), Vec::new())
         }
     };
 
     match parse_stitch_from_lines(&lines, knot_name, &stitch_name, meta_data) {
-        Ok(stitch) => {
+        Ok(mut stitch) => {
+            stitch.parameters = parameters;
+
             if line_errors.is_empty() {
                 Ok((stitch_name, stitch))
             } else {
@@ -306,16 +718,16 @@ fn get_stitch_from_lines(
     }
 }
 
-/// Read stitch name from the first line in a set.
+/// Read stitch name and parameters from the first line in a set.
 ///
 /// If the name was present, return it. If it was not present, return None. If there was
 /// another type of error reading the name, return that.
-fn get_stitch_name(
+fn get_stitch_signature(
     first_line: &str,
     meta_data: &MetaData,
-) -> Result<Option<String>, KnotErrorKind> {
-    match read_stitch_name(first_line) {
-        Ok(name) => Ok(Some(name)),
+) -> Result<Option<(String, Vec<String>)>, KnotErrorKind> {
+    match read_stitch_signature(first_line) {
+        Ok(signature) => Ok(Some(signature)),
         Err(KnotNameError::Empty) => Ok(None),
         Err(kind) => Err(KnotErrorKind::InvalidName {
             line: first_line.to_string(),
@@ -365,17 +777,27 @@ fn get_stitch_identifier(name: Option<String>, stitch_index: usize) -> String {
 /// # Notes
 /// Assumes that the lines have been trimmed of whitespace from both ends,
 /// which we do with `get_content_from_string`.
-fn get_knot_tags(lines: &mut Vec<(&str, MetaData)>) -> Vec<String> {
+fn get_knot_tags(lines: &mut Vec<(&str, MetaData)>) -> (Vec<LineChunk>, Vec<KnotErrorKind>) {
     let i = lines
         .iter()
         .position(|(line, _)| !(line.is_empty() || line.starts_with(TAG_MARKER)))
         .unwrap_or(lines.len());
 
-    lines
-        .drain(..i)
-        .filter(|(line, _)| !line.is_empty())
-        .map(|(line, _)| parse_tag_from_line(line))
-        .collect()
+    let mut tags = Vec::new();
+    let mut errors = Vec::new();
+
+    for (line, meta_data) in lines.drain(..i).filter(|(line, _)| !line.is_empty()) {
+        match parse_tag(&parse_tag_from_line(line)) {
+            Ok(tag) => tags.push(tag),
+            Err(kind) => errors.push(KnotErrorKind::LineError(LineError {
+                line: unescape(line),
+                kind,
+                meta_data,
+            })),
+        }
+    }
+
+    (tags, errors)
 }
 
 /// Split a set of lines where they start with a marker.
@@ -409,8 +831,10 @@ fn parse_tag_from_line(line: &str) -> String {
 }
 
 /// Trim TODO and line comments from a line.
+///
+/// Escaped comment markers are not comments: `https:\/\/example.com`.
 fn trim_comment<'a>(line: &'a str, log: &mut Logger, meta_data: &MetaData) -> &'a str {
-    if let Some(i) = line.find(LINE_COMMENT_MARKER) {
+    if let Some(i) = find_unescaped(line, LINE_COMMENT_MARKER) {
         line.get(..i).unwrap()
     } else if line.trim_start().starts_with(TODO_COMMENT_MARKER) {
         log.add_todo(line, meta_data);
@@ -437,6 +861,17 @@ fn split_off_prelude_lines<'a>(lines: &mut Vec<(&'a str, MetaData)>) -> Vec<(&'a
     lines.drain(..i).collect()
 }
 
+/// Split off all lines which declare global variables or constants from the given lines.
+fn split_off_variable_lines<'a>(lines: &mut Vec<(&'a str, MetaData)>) -> Vec<(&'a str, MetaData)> {
+    let (variable_lines, other_lines) = lines
+        .drain(..)
+        .partition(|(line, _)| is_variable_line(line.trim()));
+
+    *lines = other_lines;
+
+    variable_lines
+}
+
 /// Split prelude content into metadata and root text content.
 fn split_prelude_into_metadata_and_text<'a>(
     lines: &[(&'a str, MetaData)],
@@ -446,6 +881,7 @@ fn split_prelude_into_metadata_and_text<'a>(
         format!("{} ", CONST_MARKER),
         format!("{} ", EXTERNAL_FUNCTION_MARKER),
         format!("{} ", INCLUDE_MARKER),
+        format!("{} ", LIST_MARKER),
         format!("{} ", VARIABLE_MARKER),
         format!("{} ", TODO_COMMENT_MARKER),
         format!("{}", LINE_COMMENT_MARKER),
@@ -475,13 +911,22 @@ fn parse_global_tags(lines: &[(&str, MetaData)]) -> Vec<String> {
         .iter()
         .map(|(line, _)| line.trim())
         .filter(|line| line.starts_with(TAG_MARKER))
-        .map(|line| parse_tag_from_line(line))
+        .map(|line| unescape(&parse_tag_from_line(line)))
         .collect()
 }
 
-/// Parse global variables from a set of metadata lines in the prelude.
-fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<PreludeError>) {
-    let mut variables = HashMap::new();
+/// Parse global variables from a set of lines.
+///
+/// Initial values may be expressions which refer to constants, like `VAR hp = MAX_HP * 2`,
+/// or to items of the given lists, like `VAR mood = (happy, emotions.calm)`. The constants
+/// are evaluated in the order that they depend on each other, so they can be referred to
+/// before they are declared.
+fn parse_global_variables(
+    lines: &[(&str, MetaData)],
+    lists: &ListDefinitionSet,
+) -> (VariableSet, Vec<PreludeError>) {
+    let mut declarations: HashMap<String, VariableDeclaration> = HashMap::new();
+    let mut names = Vec::new();
     let mut errors = Vec::new();
 
     for (line, meta_data) in lines
@@ -489,10 +934,333 @@ fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<Prelu
         .map(|(line, meta_data)| (line.trim(), meta_data))
         .filter(|(line, _)| is_variable_line(line))
     {
-        if let Err(kind) =
-            parse_variable_info_from_line(line, &meta_data).and_then(|(name, variable_info)| {
-                match variables.insert(name.clone(), variable_info) {
-                    Some(_) => Err(PreludeErrorKind::DuplicateVariable { name }),
+        if let Err(kind) = parse_variable_declaration_from_line(line, &meta_data).and_then(
+            |(name, declaration)| match declarations.insert(name.clone(), declaration) {
+                Some(_) => Err(PreludeErrorKind::DuplicateVariable { name }),
+                None => {
+                    names.push(name);
+                    Ok(())
+                }
+            },
+        ) {
+            errors.push(PreludeError {
+                line: line.to_string(),
+                kind,
+                meta_data: meta_data.clone(),
+            });
+        }
+    }
+
+    let mut values = HashMap::new();
+
+    for name in names {
+        evaluate_variable_declaration(&name, &declarations, lists, &mut values, &mut Vec::new())
+            .unwrap_or_else(|error| errors.extend(error));
+    }
+
+    let variables = values
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|variable| (name, variable)))
+        .map(|(name, variable)| {
+            let declaration = declarations.get(&name).unwrap();
+
+            let info = VariableInfo {
+                is_const: declaration.is_const,
+                variable,
+                meta_data: declaration.meta_data.clone(),
+            };
+
+            (name, info)
+        })
+        .collect();
+
+    (variables, errors)
+}
+
+/// Declaration of a global variable, with an initial value that has not been evaluated.
+struct VariableDeclaration {
+    /// Whether or not the variable is a constant.
+    is_const: bool,
+    /// Expression for the initial value.
+    initial_value: Expression,
+    /// Line of the declaration.
+    line: String,
+    /// Information about the origin of the declaration.
+    meta_data: MetaData,
+}
+
+/// Evaluate the initial value of a declared global variable and add it to a set of values.
+///
+/// Constants which the initial value refers to are evaluated first. The names of the
+/// declarations which are currently being evaluated are kept in a stack to detect if
+/// constants refer to each other in a cycle. Names which are not declared are items
+/// of the given lists.
+///
+/// Declarations which could not be evaluated are set as `None` in the set of values,
+/// and return an error. If a declaration could not be evaluated because a constant
+/// it refers to could not be, no error is returned for it since one has already been
+/// returned for that constant.
+fn evaluate_variable_declaration(
+    name: &str,
+    declarations: &HashMap<String, VariableDeclaration>,
+    lists: &ListDefinitionSet,
+    values: &mut HashMap<String, Option<Variable>>,
+    stack: &mut Vec<String>,
+) -> Result<(), Option<PreludeError>> {
+    if values.contains_key(name) {
+        return Ok(());
+    }
+
+    let declaration = declarations.get(name).unwrap();
+
+    let get_error = |kind| PreludeError {
+        line: declaration.line.clone(),
+        kind,
+        meta_data: declaration.meta_data.clone(),
+    };
+
+    stack.push(name.to_string());
+
+    let mut result = Ok(());
+    let mut items = HashMap::new();
+
+    for reference in declaration.initial_value.get_raw_addresses() {
+        match declarations.get(&reference) {
+            Some(VariableDeclaration { is_const: true, .. }) => {
+                if let Some(i) = stack.iter().position(|name| name == &reference) {
+                    let mut names = stack[i..].to_vec();
+                    names.push(reference);
+
+                    result = Err(Some(get_error(PreludeErrorKind::ConstantCycle { names })));
+                } else {
+                    result = evaluate_variable_declaration(
+                        &reference,
+                        declarations,
+                        lists,
+                        values,
+                        stack,
+                    )
+                    .and_then(|_| match values.get(&reference) {
+                        Some(Some(_)) => Ok(()),
+                        _ => Err(None),
+                    });
+                }
+            }
+            Some(VariableDeclaration {
+                is_const: false, ..
+            }) => {
+                result = Err(Some(get_error(PreludeErrorKind::NonConstantReference {
+                    name: reference,
+                })));
+            }
+            None if lists.contains_key(&reference) => {
+                result = Err(Some(get_error(PreludeErrorKind::NonConstantReference {
+                    name: reference,
+                })));
+            }
+            None => match get_list_item(&reference, lists) {
+                Some(item) => {
+                    items.insert(reference, Variable::List(std::iter::once(item).collect()));
+                }
+                None => {
+                    result = Err(Some(get_error(PreludeErrorKind::UnknownReference {
+                        name: reference,
+                    })));
+                }
+            },
+        }
+
+        if result.is_err() {
+            break;
+        }
+    }
+
+    stack.pop();
+
+    let value = result.and_then(|_| {
+        let constants = values
+            .iter()
+            .filter_map(|(name, value)| value.clone().map(|variable| (name.clone(), variable)))
+            .chain(items)
+            .collect();
+
+        evaluate_initial_value(&declaration.initial_value, &constants, lists)
+            .map_err(|kind| Some(get_error(kind)))
+    });
+
+    match value {
+        Ok(variable) => {
+            values.insert(name.to_string(), Some(variable));
+            Ok(())
+        }
+        Err(error) => {
+            values.insert(name.to_string(), None);
+            Err(error)
+        }
+    }
+}
+
+/// Evaluate the initial value of a global variable with the values of constants and list
+/// items it refers to.
+fn evaluate_initial_value(
+    initial_value: &Expression,
+    constants: &HashMap<String, Variable>,
+    lists: &ListDefinitionSet,
+) -> Result<Variable, PreludeErrorKind> {
+    let mut expression = initial_value.clone();
+    expression.replace_raw_addresses(constants);
+
+    let mut data = FollowData {
+        knot_visit_counts: HashMap::new(),
+        knot_visit_turns: HashMap::new(),
+        turn_index: 0,
+        choice_count: 0,
+        label_visit_counts: HashMap::new(),
+        branch_visit_counts: HashMap::new(),
+        stacks: HashMap::new(),
+        alternative_inds: HashMap::new(),
+        variables: HashMap::new(),
+        lists: Arc::new(lists.clone()),
+        temp_variables: HashMap::new(),
+        external_functions: HashMap::new(),
+        functions: Arc::default(),
+        call_stack: Vec::new(),
+        function_text: String::new(),
+        tunnel_stack: Vec::new(),
+        rng: StoryRng::default(),
+        pause_after_lines: false,
+        pause_before_logic: false,
+    };
+
+    evaluate_expression(&expression, &mut data).map_err(|err| err.into())
+}
+
+/// Parse list definitions from a set of metadata lines in the prelude.
+///
+/// Definitions are on the form `LIST name = a, (b), c`. Every list also defines a global
+/// variable with the same name, which contains the items in parenthesis. These variables
+/// are returned along with the definitions.
+fn parse_global_lists(
+    lines: &[(&str, MetaData)],
+) -> (
+    ListDefinitionSet,
+    Vec<(String, VariableInfo)>,
+    Vec<PreludeError>,
+) {
+    let mut lists = HashMap::new();
+    let mut variables = Vec::new();
+    let mut errors = Vec::new();
+
+    for (line, meta_data) in lines
+        .iter()
+        .map(|(line, meta_data)| (line.trim(), meta_data))
+        .filter(|(line, _)| line.starts_with(&format!("{} ", LIST_MARKER)))
+    {
+        let definition = line.get(LIST_MARKER.len()..).unwrap();
+
+        if let Err(kind) = parse_list_definition(definition)
+            .ok_or(PreludeErrorKind::InvalidList)
+            .and_then(|(name, items)| {
+                let mut list = InkList::with_origins(vec![name.clone()]);
+
+                items
+                    .iter()
+                    .filter(|(_, _, is_selected)| *is_selected)
+                    .for_each(|(item, value, _)| {
+                        list.insert(ListItem {
+                            value: *value,
+                            list: name.clone(),
+                            name: item.clone(),
+                        })
+                    });
+
+                let definition = ListDefinition {
+                    items: items
+                        .into_iter()
+                        .map(|(item, value, _)| (item, value))
+                        .collect(),
+                    meta_data: meta_data.clone(),
+                };
+
+                match lists.insert(name.clone(), definition) {
+                    Some(_) => Err(PreludeErrorKind::DuplicateList { name }),
+                    None => {
+                        let variable_info = VariableInfo {
+                            is_const: false,
+                            variable: list.into(),
+                            meta_data: meta_data.clone(),
+                        };
+
+                        variables.push((name, variable_info));
+
+                        Ok(())
+                    }
+                }
+            })
+        {
+            errors.push(PreludeError {
+                line: line.to_string(),
+                kind,
+                meta_data: meta_data.clone(),
+            });
+        }
+    }
+
+    (lists, variables, errors)
+}
+
+/// Add the variables of list definitions to the set of global variables.
+///
+/// Lists and variables share the same name space. Returns an error for every list
+/// which has the same name as a variable.
+fn merge_list_variables(
+    variables: &mut VariableSet,
+    list_variables: Vec<(String, VariableInfo)>,
+) -> Vec<PreludeError> {
+    list_variables
+        .into_iter()
+        .filter_map(|(name, variable_info)| {
+            if variables.contains_key(&name) {
+                Some(PreludeError {
+                    line: format!("{} {}", LIST_MARKER, name),
+                    kind: PreludeErrorKind::DuplicateVariable { name },
+                    meta_data: variable_info.meta_data,
+                })
+            } else {
+                variables.insert(name, variable_info);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse external function declarations from a set of metadata lines in the prelude.
+///
+/// Declarations are on the form `EXTERNAL name(a, b)`.
+fn parse_external_functions(
+    lines: &[(&str, MetaData)],
+) -> (ExternalFunctionSet, Vec<PreludeError>) {
+    let mut external_functions = HashMap::new();
+    let mut errors = Vec::new();
+
+    for (line, meta_data) in lines
+        .iter()
+        .map(|(line, meta_data)| (line.trim(), meta_data))
+        .filter(|(line, _)| line.starts_with(&format!("{} ", EXTERNAL_FUNCTION_MARKER)))
+    {
+        let signature = line.get(EXTERNAL_FUNCTION_MARKER.len()..).unwrap();
+
+        if let Err(kind) = parse_function_signature(signature)
+            .ok_or(PreludeErrorKind::InvalidExternalFunction)
+            .and_then(|(name, parameters)| {
+                let info = ExternalFunctionInfo {
+                    parameters,
+                    function: None,
+                    meta_data: meta_data.clone(),
+                };
+
+                match external_functions.insert(name.clone(), info) {
+                    Some(_) => Err(PreludeErrorKind::DuplicateExternalFunction { name }),
                     None => Ok(()),
                 }
             })
@@ -505,29 +1273,36 @@ fn parse_global_variables(lines: &[(&str, MetaData)]) -> (VariableSet, Vec<Prelu
         }
     }
 
-    (variables, errors)
+    (external_functions, errors)
 }
 
-/// Parse a single variable line into the variable name, initial value and whether it is constant.
+/// Parse a single variable line into the variable name and its declaration.
 ///
 /// Variable lines are on the form `VAR variable_name = initial_value` and constant variables
-/// on the form `CONST variable_name = constant_value`.
-fn parse_variable_info_from_line(
+/// on the form `CONST variable_name = constant_value`. Initial values which are not single
+/// values are parsed as expressions.
+fn parse_variable_declaration_from_line(
     line: &str,
     meta_data: &MetaData,
-) -> Result<(String, VariableInfo), PreludeErrorKind> {
+) -> Result<(String, VariableDeclaration), PreludeErrorKind> {
     if let Some(i) = line.find('=') {
         let (lhs, rhs) = line.split_at(i);
+        let rhs = rhs.get(1..).unwrap();
 
         let is_const = lhs.starts_with(CONST_MARKER);
         let name = parse_variable_name(lhs, is_const)?;
-        let variable = parse_variable(rhs.get(1..).unwrap())?;
+
+        let initial_value = match parse_variable(rhs) {
+            Ok(Variable::Address(..)) | Err(_) => parse_expression(rhs)?,
+            Ok(variable) => Expression::from(variable),
+        };
 
         Ok((
             name,
-            VariableInfo {
+            VariableDeclaration {
                 is_const,
-                variable,
+                initial_value,
+                line: line.to_string(),
                 meta_data: meta_data.clone(),
             },
         ))
@@ -540,7 +1315,8 @@ fn parse_variable_info_from_line(
 ///
 /// Assumes that the line has been trimmed from both ends.
 fn is_variable_line(line: &str) -> bool {
-    line.starts_with(VARIABLE_MARKER) || line.starts_with(CONST_MARKER)
+    line.starts_with(&format!("{} ", VARIABLE_MARKER))
+        || line.starts_with(&format!("{} ", CONST_MARKER))
 }
 
 /// Parse the name from a variable string and assert that it is non-empty.
@@ -571,7 +1347,7 @@ pub mod tests {
             .map(|(i, line)| (line, MetaData::from(i)))
             .collect();
 
-        let (knots, knot_errors) = parse_knots_from_lines(lines);
+        let (knots, _, knot_errors) = parse_knots_from_lines(lines);
 
         if knot_errors.is_empty() {
             Ok(knots)
@@ -693,7 +1469,7 @@ pub mod tests {
             "VAR string = \"two words\"",
         ];
 
-        let (variables, _) = parse_global_variables(&enumerate(lines));
+        let (variables, _) = parse_global_variables(&enumerate(lines), &HashMap::new());
 
         assert_eq!(variables.len(), 2);
         assert_eq!(
@@ -709,119 +1485,343 @@ pub mod tests {
     #[test]
     fn parse_variable_from_line_yields_correct_name() {
         let (name, _) =
-            parse_variable_info_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
+            parse_variable_declaration_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
+
+        assert_eq!(&name, "variable");
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_correct_value() {
+        let (_, declaration) =
+            parse_variable_declaration_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
+
+        assert_eq!(
+            declaration.initial_value,
+            Expression::from(Variable::from(1.0))
+        );
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_whether_const_or_not() {
+        let (_, non_const_var) =
+            parse_variable_declaration_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
+        let (_, const_var) =
+            parse_variable_declaration_from_line("CONST variable = 1.0", &MetaData::from(0))
+                .unwrap();
+
+        assert!(!non_const_var.is_const);
+        assert!(const_var.is_const);
+    }
+
+    #[test]
+    fn parse_const_variable_from_line_yields_correct_name_and_value() {
+        let (name, const_var) =
+            parse_variable_declaration_from_line("CONST variable = 1.0", &MetaData::from(0))
+                .unwrap();
+
+        assert_eq!(&name, "variable");
+        assert_eq!(
+            const_var.initial_value,
+            Expression::from(Variable::from(1.0))
+        );
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_error_if_no_name() {
+        assert!(parse_variable_declaration_from_line("CONST = 1.0", &MetaData::from(0)).is_err());
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_error_if_no_value() {
+        assert!(parse_variable_declaration_from_line("CONST =", &MetaData::from(0)).is_err());
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_error_if_no_equal_sign() {
+        assert!(
+            parse_variable_declaration_from_line("CONST variable 1.0", &MetaData::from(0)).is_err()
+        );
+    }
+
+    #[test]
+    fn parse_variable_from_line_yields_error_if_empty_beyond_keyword() {
+        assert!(parse_variable_declaration_from_line("CONST", &MetaData::from(0)).is_err());
+        assert!(parse_variable_declaration_from_line("VAR", &MetaData::from(0)).is_err());
+    }
+
+    #[test]
+    fn variables_can_be_const_or_not() {
+        let lines = &["VAR float = 1.0", "CONST string = \"two words\""];
+
+        let (variables, _) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        let non_const_var = variables.get("float").unwrap();
+        let const_var = variables.get("string").unwrap();
+
+        assert!(!non_const_var.is_const);
+        assert!(const_var.is_const);
+    }
+
+    #[test]
+    fn const_variables_are_parsed_identically_to_non_const() {
+        let lines = &["VAR non_const_var = 1.0", "CONST const_var = 1.0"];
+
+        let (variables, _) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        let non_const_var = variables.get("non_const_var").unwrap();
+        let const_var = variables.get("const_var").unwrap();
+
+        assert_eq!(non_const_var.variable, const_var.variable);
+    }
+
+    #[test]
+    fn two_variables_with_same_name_yields_error() {
+        let lines = &["VAR variable = 1.0", "VAR variable = \"two words\""];
+
+        let (_, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn global_variables_are_parsed_with_metadata() {
+        let lines = &["VAR float = 1.0", "VAR string = \"two words\""];
+
+        let (variables, _) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        assert_eq!(variables.get("string").unwrap().meta_data, 1.into());
+    }
+
+    #[test]
+    fn parse_global_variables_returns_all_errors() {
+        let lines = &[
+            "VAR float = 1.0",
+            "VAR = 1.0",                  // no variable name
+            "VAR variable = ",            // no assignment
+            "VAR variable 10",            // no assignment operator
+            "VAR variable = 10chars",     // invalid characters in number
+            "VAR variable = \"two words", // unmatched quote marks
+            "VAR int = 10",
+        ];
+
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        assert_eq!(variables.len(), 2);
+        assert_eq!(errors.len(), 5);
+    }
+
+    #[test]
+    fn initial_values_are_evaluated_with_constants_in_dependency_order() {
+        let lines = &[
+            "VAR hp = MAX_HP * 2",
+            "CONST MAX_HP = BASE_HP + 5",
+            "CONST BASE_HP = 5",
+            "VAR is_strong = MAX_HP > 5 and not (BASE_HP == 0)",
+            "VAR greeting = \"Hello\" + \", World!\"",
+        ];
+
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        assert!(errors.is_empty());
+
+        assert_eq!(variables.get("BASE_HP").unwrap().variable, Variable::Int(5));
+        assert_eq!(variables.get("MAX_HP").unwrap().variable, Variable::Int(10));
+        assert_eq!(variables.get("hp").unwrap().variable, Variable::Int(20));
+        assert_eq!(
+            variables.get("is_strong").unwrap().variable,
+            Variable::Bool(true)
+        );
+        assert_eq!(
+            variables.get("greeting").unwrap().variable,
+            Variable::String("Hello, World!".to_string())
+        );
+
+        assert_eq!(variables.get("hp").unwrap().meta_data, 0.into());
+    }
+
+    #[test]
+    fn constants_which_refer_to_each_other_in_a_cycle_yield_a_single_error() {
+        let lines = &[
+            "CONST A = B + 1",
+            "CONST B = C + 1",
+            "CONST C = A + 1",
+            "VAR d = A",
+            "CONST E = E",
+        ];
+
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
+
+        assert!(variables.is_empty());
+        assert_eq!(errors.len(), 2);
+
+        match &errors[0].kind {
+            PreludeErrorKind::ConstantCycle { names } => {
+                assert_eq!(names, &["A", "B", "C", "A"]);
+                assert_eq!(errors[0].meta_data, 2.into());
+            }
+            other => panic!(
+                "expected `PreludeErrorKind::ConstantCycle` but got {:?}",
+                other
+            ),
+        }
 
-        assert_eq!(&name, "variable");
+        match &errors[1].kind {
+            PreludeErrorKind::ConstantCycle { names } => assert_eq!(names, &["E", "E"]),
+            other => panic!(
+                "expected `PreludeErrorKind::ConstantCycle` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
-    fn parse_variable_from_line_yields_correct_value() {
-        let (_, variable_info) =
-            parse_variable_info_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
+    fn initial_values_may_only_refer_to_constants() {
+        let lines = &["VAR hp = 10", "VAR max_hp = hp * 2", "VAR mana = unknown"];
 
-        assert_eq!(variable_info.variable, Variable::from(1.0));
-    }
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
 
-    #[test]
-    fn parse_variable_from_line_yields_whether_const_or_not() {
-        let (_, non_const_var) =
-            parse_variable_info_from_line("VAR variable = 1.0", &MetaData::from(0)).unwrap();
-        let (_, const_var) =
-            parse_variable_info_from_line("CONST variable = 1.0", &MetaData::from(0)).unwrap();
+        assert_eq!(variables.len(), 1);
+        assert_eq!(errors.len(), 2);
+
+        match &errors[0].kind {
+            PreludeErrorKind::NonConstantReference { name } => assert_eq!(name, "hp"),
+            other => panic!(
+                "expected `PreludeErrorKind::NonConstantReference` but got {:?}",
+                other
+            ),
+        }
 
-        assert!(!non_const_var.is_const);
-        assert!(const_var.is_const);
+        match &errors[1].kind {
+            PreludeErrorKind::UnknownReference { name } => assert_eq!(name, "unknown"),
+            other => panic!(
+                "expected `PreludeErrorKind::UnknownReference` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
-    fn parse_const_variable_from_line_yields_correct_name_and_value() {
-        let (name, const_var) =
-            parse_variable_info_from_line("CONST variable = 1.0", &MetaData::from(0)).unwrap();
+    fn initial_values_may_refer_to_list_items() {
+        let (lists, _, _) = parse_global_lists(&enumerate(&["LIST letters = a, b, c"]));
 
-        assert_eq!(&name, "variable");
-        assert_eq!(const_var.variable, Variable::from(1.0));
-    }
+        let lines = &[
+            "VAR single = b",
+            "VAR union = (b, c)",
+            "VAR qualified = letters.c",
+            "VAR empty = ()",
+        ];
 
-    #[test]
-    fn parse_variable_from_line_yields_error_if_no_name() {
-        assert!(parse_variable_info_from_line("CONST = 1.0", &MetaData::from(0)).is_err());
-    }
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &lists);
 
-    #[test]
-    fn parse_variable_from_line_yields_error_if_no_value() {
-        assert!(parse_variable_info_from_line("CONST =", &MetaData::from(0)).is_err());
-    }
+        assert!(errors.is_empty());
 
-    #[test]
-    fn parse_variable_from_line_yields_error_if_no_equal_sign() {
-        assert!(parse_variable_info_from_line("CONST variable 1.0", &MetaData::from(0)).is_err());
-    }
+        let get_items = |name: &str| match &variables.get(name).unwrap().variable {
+            Variable::List(list) => list
+                .items()
+                .iter()
+                .map(|item| item.name.clone())
+                .collect::<Vec<_>>(),
+            other => panic!("expected a list but got {:?}", other),
+        };
 
-    #[test]
-    fn parse_variable_from_line_yields_error_if_empty_beyond_keyword() {
-        assert!(parse_variable_info_from_line("CONST", &MetaData::from(0)).is_err());
-        assert!(parse_variable_info_from_line("VAR", &MetaData::from(0)).is_err());
+        assert_eq!(get_items("single"), &["b"]);
+        assert_eq!(get_items("union"), &["b", "c"]);
+        assert_eq!(get_items("qualified"), &["c"]);
+        assert!(get_items("empty").is_empty());
     }
 
     #[test]
-    fn variables_can_be_const_or_not() {
-        let lines = &["VAR float = 1.0", "CONST string = \"two words\""];
-
-        let (variables, _) = parse_global_variables(&enumerate(lines));
+    fn initial_values_may_not_refer_to_list_variables() {
+        let (lists, _, _) = parse_global_lists(&enumerate(&["LIST letters = a, b, c"]));
 
-        let non_const_var = variables.get("float").unwrap();
-        let const_var = variables.get("string").unwrap();
+        let (_, errors) = parse_global_variables(&enumerate(&["VAR x = letters"]), &lists);
 
-        assert!(!non_const_var.is_const);
-        assert!(const_var.is_const);
+        match &errors[0].kind {
+            PreludeErrorKind::NonConstantReference { name } => assert_eq!(name, "letters"),
+            other => panic!(
+                "expected `PreludeErrorKind::NonConstantReference` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
-    fn const_variables_are_parsed_identically_to_non_const() {
-        let lines = &["VAR non_const_var = 1.0", "CONST const_var = 1.0"];
+    fn initial_values_which_cannot_be_evaluated_yield_errors() {
+        let lines = &["CONST NAME = \"Anna\"", "VAR hp = NAME * 2", "VAR mp = 2 *"];
 
-        let (variables, _) = parse_global_variables(&enumerate(lines));
+        let (variables, errors) = parse_global_variables(&enumerate(lines), &HashMap::new());
 
-        let non_const_var = variables.get("non_const_var").unwrap();
-        let const_var = variables.get("const_var").unwrap();
+        assert_eq!(variables.len(), 1);
+        assert_eq!(errors.len(), 2);
+
+        match &errors[0].kind {
+            PreludeErrorKind::InvalidInitialValue(..) => (),
+            other => panic!(
+                "expected `PreludeErrorKind::InvalidInitialValue` but got {:?}",
+                other
+            ),
+        }
 
-        assert_eq!(non_const_var.variable, const_var.variable);
+        match &errors[1].kind {
+            PreludeErrorKind::CouldNotEvaluate(..) => (),
+            other => panic!(
+                "expected `PreludeErrorKind::CouldNotEvaluate` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
-    fn two_variables_with_same_name_yields_error() {
-        let lines = &["VAR variable = 1.0", "VAR variable = \"two words\""];
+    fn external_functions_are_parsed_with_parameters_and_metadata() {
+        let lines = &[
+            "VAR volume = 1.0",
+            "EXTERNAL play_sound(name, volume)",
+            "EXTERNAL get_time()",
+        ];
 
-        let (_, errors) = parse_global_variables(&enumerate(lines));
+        let (external_functions, errors) = parse_external_functions(&enumerate(lines));
 
-        assert_eq!(errors.len(), 1);
-    }
+        assert!(errors.is_empty());
+        assert_eq!(external_functions.len(), 2);
 
-    #[test]
-    fn global_variables_are_parsed_with_metadata() {
-        let lines = &["VAR float = 1.0", "VAR string = \"two words\""];
+        let info = external_functions.get("play_sound").unwrap();
 
-        let (variables, _) = parse_global_variables(&enumerate(lines));
+        assert_eq!(
+            info.parameters,
+            vec!["name".to_string(), "volume".to_string()]
+        );
+        assert!(info.function.is_none());
+        assert_eq!(info.meta_data, 1.into());
 
-        assert_eq!(variables.get("string").unwrap().meta_data, 1.into());
+        assert!(external_functions
+            .get("get_time")
+            .unwrap()
+            .parameters
+            .is_empty());
     }
 
     #[test]
-    fn parse_global_variables_returns_all_errors() {
+    fn parse_external_functions_returns_all_errors() {
         let lines = &[
-            "VAR float = 1.0",
-            "VAR = 1.0",                  // no variable name
-            "VAR variable = ",            // no assignment
-            "VAR variable 10",            // no assignment operator
-            "VAR variable = 10chars",     // invalid characters in number
-            "VAR variable = \"two words", // unmatched quote marks
-            "VAR int = 10",
+            "EXTERNAL play_sound(name)",
+            "EXTERNAL play_sound(name)", // duplicate declaration
+            "EXTERNAL get_time",         // no parenthesis
+            "EXTERNAL get weather()",    // invalid name
+            "EXTERNAL add(a + b)",       // invalid parameter name
         ];
 
-        let (variables, errors) = parse_global_variables(&enumerate(lines));
+        let (external_functions, errors) = parse_external_functions(&enumerate(lines));
 
-        assert_eq!(variables.len(), 2);
-        assert_eq!(errors.len(), 5);
+        assert_eq!(external_functions.len(), 1);
+        assert_eq!(errors.len(), 4);
+
+        match &errors[0].kind {
+            PreludeErrorKind::DuplicateExternalFunction { name } => assert_eq!(name, "play_sound"),
+            other => panic!(
+                "expected `PreludeErrorKind::DuplicateExternalFunction` but got {:?}",
+                other
+            ),
+        }
     }
 
     #[test]
@@ -937,7 +1937,7 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines = process_file_content_into_lines_and_metadata(&content, None, &mut log);
 
         assert_eq!(lines.len(), 4);
         assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
@@ -957,7 +1957,7 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines = process_file_content_into_lines_and_metadata(&content, None, &mut log);
 
         assert_eq!(lines.len(), 3);
         assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
@@ -965,6 +1965,125 @@ Second line.
         assert_eq!(lines[2], (content_lines[2], MetaData::from(2)));
     }
 
+    #[test]
+    fn escaped_comment_markers_are_not_comments() {
+        let content = "Visit https:\\/\\/example.com \\/* for more \\*/ // Comment";
+
+        let mut log = Logger::default();
+        let without_comments = remove_multiline_comments(content, None, &mut log);
+
+        let lines = process_file_content_into_lines_and_metadata(&without_comments, None, &mut log);
+
+        assert_eq!(
+            lines[0].0,
+            "Visit https:\\/\\/example.com \\/* for more \\*/"
+        );
+    }
+
+    #[test]
+    fn multiline_comments_are_removed_while_keeping_line_breaks() {
+        let content = "\
+Line one /* We can use multiline comments
+            to split them over several lines,
+            which may aid readability. */
+Line two";
+
+        let mut log = Logger::default();
+        let without_comments = remove_multiline_comments(content, None, &mut log);
+
+        let lines = process_file_content_into_lines_and_metadata(&without_comments, None, &mut log);
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], ("Line one", MetaData::from(0)));
+        assert_eq!(lines[1], ("", MetaData::from(1)));
+        assert_eq!(lines[2], ("", MetaData::from(2)));
+        assert_eq!(lines[3], ("Line two", MetaData::from(3)));
+    }
+
+    #[test]
+    fn multiline_comments_can_be_in_the_middle_of_lines() {
+        let mut log = Logger::default();
+
+        assert_eq!(
+            &remove_multiline_comments("Line /* comment */one /* two */", None, &mut log),
+            "Line one \n"
+        );
+    }
+
+    #[test]
+    fn content_after_multiline_comment_end_on_the_same_line_is_kept() {
+        let content = "\
+/* Comment
+*/ Line one
+Line two";
+
+        let mut log = Logger::default();
+
+        assert_eq!(
+            &remove_multiline_comments(content, None, &mut log),
+            "\n Line one\nLine two\n"
+        );
+    }
+
+    #[test]
+    fn multiline_comment_markers_after_line_comment_markers_are_ignored() {
+        let content = "\
+Line one // Line comment /* not a multiline comment
+Line two";
+
+        let mut log = Logger::default();
+
+        assert_eq!(
+            &remove_multiline_comments(content, None, &mut log),
+            "Line one // Line comment /* not a multiline comment\nLine two\n"
+        );
+    }
+
+    #[test]
+    fn todo_comments_inside_of_multiline_comments_are_logged_with_their_line() {
+        let content = "\
+Line one /* TODO: First comment */
+/*
+ * TODO: Second comment
+ */
+Line two";
+
+        let mut log = Logger::default();
+        remove_multiline_comments(content, None, &mut log);
+
+        assert_eq!(log.todo_comments.len(), 2);
+
+        assert_eq!(
+            log.todo_comments[0].message,
+            MessageKind::Todo("First comment".to_string())
+        );
+        assert_eq!(log.todo_comments[0].meta_data, MetaData::from(0));
+
+        assert_eq!(
+            log.todo_comments[1].message,
+            MessageKind::Todo("Second comment".to_string())
+        );
+        assert_eq!(log.todo_comments[1].meta_data, MetaData::from(2));
+    }
+
+    #[test]
+    fn unclosed_multiline_comments_remove_all_remaining_content_and_log_warning() {
+        let content = "\
+Line one
+/* Unclosed comment
+Line two";
+
+        let mut log = Logger::default();
+
+        assert_eq!(
+            &remove_multiline_comments(content, None, &mut log),
+            "Line one\n\n\n"
+        );
+
+        assert_eq!(log.warnings.len(), 1);
+        assert_eq!(log.warnings[0].meta_data, MetaData::from(1));
+    }
+
     #[test]
     fn todo_comments_can_have_initial_whitespace() {
         let content_lines = vec![
@@ -977,7 +2096,7 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines = process_file_content_into_lines_and_metadata(&content, None, &mut log);
 
         assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
         assert_eq!(lines[1], ("", MetaData::from(1)));
@@ -999,7 +2118,7 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines = process_file_content_into_lines_and_metadata(&content, None, &mut log);
 
         assert_eq!(lines.len(), 6);
         assert_eq!(lines[0], ("Initial", MetaData::from(0)));
@@ -1017,7 +2136,7 @@ Second line.
         let content = content_lines.join("\n");
 
         let mut log = Logger::default();
-        let lines = process_file_content_into_lines_and_metadata(&content, &mut log);
+        let lines = process_file_content_into_lines_and_metadata(&content, None, &mut log);
 
         assert_eq!(lines.len(), 5);
         assert_eq!(lines[0], (content_lines[0], MetaData::from(0)));
@@ -1035,6 +2154,44 @@ Second line.
         assert_eq!(&name, "Knot_name");
     }
 
+    #[test]
+    fn knot_parameters_are_set_before_parameters_of_the_default_stitch() {
+        let content = enumerate(&["== meet(character) ==", "= greet(mood)", "Line 1"]);
+
+        let (name, knot) = get_knot_from_lines(content).unwrap();
+        assert_eq!(&name, "meet");
+
+        assert_eq!(&knot.default_stitch, "greet");
+        assert_eq!(
+            knot.stitches.get("greet").unwrap().parameters,
+            vec!["character".to_string(), "mood".to_string()]
+        );
+    }
+
+    #[test]
+    fn stitches_which_are_not_default_only_get_their_own_parameters() {
+        let content = enumerate(&[
+            "== meet(character) ==",
+            "Line 1",
+            "= greet(mood)",
+            "Line 2",
+            "= leave",
+            "Line 3",
+        ]);
+
+        let (_, knot) = get_knot_from_lines(content).unwrap();
+
+        assert_eq!(
+            knot.stitches.get(ROOT_KNOT_NAME).unwrap().parameters,
+            vec!["character".to_string()]
+        );
+        assert_eq!(
+            knot.stitches.get("greet").unwrap().parameters,
+            vec!["mood".to_string()]
+        );
+        assert!(knot.stitches.get("leave").unwrap().parameters.is_empty());
+    }
+
     #[test]
     fn parsing_knot_from_lines_without_stitches_sets_content_in_default_named_stitch() {
         let content = enumerate(&["== Knot_name ==", "Line 1", "Line 2"]);
@@ -1161,7 +2318,10 @@ Second line.
         let lines = enumerate(&["== knot_name", "# Tag one", "# Tag two", "Line 1"]);
 
         let (_, knot) = get_knot_from_lines(lines).unwrap();
-        assert_eq!(&knot.tags, &["Tag one".to_string(), "Tag two".to_string()]);
+        assert_eq!(
+            &knot.tags,
+            &[parse_tag("Tag one").unwrap(), parse_tag("Tag two").unwrap()]
+        );
     }
 
     #[test]
@@ -1169,7 +2329,10 @@ Second line.
         let lines = enumerate(&["== knot_name", "", "# Tag one", "", "# Tag two", "Line 1"]);
 
         let (_, knot) = get_knot_from_lines(lines).unwrap();
-        assert_eq!(&knot.tags, &["Tag one".to_string(), "Tag two".to_string()]);
+        assert_eq!(
+            &knot.tags,
+            &[parse_tag("Tag one").unwrap(), parse_tag("Tag two").unwrap()]
+        );
     }
 
     #[test]
@@ -1210,6 +2373,19 @@ Second line.
         }
     }
 
+    #[test]
+    fn knot_tags_with_invalid_variable_content_yield_errors() {
+        let lines = enumerate(&["== knot_name", "# Tag {}", "Line 1"]);
+
+        match get_knot_from_lines(lines) {
+            Err(KnotError { line_errors, .. }) => match &line_errors[0] {
+                KnotErrorKind::LineError(..) => (),
+                other => panic!("expected `KnotErrorKind::LineError` but got `{:?}`", other),
+            },
+            Ok(other) => panic!("expected `KnotError` but got `{:?}`", other),
+        }
+    }
+
     #[test]
     fn empty_knot_with_tags_yields_error() {
         let lines = enumerate(&["== knot_name", "# Tag", "# Tag 2"]);
@@ -1236,7 +2412,7 @@ VAR hazardous = true
 ";
 
         let mut log = Logger::default();
-        let (_, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (_, _, variables, _, _, _) = read_story_content_from_string(content, &mut log).unwrap();
 
         assert_eq!(variables.len(), 2);
         assert!(variables.contains_key("counter"));
@@ -1244,19 +2420,44 @@ VAR hazardous = true
     }
 
     #[test]
-    fn variables_after_first_line_of_text_are_ignored() {
+    fn variables_are_collected_from_the_whole_story_and_removed_from_its_content() {
         let content = "
 VAR counter = 0
 
 -> introduction
 VAR hazardous = true
+
+=== introduction ===
+CONST MAX_HP = 10
+Hello, World!
 ";
 
         let mut log = Logger::default();
-        let (_, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, _, variables, _, _, _) =
+            read_story_content_from_string(content, &mut log).unwrap();
 
-        assert_eq!(variables.len(), 1);
+        assert_eq!(variables.len(), 3);
         assert!(variables.contains_key("counter"));
+        assert!(variables.contains_key("hazardous"));
+        assert!(variables.get("MAX_HP").unwrap().is_const);
+
+        let root_stitch = knots
+            .get(ROOT_KNOT_NAME)
+            .unwrap()
+            .stitches
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(root_stitch.root.items.len(), 1);
+
+        let stitch = knots
+            .get("introduction")
+            .unwrap()
+            .stitches
+            .values()
+            .next()
+            .unwrap();
+        assert_eq!(stitch.root.items.len(), 1);
     }
 
     #[test]
@@ -1267,7 +2468,7 @@ VAR hazardous = true
 ";
 
         let mut log = Logger::default();
-        let (_, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (_, _, variables, _, _, _) = read_story_content_from_string(content, &mut log).unwrap();
 
         assert_eq!(variables.len(), 0);
     }
@@ -1285,7 +2486,7 @@ VAR hazardous = true
 ";
 
         let mut log = Logger::default();
-        let (_, _, tags) = read_story_content_from_string(content, &mut log).unwrap();
+        let (_, _, _, _, _, tags) = read_story_content_from_string(content, &mut log).unwrap();
 
         assert_eq!(
             &tags,
@@ -1309,7 +2510,7 @@ Second line.
 ";
 
         let mut log = Logger::default();
-        let (knots, _, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, _, _, _, _, _) = read_story_content_from_string(content, &mut log).unwrap();
 
         assert_eq!(knots.get("root").unwrap().meta_data.line_index, 5);
         assert_eq!(knots.get("second").unwrap().meta_data.line_index, 8);
@@ -1479,4 +2680,183 @@ Placeholder text.
         );
         assert_eq!(log.todo_comments[0].meta_data.line(), 2);
     }
+
+    fn get_loader(files: &[(&str, &str)]) -> HashMap<String, String> {
+        files
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn included_file_name_is_read_from_include_lines() {
+        assert_eq!(get_included_file_name("INCLUDE file.ink"), Some("file.ink"));
+        assert_eq!(
+            get_included_file_name("  INCLUDE  dir/file.ink  "),
+            Some("dir/file.ink")
+        );
+        assert_eq!(get_included_file_name("INCLUDE "), None);
+        assert_eq!(get_included_file_name("INCLUDED file.ink"), None);
+    }
+
+    #[test]
+    fn knots_and_variables_from_included_files_are_merged_into_story() {
+        let loader = get_loader(&[
+            (
+                "main.ink",
+                "INCLUDE paris.ink\nVAR x = 1\n-> paris\n== london ==\nLondon",
+            ),
+            ("paris.ink", "VAR y = 2\n== paris ==\nParis"),
+        ]);
+
+        let mut log = Logger::default();
+        let (knots, _, variables, _, _, _) =
+            read_story_content_with_loader("main.ink", &loader, &mut log).unwrap();
+
+        assert!(knots.contains_key("paris"));
+        assert!(knots.contains_key("london"));
+        assert!(knots.contains_key(ROOT_KNOT_NAME));
+
+        assert!(variables.contains_key("x"));
+        assert!(variables.contains_key("y"));
+    }
+
+    #[test]
+    fn files_included_by_included_files_are_merged_into_story() {
+        let loader = get_loader(&[
+            ("main.ink", "INCLUDE paris.ink\n-> paris"),
+            ("paris.ink", "INCLUDE louvre.ink\n== paris ==\nParis"),
+            ("louvre.ink", "== louvre ==\nLouvre"),
+        ]);
+
+        let mut log = Logger::default();
+        let (knots, _, _, _, _, _) =
+            read_story_content_with_loader("main.ink", &loader, &mut log).unwrap();
+
+        assert!(knots.contains_key("paris"));
+        assert!(knots.contains_key("louvre"));
+    }
+
+    #[test]
+    fn files_included_several_times_are_only_merged_once() {
+        let loader = get_loader(&[
+            (
+                "main.ink",
+                "INCLUDE paris.ink\nINCLUDE louvre.ink\n-> paris",
+            ),
+            ("paris.ink", "INCLUDE louvre.ink\n== paris ==\nParis"),
+            ("louvre.ink", "VAR x = 1\n== louvre ==\nLouvre"),
+        ]);
+
+        let mut log = Logger::default();
+
+        assert!(read_story_content_with_loader("main.ink", &loader, &mut log).is_ok());
+    }
+
+    #[test]
+    fn meta_data_of_included_content_has_file_name_and_line_in_file() {
+        let loader = get_loader(&[
+            ("main.ink", "INCLUDE paris.ink\n-> paris"),
+            ("paris.ink", "\n\n== paris ==\nParis"),
+        ]);
+
+        let mut log = Logger::default();
+        let (knots, _, _, _, _, _) =
+            read_story_content_with_loader("main.ink", &loader, &mut log).unwrap();
+
+        let meta_data = &knots.get("paris").unwrap().meta_data;
+
+        assert_eq!(meta_data.file_name(), Some("paris.ink"));
+        assert_eq!(meta_data.line(), 3);
+    }
+
+    #[test]
+    fn files_which_could_not_be_loaded_yield_error_with_include_line() {
+        let loader = get_loader(&[("main.ink", "\nINCLUDE paris.ink\nLine")]);
+
+        let mut log = Logger::default();
+
+        match read_story_content_with_loader("main.ink", &loader, &mut log) {
+            Err(ReadError::IncludeError(IncludeError {
+                name,
+                kind: IncludeErrorKind::CouldNotLoad { .. },
+                meta_data: Some(meta_data),
+            })) => {
+                assert_eq!(&name, "paris.ink");
+                assert_eq!(meta_data, MetaData::from_file("main.ink", 1));
+            }
+            other => panic!("expected `IncludeError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_root_file_yields_error_without_meta_data() {
+        let loader = get_loader(&[]);
+
+        let mut log = Logger::default();
+
+        match read_story_content_with_loader("main.ink", &loader, &mut log) {
+            Err(ReadError::IncludeError(IncludeError {
+                meta_data: None, ..
+            })) => (),
+            other => panic!("expected `IncludeError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn include_cycles_yield_error_with_all_files_in_cycle() {
+        let loader = get_loader(&[
+            ("main.ink", "INCLUDE paris.ink\nLine"),
+            ("paris.ink", "INCLUDE louvre.ink\n== paris ==\nParis"),
+            ("louvre.ink", "INCLUDE paris.ink\n== louvre ==\nLouvre"),
+        ]);
+
+        let mut log = Logger::default();
+
+        match read_story_content_with_loader("main.ink", &loader, &mut log) {
+            Err(ReadError::IncludeError(IncludeError {
+                name,
+                kind: IncludeErrorKind::IncludeCycle { cycle },
+                meta_data: Some(meta_data),
+            })) => {
+                assert_eq!(&name, "paris.ink");
+                assert_eq!(cycle, &["paris.ink", "louvre.ink", "paris.ink"]);
+                assert_eq!(meta_data, MetaData::from_file("louvre.ink", 0));
+            }
+            other => panic!("expected `IncludeError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn files_including_themselves_yield_cycle_error() {
+        let loader = get_loader(&[("main.ink", "INCLUDE main.ink\nLine")]);
+
+        let mut log = Logger::default();
+
+        match read_story_content_with_loader("main.ink", &loader, &mut log) {
+            Err(ReadError::IncludeError(IncludeError {
+                kind: IncludeErrorKind::IncludeCycle { .. },
+                ..
+            })) => (),
+            other => panic!("expected `IncludeError` but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_errors_in_included_files_have_the_file_name() {
+        let loader = get_loader(&[
+            ("main.ink", "INCLUDE paris.ink\nLine"),
+            ("paris.ink", "VAR = 2\n== paris ==\nParis"),
+        ]);
+
+        let mut log = Logger::default();
+
+        match read_story_content_with_loader("main.ink", &loader, &mut log) {
+            Err(ReadError::ParseError(ParseError { prelude_errors, .. })) => {
+                assert_eq!(prelude_errors.len(), 1);
+                assert_eq!(prelude_errors[0].meta_data.file_name(), Some("paris.ink"));
+            }
+            other => panic!("expected `ParseError` but got {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,274 @@
+//! Record of the calls which drive a playthrough, from which it can be replayed.
+
+use crate::{
+    line::Variable,
+    story::types::{Line, Location},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+/// Offset basis of the 64-bit FNV-1a hash, which is the hash of no bytes.
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Hash of an empty set of lines.
+pub(crate) const EMPTY_LINES_HASH: u64 = FNV_OFFSET_BASIS;
+
+/// Prime of the 64-bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Record of the seed and the calls made to a story, which can be replayed on it.
+///
+/// Unlike a saved state, a replay log does not refer to the content of the story. It can
+/// be replayed with [`replay`][crate::story::Story::replay()] on a story compiled from
+/// a changed script, which reports the first step at which the story diverged from the
+/// recorded playthrough. To detect changed text, a hash of the lines which were returned
+/// is recorded with the steps that return lines.
+///
+/// Recording is started with [`enable_replay_log`][crate::story::Story::enable_replay_log()].
+///
+/// # Examples
+/// ```
+/// # use inkling::read_story_from_string;
+/// let content = "\
+/// VAR coins = 3
+/// A merchant showed you a lamp.
+/// *   Buy it
+///     ~ coins = coins - 2
+/// *   Leave
+/// -   You had {coins} coins left.
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// let mut line_buffer = Vec::new();
+///
+/// story.enable_replay_log();
+///
+/// story.set_variable("coins", 10).unwrap();
+/// story.resume(&mut line_buffer).unwrap();
+/// story.make_choice(0).unwrap();
+///
+/// let log = story.get_replay_log().unwrap().clone();
+///
+/// let mut replayed = read_story_from_string(content).unwrap();
+/// replayed.replay(&log).unwrap();
+///
+/// line_buffer.clear();
+/// replayed.resume(&mut line_buffer).unwrap();
+///
+/// assert_eq!(&line_buffer.last().unwrap().text, "You had 8 coins left.\n");
+/// ```
+pub struct ReplayLog {
+    /// Seed of the random number generator when the recording started.
+    seed: Option<u64>,
+    /// Recorded calls, in order.
+    steps: Vec<ReplayStep>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Call to a story which has been recorded in a [`ReplayLog`][crate::story::ReplayLog].
+pub enum ReplayStep {
+    /// Continued the story with `continue_line`, a number of times in a row.
+    ContinueLines {
+        count: usize,
+        /// Hash of the text of the returned lines, which is checked when replaying.
+        lines_hash: u64,
+    },
+    /// Made a choice with `make_choice`.
+    Choice {
+        /// Index of the choice in the presented set.
+        index: usize,
+        /// Text of the choice, which is checked when replaying.
+        text: String,
+    },
+    /// Moved to a location with `move_to` or `move_to_with_args`.
+    MoveTo {
+        location: Location,
+        arguments: Vec<Variable>,
+    },
+    /// Resumed the story with `resume`.
+    Resume {
+        /// Hash of the text of the returned lines, which is checked when replaying.
+        lines_hash: u64,
+    },
+    /// Rewound the story by a number of choices with `rewind`.
+    Rewind { steps: usize },
+    /// Set a global variable with `set_variable`.
+    SetVariable { name: String, value: Variable },
+}
+
+impl ReplayLog {
+    /// Create an empty log for a story with the given generator seed.
+    pub(crate) fn new(seed: Option<u64>) -> Self {
+        ReplayLog {
+            seed,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Get the seed of the random number generator that the playthrough started with.
+    ///
+    /// There is no seed if the `random` feature is not enabled.
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// Get the recorded steps, in order.
+    pub fn get_steps(&self) -> &[ReplayStep] {
+        &self.steps
+    }
+
+    /// Record a step.
+    ///
+    /// Consecutive calls to `resume` are recorded once, since the story is at a set of
+    /// choices or its end after the first. Consecutive calls to `continue_line` are
+    /// recorded as a single step with their count. The lines returned by the calls are
+    /// added to the step with `record_lines`.
+    pub(crate) fn record(&mut self, step: ReplayStep) {
+        match (self.steps.last_mut(), &step) {
+            (Some(ReplayStep::Resume { .. }), ReplayStep::Resume { .. }) => (),
+            (
+                Some(ReplayStep::ContinueLines { count, .. }),
+                ReplayStep::ContinueLines {
+                    count: num_added, ..
+                },
+            ) => *count += num_added,
+            _ => self.steps.push(step),
+        }
+    }
+
+    /// Add lines which were returned by the last recorded step to its hash.
+    pub(crate) fn record_lines(&mut self, lines: &[Line]) {
+        match self.steps.last_mut() {
+            Some(ReplayStep::ContinueLines { lines_hash, .. })
+            | Some(ReplayStep::Resume { lines_hash }) => {
+                *lines_hash = hash_lines(*lines_hash, lines);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Add the text of lines to a hash.
+///
+/// Uses the 64-bit FNV-1a hash, which is stable across platforms and versions. Every line
+/// is terminated by a byte which cannot occur in text, so that lines are not confused with
+/// their concatenation.
+pub(crate) fn hash_lines(hash: u64, lines: &[Line]) -> u64 {
+    hash_bytes(
+        hash,
+        lines
+            .iter()
+            .flat_map(|line| line.text.bytes().chain(std::iter::once(0xff))),
+    )
+}
+
+/// Add bytes to a 64-bit FNV-1a hash.
+pub(crate) fn hash_bytes<I: IntoIterator<Item = u8>>(hash: u64, bytes: I) -> u64 {
+    bytes.into_iter().fold(hash, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> Line {
+        Line {
+            text: text.to_string(),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn consecutive_resumes_are_recorded_once() {
+        let mut log = ReplayLog::new(None);
+
+        log.record(ReplayStep::Resume {
+            lines_hash: EMPTY_LINES_HASH,
+        });
+        log.record(ReplayStep::Resume {
+            lines_hash: EMPTY_LINES_HASH,
+        });
+
+        assert_eq!(
+            log.get_steps(),
+            &[ReplayStep::Resume {
+                lines_hash: EMPTY_LINES_HASH
+            }]
+        );
+    }
+
+    #[test]
+    fn consecutive_continued_lines_are_counted_in_a_single_step() {
+        let mut log = ReplayLog::new(None);
+
+        log.record(ReplayStep::ContinueLines {
+            count: 1,
+            lines_hash: EMPTY_LINES_HASH,
+        });
+        log.record(ReplayStep::ContinueLines {
+            count: 1,
+            lines_hash: EMPTY_LINES_HASH,
+        });
+        log.record(ReplayStep::Resume {
+            lines_hash: EMPTY_LINES_HASH,
+        });
+        log.record(ReplayStep::ContinueLines {
+            count: 1,
+            lines_hash: EMPTY_LINES_HASH,
+        });
+
+        assert_eq!(
+            log.get_steps(),
+            &[
+                ReplayStep::ContinueLines {
+                    count: 2,
+                    lines_hash: EMPTY_LINES_HASH
+                },
+                ReplayStep::Resume {
+                    lines_hash: EMPTY_LINES_HASH
+                },
+                ReplayStep::ContinueLines {
+                    count: 1,
+                    lines_hash: EMPTY_LINES_HASH
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_recorded_for_consecutive_continued_lines_are_hashed_together() {
+        let mut log = ReplayLog::new(None);
+
+        for text in &["One\n", "Two\n"] {
+            log.record(ReplayStep::ContinueLines {
+                count: 1,
+                lines_hash: EMPTY_LINES_HASH,
+            });
+            log.record_lines(&[line(text)]);
+        }
+
+        let expected = hash_lines(EMPTY_LINES_HASH, &[line("One\n"), line("Two\n")]);
+
+        assert_eq!(
+            log.get_steps(),
+            &[ReplayStep::ContinueLines {
+                count: 2,
+                lines_hash: expected
+            }]
+        );
+    }
+
+    #[test]
+    fn lines_are_not_hashed_as_their_concatenation() {
+        assert_ne!(
+            hash_lines(EMPTY_LINES_HASH, &[line("ab"), line("c")]),
+            hash_lines(EMPTY_LINES_HASH, &[line("a"), line("bc")])
+        );
+    }
+}
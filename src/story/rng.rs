@@ -32,6 +32,22 @@ mod feature_wrapper {
     /// If you are reading this text, the `random` feature is **not**
     /// currently enabled.
     pub struct StoryRng;
+
+    impl StoryRng {
+        /// Get the seed that the generator was initiated with.
+        ///
+        /// There is no seed without the `random` feature.
+        pub fn get_seed(&self) -> Option<u64> {
+            None
+        }
+
+        /// Initiate the generator with a seed if one is given.
+        ///
+        /// The seed is ignored without the `random` feature.
+        pub fn from_seed(_seed: Option<u64>) -> Self {
+            StoryRng
+        }
+    }
 }
 
 #[cfg(feature = "random")]
@@ -70,7 +86,7 @@ mod feature_wrapper {
 
     impl StoryRng {
         /// Initiate the random number generator with a seed.
-        fn with_seed(seed: u64) -> Self {
+        pub fn with_seed(seed: u64) -> Self {
             let mut gen = ChaCha8Rng::seed_from_u64(seed);
 
             // `get_word_pos()` will panic unless we set the stream to 0
@@ -79,6 +95,16 @@ mod feature_wrapper {
             StoryRng { gen, seed }
         }
 
+        /// Get the seed that the generator was initiated with.
+        pub fn get_seed(&self) -> Option<u64> {
+            Some(self.seed)
+        }
+
+        /// Initiate the generator with a seed if one is given, otherwise with a random seed.
+        pub fn from_seed(seed: Option<u64>) -> Self {
+            seed.map(StoryRng::with_seed).unwrap_or_default()
+        }
+
         #[cfg(feature = "serde_support")]
         /// Initiate the random number generator with a seed and word position.
         fn with_seed_and_position(seed: u64, position: u128) -> Self {
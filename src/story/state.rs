@@ -0,0 +1,269 @@
+//! State of a single playthrough of a compiled story.
+
+use crate::{
+    error::InklingError,
+    follow::{FollowData, LineDataBuffer},
+    knot::Address,
+    story::{
+        compiled::CompiledStory,
+        replay::{ReplayLog, ReplayStep},
+        transcript::Transcript,
+        types::{Choice, Line},
+    },
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::{collections::HashMap, mem, sync::Arc};
+
+/// Number of checkpoints which are kept in the history of a new state.
+const DEFAULT_MAX_HISTORY: usize = 10;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// State of a playthrough of a [`CompiledStory`][crate::story::CompiledStory].
+///
+/// Contains the current position in the story, visit counts, variables and the state of
+/// alternatives, but none of the story content. This is what needs to be saved to resume
+/// a playthrough later: restore it with [`Story::from_state`][crate::story::Story::from_state]
+/// using the same compiled story.
+///
+/// External functions bound to a story are not a part of its state and have to be bound
+/// again after the state has been restored.
+pub struct StoryState {
+    /// Current address in the story.
+    pub(crate) current_address: Address,
+    /// Internal data for the story.
+    pub(crate) data: FollowData,
+    /// Set of last choices presented to the user.
+    pub(crate) last_choices: Option<Vec<Choice>>,
+    /// Choice that has been set to resume the story with.
+    pub(crate) selected_choice: Option<Choice>,
+    /// Lines which have been followed but not yet returned when continuing line by line.
+    pub(crate) pending_lines: LineDataBuffer,
+    /// Whether the story has reached its end.
+    pub(crate) is_done: bool,
+    /// Checkpoints recorded at the sets of choices that have been presented, oldest first.
+    pub(crate) history: Vec<Checkpoint>,
+    /// Maximum number of checkpoints to keep in the history.
+    pub(crate) max_history: usize,
+    /// Record of the lines and choices that have been shown, if enabled.
+    pub(crate) transcript: Option<Transcript>,
+    /// Record of the calls made to the story, if enabled.
+    pub(crate) replay_log: Option<ReplayLog>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// State of a playthrough at a set of choices that was presented to the user.
+///
+/// Checkpoints are recorded in the history of a story as it is followed and can be
+/// returned to with [`rewind`][crate::story::Story::rewind()].
+pub struct Checkpoint {
+    /// Address of the story at the choices.
+    pub(crate) current_address: Address,
+    /// Variables, visit counts, alternative indices and generator at the choices.
+    ///
+    /// Only the knots, stitches and labels which had been visited are kept in the visit
+    /// counts. The others are set to 0 when the checkpoint is restored.
+    pub(crate) data: FollowData,
+    /// Choices that were presented.
+    pub(crate) choices: Vec<Choice>,
+}
+
+impl Checkpoint {
+    /// Get the set of choices that was presented at the checkpoint.
+    pub fn get_choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    /// Get the number of choices that had been made in the story before the checkpoint.
+    pub fn get_turn_index(&self) -> u32 {
+        self.data.turn_index
+    }
+}
+
+impl StoryState {
+    /// Create a state at the given address.
+    pub(crate) fn new(current_address: Address, data: FollowData) -> Self {
+        StoryState {
+            current_address,
+            data,
+            last_choices: None,
+            selected_choice: None,
+            pending_lines: Vec::new(),
+            is_done: false,
+            history: Vec::new(),
+            max_history: DEFAULT_MAX_HISTORY,
+            transcript: None,
+            replay_log: None,
+        }
+    }
+
+    /// Record a step in the replay log, if it is enabled.
+    pub(crate) fn record_replay_step(&mut self, step: ReplayStep) {
+        if let Some(replay_log) = self.replay_log.as_mut() {
+            replay_log.record(step);
+        }
+    }
+
+    /// Record lines returned by the last step in the replay log, if it is enabled.
+    pub(crate) fn record_replay_lines(&mut self, lines: &[Line]) {
+        if let Some(replay_log) = self.replay_log.as_mut() {
+            replay_log.record_lines(lines);
+        }
+    }
+
+    /// Whether the story is waiting for a choice to be made from a presented set.
+    pub(crate) fn is_waiting_for_choice(&self) -> bool {
+        self.selected_choice.is_none() && self.last_choices.is_some()
+    }
+
+    /// Record a checkpoint at a set of choices which is presented to the user.
+    ///
+    /// The oldest checkpoint is dropped if the history is full.
+    pub(crate) fn record_checkpoint(&mut self, choices: Vec<Choice>) {
+        if self.max_history == 0 {
+            return;
+        }
+
+        if self.history.len() >= self.max_history {
+            self.history.remove(0);
+        }
+
+        let mut data = self.data.clone();
+
+        data.external_functions.clear();
+        remove_unvisited_knots(&mut data.knot_visit_counts);
+        remove_unvisited_labels(&mut data.label_visit_counts);
+
+        self.history.push(Checkpoint {
+            current_address: self.current_address.clone(),
+            data,
+            choices,
+        });
+    }
+
+    /// Set the maximum number of checkpoints to keep, dropping the oldest ones if needed.
+    pub(crate) fn set_max_history(&mut self, max_history: usize) {
+        let num_excess = self.history.len().saturating_sub(max_history);
+        self.history.drain(..num_excess);
+
+        self.max_history = max_history;
+    }
+
+    /// Restore the checkpoint at which the choice `steps` choices back was made.
+    ///
+    /// Rewinding by zero choices does nothing.
+    ///
+    /// Later checkpoints are removed from the history. The story is left waiting for
+    /// a choice to be made from the restored set of choices.
+    pub(crate) fn rewind(&mut self, steps: usize) -> Result<(), InklingError> {
+        // The last checkpoint is the current set of choices if one has not been made from it
+        let num_recorded = if self.is_waiting_for_choice() {
+            self.history.len().saturating_sub(1)
+        } else {
+            self.history.len()
+        };
+
+        if steps == 0 {
+            return Ok(());
+        } else if steps > num_recorded {
+            return Err(InklingError::InvalidRewind {
+                steps,
+                num_recorded,
+            });
+        }
+
+        self.history.truncate(num_recorded - steps + 1);
+
+        let checkpoint = self.history.last().cloned().unwrap();
+        let mut data = checkpoint.data;
+
+        // Shared content, bound functions and unvisited addresses are kept from the current
+        // data, since they are not saved with checkpoints
+        add_unvisited_knots(&mut data.knot_visit_counts, &self.data.knot_visit_counts);
+        add_unvisited_labels(&mut data.label_visit_counts, &self.data.label_visit_counts);
+
+        data.lists = Arc::clone(&self.data.lists);
+        data.functions = Arc::clone(&self.data.functions);
+        data.external_functions = mem::take(&mut self.data.external_functions);
+        data.pause_after_lines = false;
+
+        self.current_address = checkpoint.current_address;
+        self.data = data;
+        self.last_choices = Some(checkpoint.choices);
+        self.selected_choice = None;
+        self.pending_lines.clear();
+        self.is_done = false;
+
+        Ok(())
+    }
+
+    /// Restore the references to the content of the compiled story that it plays.
+    ///
+    /// Bound external functions are reset.
+    pub(crate) fn attach(&mut self, story: &CompiledStory) {
+        self.data.lists = Arc::clone(&story.lists);
+        self.data.functions = Arc::clone(&story.functions);
+        self.data.external_functions = story.external_functions.clone();
+    }
+}
+
+/// Remove knots and stitches which have not been visited from a set of visit counts.
+fn remove_unvisited_knots(knot_visit_counts: &mut HashMap<String, HashMap<String, u32>>) {
+    knot_visit_counts.retain(|_, stitches| {
+        stitches.retain(|_, num_visited| *num_visited > 0);
+        !stitches.is_empty()
+    });
+}
+
+/// Remove labels which have not been visited from a set of visit counts.
+fn remove_unvisited_labels(
+    label_visit_counts: &mut HashMap<String, HashMap<String, HashMap<String, u32>>>,
+) {
+    label_visit_counts.retain(|_, stitches| {
+        stitches.retain(|_, labels| {
+            labels.retain(|_, num_visited| *num_visited > 0);
+            !labels.is_empty()
+        });
+        !stitches.is_empty()
+    });
+}
+
+/// Add the knots and stitches of a full set of visit counts which are missing from another,
+/// with their number of visits set to 0.
+fn add_unvisited_knots(
+    knot_visit_counts: &mut HashMap<String, HashMap<String, u32>>,
+    all_counts: &HashMap<String, HashMap<String, u32>>,
+) {
+    for (knot_name, stitches) in all_counts {
+        let counts = knot_visit_counts.entry(knot_name.clone()).or_default();
+
+        for stitch_name in stitches.keys() {
+            counts.entry(stitch_name.clone()).or_insert(0);
+        }
+    }
+}
+
+/// Add the labels of a full set of visit counts which are missing from another,
+/// with their number of visits set to 0.
+fn add_unvisited_labels(
+    label_visit_counts: &mut HashMap<String, HashMap<String, HashMap<String, u32>>>,
+    all_counts: &HashMap<String, HashMap<String, HashMap<String, u32>>>,
+) {
+    for (knot_name, stitches) in all_counts {
+        let knot_counts = label_visit_counts.entry(knot_name.clone()).or_default();
+
+        for (stitch_name, labels) in stitches {
+            let counts = knot_counts.entry(stitch_name.clone()).or_default();
+
+            for label in labels.keys() {
+                counts.entry(label.clone()).or_insert(0);
+            }
+        }
+    }
+}
@@ -2,47 +2,130 @@
 
 use crate::{
     consts::ROOT_KNOT_NAME,
-    error::{InklingError, ReadError},
-    follow::{ChoiceInfo, EncounteredEvent, FollowData, LineDataBuffer},
-    knot::{get_empty_knot_counts, get_mut_stitch, get_num_visited, Address, KnotSet},
+    error::{utils::MetaData, InklingError, ReadError, ReplayError, ReplayErrorKind},
+    follow::{ChoiceInfo, EncounteredEvent, FollowData, LineDataBuffer, ThreadState, TunnelFrame},
+    knot::{
+        get_num_visited, get_stitch, get_turns_since, set_stack, take_stack, Address, AddressKind,
+        KnotSet,
+    },
     line::Variable,
     log::Logger,
-    process::{get_fallback_choices, prepare_choices_for_user, process_buffer},
+    process::{
+        get_fallback_choices, is_empty_line, prepare_choices_for_user, process_buffer,
+        process_line_text, process_tags,
+    },
     story::{
-        parse::read_story_content_from_string,
+        compiled::{compile_story_from_string, compile_story_with_loader, CompiledStory},
+        loader::StoryLoader,
+        replay::{hash_lines, ReplayLog, ReplayStep, EMPTY_LINES_HASH},
         rng::StoryRng,
-        types::{Choice, LineBuffer, Location, Prompt},
-        validate::validate_story_content,
+        state::{Checkpoint, StoryState},
+        transcript::Transcript,
+        types::{Choice, ExternalFunction, Line, LineBuffer, Location, Prompt},
     },
 };
 
 #[cfg(feature = "serde_support")]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use std::{collections::HashMap, fmt, mem, sync::Arc};
 
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
-#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
 /// Story with knots, diverts, choices and possibly lots of text.
+///
+/// A story plays through a [`CompiledStory`][crate::story::CompiledStory], which may be
+/// shared with other stories, and keeps the state of its playthrough in a
+/// [`StoryState`][crate::story::StoryState].
+///
+/// A serialized story contains its compiled content along with the state, since it has
+/// to be deserialized into a story which can be played on its own. To save only the state
+/// of the playthrough, serialize its [state][crate::story::Story::get_state()] and restore
+/// it with [`from_state`][crate::story::Story::from_state()].
 pub struct Story {
-    /// Current address in the story.
-    current_address: Address,
-    /// Collection of `Knot`s which make up the story.
-    knots: KnotSet,
-    /// History of visited addresses.
-    history: Vec<Address>,
-    /// Internal data for the story.
-    data: FollowData,
-    /// Global tags associated with the story.
-    tags: Vec<String>,
-    /// Set of last choices presented to the user.
-    last_choices: Option<Vec<Choice>>,
-    /// Choice that has been set to resume the story with.
-    selected_choice: Option<usize>,
-    /// Log of warnings and to-do comments encountered when parsing the `Story` from the script.
-    pub log: Logger,
+    /// Compiled content of the story.
+    story: Arc<CompiledStory>,
+    /// State of the playthrough.
+    state: StoryState,
 }
 
 impl Story {
+    /// Create a story which plays through a compiled story from its beginning.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{compile_story_from_string, Story};
+    /// use std::sync::Arc;
+    ///
+    /// let compiled = Arc::new(compile_story_from_string("It was a dark night.").unwrap());
+    ///
+    /// let mut first = Story::new(Arc::clone(&compiled));
+    /// let mut second = Story::new(Arc::clone(&compiled));
+    /// ```
+    pub fn new(story: Arc<CompiledStory>) -> Self {
+        let state = story.new_state();
+
+        Story { story, state }
+    }
+
+    /// Create a story which resumes a playthrough from a saved state.
+    ///
+    /// The state must have been created from the same compiled story, for example by
+    /// saving the [state][crate::story::Story::get_state()] of an earlier story. External
+    /// functions have to be bound to the new story again before it is resumed.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{compile_story_from_string, Story};
+    /// # use std::sync::Arc;
+    /// let content = "\
+    /// A man stood by the gate.
+    /// *   Greet him
+    ///     He nodded at you.
+    /// *   Pass him by
+    /// ";
+    ///
+    /// let compiled = Arc::new(compile_story_from_string(content).unwrap());
+    ///
+    /// let mut story = Story::new(Arc::clone(&compiled));
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// let state = story.get_state().clone();
+    ///
+    /// let mut restored = Story::from_state(Arc::clone(&compiled), state);
+    /// restored.make_choice(0).unwrap();
+    ///
+    /// line_buffer.clear();
+    /// restored.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[1].text, "He nodded at you.\n");
+    /// ```
+    pub fn from_state(story: Arc<CompiledStory>, mut state: StoryState) -> Self {
+        state.attach(&story);
+
+        Story { story, state }
+    }
+
+    /// Get the state of the playthrough.
+    ///
+    /// The state contains everything that has to be saved to resume the story later
+    /// with [`from_state`][crate::story::Story::from_state()], but none of its content.
+    pub fn get_state(&self) -> &StoryState {
+        &self.state
+    }
+
+    /// Get the compiled story which this story plays through.
+    pub fn get_compiled_story(&self) -> &Arc<CompiledStory> {
+        &self.story
+    }
+
+    /// Get the log of warnings and to-do comments encountered when reading the story.
+    pub fn get_log(&self) -> &Logger {
+        &self.story.log
+    }
+
     /// Resume the story text flow while reading all encountered lines into the supplied buffer.
     ///
     /// Should be called to start the flow through the story or to resume it
@@ -127,6 +210,7 @@ impl Story {
     /// let location = Location {
     ///     knot: "mirandas_den".to_string(),
     ///     stitch: Some("meeting".to_string()),
+    ///     label: None,
     /// };
     ///
     /// story.move_to(&location).unwrap();
@@ -135,16 +219,159 @@ impl Story {
     ///
     /// assert_eq!(&line_buffer[0].text, "Miranda was waiting in her office.\n");
     /// ```
+    ///
+    /// # Errors
+    /// *   [`UnboundExternalFunctions`][crate::error::InklingError::UnboundExternalFunctions]:
+    ///     if any external functions declared in the story have not been bound to functions
+    ///     with [`bind_external_function`][crate::story::Story::bind_external_function()].
     pub fn resume(&mut self, line_buffer: &mut LineBuffer) -> Result<Prompt, InklingError> {
+        self.check_external_functions_are_bound()?;
+        self.state.record_replay_step(ReplayStep::Resume {
+            lines_hash: EMPTY_LINES_HASH,
+        });
+
+        // Lines which were followed by `continue_line` but not yet returned come first
+        let mut internal_buffer = mem::take(&mut self.state.pending_lines);
+
         // Break early if we are at a choice but no choice has yet been made
-        match (self.selected_choice, self.last_choices.as_ref()) {
-            (None, Some(choices)) => return Ok(Prompt::Choice(choices.clone())),
-            _ => (),
+        if let Some(choices) = self.get_current_choices() {
+            self.add_lines_to_buffer(line_buffer, internal_buffer);
+            return Ok(Prompt::Choice(choices));
+        }
+
+        if self.state.is_done {
+            self.add_lines_to_buffer(line_buffer, internal_buffer);
+            return Ok(Prompt::Done);
         }
 
-        let selection = self.selected_choice.take();
+        let selection = self.state.selected_choice.take();
 
-        self.follow_story_wrapper(selection, line_buffer)
+        let prompt = self
+            .follow_story_wrapper(selection, &mut internal_buffer)?
+            .expect("the follow only pauses when the story is continued line by line");
+
+        self.add_lines_to_buffer(line_buffer, internal_buffer);
+
+        Ok(prompt)
+    }
+
+    /// Continue the story text flow by a single line.
+    ///
+    /// Returns the next line of text in the story, or `None` if the story has reached
+    /// a set of choices or its end. Like with [`resume`][crate::story::Story::resume()],
+    /// choices are made with [`make_choice`][crate::story::Story::make_choice()], after
+    /// which the story can be continued from the selected branch.
+    ///
+    /// Since the next line may be glued to the returned line, the story is followed up to
+    /// it before the line is returned. The follow stops before any content which assigns
+    /// variables or calls functions, which is evaluated when the story is continued past
+    /// the returned line. If the story reaches a set of choices or its end before another
+    /// line, it is followed to them. Content after a thread is followed along with the
+    /// thread until the next set of choices.
+    ///
+    /// The lines that are returned are identical to the ones that `resume` would return,
+    /// except that lines are not glued together across such logic: a line which is
+    /// followed by an assignment or function call and then a line which begins with glue
+    /// is returned with a newline. The methods can be mixed: `resume` returns all lines
+    /// which have been followed but not yet returned by this method before continuing
+    /// the story.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// The rain fell on the roof.
+    /// It was going to be a long night.
+    /// *   Light a candle
+    /// *   Go to sleep
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let line = story.continue_line().unwrap().unwrap();
+    /// assert_eq!(&line.text, "The rain fell on the roof.\n");
+    ///
+    /// let line = story.continue_line().unwrap().unwrap();
+    /// assert_eq!(&line.text, "It was going to be a long night.\n");
+    ///
+    /// assert!(story.continue_line().unwrap().is_none());
+    /// assert_eq!(story.get_current_choices().unwrap().len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`UnboundExternalFunctions`][crate::error::InklingError::UnboundExternalFunctions]:
+    ///     if any external functions declared in the story have not been bound to functions
+    ///     with [`bind_external_function`][crate::story::Story::bind_external_function()].
+    pub fn continue_line(&mut self) -> Result<Option<Line>, InklingError> {
+        self.check_external_functions_are_bound()?;
+        self.state.record_replay_step(ReplayStep::ContinueLines {
+            count: 1,
+            lines_hash: EMPTY_LINES_HASH,
+        });
+
+        while self.state.pending_lines.is_empty() && self.can_follow() {
+            self.follow_line()?;
+        }
+
+        if self.state.pending_lines.is_empty() {
+            return Ok(None);
+        }
+
+        // The next line is needed to check whether it is glued to the returned line
+        self.follow_to_next_line()?;
+
+        let line = self.state.pending_lines.remove(0);
+        let (address, meta_data) = (line.address.clone(), line.meta_data.clone());
+
+        let line = process_line_text(line, self.state.pending_lines.first());
+        self.record_line(&line, address.as_ref(), meta_data);
+        self.state.record_replay_lines(std::slice::from_ref(&line));
+
+        Ok(Some(line))
+    }
+
+    /// Return whether the story can be continued with another line.
+    ///
+    /// This is false when all lines before the next set of choices or the end of the story
+    /// have been returned by [`continue_line`][crate::story::Story::continue_line()],
+    /// or if the next content in the story is a set of choices. In the latter case
+    /// `continue_line` follows the story to the choices and returns `None`.
+    ///
+    /// If the lines before the choices end with logic, such as an assignment or a call to
+    /// a function, the story can be continued past the last line to evaluate it, after
+    /// which `continue_line` returns `None`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// The rain fell on the roof.
+    /// It was going to be a long night.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut lines = Vec::new();
+    ///
+    /// while story.can_continue() {
+    ///     lines.extend(story.continue_line().unwrap());
+    /// }
+    ///
+    /// assert_eq!(lines.len(), 2);
+    /// ```
+    pub fn can_continue(&self) -> bool {
+        !self.state.pending_lines.is_empty() || (self.can_follow() && !self.is_before_choices())
+    }
+
+    /// Get the set of choices that the story is waiting at, if any.
+    ///
+    /// The choices are available after the story has been followed to them, either with
+    /// [`resume`][crate::story::Story::resume()] or
+    /// [`continue_line`][crate::story::Story::continue_line()], until a choice is made.
+    pub fn get_current_choices(&self) -> Option<Vec<Choice>> {
+        match (&self.state.selected_choice, &self.state.last_choices) {
+            (None, Some(choices)) => Some(choices.clone()),
+            _ => None,
+        }
     }
 
     /// Make a choice from a given set of options.
@@ -188,7 +415,8 @@ impl Story {
     /// *   [`MadeChoiceWithoutChoice`][crate::error::InklingError::MadeChoiceWithoutChoice]:
     ///     if the story is not currently at a branching point.
     pub fn make_choice(&mut self, selection: usize) -> Result<(), InklingError> {
-        let index = self
+        let choice = self
+            .state
             .last_choices
             .as_ref()
             .ok_or(InklingError::MadeChoiceWithoutChoice)
@@ -199,21 +427,111 @@ impl Story {
                         selection,
                         presented_choices: last_choices.clone(),
                     })
-                    .map(|choice| choice.index)
+                    .cloned()
             })?;
 
-        self.selected_choice.replace(index);
-        self.last_choices = None;
+        if let Some(transcript) = self.state.transcript.as_mut() {
+            let address = choice
+                .thread
+                .as_ref()
+                .map(|thread| &thread.address)
+                .unwrap_or(&self.state.current_address);
+
+            transcript.record_choice(
+                &choice,
+                selection,
+                get_location(address),
+                self.state.data.turn_index,
+            );
+        }
+
+        self.state.record_replay_step(ReplayStep::Choice {
+            index: selection,
+            text: choice.text.clone(),
+        });
+
+        self.state.selected_choice.replace(choice);
+        self.state.last_choices = None;
 
         Ok(())
     }
 
+    /// Return the story to a set of choices that was presented earlier.
+    ///
+    /// Every time the story presents a set of choices, a [`Checkpoint`][crate::story::Checkpoint]
+    /// of its variables, visit counts, alternatives and random number generator is recorded.
+    /// Rewinding by `steps` undoes that many of the choices made since, restoring the state
+    /// to the checkpoint at which the earliest of them was made. The story is then waiting
+    /// for a choice to be made from the same set of choices again.
+    ///
+    /// Checkpoints after the restored one are removed from the history. Rewinding by zero
+    /// choices does nothing.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// VAR coins = 3
+    /// A merchant showed you a lamp.
+    /// *   Buy it
+    ///     ~ coins = coins - 2
+    ///     You bought the lamp.
+    /// *   Leave
+    /// -   You had {coins} coins left.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("coins").unwrap(), 1.into());
+    ///
+    /// story.rewind(1).unwrap();
+    ///
+    /// assert_eq!(story.get_variable("coins").unwrap(), 3.into());
+    /// assert_eq!(story.get_current_choices().unwrap().len(), 2);
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidRewind`][crate::error::InklingError::InvalidRewind]: if fewer choices
+    ///     than `steps` are recorded in the history.
+    pub fn rewind(&mut self, steps: usize) -> Result<(), InklingError> {
+        self.state.rewind(steps)?;
+        self.state.record_replay_step(ReplayStep::Rewind { steps });
+
+        Ok(())
+    }
+
+    /// Get the checkpoints recorded at the sets of choices that have been presented.
+    ///
+    /// The checkpoints are ordered from oldest to newest. If the story is waiting at a set
+    /// of choices, the last checkpoint is at that set.
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.state.history
+    }
+
+    /// Set the maximum number of checkpoints to keep in the history.
+    ///
+    /// The oldest checkpoints are dropped when the history is full. By default 10
+    /// checkpoints are kept. Each checkpoint holds the variables and the visit counts of
+    /// visited content in the story, which are saved with its state. Set it to 0 to not
+    /// record any checkpoints.
+    pub fn set_max_history(&mut self, max_history: usize) {
+        self.state.set_max_history(max_history);
+    }
+
     /// Move the story to another knot or stitch.
     ///
     /// A move can be performed at any time, before or after starting the story. It
     /// simply updates the current internal address in the story to the given address.
     /// If no stitch name is given the default stitch from the root will be selected.
     ///
+    /// The location may also be a labelled choice or gather, in which case the text flow
+    /// continues from that point inside its stitch.
+    ///
     /// After moving to a new location, call [`resume`][crate::story::Story::resume()]
     /// to continue the text flow from that point.
     ///
@@ -239,6 +557,7 @@ impl Story {
     /// let location = Location {
     ///     knot: "chapter_one".to_string(),
     ///     stitch: None,
+    ///     label: None,
     /// };
     ///
     /// story.move_to(&location).unwrap();
@@ -264,17 +583,75 @@ impl Story {
     /// # Errors
     /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the given
     ///     location does not exist in the story.
+    /// *   [`InvalidNumberOfArguments`][crate::error::InklingError::InvalidNumberOfArguments]:
+    ///     if the knot or stitch at the location has parameters. Use
+    ///     [`move_to_with_args`][crate::story::Story::move_to_with_args()] to move to it.
     pub fn move_to(&mut self, location: &Location) -> Result<(), InklingError> {
-        let to_address = Address::from_location(location, &self.knots).map_err(|_| {
+        self.move_to_with_args(location, &[])
+    }
+
+    /// Move the story to a knot or stitch which takes parameters.
+    ///
+    /// The arguments are bound to the parameters of the knot or stitch, in order, as
+    /// temporary variables. They are available until the story moves to another location.
+    /// Otherwise this works like [`move_to`][crate::story::Story::move_to()].
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location, Variable};
+    /// let content = "\
+    /// === meet(character, mood) ===
+    /// You meet {character}, who is in a {mood} mood.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story
+    ///     .move_to_with_args(&"meet".into(), &["Anna".into(), "good".into()])
+    ///     .unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "You meet Anna, who is in a good mood.\n");
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`InvalidAddress`][crate::error::InklingError::InvalidAddress]: if the given
+    ///     location does not exist in the story.
+    /// *   [`InvalidNumberOfArguments`][crate::error::InklingError::InvalidNumberOfArguments]:
+    ///     if the number of arguments does not match the parameters of the knot or stitch.
+    ///     Labels are moved to without arguments.
+    pub fn move_to_with_args(
+        &mut self,
+        location: &Location,
+        arguments: &[Variable],
+    ) -> Result<(), InklingError> {
+        let to_address = Address::from_location(location, &self.story.knots).map_err(|_| {
             InklingError::InvalidAddress {
                 location: location.clone(),
             }
         })?;
 
+        let parameters = get_parameter_bindings(&to_address, arguments, &self.story.knots)?;
+
+        // A follow which paused after a line is not resumed after moving away from it
+        if self.state.last_choices.is_none() && self.state.selected_choice.is_none() {
+            take_stack(&self.state.current_address, &mut self.state.data)?;
+        }
+
         self.update_last_stack(&to_address);
 
-        self.last_choices = None;
-        self.selected_choice = None;
+        self.state.last_choices = None;
+        self.state.selected_choice = None;
+        self.state.pending_lines.clear();
+        self.state.is_done = false;
+        self.state.data.temp_variables = parameters;
+        self.state.data.tunnel_stack.clear();
+
+        self.state.record_replay_step(ReplayStep::MoveTo {
+            location: location.clone(),
+            arguments: arguments.to_vec(),
+        });
 
         Ok(())
     }
@@ -301,6 +678,7 @@ impl Story {
     /// let location = Location {
     ///     knot: "gesichts_apartment".to_string(),
     ///     stitch: Some("dream".to_string()),
+    ///     label: None,
     /// };
     ///
     /// story.move_to(&location).unwrap();
@@ -308,24 +686,17 @@ impl Story {
     /// assert_eq!(story.get_current_location(), location);
     /// ```
     pub fn get_current_location(&self) -> Location {
-        let (knot, stitch) = match self.current_address.get_knot_and_stitch() {
-            Ok(result) => result,
-            Err(_) => {
-                eprintln!("`inkling` encountered an error: the current location in the story is a variable, which should not happen");
-                (ROOT_KNOT_NAME, ROOT_KNOT_NAME)
-            }
-        };
-
-        if stitch == ROOT_KNOT_NAME {
-            Location::from(knot)
-        } else {
-            Location::with_stitch(knot, stitch)
-        }
+        get_location(&self.state.current_address)
     }
 
     /// Get the tags associated with the given knot.
     ///
-    /// Returns `None` if no knot with the given name exists in the story.
+    /// Variables, alternatives and conditions in the tags are processed with the current
+    /// state of the story. Processing the tags does not change that state: alternatives
+    /// in them are not advanced.
+    ///
+    /// Returns `None` if no knot with the given name exists in the story, or if its
+    /// tags could not be processed.
     ///
     /// # Examples
     /// ```
@@ -345,12 +716,15 @@ impl Story {
     /// assert_eq!(&tags[1], "sound: crowds");
     /// ```
     pub fn get_knot_tags(&self, knot_name: &str) -> Option<Vec<String>> {
-        self.knots.get(knot_name).map(|knot| knot.tags.clone())
+        let tags = &self.story.knots.get(knot_name)?.tags;
+        let mut data = self.state.data.clone();
+
+        process_tags(tags, &mut data).ok()
     }
 
-    /// Get the number of times a knot or stitch has been visited so far.
+    /// Get the number of times a knot, stitch or label has been visited so far.
     ///
-    /// Returns `None` if the given knot and stitch does not exist in the `Story`.
+    /// Returns `None` if the given location does not exist in the `Story`.
     ///
     /// # Examples
     /// ```
@@ -367,6 +741,7 @@ impl Story {
     /// let location = Location {
     ///     knot: "depths".to_string(),
     ///     stitch: None,
+    ///     label: None,
     /// };
     ///
     /// # story.move_to(&location).unwrap();
@@ -376,9 +751,79 @@ impl Story {
     /// assert_eq!(num_visited, 2);
     /// ```
     pub fn get_num_visited(&self, location: &Location) -> Option<u32> {
-        let address = Address::from_location(&location, &self.knots).ok()?;
+        let address = Address::from_location(&location, &self.story.knots).ok()?;
 
-        get_num_visited(&address, &self.data).ok()
+        get_num_visited(&address, &self.state.data).ok()
+    }
+
+    /// Get the number of choices that have been made in the story so far.
+    ///
+    /// This is the turn index which `TURNS()` evaluates to in the script. Fallback choices,
+    /// which are followed automatically, count as turns.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// *   Knock on the door.
+    /// *   Walk away.
+    /// - Nobody answered.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert_eq!(story.get_turn_index(), 0);
+    ///
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert_eq!(story.get_turn_index(), 1);
+    /// ```
+    pub fn get_turn_index(&self) -> u32 {
+        self.state.data.turn_index
+    }
+
+    /// Get the number of turns since a knot or stitch was last visited.
+    ///
+    /// This is the value which `TURNS_SINCE(-> location)` evaluates to in the script:
+    /// 0 if the location was visited in the current turn and -1 if it has not been visited.
+    ///
+    /// Returns `None` if the given location does not exist in the `Story` or is a label,
+    /// since visits to labels are not tracked by turn.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Location};
+    /// let content = "\
+    /// -> tavern
+    ///
+    /// === tavern ===
+    /// *   Order a drink.
+    ///     -> street
+    ///
+    /// === street ===
+    /// *   Walk home.
+    ///     -> END
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// let tavern = Location::from("tavern");
+    /// assert_eq!(story.get_turns_since(&tavern), Some(-1));
+    ///
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert_eq!(story.get_turns_since(&tavern), Some(0));
+    ///
+    /// story.make_choice(0).unwrap();
+    /// story.resume(&mut line_buffer).unwrap();
+    /// assert_eq!(story.get_turns_since(&tavern), Some(1));
+    /// ```
+    pub fn get_turns_since(&self, location: &Location) -> Option<i32> {
+        let address = Address::from_location(&location, &self.story.knots).ok()?;
+
+        get_turns_since(&address, &self.state.data).ok()
     }
 
     /// Retrieve the global tags associated with the story.
@@ -398,7 +843,7 @@ impl Story {
     /// assert_eq!(&tags[1], "author: Petter Johansson");
     /// ```
     pub fn get_story_tags(&self) -> Vec<String> {
-        self.tags.clone()
+        self.story.tags.clone()
     }
 
     /// Retrieve the value of a global variable.
@@ -418,7 +863,8 @@ impl Story {
     /// assert_eq!(story.get_variable("books_in_library").unwrap(), Variable::Int(3));
     /// ```
     pub fn get_variable(&self, name: &str) -> Option<Variable> {
-        self.data
+        self.state
+            .data
             .variables
             .get(name)
             .map(|variable_info| variable_info.variable.clone())
@@ -478,75 +924,505 @@ impl Story {
     /// assert!(story.set_variable("hunted_by_police", true).is_ok());
     /// ```
     ///
-    /// ## Assignment to constant variable is invalid
-    /// ```
-    /// # use inkling::{read_story_from_string, Variable};
-    /// # let content = "\
-    /// # VAR hunted_by_police = false
-    /// # VAR num_passengers = 0
-    /// # CONST price_of_ticket = 7.50
-    /// # ";
-    /// # let mut story = read_story_from_string(content).unwrap();
-    /// assert!(story.set_variable("price_of_ticket", 1.5).is_err());
-    /// ```
+    /// ## Assignment to constant variable is invalid
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// # let content = "\
+    /// # VAR hunted_by_police = false
+    /// # VAR num_passengers = 0
+    /// # CONST price_of_ticket = 7.50
+    /// # ";
+    /// # let mut story = read_story_from_string(content).unwrap();
+    /// assert!(story.set_variable("price_of_ticket", 1.5).is_err());
+    /// ```
+    ///
+    /// ## Setting the items of a list
+    /// List items are given by the name of their list and their own name. Their values
+    /// are set from the list definitions in the story.
+    /// ```
+    /// # use inkling::{read_story_from_string, InkList, ListItem, Variable};
+    /// let content = "\
+    /// LIST luggage = (suitcase), umbrella, hat
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// let list: InkList = vec![ListItem::new("luggage", "umbrella"), ListItem::new("luggage", "hat")]
+    ///     .into_iter()
+    ///     .collect();
+    ///
+    /// assert!(story.set_variable("luggage", list).is_ok());
+    ///
+    /// let invalid: InkList = vec![ListItem::new("luggage", "train")].into_iter().collect();
+    /// assert!(story.set_variable("luggage", invalid).is_err());
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`AssignedToConst`][crate::error::InklingError::AssignedToConst]: if the name
+    ///     refers to a constant variable.
+    /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
+    ///     does not refer to a global variable that exists in the story.
+    /// *   [`InvalidListItem`][crate::error::InklingError::InvalidListItem]: if a list
+    ///     item is not defined by a list in the story.
+    /// *   [`VariableError`][crate::error::InklingError::VariableError]: if
+    ///     the existing variable has a different type to the input variable.
+    pub fn set_variable<T: Into<Variable>>(
+        &mut self,
+        name: &str,
+        value: T,
+    ) -> Result<(), InklingError> {
+        let mut value = value.into();
+
+        if let Variable::List(list) = &mut value {
+            list.normalize(&self.state.data.lists)
+                .map_err(|name| InklingError::InvalidListItem { name })?;
+        }
+
+        self.state
+            .data
+            .variables
+            .get_mut(name)
+            .ok_or(InklingError::InvalidVariable {
+                name: name.to_string(),
+            })
+            .and_then(|variable_info| variable_info.assign(value.clone(), name))?;
+
+        self.state.record_replay_step(ReplayStep::SetVariable {
+            name: name.to_string(),
+            value,
+        });
+
+        Ok(())
+    }
+
+    /// Bind a function to an external function declared in the story.
+    ///
+    /// External functions are declared in the prelude of the story with their name and
+    /// parameters: `EXTERNAL play_sound(name, volume)`. They can then be called from
+    /// expressions, conditions and logic lines in the story, just like any other function.
+    /// All external functions have to be bound before the story is resumed.
+    ///
+    /// The function is called with the evaluated arguments and returns the value of the call.
+    /// Functions which are only called for their side effects can return any value, since
+    /// it will be discarded. Errors returned by the function stop the story and are returned
+    /// to the caller of [`resume`][crate::story::Story::resume()].
+    ///
+    /// The number of arguments that the function takes has to match the number of parameters
+    /// in the declaration. Binding a function to a name that has already been bound
+    /// replaces the previous function.
+    ///
+    /// Bound functions are not serialized along with the story, so they have to be bound
+    /// again after a story is deserialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::{read_story_from_string, Variable};
+    /// let content = "\
+    /// EXTERNAL double(x)
+    ///
+    /// VAR ravens = 3
+    ///
+    /// There were {double(ravens)} ravens on the wall.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    ///
+    /// story
+    ///     .bind_external_function("double", 1, |arguments: &[Variable]| {
+    ///         arguments[0].multiply(&Variable::Int(2))
+    ///     })
+    ///     .unwrap();
+    ///
+    /// let mut line_buffer = Vec::new();
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// assert_eq!(&line_buffer[0].text, "There were 6 ravens on the wall.\n");
+    /// ```
+    ///
+    /// # Errors
+    /// *   [`UnknownExternalFunction`][crate::error::InklingError::UnknownExternalFunction]:
+    ///     if no external function with the name was declared in the story.
+    /// *   [`InvalidExternalFunctionArity`][crate::error::InklingError::InvalidExternalFunctionArity]:
+    ///     if the number of arguments does not match the number of parameters
+    ///     in the declaration.
+    pub fn bind_external_function<F, E>(
+        &mut self,
+        name: &str,
+        num_arguments: usize,
+        function: F,
+    ) -> Result<(), InklingError>
+    where
+        F: Fn(&[Variable]) -> Result<Variable, E> + Send + Sync + 'static,
+        E: fmt::Display,
+    {
+        let info = self.state.data.external_functions.get_mut(name).ok_or(
+            InklingError::UnknownExternalFunction {
+                name: name.to_string(),
+            },
+        )?;
+
+        if info.parameters.len() != num_arguments {
+            return Err(InklingError::InvalidExternalFunctionArity {
+                name: name.to_string(),
+                num_declared: info.parameters.len(),
+                num_bound: num_arguments,
+            });
+        }
+
+        let function =
+            move |arguments: &[Variable]| function(arguments).map_err(|err| err.to_string());
+
+        info.function.replace(ExternalFunction(Arc::new(function)));
+
+        Ok(())
+    }
+
+    /// Start recording the lines and choices that are shown in a transcript.
+    ///
+    /// If `max_entries` is given, only that number of the latest entries is kept. If the
+    /// transcript is already being recorded its entries are kept and the maximum is updated.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::read_story_from_string;
+    /// let content = "\
+    /// The train came to a halt.
+    /// The doors slid open.
+    /// ";
+    ///
+    /// let mut story = read_story_from_string(content).unwrap();
+    /// let mut line_buffer = Vec::new();
+    ///
+    /// story.enable_transcript(Some(1));
+    /// story.resume(&mut line_buffer).unwrap();
+    ///
+    /// let transcript = story.get_transcript().unwrap();
+    ///
+    /// assert_eq!(transcript.len(), 1);
+    /// assert_eq!(&transcript.iter().next().unwrap().text, "The doors slid open.\n");
+    /// ```
+    pub fn enable_transcript(&mut self, max_entries: Option<usize>) {
+        match self.state.transcript.as_mut() {
+            Some(transcript) => transcript.set_max_entries(max_entries),
+            None => self.state.transcript = Some(Transcript::new(max_entries)),
+        }
+    }
+
+    /// Stop recording the transcript and discard its entries.
+    pub fn disable_transcript(&mut self) {
+        self.state.transcript = None;
+    }
+
+    /// Get the transcript of the lines and choices that have been shown, if it is recorded.
+    ///
+    /// See [`enable_transcript`][crate::story::Story::enable_transcript()] for how to
+    /// start recording it.
+    pub fn get_transcript(&self) -> Option<&Transcript> {
+        self.state.transcript.as_ref()
+    }
+
+    /// Start recording the calls made to the story in a replay log.
+    ///
+    /// The log records the seed of the random number generator along with every call
+    /// that drives the story: resuming and continuing it, making choices, moving to
+    /// locations, setting variables and rewinding. Enable it before the story is resumed
+    /// for the first time, since replays start from the beginning of the story.
+    ///
+    /// If the log is already being recorded this does nothing.
+    pub fn enable_replay_log(&mut self) {
+        if self.state.replay_log.is_none() {
+            let seed = self.state.data.rng.get_seed();
+            self.state.replay_log = Some(ReplayLog::new(seed));
+        }
+    }
+
+    /// Get the replay log of the calls made to the story, if it is recorded.
+    ///
+    /// See [`enable_replay_log`][crate::story::Story::enable_replay_log()] for how to
+    /// start recording it.
+    pub fn get_replay_log(&self) -> Option<&ReplayLog> {
+        self.state.replay_log.as_ref()
+    }
+
+    /// Restart the story from its beginning and replay the calls recorded in a log.
+    ///
+    /// The random number generator is seeded with the seed of the log before the steps
+    /// are replayed in order. Bound external functions are kept and called as the story
+    /// is followed. If a transcript or replay log is recorded for this story, they are
+    /// restarted and record the replayed playthrough.
+    ///
+    /// After a successful replay the story is in the state that it was in when the last
+    /// step was recorded. Lines which are followed during the replay are not returned.
+    ///
+    /// # Errors
+    /// Returns a [`ReplayError`][crate::error::ReplayError] with the index of the first
+    /// step which could not be replayed, for example if a recorded choice is no longer
+    /// presented, or the text of a choice or of the returned lines has changed. The story
+    /// is left in the state it had reached before that step.
+    pub fn replay(&mut self, log: &ReplayLog) -> Result<(), ReplayError> {
+        let mut state = self.story.new_state();
+
+        state.data.rng = StoryRng::from_seed(log.get_seed());
+        state.data.external_functions = mem::take(&mut self.state.data.external_functions);
+        state.max_history = self.state.max_history;
+
+        state.transcript = self
+            .state
+            .transcript
+            .as_ref()
+            .map(|transcript| Transcript::new(transcript.get_max_entries()));
+
+        state.replay_log = self
+            .state
+            .replay_log
+            .as_ref()
+            .map(|_| ReplayLog::new(log.get_seed()));
+
+        self.state = state;
+
+        for (step, replay_step) in log.get_steps().iter().enumerate() {
+            self.replay_step(replay_step)
+                .map_err(|kind| ReplayError { step, kind })?;
+        }
+
+        Ok(())
+    }
+
+    /// Replay a single step of a replay log.
+    fn replay_step(&mut self, step: &ReplayStep) -> Result<(), ReplayErrorKind> {
+        match step {
+            ReplayStep::ContinueLines { count, lines_hash } => {
+                let mut lines = Vec::new();
+
+                for _ in 0..*count {
+                    if let Some(line) = self.continue_line().map_err(ReplayErrorKind::Error)? {
+                        lines.push(line);
+                    }
+                }
+
+                check_lines_hash(&lines, *lines_hash)
+            }
+            ReplayStep::Choice { index, text } => {
+                let choices = self
+                    .get_current_choices()
+                    .ok_or(ReplayErrorKind::NotAtChoice)?;
+
+                let choice = choices.get(*index).ok_or(ReplayErrorKind::InvalidChoice {
+                    index: *index,
+                    num_choices: choices.len(),
+                })?;
+
+                if &choice.text != text {
+                    return Err(ReplayErrorKind::ChoiceTextDiffers {
+                        expected: text.clone(),
+                        found: choice.text.clone(),
+                    });
+                }
+
+                self.make_choice(*index).map_err(ReplayErrorKind::Error)
+            }
+            ReplayStep::MoveTo {
+                location,
+                arguments,
+            } => self
+                .move_to_with_args(location, arguments)
+                .map_err(ReplayErrorKind::Error),
+            ReplayStep::Resume { lines_hash } => {
+                let mut line_buffer = Vec::new();
+
+                self.resume(&mut line_buffer)
+                    .map_err(ReplayErrorKind::Error)?;
+
+                check_lines_hash(&line_buffer, *lines_hash)
+            }
+            ReplayStep::Rewind { steps } => self.rewind(*steps).map_err(ReplayErrorKind::Error),
+            ReplayStep::SetVariable { name, value } => self
+                .set_variable(name, value.clone())
+                .map_err(ReplayErrorKind::Error),
+        }
+    }
+
+    /// Assert that all external functions in the story have been bound to functions.
+    fn check_external_functions_are_bound(&self) -> Result<(), InklingError> {
+        let mut names = self
+            .state
+            .data
+            .external_functions
+            .iter()
+            .filter(|(name, info)| {
+                info.function.is_none() && !self.state.data.functions.contains_key(name.as_str())
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        if names.is_empty() {
+            Ok(())
+        } else {
+            names.sort();
+            Err(InklingError::UnboundExternalFunctions { names })
+        }
+    }
+
+    /// Return whether the story is neither waiting at a set of choices nor at its end.
+    fn can_follow(&self) -> bool {
+        !self.state.is_waiting_for_choice() && !self.state.is_done
+    }
+
+    /// Follow the story until it pauses after a line and add the line to the pending lines.
+    ///
+    /// Returns `false` if the follow paused before logic without reaching a line.
+    fn follow_line(&mut self) -> Result<bool, InklingError> {
+        let selection = self.state.selected_choice.take();
+
+        let mut internal_buffer = Vec::new();
+
+        self.state.data.pause_after_lines = true;
+        let result = self.follow_story_wrapper(selection, &mut internal_buffer);
+        self.state.data.pause_after_lines = false;
+
+        let is_paused_before_logic = result?.is_none() && internal_buffer.is_empty();
+
+        self.state.pending_lines.extend(
+            internal_buffer
+                .into_iter()
+                .filter(|line| !is_empty_line(line)),
+        );
+
+        Ok(!is_paused_before_logic)
+    }
+
+    /// Follow the story to the line after the next pending line.
     ///
-    /// # Errors
-    /// *   [`AssignedToConst`][crate::error::InklingError::AssignedToConst]: if the name
-    ///     refers to a constant variable.
-    /// *   [`InvalidVariable`][crate::error::InklingError::InvalidVariable]: if the name
-    ///     does not refer to a global variable that exists in the story.
-    /// *   [`VariableError`][crate::error::InklingError::VariableError]: if
-    ///     the existing variable has a different type to the input variable.
-    pub fn set_variable<T: Into<Variable>>(
-        &mut self,
-        name: &str,
-        value: T,
-    ) -> Result<(), InklingError> {
-        self.data
-            .variables
-            .get_mut(name)
-            .ok_or(InklingError::InvalidVariable {
-                name: name.to_string(),
-            })
-            .and_then(|variable_info| variable_info.assign(value.into(), name))
+    /// The follow pauses before content which assigns variables or calls functions, so that
+    /// it is not evaluated before the pending line is returned. In that case the line after
+    /// is not known. If the story reaches a set of choices or its end before another line,
+    /// it is followed to them.
+    fn follow_to_next_line(&mut self) -> Result<(), InklingError> {
+        while self.state.pending_lines.len() < 2 && self.can_follow() {
+            self.state.data.pause_before_logic = true;
+            let result = self.follow_line();
+            self.state.data.pause_before_logic = false;
+
+            if !result? {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the next content to follow in the story is a set of choices.
+    fn is_before_choices(&self) -> bool {
+        self.state.selected_choice.is_none()
+            && get_stitch(&self.state.current_address, &self.story.knots)
+                .and_then(|stitch| stitch.is_at_branching_point(&self.state.data))
+                .unwrap_or(false)
     }
 
-    /// Wrapper for calling `follow_story` with a prepared internal buffer.
+    /// Wrapper for calling `follow_story` which records the state of the story afterwards.
     ///
     /// Updates the stack to the last visited address and the last presented set of choices
-    /// if encountered.
+    /// or end of the story if encountered. Returns `None` if the follow paused after a line.
     fn follow_story_wrapper(
         &mut self,
-        selection: Option<usize>,
-        line_buffer: &mut LineBuffer,
-    ) -> Result<Prompt, InklingError> {
-        let mut internal_buffer = Vec::new();
-
+        selection: Option<Choice>,
+        internal_buffer: &mut LineDataBuffer,
+    ) -> Result<Option<Prompt>, InklingError> {
         let (result, last_address) = follow_story(
-            &self.current_address,
-            &mut internal_buffer,
+            &self.state.current_address,
+            internal_buffer,
             selection,
-            &mut self.knots,
-            &mut self.data,
+            &self.story.knots,
+            &mut self.state.data,
         )?;
 
+        self.update_last_stack(&last_address);
+
+        match &result {
+            Some(Prompt::Choice(choices)) => {
+                self.state.last_choices.replace(choices.clone());
+                self.state.record_checkpoint(choices.clone());
+            }
+            Some(Prompt::Done) => self.state.is_done = true,
+            None => (),
+        }
+
+        Ok(result)
+    }
+
+    /// Process followed lines into the user's buffer and record them in the transcript.
+    fn add_lines_to_buffer(
+        &mut self,
+        line_buffer: &mut LineBuffer,
+        internal_buffer: LineDataBuffer,
+    ) {
+        let origins = if self.state.transcript.is_some() {
+            internal_buffer
+                .iter()
+                .filter(|line| !is_empty_line(line))
+                .map(|line| (line.address.clone(), line.meta_data.clone()))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let num_lines = line_buffer.len();
         process_buffer(line_buffer, internal_buffer);
 
-        self.update_last_stack(&last_address);
+        for (line, (address, meta_data)) in line_buffer[num_lines..].iter().zip(origins) {
+            self.record_line(line, address.as_ref(), meta_data);
+        }
 
-        match result {
-            Prompt::Choice(choices) => {
-                self.last_choices.replace(choices.clone());
+        self.state.record_replay_lines(&line_buffer[num_lines..]);
+    }
 
-                Ok(Prompt::Choice(choices))
-            }
-            other => Ok(other),
+    /// Record a line that is returned to the user in the transcript, if it is enabled.
+    ///
+    /// Lines without an address are recorded at the current location.
+    fn record_line(&mut self, line: &Line, address: Option<&Address>, meta_data: MetaData) {
+        if let Some(transcript) = self.state.transcript.as_mut() {
+            let address = address.unwrap_or(&self.state.current_address);
+
+            transcript.record_line(
+                line,
+                get_location(address),
+                meta_data,
+                self.state.data.turn_index,
+            );
         }
     }
 
     /// Set the given address as active on the stack.
     fn update_last_stack(&mut self, address: &Address) {
-        self.current_address = address.clone();
+        self.state.current_address = address.clone();
+    }
+}
+
+/// Assert that replayed lines have the hash of the lines that were recorded.
+fn check_lines_hash(lines: &[Line], lines_hash: u64) -> Result<(), ReplayErrorKind> {
+    if hash_lines(EMPTY_LINES_HASH, lines) == lines_hash {
+        Ok(())
+    } else {
+        Err(ReplayErrorKind::LinesDiffer)
+    }
+}
+
+#[cfg(feature = "serde_support")]
+impl<'de> Deserialize<'de> for Story {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        /// Serialized form of a `Story`.
+        #[derive(Deserialize)]
+        struct SavedStory {
+            story: Arc<CompiledStory>,
+            state: StoryState,
+        }
+
+        let SavedStory { story, state } = SavedStory::deserialize(deserializer)?;
+
+        Ok(Story::from_state(story, state))
     }
 }
 
@@ -563,69 +1439,122 @@ impl Story {
 /// let story: Story = read_story_from_string(content).unwrap();
 /// ```
 pub fn read_story_from_string(string: &str) -> Result<Story, ReadError> {
-    let mut log = Logger::default();
-    let (mut knots, variables, tags) = read_story_content_from_string(string, &mut log)?;
+    compile_story_from_string(string).map(|story| Story::new(Arc::new(story)))
+}
+
+/// Read a `Story` from a root file and all files that it includes.
+///
+/// Files are included by name with `INCLUDE` lines in the prelude of the story. The loader
+/// resolves the names, starting with the root file, to their content. Knots and variables
+/// from all files are merged into a single story.
+///
+/// Errors are returned if a file could not be loaded or if a file includes itself. Errors
+/// from parsing and validating the content contain the name of the file they originated from.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_with_loader, FileStoryLoader, Story};
+/// # let directory = std::env::temp_dir().join("inkling_read_story_with_loader_example");
+/// # std::fs::create_dir_all(&directory).unwrap();
+/// # std::fs::write(directory.join("main.ink"), "INCLUDE train.ink\n-> train").unwrap();
+/// # std::fs::write(directory.join("train.ink"), "== train ==\nHe drifted off.").unwrap();
+/// let loader = FileStoryLoader::new(&directory);
+/// let story: Story = read_story_with_loader("main.ink", &loader).unwrap();
+/// ```
+pub fn read_story_with_loader(root: &str, loader: &dyn StoryLoader) -> Result<Story, ReadError> {
+    compile_story_with_loader(root, loader).map(|story| Story::new(Arc::new(story)))
+}
 
-    let data = FollowData {
-        knot_visit_counts: get_empty_knot_counts(&knots),
-        variables,
-        rng: StoryRng::default(),
+/// Get the knot, stitch and label of an address as a `Location`.
+fn get_location(address: &Address) -> Location {
+    let (knot, stitch) = match address.get_knot_and_stitch() {
+        Ok(result) => result,
+        Err(_) => {
+            eprintln!("`inkling` encountered an error: the current location in the story is a variable, which should not happen");
+            (ROOT_KNOT_NAME, ROOT_KNOT_NAME)
+        }
     };
 
-    validate_story_content(&mut knots, &data, &mut log)?;
-
-    let root_address = Address::from_root_knot(ROOT_KNOT_NAME, &knots).expect(
-        "After successfully creating all knots, the root knot name that was returned from \
-         `read_knots_from_string` is not present in the set of created knots. \
-         This simply should not be possible",
-    );
-
-    Ok(Story {
-        current_address: root_address,
-        knots,
-        history: Vec::new(),
-        data,
-        tags,
-        last_choices: None,
-        selected_choice: None,
-        log,
-    })
+    let stitch = if stitch == ROOT_KNOT_NAME {
+        None
+    } else {
+        Some(stitch)
+    };
+
+    match address {
+        Address::Validated(AddressKind::Label { label, .. }) => {
+            Location::with_label(knot, stitch, label.as_str())
+        }
+        _ => Location::new(knot, stitch),
+    }
 }
 
-/// Follow the nodes in a story with selected branch index if supplied.
+/// Follow the nodes in a story with selected choice if supplied.
 ///
 /// When an event that triggers a `Prompt` is encountered it will be returned along with
 /// the last visited address. Lines that are followed in the story will be processed
 /// and added to the input buffer.
+///
+/// If the selected choice was encountered in a thread, the story continues from that
+/// thread instead of the current address.
+///
+/// If the follow paused after a line, no `Prompt` is returned.
+///
+/// The choice count is reset, since it only refers to the next set of choices.
 fn follow_story(
     current_address: &Address,
     internal_buffer: &mut LineDataBuffer,
-    selection: Option<usize>,
-    knots: &mut KnotSet,
+    selection: Option<Choice>,
+    knots: &KnotSet,
     data: &mut FollowData,
-) -> Result<(Prompt, Address), InklingError> {
-    let (last_address, event) =
-        follow_knot(current_address, internal_buffer, selection, knots, data)?;
+) -> Result<(Option<Prompt>, Address), InklingError> {
+    data.choice_count = 0;
+
+    if selection.is_some() {
+        data.turn_index += 1;
+    }
+
+    let (address, selection) = match selection {
+        Some(Choice {
+            index,
+            thread: Some(thread),
+            ..
+        }) => {
+            take_stack(current_address, data)?;
+            set_stack(&thread.address, thread.stack, data)?;
+
+            data.temp_variables = thread.temp_variables;
+            data.tunnel_stack = thread.tunnel_stack;
+
+            (thread.address, Some(index))
+        }
+        Some(choice) => (current_address.clone(), Some(choice.index)),
+        None => (current_address.clone(), None),
+    };
+
+    let (last_address, event) = follow_knot(&address, internal_buffer, selection, knots, data)?;
 
     match event {
         EncounteredEvent::BranchingChoice(choice_set) => {
             let user_choice_lines = prepare_choices_for_user(&choice_set, data)?;
             if !user_choice_lines.is_empty() {
-                Ok((Prompt::Choice(user_choice_lines), last_address))
+                Ok((Some(Prompt::Choice(user_choice_lines)), last_address))
             } else {
                 let choice = get_fallback_choice(&choice_set, &last_address, data)?;
 
-                follow_story(
-                    &last_address,
-                    internal_buffer,
-                    Some(choice.index),
-                    knots,
-                    data,
-                )
+                follow_story(&last_address, internal_buffer, Some(choice), knots, data)
             }
         }
-        EncounteredEvent::Done => Ok((Prompt::Done, last_address)),
-        EncounteredEvent::Divert(..) => unreachable!("diverts are treated in `follow_knot`"),
+        EncounteredEvent::Done => Ok((Some(Prompt::Done), last_address)),
+        EncounteredEvent::Paused => Ok((None, last_address)),
+        EncounteredEvent::Divert(..) | EncounteredEvent::DivertWithArguments { .. } => {
+            unreachable!("diverts are treated in `follow_knot`")
+        }
+        EncounteredEvent::Return(..) => unreachable!("returns are treated in `follow_knot`"),
+        EncounteredEvent::TunnelCall { .. } | EncounteredEvent::TunnelReturn(..) => {
+            unreachable!("tunnels are treated in `follow_knot`")
+        }
+        EncounteredEvent::Thread { .. } => unreachable!("threads are treated in `follow_knot`"),
     }
 }
 
@@ -634,19 +1563,56 @@ fn follow_story(
 /// Will [follow][crate::node::Follow] through the story starting from the input address
 /// and return all encountered lines. Diverts will be automatically moved to.
 ///
+/// Tunnels are moved to like diverts, after the address to return to has been pushed
+/// to the [tunnel stack][crate::follow::FollowData::tunnel_stack]. When a tunnel
+/// returns the follow continues from the last address in the stack.
+///
+/// Threads are followed until they encounter choices or are done, after which the follow
+/// continues from the line after the thread. The choices of all threads are merged
+/// with the choices of the knot.
+///
 /// The function returns when either a branching point is encountered or there is no
 /// content left to follow. When it returns it will return with the last visited address.
+///
+/// If the address is a label the follow starts from its position inside the stitch.
+/// The returned address is then the stitch that contains the label.
 fn follow_knot(
+    address: &Address,
+    internal_buffer: &mut LineDataBuffer,
+    selection: Option<usize>,
+    knots: &KnotSet,
+    data: &mut FollowData,
+) -> Result<(Address, EncounteredEvent), InklingError> {
+    let (last_address, event) = follow_flow(address, internal_buffer, selection, knots, data)?;
+
+    match event {
+        EncounteredEvent::Divert(Address::End) => Ok((last_address, EncounteredEvent::Done)),
+        other => Ok((last_address, other)),
+    }
+}
+
+/// Follow a flow of content through diverts, tunnels and threads.
+///
+/// This implements [`follow_knot`], with the difference that a divert to `END` is
+/// returned as is. Threads use this to end the entire story instead of only themselves.
+fn follow_flow(
     address: &Address,
     internal_buffer: &mut LineDataBuffer,
     mut selection: Option<usize>,
-    knots: &mut KnotSet,
+    knots: &KnotSet,
     data: &mut FollowData,
 ) -> Result<(Address, EncounteredEvent), InklingError> {
     let mut current_address = address.clone();
+    let mut thread_choices = Vec::new();
 
     let event = loop {
-        let current_stitch = get_mut_stitch(&current_address, knots)?;
+        let current_stitch = get_stitch(&current_address, knots)?;
+
+        if let Address::Validated(AddressKind::Label { knot, stitch, .. }) = current_address.clone()
+        {
+            current_stitch.move_to_label(&current_address, data)?;
+            current_address = Address::Validated(AddressKind::Location { knot, stitch });
+        }
 
         let result = match selection.take() {
             Some(i) => current_stitch.follow_with_choice(i, internal_buffer, data),
@@ -654,17 +1620,229 @@ fn follow_knot(
         }?;
 
         match result {
-            EncounteredEvent::Divert(Address::End) => break EncounteredEvent::Done,
+            EncounteredEvent::Divert(Address::Done) => break EncounteredEvent::Done,
+            EncounteredEvent::Divert(Address::End) => break result,
             EncounteredEvent::Divert(to_address) => {
+                clear_temp_variables(&current_address, &to_address, data)?;
                 current_address = to_address;
             }
+            EncounteredEvent::DivertWithArguments { address, arguments } => {
+                data.temp_variables = get_parameter_bindings(&address, &arguments, knots)?;
+                current_address = address;
+            }
+            EncounteredEvent::Return(..) => {
+                return Err(InklingError::ReturnOutsideFunction {
+                    location: Location::from(current_address.to_string().as_ref()),
+                });
+            }
+            EncounteredEvent::TunnelCall { targets, divert } => {
+                let stack = take_stack(&current_address, data)?;
+
+                let frame = match divert {
+                    Some(address) => TunnelFrame {
+                        address,
+                        stack: None,
+                        caller_temp_variables: HashMap::new(),
+                    },
+                    None => TunnelFrame {
+                        address: current_address.clone(),
+                        stack: Some(stack),
+                        caller_temp_variables: mem::take(&mut data.temp_variables),
+                    },
+                };
+
+                data.tunnel_stack.push(frame);
+
+                let (first_target, chained_targets) = targets
+                    .split_first()
+                    .expect("tunnel calls are always parsed with at least one target");
+
+                // Chained tunnels are moved to in order as every previous tunnel returns,
+                // with their arguments bound when they are called
+                for (address, arguments) in chained_targets.iter().rev() {
+                    data.tunnel_stack.push(TunnelFrame {
+                        address: address.clone(),
+                        stack: None,
+                        caller_temp_variables: get_parameter_bindings(address, arguments, knots)?,
+                    });
+                }
+
+                let (address, arguments) = first_target;
+
+                data.temp_variables = get_parameter_bindings(address, arguments, knots)?;
+                current_address = address.clone();
+            }
+            EncounteredEvent::TunnelReturn(divert) => {
+                let frame = data.tunnel_stack.pop().ok_or_else(|| {
+                    InklingError::TunnelReturnOutsideTunnel {
+                        location: Location::from(current_address.to_string().as_ref()),
+                    }
+                })?;
+
+                // A divert after the return replaces the address that would be returned to
+                let frame = match divert {
+                    Some(address) => TunnelFrame {
+                        address,
+                        stack: None,
+                        caller_temp_variables: HashMap::new(),
+                    },
+                    None => frame,
+                };
+
+                match frame.address {
+                    Address::Done => break EncounteredEvent::Done,
+                    Address::End => break EncounteredEvent::Divert(Address::End),
+                    _ => (),
+                }
+
+                if let Some(stack) = frame.stack {
+                    set_stack(&frame.address, stack, data)?;
+                }
+
+                data.temp_variables = frame.caller_temp_variables;
+                current_address = frame.address;
+            }
+            EncounteredEvent::Thread { address, arguments } => {
+                let stack = take_stack(&current_address, data)?;
+
+                match follow_thread(&address, &arguments, internal_buffer, knots, data)? {
+                    Some(choices) => thread_choices.extend(choices),
+                    None => break EncounteredEvent::Divert(Address::End),
+                }
+
+                set_stack(&current_address, stack, data)?;
+
+                // The choices of the thread are only kept until this follow returns,
+                // so it cannot pause before the choices of the flow are reached
+                data.pause_after_lines = false;
+                data.pause_before_logic = false;
+            }
             _ => break result,
         }
     };
 
+    let event = match event {
+        EncounteredEvent::BranchingChoice(choices) => {
+            thread_choices.extend(choices);
+            EncounteredEvent::BranchingChoice(thread_choices)
+        }
+        EncounteredEvent::Done if !thread_choices.is_empty() => {
+            EncounteredEvent::BranchingChoice(thread_choices)
+        }
+        other => other,
+    };
+
     Ok((current_address, event))
 }
 
+/// Follow a thread from an address and return the choices that it encountered.
+///
+/// The thread is followed with its own temporary variables and tunnels, which are saved
+/// along with its choices. The arguments are bound to the parameters of the address
+/// as its first temporary variables. Returns `None` if the thread ended the story.
+///
+/// Threads are always followed to their choices without pausing.
+fn follow_thread(
+    address: &Address,
+    arguments: &[Variable],
+    internal_buffer: &mut LineDataBuffer,
+    knots: &KnotSet,
+    data: &mut FollowData,
+) -> Result<Option<Vec<ChoiceInfo>>, InklingError> {
+    let parameters = get_parameter_bindings(address, arguments, knots)?;
+
+    let temp_variables = mem::replace(&mut data.temp_variables, parameters);
+    let tunnel_stack = mem::take(&mut data.tunnel_stack);
+    let pause_after_lines = mem::replace(&mut data.pause_after_lines, false);
+    let pause_before_logic = mem::replace(&mut data.pause_before_logic, false);
+
+    let result = follow_flow(address, internal_buffer, None, knots, data);
+
+    data.pause_after_lines = pause_after_lines;
+    data.pause_before_logic = pause_before_logic;
+
+    let thread_temp_variables = mem::replace(&mut data.temp_variables, temp_variables);
+    let thread_tunnel_stack = mem::replace(&mut data.tunnel_stack, tunnel_stack);
+
+    match result? {
+        (last_address, EncounteredEvent::BranchingChoice(choices)) => {
+            let thread = ThreadState {
+                stack: take_stack(&last_address, data)?,
+                address: last_address,
+                temp_variables: thread_temp_variables,
+                tunnel_stack: thread_tunnel_stack,
+            };
+
+            // Choices from nested threads already belong to their own thread
+            let choices = choices
+                .into_iter()
+                .map(|mut choice| {
+                    choice.thread.get_or_insert_with(|| thread.clone());
+                    choice
+                })
+                .collect();
+
+            Ok(Some(choices))
+        }
+        (_, EncounteredEvent::Divert(Address::End)) => Ok(None),
+        _ => Ok(Some(Vec::new())),
+    }
+}
+
+/// Get the temporary variables which bind arguments to the parameters of an address.
+///
+/// Labels are moved to without arguments, since they are inside of a stitch which has
+/// already been entered.
+fn get_parameter_bindings(
+    address: &Address,
+    arguments: &[Variable],
+    knots: &KnotSet,
+) -> Result<HashMap<String, Variable>, InklingError> {
+    let parameters = match address {
+        Address::Validated(AddressKind::Location { .. }) => {
+            get_stitch(address, knots)?.parameters.as_slice()
+        }
+        _ => &[],
+    };
+
+    if parameters.len() != arguments.len() {
+        return Err(InklingError::InvalidNumberOfArguments {
+            location: Location::from(address.to_string().as_ref()),
+            num_parameters: parameters.len(),
+            num_arguments: arguments.len(),
+        });
+    }
+
+    Ok(parameters
+        .iter()
+        .cloned()
+        .zip(arguments.iter().cloned())
+        .collect())
+}
+
+/// Clear the temporary variables before a divert to an address.
+///
+/// If the address is in the current knot and stitch, for example a label which is looped
+/// back to, the story stays in the scope of the variables and they are kept.
+fn clear_temp_variables(
+    current_address: &Address,
+    to_address: &Address,
+    data: &mut FollowData,
+) -> Result<(), InklingError> {
+    let is_in_current_stitch = match to_address {
+        Address::Validated(..) => {
+            to_address.get_knot_and_stitch()? == current_address.get_knot_and_stitch()?
+        }
+        _ => false,
+    };
+
+    if !is_in_current_stitch {
+        data.temp_variables.clear();
+    }
+
+    Ok(())
+}
+
 /// Return the first available fallback choice from the given set of choices.
 ///
 /// Choices are filtered as usual by conditions and visits.
@@ -686,8 +1864,8 @@ mod tests {
 
     use crate::{
         follow::FollowDataBuilder,
-        knot::{get_num_visited, increment_num_visited},
-        story::parse::tests::read_knots_from_string,
+        knot::{get_empty_knot_counts, get_num_visited, increment_num_visited},
+        story::{parse::tests::read_knots_from_string, validate::validate_story_content},
     };
 
     fn mock_last_choices(choices: &[(&str, usize)]) -> Vec<Choice> {
@@ -697,6 +1875,8 @@ mod tests {
                 text: text.to_string(),
                 tags: Vec::new(),
                 index: *index,
+                thread: None,
+                meta_data: ().into(),
             })
             .collect()
     }
@@ -721,13 +1901,19 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        follow_knot(&root_address, &mut buffer, None, &knots, &mut data).unwrap();
 
         assert_eq!(
             &buffer.last().unwrap().text,
@@ -749,14 +1935,19 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        let (_, event) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let (_, event) = follow_knot(&root_address, &mut buffer, None, &knots, &mut data).unwrap();
 
         match event {
             EncounteredEvent::Done => (),
@@ -776,14 +1967,19 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let root_address = Address::from_root_knot("select_destination", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        let (_, event) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        let (_, event) = follow_knot(&root_address, &mut buffer, None, &knots, &mut data).unwrap();
 
         match event {
             EncounteredEvent::BranchingChoice(ref choices) => {
@@ -810,14 +2006,20 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let root_address = Address::from_root_knot("back_in_london", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
         let (last_address, _) =
-            follow_knot(&root_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+            follow_knot(&root_address, &mut buffer, None, &knots, &mut data).unwrap();
 
         assert_eq!(
             last_address,
@@ -838,19 +2040,25 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let done_address = Address::from_root_knot("knot_done", &knots).unwrap();
         let end_address = Address::from_root_knot("knot_end", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        match follow_knot(&done_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
+        match follow_knot(&done_address, &mut buffer, None, &knots, &mut data).unwrap() {
             (_, EncounteredEvent::Done) => (),
             _ => panic!("story should be done when diverting to DONE knot"),
         }
 
-        match follow_knot(&end_address, &mut buffer, None, &mut knots, &mut data).unwrap() {
+        match follow_knot(&end_address, &mut buffer, None, &knots, &mut data).unwrap() {
             (_, EncounteredEvent::Done) => (),
             _ => panic!("story should be done when diverting to END knot"),
         }
@@ -869,14 +2077,20 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
         let divert_address = Address::from_root_knot("tripoli", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        follow_knot(&current_address, &mut buffer, None, &mut knots, &mut data).unwrap();
+        follow_knot(&current_address, &mut buffer, None, &knots, &mut data).unwrap();
 
         assert_eq!(get_num_visited(&divert_address, &data).unwrap(), 1);
     }
@@ -892,20 +2106,19 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
 
         let mut buffer = Vec::new();
 
-        follow_knot(
-            &current_address,
-            &mut buffer,
-            Some(1),
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        follow_knot(&current_address, &mut buffer, Some(1), &knots, &mut data).unwrap();
 
         assert_eq!(get_num_visited(&current_address, &data).unwrap(), 0);
     }
@@ -928,20 +2141,20 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
 
         let mut line_buffer = Vec::new();
 
-        let (_, last_address) = follow_story(
-            &current_address,
-            &mut line_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        let (_, last_address) =
+            follow_story(&current_address, &mut line_buffer, None, &knots, &mut data).unwrap();
 
         assert_eq!(
             last_address,
@@ -964,20 +2177,20 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("addis_ababa", &knots).unwrap();
 
         let mut line_buffer = Vec::new();
 
-        let (_, last_address) = follow_story(
-            &current_address,
-            &mut line_buffer,
-            None,
-            &mut knots,
-            &mut data,
-        )
-        .unwrap();
+        let (_, last_address) =
+            follow_story(&current_address, &mut line_buffer, None, &knots, &mut data).unwrap();
 
         assert_eq!(last_address, Address::from_parts_unchecked("tripoli", None));
     }
@@ -990,22 +2203,29 @@ We hurried home to Savile Row as fast as we could.
     fn make_choice_sets_the_choice_index_from_the_last_choices_set() {
         let mut story = read_story_from_string("Content.").unwrap();
         story
+            .state
             .last_choices
             .replace(mock_last_choices(&[("", 2), ("", 4)]));
 
         story.make_choice(1).unwrap();
 
-        assert_eq!(story.selected_choice, Some(4));
+        assert_eq!(
+            story.state.selected_choice.map(|choice| choice.index),
+            Some(4)
+        );
     }
 
     #[test]
     fn make_choice_resets_last_choices_vector() {
         let mut story = read_story_from_string("Content.").unwrap();
-        story.last_choices.replace(mock_last_choices(&[("", 0)]));
+        story
+            .state
+            .last_choices
+            .replace(mock_last_choices(&[("", 0)]));
 
         story.make_choice(0).unwrap();
 
-        assert!(story.last_choices.is_none());
+        assert!(story.state.last_choices.is_none());
     }
 
     #[test]
@@ -1026,7 +2246,7 @@ We hurried home to Savile Row as fast as we could.
         let mut story = read_story_from_string("Content.").unwrap();
 
         let last_choices = mock_last_choices(&[("Choice 1", 0), ("Choice 2", 2)]);
-        story.last_choices.replace(last_choices.clone());
+        story.state.last_choices.replace(last_choices.clone());
 
         match story.make_choice(2) {
             Err(InklingError::InvalidChoice {
@@ -1079,13 +2299,15 @@ We hurried home to Savile Row as fast as we could.
         let mut story = read_story_from_string(content).unwrap();
         story.move_to(&"addis_ababa".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        let mut internal_buffer = Vec::new();
 
-        story.follow_story_wrapper(None, &mut line_buffer).unwrap();
+        story
+            .follow_story_wrapper(None, &mut internal_buffer)
+            .unwrap();
 
         let address = Address::from_parts_unchecked("tripoli", Some("cinema"));
 
-        assert_eq!(story.current_address, address);
+        assert_eq!(story.state.current_address, address);
     }
 
     #[test]
@@ -1129,7 +2351,13 @@ We hurried home to Savile Row as fast as we could.
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("first", &knots).unwrap();
 
@@ -1140,7 +2368,7 @@ We hurried home to Savile Row as fast as we could.
             &current_address,
             &mut internal_buffer,
             None,
-            &mut knots,
+            &knots,
             &mut data,
         )
         .unwrap();
@@ -1160,7 +2388,13 @@ We decided to go to the <>
         let mut knots = read_knots_from_string(content).unwrap();
 
         let mut data = mock_follow_data(&knots);
-        validate_story_content(&mut knots, &data, &mut Logger::default()).unwrap();
+        validate_story_content(
+            &mut knots,
+            &mut HashMap::new(),
+            &mut data,
+            &mut Logger::default(),
+        )
+        .unwrap();
 
         let current_address = Address::from_root_knot("tripoli", &knots).unwrap();
 
@@ -1171,7 +2405,7 @@ We decided to go to the <>
             &current_address,
             &mut internal_buffer,
             None,
-            &mut knots,
+            &knots,
             &mut data,
         )
         .unwrap();
@@ -1215,12 +2449,12 @@ We decided to go to the <>
         let mut story = read_story_from_string(content).unwrap();
         let mut line_buffer = Vec::new();
 
-        assert!(story.last_choices.is_none());
+        assert!(story.state.last_choices.is_none());
 
         story.move_to(&"knot".into()).unwrap();
         story.resume(&mut line_buffer).unwrap();
 
-        let last_choices = story.last_choices.as_ref().unwrap();
+        let last_choices = story.state.last_choices.as_ref().unwrap();
 
         assert_eq!(last_choices.len(), 2);
         assert_eq!(&last_choices[0].text, "Choice 1");
@@ -1244,13 +2478,20 @@ We arrived into Almaty at 9.45pm exactly.
         let mut story = read_story_from_string(content).unwrap();
         story.move_to(&"back_in_almaty".into()).unwrap();
 
-        let mut line_buffer = Vec::new();
+        let mut internal_buffer = Vec::new();
+
+        story
+            .follow_story_wrapper(None, &mut internal_buffer)
+            .unwrap();
 
-        story.follow_story_wrapper(None, &mut line_buffer).unwrap();
+        let choice = story.state.last_choices.as_ref().unwrap()[1].clone();
         story
-            .follow_story_wrapper(Some(1), &mut line_buffer)
+            .follow_story_wrapper(Some(choice), &mut internal_buffer)
             .unwrap();
 
+        let mut line_buffer = Vec::new();
+        process_buffer(&mut line_buffer, internal_buffer);
+
         assert_eq!(
             &line_buffer[0].text,
             "We arrived into Almaty at 9.45pm exactly.\n"
@@ -1272,9 +2513,9 @@ Hello, World!
 
         story.resume(&mut line_buffer).unwrap();
 
-        let address = Address::from_root_knot("$ROOT$", &story.knots).unwrap();
+        let address = Address::from_root_knot("$ROOT$", &story.story.knots).unwrap();
 
-        assert_eq!(get_num_visited(&address, &story.data).unwrap(), 1);
+        assert_eq!(get_num_visited(&address, &story.state.data).unwrap(), 1);
     }
 
     #[test]
@@ -1297,9 +2538,12 @@ Hello, World!
         let hurry_home = Address::from_parts_unchecked("hurry_home", None);
         let at_home = Address::from_parts_unchecked("hurry_home", Some("at_home"));
 
-        assert_eq!(get_num_visited(&back_in_almaty, &story.data).unwrap(), 0);
-        assert_eq!(get_num_visited(&hurry_home, &story.data).unwrap(), 0);
-        assert_eq!(get_num_visited(&at_home, &story.data).unwrap(), 0);
+        assert_eq!(
+            get_num_visited(&back_in_almaty, &story.state.data).unwrap(),
+            0
+        );
+        assert_eq!(get_num_visited(&hurry_home, &story.state.data).unwrap(), 0);
+        assert_eq!(get_num_visited(&at_home, &story.state.data).unwrap(), 0);
     }
 
     #[test]
@@ -1313,7 +2557,7 @@ Hello, World!
         let story = read_story_from_string(content).unwrap();
 
         assert_eq!(
-            &story.tags,
+            &story.story.tags,
             &[
                 "title: inkling".to_string(),
                 "author: Petter Johansson".to_string()
@@ -1333,7 +2577,7 @@ VAR warning_message = \"ADVARSEL\"
 
         let story = read_story_from_string(content).unwrap();
 
-        let variables = &story.data.variables;
+        let variables = &story.state.data.variables;
         assert_eq!(variables.len(), 3);
 
         assert_eq!(variables.get("counter").unwrap().variable, Variable::Int(0));
@@ -1397,15 +2641,24 @@ Three
 
         story.resume(&mut line_buffer).unwrap();
 
-        let knots = &story.knots;
+        let knots = &story.story.knots;
 
         let address_root = Address::from_root_knot("root", &knots).unwrap();
         let address_twice = Address::from_root_knot("visit_twice", &knots).unwrap();
         let address_thrice = Address::from_root_knot("visit_thrice", &knots).unwrap();
 
-        assert_eq!(get_num_visited(&address_twice, &story.data).unwrap(), 2);
-        assert_eq!(get_num_visited(&address_thrice, &story.data).unwrap(), 3);
-        assert_eq!(get_num_visited(&address_root, &story.data).unwrap(), 6);
+        assert_eq!(
+            get_num_visited(&address_twice, &story.state.data).unwrap(),
+            2
+        );
+        assert_eq!(
+            get_num_visited(&address_thrice, &story.state.data).unwrap(),
+            3
+        );
+        assert_eq!(
+            get_num_visited(&address_root, &story.state.data).unwrap(),
+            6
+        );
     }
 
     #[test]
@@ -1489,7 +2742,7 @@ We hurried home as fast as we could.
 
         story.move_to(&"hurry_home".into()).unwrap();
 
-        let address = story.current_address.clone();
+        let address = story.state.current_address.clone();
         assert_eq!(address.get_knot().unwrap(), "hurry_home");
         assert_eq!(address.get_stitch().unwrap(), ROOT_KNOT_NAME);
 
@@ -1527,7 +2780,7 @@ We hurried home as fast as we could.
 
         story.move_to(&"hurry_home".into()).unwrap();
 
-        assert!(story.last_choices.is_none());
+        assert!(story.state.last_choices.is_none());
     }
 
     #[test]
@@ -1556,7 +2809,7 @@ We hurried home as fast as we could.
 
         story.move_to(&"hurry_home".into()).unwrap();
 
-        assert!(story.selected_choice.is_none());
+        assert!(story.state.selected_choice.is_none());
     }
 
     #[test]
@@ -1582,7 +2835,7 @@ Once back home we feasted on cheese.
         let location = Location::with_stitch("hurry_home", "at_home");
         story.move_to(&location).unwrap();
 
-        let address = story.current_address.clone();
+        let address = story.state.current_address.clone();
         assert_eq!(address.get_knot().unwrap(), "hurry_home");
         assert_eq!(address.get_stitch().unwrap(), "at_home");
 
@@ -1701,10 +2954,10 @@ Once back home we feasted on cheese.
         let mut story = read_story_from_string(content).unwrap();
 
         let location = Location::with_stitch("hurry_home", "at_home");
-        let address = Address::from_location(&location, &story.knots).unwrap();
+        let address = Address::from_location(&location, &story.story.knots).unwrap();
 
-        increment_num_visited(&address, &mut story.data).unwrap();
-        increment_num_visited(&address, &mut story.data).unwrap();
+        increment_num_visited(&address, &mut story.state.data).unwrap();
+        increment_num_visited(&address, &mut story.state.data).unwrap();
 
         assert_eq!(story.get_num_visited(&"hurry_home".into()).unwrap(), 0);
         assert_eq!(story.get_num_visited(&location).unwrap(), 2);
@@ -1763,7 +3016,7 @@ VAR counter = 3
 
         story.set_variable("counter", Variable::Int(5)).unwrap();
         assert_eq!(
-            story.data.variables.get("counter").unwrap().variable,
+            story.state.data.variables.get("counter").unwrap().variable,
             Variable::Int(5)
         );
 
@@ -1817,22 +3070,34 @@ VAR message = \"boring text\"
             .is_ok());
 
         assert_eq!(
-            story.data.variables.get("counter").unwrap().variable,
+            story.state.data.variables.get("counter").unwrap().variable,
             Variable::Int(-10)
         );
 
         assert_eq!(
-            story.data.variables.get("hazardous").unwrap().variable,
+            story
+                .state
+                .data
+                .variables
+                .get("hazardous")
+                .unwrap()
+                .variable,
             Variable::Bool(true)
         );
 
         assert_eq!(
-            story.data.variables.get("precision").unwrap().variable,
+            story
+                .state
+                .data
+                .variables
+                .get("precision")
+                .unwrap()
+                .variable,
             Variable::Float(5.45)
         );
 
         assert_eq!(
-            story.data.variables.get("message").unwrap().variable,
+            story.state.data.variables.get("message").unwrap().variable,
             Variable::String("What a pleasure to see you!".to_string())
         );
     }
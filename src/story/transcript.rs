@@ -0,0 +1,223 @@
+//! Record of the lines and choices that have been shown in a playthrough.
+
+use crate::{
+    error::utils::MetaData,
+    story::types::{Choice, Line, Location},
+};
+
+#[cfg(feature = "serde_support")]
+use serde::{Deserialize, Serialize};
+
+use std::collections::{vec_deque, VecDeque};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Record of the lines and selected choices of a story, in the order they were shown.
+///
+/// Recording is opt-in: start it with
+/// [`enable_transcript`][crate::story::Story::enable_transcript()] and read it with
+/// [`get_transcript`][crate::story::Story::get_transcript()]. Lines are recorded when they
+/// are returned to the user and choices when they are made.
+///
+/// # Examples
+/// ```
+/// # use inkling::{read_story_from_string, TranscriptEntryKind};
+/// let content = "\
+/// The train came to a halt.
+/// *   Get off.
+///     The platform was empty.
+/// ";
+///
+/// let mut story = read_story_from_string(content).unwrap();
+/// let mut line_buffer = Vec::new();
+///
+/// story.enable_transcript(None);
+///
+/// story.resume(&mut line_buffer).unwrap();
+/// story.make_choice(0).unwrap();
+/// story.resume(&mut line_buffer).unwrap();
+///
+/// let transcript = story.get_transcript().unwrap();
+/// let texts = transcript.iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>();
+///
+/// assert_eq!(
+///     texts,
+///     &["The train came to a halt.\n", "Get off.", "Get off.\n", "The platform was empty.\n"]
+/// );
+///
+/// assert_eq!(transcript.iter().nth(1).unwrap().kind, TranscriptEntryKind::Choice { index: 0 });
+/// ```
+pub struct Transcript {
+    /// Recorded entries, oldest first.
+    entries: VecDeque<TranscriptEntry>,
+    /// Maximum number of entries to keep, if limited.
+    max_entries: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Line or selected choice which has been recorded in a [`Transcript`][crate::story::Transcript].
+pub struct TranscriptEntry {
+    /// Whether the entry is a line or a choice.
+    pub kind: TranscriptEntryKind,
+    /// Text of the line or choice, as it was shown to the user.
+    pub text: String,
+    /// Tags associated with the line or choice.
+    pub tags: Vec<String>,
+    /// Knot and stitch that the line or choice was encountered in.
+    pub location: Location,
+    /// Information about the origin of the line or choice in the story file or text.
+    pub meta_data: MetaData,
+    /// Number of choices that had been made in the story when the entry was recorded.
+    pub turn_index: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Kind of a recorded [`TranscriptEntry`][crate::story::TranscriptEntry].
+pub enum TranscriptEntryKind {
+    /// Line of text.
+    Line,
+    /// Choice which was selected by the user.
+    Choice {
+        /// Index of the choice in the set that was presented.
+        index: usize,
+    },
+}
+
+impl Transcript {
+    /// Create an empty transcript which keeps at most the given number of entries.
+    pub(crate) fn new(max_entries: Option<usize>) -> Self {
+        Transcript {
+            entries: VecDeque::new(),
+            max_entries,
+        }
+    }
+
+    /// Iterate over the recorded entries, oldest first.
+    pub fn iter(&self) -> vec_deque::Iter<'_, TranscriptEntry> {
+        self.entries.iter()
+    }
+
+    /// Get the number of recorded entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no entries have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the maximum number of entries which are kept, if limited.
+    pub fn get_max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    /// Set the maximum number of entries to keep, dropping the oldest ones if needed.
+    pub(crate) fn set_max_entries(&mut self, max_entries: Option<usize>) {
+        self.max_entries = max_entries;
+        self.drop_excess_entries();
+    }
+
+    /// Record a line that was returned to the user.
+    pub(crate) fn record_line(
+        &mut self,
+        line: &Line,
+        location: Location,
+        meta_data: MetaData,
+        turn_index: u32,
+    ) {
+        self.add_entry(TranscriptEntry {
+            kind: TranscriptEntryKind::Line,
+            text: line.text.clone(),
+            tags: line.tags.clone(),
+            location,
+            meta_data,
+            turn_index,
+        });
+    }
+
+    /// Record a choice that was selected by the user.
+    pub(crate) fn record_choice(
+        &mut self,
+        choice: &Choice,
+        selection: usize,
+        location: Location,
+        turn_index: u32,
+    ) {
+        self.add_entry(TranscriptEntry {
+            kind: TranscriptEntryKind::Choice { index: selection },
+            text: choice.text.clone(),
+            tags: choice.tags.clone(),
+            location,
+            meta_data: choice.meta_data.clone(),
+            turn_index,
+        });
+    }
+
+    /// Add an entry, dropping the oldest entry if the transcript is full.
+    fn add_entry(&mut self, entry: TranscriptEntry) {
+        self.entries.push_back(entry);
+        self.drop_excess_entries();
+    }
+
+    /// Drop the oldest entries until no more than the maximum number remains.
+    fn drop_excess_entries(&mut self) {
+        if let Some(max_entries) = self.max_entries {
+            let num_excess = self.entries.len().saturating_sub(max_entries);
+            self.entries.drain(..num_excess);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Transcript {
+    type Item = &'a TranscriptEntry;
+    type IntoIter = vec_deque::Iter<'a, TranscriptEntry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_line(text: &str) -> Line {
+        Line {
+            text: text.to_string(),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn transcript_keeps_at_most_the_maximum_number_of_entries() {
+        let mut transcript = Transcript::new(Some(2));
+
+        for text in &["One", "Two", "Three"] {
+            transcript.record_line(&mock_line(text), Location::from("knot"), ().into(), 0);
+        }
+
+        let texts = transcript
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>();
+
+        assert_eq!(texts, &["Two", "Three"]);
+    }
+
+    #[test]
+    fn lowering_the_maximum_number_of_entries_drops_the_oldest() {
+        let mut transcript = Transcript::new(None);
+
+        for text in &["One", "Two", "Three"] {
+            transcript.record_line(&mock_line(text), Location::from("knot"), ().into(), 0);
+        }
+
+        transcript.set_max_entries(Some(1));
+
+        assert_eq!(transcript.len(), 1);
+        assert_eq!(&transcript.iter().next().unwrap().text, "Three");
+    }
+}
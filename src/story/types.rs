@@ -2,10 +2,11 @@
 
 use crate::{
     error::{utils::MetaData, InklingError},
-    line::Variable,
+    follow::ThreadState,
+    line::{ListItem, Variable},
 };
 
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, sync::Arc};
 
 #[cfg(feature = "serde_support")]
 use serde::{Deserialize, Serialize};
@@ -37,6 +38,10 @@ pub struct Choice {
     pub tags: Vec<String>,
     /// Internal index of choice in set.
     pub(crate) index: usize,
+    /// Thread that the choice belongs to, if it was not encountered in the main flow.
+    pub(crate) thread: Option<ThreadState>,
+    /// Information about the origin of the choice in the story file or text.
+    pub(crate) meta_data: MetaData,
 }
 
 #[derive(Clone, Debug)]
@@ -101,12 +106,13 @@ impl Prompt {
 
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
-/// Knot and (possible) stitch location in the story.
+/// Knot and (possible) stitch or label location in the story.
 ///
 /// Can be used to move to new locations with [`Story::move_to`][crate::story::Story::move_to()].
 ///
 /// Implements `From<&str>` for strings. Strings are parsed like `Ink` addresses
-/// in `knot.stitch` format.
+/// in `knot.stitch` or `knot.stitch.label` format. Since the stitch of a label in
+/// the default stitch of a knot can be omitted, `knot.label` is also a valid location.
 ///
 /// # Examples
 ///
@@ -126,6 +132,7 @@ impl Prompt {
 /// let twenty_fifth = Location {
 ///     knot: "25th_island_sequence".to_string(),
 ///     stitch: None,
+///     label: None,
 /// };
 ///
 /// story.move_to(&twenty_fifth).unwrap();
@@ -140,6 +147,7 @@ impl Prompt {
 ///     Location {
 ///         knot: "25th_island_sequence".to_string(),
 ///         stitch: None,
+///         label: None,
 ///     }
 /// );
 ///
@@ -148,21 +156,28 @@ impl Prompt {
 ///     Location {
 ///         knot: "24th_island_sequence".to_string(),
 ///         stitch: Some("pyramids".to_string()),
+///         label: None,
 ///     }
 /// );
 /// ```
 pub struct Location {
     pub knot: String,
     pub stitch: Option<String>,
+    pub label: Option<String>,
 }
 
 impl From<&str> for Location {
     fn from(address: &str) -> Self {
-        if let Some(i) = address.find('.') {
-            let (knot, stitch) = address.split_at(i);
-            Location::with_stitch(knot, stitch.get(1..).unwrap())
-        } else {
-            Location::new(address, None)
+        let mut parts = address.splitn(3, '.');
+
+        let knot = parts.next().unwrap();
+        let stitch = parts.next();
+        let label = parts.next();
+
+        Location {
+            knot: knot.to_string(),
+            stitch: stitch.map(|s| s.to_string()),
+            label: label.map(|s| s.to_string()),
         }
     }
 }
@@ -178,6 +193,7 @@ impl Location {
     ///     Location {
     ///         knot: "gesichts_apartment".to_string(),
     ///         stitch: None,
+    ///         label: None,
     ///     }
     /// );
     ///
@@ -186,6 +202,7 @@ impl Location {
     ///     Location {
     ///         knot: "mirandas_den".to_string(),
     ///         stitch: Some("dream".to_string()),
+    ///         label: None,
     ///     }
     /// );
     /// ```
@@ -193,6 +210,7 @@ impl Location {
         Location {
             knot: knot.to_string(),
             stitch: stitch.map(|s| s.to_string()),
+            label: None,
         }
     }
 
@@ -206,6 +224,7 @@ impl Location {
     ///     Location {
     ///         knot: "mirandas_den".to_string(),
     ///         stitch: Some("dream".to_string()),
+    ///         label: None,
     ///     }
     /// );
     /// ```
@@ -213,6 +232,31 @@ impl Location {
         Location {
             knot: knot.to_string(),
             stitch: Some(stitch.to_string()),
+            label: None,
+        }
+    }
+
+    /// Create a `Location` with a label in a knot and possible stitch.
+    ///
+    /// If no stitch is given the label is searched for in the default stitch of the knot.
+    ///
+    /// # Examples
+    /// ```
+    /// # use inkling::Location;
+    /// assert_eq!(
+    ///     Location::with_label("mirandas_den", Some("dream"), "awake"),
+    ///     Location {
+    ///         knot: "mirandas_den".to_string(),
+    ///         stitch: Some("dream".to_string()),
+    ///         label: Some("awake".to_string()),
+    ///     }
+    /// );
+    /// ```
+    pub fn with_label<S: ToString>(knot: S, stitch: Option<S>, label: S) -> Self {
+        Location {
+            knot: knot.to_string(),
+            stitch: stitch.map(|s| s.to_string()),
+            label: Some(label.to_string()),
         }
     }
 }
@@ -260,6 +304,118 @@ impl VariableInfo {
     }
 }
 
+/// Convenience type for a set of list definitions.
+pub type ListDefinitionSet = HashMap<String, ListDefinition>;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Definition of a list in the story.
+///
+/// Lists are defined with `LIST name = a, (b), c = 5` lines. Items are numbered from 1
+/// in order unless they are given explicit values, which following items count up from.
+pub struct ListDefinition {
+    /// Names and values of the items in the list, in the order that they were defined.
+    pub items: Vec<(String, i32)>,
+    /// Information about the origin of the definition in the story file or text.
+    pub meta_data: MetaData,
+}
+
+impl ListDefinition {
+    /// Get the value of the item with the given name.
+    pub fn get_value(&self, name: &str) -> Option<i32> {
+        self.items
+            .iter()
+            .find(|(item, _)| item == name)
+            .map(|(_, value)| *value)
+    }
+
+    /// Get the item with the given name as an item of the list with the given name.
+    pub fn get_item(&self, list: &str, name: &str) -> Option<ListItem> {
+        self.get_value(name).map(|value| ListItem {
+            value,
+            list: list.to_string(),
+            name: name.to_string(),
+        })
+    }
+
+    /// Get the item with the given value as an item of the list with the given name.
+    pub fn get_item_with_value(&self, list: &str, value: i32) -> Option<ListItem> {
+        self.items
+            .iter()
+            .find(|(_, item_value)| *item_value == value)
+            .map(|(name, value)| ListItem {
+                value: *value,
+                list: list.to_string(),
+                name: name.clone(),
+            })
+    }
+
+    /// Get all items of the list with the given name.
+    pub fn all_items(&self, list: &str) -> Vec<ListItem> {
+        self.items
+            .iter()
+            .map(|(name, value)| ListItem {
+                value: *value,
+                list: list.to_string(),
+                name: name.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Convenience type for a set of external functions.
+pub type ExternalFunctionSet = HashMap<String, ExternalFunctionInfo>;
+
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "serde_support", derive(Deserialize, Serialize))]
+/// Information about an external function declared in the story.
+///
+/// External functions are declared with `EXTERNAL name(a, b)` lines and are bound
+/// to Rust functions by the user after the story has been read.
+pub struct ExternalFunctionInfo {
+    /// Names of the function parameters in the declaration.
+    pub parameters: Vec<String>,
+    /// Function bound to the declaration, if it has been.
+    ///
+    /// Bound functions cannot be saved with the story and have to be bound again after
+    /// a story has been loaded.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub function: Option<ExternalFunction>,
+    /// Information about the origin of the declaration in the story file or text.
+    pub meta_data: MetaData,
+}
+
+/// Convenience type for the signature of functions bound to external function declarations.
+pub type ExternalFunctionSignature = dyn Fn(&[Variable]) -> Result<Variable, String> + Send + Sync;
+
+#[derive(Clone)]
+/// Function bound to an external function declaration.
+///
+/// Errors returned by the function are converted into their string representation.
+pub struct ExternalFunction(pub Arc<ExternalFunctionSignature>);
+
+impl ExternalFunction {
+    /// Call the function with the given arguments.
+    pub fn call(&self, arguments: &[Variable]) -> Result<Variable, String> {
+        (self.0)(arguments)
+    }
+}
+
+impl fmt::Debug for ExternalFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ExternalFunction")
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for ExternalFunction {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,10 +462,10 @@ mod tests {
     }
 
     #[test]
-    fn location_from_string_splits_at_first_period_if_multiple() {
+    fn location_from_string_with_two_periods_splits_into_knot_stitch_and_label() {
         assert_eq!(
-            Location::from("knot_address.stitch_address.second"),
-            Location::with_stitch("knot_address", "stitch_address.second"),
+            Location::from("knot_address.stitch_address.label"),
+            Location::with_label("knot_address", Some("stitch_address"), "label"),
         );
     }
 
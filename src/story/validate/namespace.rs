@@ -8,7 +8,10 @@ use crate::{
     },
     story::{
         types::VariableInfo,
-        validate::validate::{KnotValidationInfo, StitchValidationInfo, ValidationData},
+        validate::validate::{
+            KnotValidationInfo, StitchValidationInfo, TemporaryVariableValidationInfo,
+            ValidationData,
+        },
     },
 };
 
@@ -45,6 +48,14 @@ impl NameSpaceCollisionData for VariableInfo {
     }
 }
 
+impl NameSpaceCollisionData for TemporaryVariableValidationInfo {
+    const KIND: CollisionKind = CollisionKind::TemporaryVariable;
+
+    fn get_meta_data(&self) -> &MetaData {
+        &self.meta_data
+    }
+}
+
 /// Validate that there are no name space collisions in the story addresses.
 ///
 /// Elements which will be validated:
@@ -90,6 +101,38 @@ pub fn validate_story_name_spaces(data: &ValidationData) -> Result<(), Vec<NameS
     }
 }
 
+/// Validate that temporary variables in a stitch do not shadow other names in the story.
+///
+/// Temporary variables may not have the same name as any knot, any stitch in the knot
+/// they are declared in, or any global variable.
+pub fn validate_temporary_variable_name_spaces(
+    temporary_variables: &[(String, TemporaryVariableValidationInfo)],
+    knot_name: &str,
+    data: &ValidationData,
+) -> Vec<NameSpaceCollision> {
+    let mut errors = Vec::new();
+
+    for (name, temporary_info) in temporary_variables {
+        if let Some(knot_info) = data.knots.get(name) {
+            errors.push(get_collision_error(name, temporary_info, knot_info));
+        }
+
+        if let Some(stitch_info) = data
+            .knots
+            .get(knot_name)
+            .and_then(|knot_info| knot_info.stitches.get(name))
+        {
+            errors.push(get_collision_error(name, temporary_info, stitch_info));
+        }
+
+        if let Some(variable_info) = data.follow_data.variables.get(name) {
+            errors.push(get_collision_error(name, temporary_info, variable_info));
+        }
+    }
+
+    errors
+}
+
 /// Construct a `NameSpaceCollision` error from the given types.
 fn get_collision_error<F, T>(name: &str, from: &F, to: &T) -> NameSpaceCollision
 where
@@ -192,4 +235,73 @@ mod tests {
 
         assert_eq!(errors.len(), 1);
     }
+
+    fn construct_temporary_variables(
+        names: &[&str],
+    ) -> Vec<(String, TemporaryVariableValidationInfo)> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let info = TemporaryVariableValidationInfo {
+                    meta_data: i.into(),
+                };
+
+                (name.to_string(), info)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn temporary_variables_with_unique_names_raise_no_name_space_errors() {
+        let knots = construct_knots(&[("knot", &["stitch"])]);
+        let variables = construct_variables(&[("variable", 1)]);
+
+        let data = ValidationData::from_data(&knots, &variables);
+        let temporary_variables = construct_temporary_variables(&["temporary"]);
+
+        assert!(
+            validate_temporary_variable_name_spaces(&temporary_variables, "knot", &data).is_empty()
+        );
+    }
+
+    #[test]
+    fn temporary_variable_names_cannot_collide_with_knot_names() {
+        let knots = construct_knots(&[("knot", &["stitch"]), ("other", &["other_stitch"])]);
+        let variables = VariableSet::new();
+
+        let data = ValidationData::from_data(&knots, &variables);
+        let temporary_variables = construct_temporary_variables(&["other"]);
+
+        let errors = validate_temporary_variable_name_spaces(&temporary_variables, "knot", &data);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn temporary_variable_names_cannot_collide_with_stitch_names_in_the_same_knot() {
+        let knots = construct_knots(&[("knot", &["stitch"]), ("other", &["other_stitch"])]);
+        let variables = VariableSet::new();
+
+        let data = ValidationData::from_data(&knots, &variables);
+        let temporary_variables = construct_temporary_variables(&["stitch", "other_stitch"]);
+
+        let errors = validate_temporary_variable_name_spaces(&temporary_variables, "knot", &data);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(&errors[0].name, "stitch");
+    }
+
+    #[test]
+    fn temporary_variable_names_cannot_collide_with_global_variable_names() {
+        let knots = construct_knots(&[("knot", &["stitch"])]);
+        let variables = construct_variables(&[("variable", 1)]);
+
+        let data = ValidationData::from_data(&knots, &variables);
+        let temporary_variables = construct_temporary_variables(&["variable"]);
+
+        let errors = validate_temporary_variable_name_spaces(&temporary_variables, "knot", &data);
+
+        assert_eq!(errors.len(), 1);
+    }
 }
@@ -1,14 +1,33 @@
 //! Trait and functions to validate a story.
 
 use crate::{
-    error::{parse::validate::ValidationError, utils::MetaData},
+    consts::ROOT_KNOT_NAME,
+    error::{
+        parse::validate::{InvalidFunctionContent, InvalidFunctionContentKind, ValidationError},
+        utils::MetaData,
+    },
     follow::FollowData,
-    knot::{get_empty_knot_counts, Address, AddressKind, KnotSet},
+    knot::{
+        get_empty_function_counts, get_empty_function_label_counts, get_empty_knot_counts,
+        get_empty_label_counts, Address, AddressKind, FunctionParameter, FunctionSet, Knot,
+        KnotSet, Stitch,
+    },
+    line::{Assignment, Content, LineChunk, Variable},
     log::Logger,
-    story::{rng::StoryRng, types::VariableSet, validate::namespace::validate_story_name_spaces},
+    node::{AlternativeBlock, NodeItem},
+    story::{
+        rng::StoryRng,
+        types::VariableSet,
+        validate::namespace::{
+            validate_story_name_spaces, validate_temporary_variable_name_spaces,
+        },
+    },
 };
 
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 pub struct ValidationData {
     /// Data required to evaluate expressions.
@@ -19,6 +38,14 @@ pub struct ValidationData {
     pub follow_data: FollowData,
     /// Structure corresponding to knots with their default stitch, stitches and meta data.
     pub knots: HashMap<String, KnotValidationInfo>,
+    /// Names of temporary variables in the current stitch which are assigned values
+    /// that are unknown until the story is followed, such as from function calls.
+    ///
+    /// These variables are present in `follow_data` with placeholder values, to validate
+    /// their addresses, but expressions using them cannot be evaluated.
+    pub unknown_temporary_variables: HashSet<String>,
+    /// Parameters of functions defined in the story, by function name.
+    pub functions: HashMap<String, Vec<FunctionParameter>>,
 }
 
 /// Basic information about a knot, required to validate its content.
@@ -35,10 +62,20 @@ pub struct KnotValidationInfo {
 
 /// Basic information about a stitch, required to validate its content.
 pub struct StitchValidationInfo {
+    /// Names of labelled choices and gathers in the stitch.
+    pub labels: Vec<String>,
+    /// Names of parameters which are bound when the stitch is entered.
+    pub parameters: Vec<String>,
     /// Information about the origin of this stitch.
     pub meta_data: MetaData,
 }
 
+/// Basic information about a temporary variable, required to validate its name.
+pub struct TemporaryVariableValidationInfo {
+    /// Information about the origin of the declaration of this variable.
+    pub meta_data: MetaData,
+}
+
 impl ValidationData {
     /// Construct the required validation data from the parsed knots and variables.
     pub fn from_data(knots: &KnotSet, variables: &VariableSet) -> Self {
@@ -52,6 +89,13 @@ impl ValidationData {
                         (
                             stitch_name.to_string(),
                             StitchValidationInfo {
+                                labels: stitch_data
+                                    .root
+                                    .get_labels()
+                                    .into_iter()
+                                    .map(|(label, _)| label)
+                                    .collect(),
+                                parameters: stitch_data.parameters.clone(),
                                 meta_data: stitch_data.meta_data.clone(),
                             },
                         )
@@ -70,15 +114,78 @@ impl ValidationData {
 
         let follow_data = FollowData {
             knot_visit_counts: get_empty_knot_counts(knots),
+            knot_visit_turns: HashMap::new(),
+            turn_index: 0,
+            choice_count: 0,
+            label_visit_counts: get_empty_label_counts(knots),
+            branch_visit_counts: HashMap::new(),
+            stacks: HashMap::new(),
+            alternative_inds: HashMap::new(),
             variables: variables.clone(),
+            lists: Arc::default(),
+            temp_variables: HashMap::new(),
+            external_functions: HashMap::new(),
+            functions: Arc::default(),
+            call_stack: Vec::new(),
+            function_text: String::new(),
+            tunnel_stack: Vec::new(),
             rng: StoryRng::default(),
+            pause_after_lines: false,
+            pause_before_logic: false,
         };
 
         ValidationData {
             follow_data,
             knots: knot_info,
+            unknown_temporary_variables: HashSet::new(),
+            functions: HashMap::new(),
         }
     }
+
+    /// Add the functions defined in the story to the validation data.
+    ///
+    /// The content of functions is validated like a knot with a single stitch, which
+    /// lets their addresses be validated and checked for name space collisions.
+    pub fn add_functions(&mut self, functions: &FunctionSet) {
+        for (name, function) in functions {
+            let labels = function
+                .stitch
+                .root
+                .get_labels()
+                .into_iter()
+                .map(|(label, _)| label)
+                .collect::<Vec<_>>();
+
+            let mut stitches = HashMap::new();
+            stitches.insert(
+                ROOT_KNOT_NAME.to_string(),
+                StitchValidationInfo {
+                    labels,
+                    parameters: Vec::new(),
+                    meta_data: function.stitch.meta_data.clone(),
+                },
+            );
+
+            self.knots.insert(
+                name.clone(),
+                KnotValidationInfo {
+                    default_stitch: ROOT_KNOT_NAME.to_string(),
+                    stitches,
+                    meta_data: function.meta_data.clone(),
+                },
+            );
+
+            self.functions
+                .insert(name.clone(), function.parameters.clone());
+        }
+
+        self.follow_data
+            .knot_visit_counts
+            .extend(get_empty_function_counts(functions));
+        self.follow_data
+            .label_visit_counts
+            .extend(get_empty_function_label_counts(functions));
+    }
 }
 
 /// Trait for nesting into all parts of a story and validating elements.
@@ -123,39 +230,74 @@ pub trait ValidateContent {
 
 /// Validate addresses, expressions, conditions and names of all content in a story.
 ///
-/// This function walks through all the knots and stitches in a story, and for each item
-/// uses the `ValidateContent` trait to nest through its content. Additionally it checks for
-/// name space collisions between variables, knots and stitches.
+/// This function walks through all the knots, stitches and functions in a story, and for
+/// each item uses the `ValidateContent` trait to nest through its content. Additionally
+/// it checks for name space collisions between variables, knots and stitches.
+///
+/// Divert targets held by global variables are validated in the given data.
 ///
 /// If any error is encountered this will yield the set of all found errors.
 pub fn validate_story_content(
     knots: &mut KnotSet,
-    follow_data: &FollowData,
+    functions: &mut FunctionSet,
+    follow_data: &mut FollowData,
     log: &mut Logger,
 ) -> Result<(), ValidationError> {
-    let validation_data = ValidationData::from_data(knots, &follow_data.variables);
+    let mut validation_data = ValidationData::from_data(knots, &follow_data.variables);
+    validation_data.follow_data.external_functions = follow_data.external_functions.clone();
+    validation_data.follow_data.lists = follow_data.lists.clone();
+    validation_data.add_functions(functions);
 
     let mut error = ValidationError::new();
 
+    validate_global_variables(
+        &mut follow_data.variables,
+        &mut error,
+        log,
+        &validation_data,
+    );
+    validation_data.follow_data.variables = follow_data.variables.clone();
+
     knots.iter_mut().for_each(|(knot_name, knot)| {
+        validate_knot_tags(knot, knot_name, &mut error, log, &mut validation_data);
+
         knot.stitches.iter_mut().for_each(|(stitch_name, stitch)| {
-            let current_location = Address::Validated(AddressKind::Location {
-                knot: knot_name.clone(),
-                stitch: stitch_name.clone(),
-            });
+            let parameters = stitch.parameters.clone();
 
-            stitch.root.validate(
+            validate_stitch(
+                stitch,
+                knot_name,
+                stitch_name,
+                &parameters,
                 &mut error,
                 log,
-                &current_location,
-                &stitch.meta_data,
-                &validation_data,
+                &mut validation_data,
             );
         })
     });
 
+    functions.iter_mut().for_each(|(name, function)| {
+        let parameters = function
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.clone())
+            .collect::<Vec<_>>();
+
+        validate_stitch(
+            &mut function.stitch,
+            name,
+            ROOT_KNOT_NAME,
+            &parameters,
+            &mut error,
+            log,
+            &mut validation_data,
+        );
+
+        validate_function_content(name, &function.stitch.root.items, &mut error);
+    });
+
     if let Err(name_space_errors) = validate_story_name_spaces(&validation_data) {
-        error.name_space_errors = name_space_errors;
+        error.name_space_errors.extend(name_space_errors);
     }
 
     if error.is_empty() {
@@ -165,12 +307,261 @@ pub fn validate_story_content(
     }
 }
 
+/// Validate that the content of a function does not leave it.
+///
+/// Functions are followed until they return, so choices, diverts, threads and tunnels
+/// in them are invalid.
+fn validate_function_content(name: &str, items: &[NodeItem], error: &mut ValidationError) {
+    for item in items {
+        match item {
+            NodeItem::BranchingPoint(branches) => {
+                if let Some(branch) = branches.first() {
+                    error.function_content_errors.push(InvalidFunctionContent {
+                        name: name.to_string(),
+                        kind: InvalidFunctionContentKind::Choice,
+                        meta_data: branch.choice.meta_data.clone(),
+                    });
+                }
+            }
+            NodeItem::Conditional(branches) => branches
+                .iter()
+                .for_each(|branch| validate_function_content(name, &branch.items, error)),
+            NodeItem::Alternative(block) => block
+                .branches
+                .iter()
+                .for_each(|branch| validate_function_content(name, &branch.items, error)),
+            NodeItem::Line(line) => {
+                if let Some(kind) = get_invalid_function_content_kind(&line.chunk) {
+                    error.function_content_errors.push(InvalidFunctionContent {
+                        name: name.to_string(),
+                        kind,
+                        meta_data: line.meta_data.clone(),
+                    });
+                }
+            }
+            NodeItem::Label(..) => (),
+        }
+    }
+}
+
+/// Get the kind of the first content in a chunk which would leave a function, if any.
+fn get_invalid_function_content_kind(chunk: &LineChunk) -> Option<InvalidFunctionContentKind> {
+    chunk
+        .items
+        .iter()
+        .chain(chunk.else_items.iter())
+        .find_map(|content| match content {
+            Content::Divert(..) | Content::DivertWithArguments { .. } => {
+                Some(InvalidFunctionContentKind::Divert)
+            }
+            Content::Thread { .. } => Some(InvalidFunctionContentKind::Thread),
+            Content::TunnelCall { .. } | Content::TunnelReturn(..) => {
+                Some(InvalidFunctionContentKind::Tunnel)
+            }
+            Content::Alternative(alternative) => alternative
+                .items
+                .iter()
+                .find_map(get_invalid_function_content_kind),
+            Content::Nested(chunk) => get_invalid_function_content_kind(chunk),
+            _ => None,
+        })
+}
+
+/// Validate the divert targets held by global variables.
+///
+/// Global variables are declared before the first knot, so their addresses are validated
+/// from the root of the story.
+fn validate_global_variables(
+    variables: &mut VariableSet,
+    error: &mut ValidationError,
+    log: &mut Logger,
+    validation_data: &ValidationData,
+) {
+    let root_location = Address::Validated(AddressKind::Location {
+        knot: ROOT_KNOT_NAME.to_string(),
+        stitch: ROOT_KNOT_NAME.to_string(),
+    });
+
+    for info in variables.values_mut() {
+        if let Variable::Divert(address) = &mut info.variable {
+            address.validate(error, log, &root_location, &info.meta_data, validation_data);
+        }
+    }
+}
+
+/// Validate the content of the tags of a knot.
+///
+/// Tags are processed outside of the flow of the story, from the default stitch
+/// of their knot, so they cannot refer to temporary variables or parameters.
+fn validate_knot_tags(
+    knot: &mut Knot,
+    knot_name: &str,
+    error: &mut ValidationError,
+    log: &mut Logger,
+    validation_data: &mut ValidationData,
+) {
+    let current_location = Address::Validated(AddressKind::Location {
+        knot: knot_name.to_string(),
+        stitch: knot.default_stitch.clone(),
+    });
+
+    validation_data.follow_data.temp_variables.clear();
+    validation_data.unknown_temporary_variables.clear();
+
+    let meta_data = &knot.meta_data;
+
+    knot.tags
+        .iter_mut()
+        .for_each(|tag| tag.validate(error, log, &current_location, meta_data, validation_data));
+}
+
+/// Validate the content of a single stitch.
+///
+/// The given parameters are temporary variables which are set when the stitch is entered,
+/// like the parameters of functions. Their values are unknown until the story is followed.
+fn validate_stitch(
+    stitch: &mut Stitch,
+    knot_name: &str,
+    stitch_name: &str,
+    parameters: &[String],
+    error: &mut ValidationError,
+    log: &mut Logger,
+    validation_data: &mut ValidationData,
+) {
+    let current_location = Address::Validated(AddressKind::Location {
+        knot: knot_name.to_string(),
+        stitch: stitch_name.to_string(),
+    });
+
+    let declarations = get_temporary_variable_declarations(&stitch.root.items);
+
+    set_temporary_variables(
+        parameters,
+        &declarations,
+        &current_location,
+        validation_data,
+    );
+
+    let temporary_variables = parameters
+        .iter()
+        .map(|name| {
+            let info = TemporaryVariableValidationInfo {
+                meta_data: stitch.meta_data.clone(),
+            };
+            (name.clone(), info)
+        })
+        .chain(declarations.into_iter().map(|(assignment, meta_data)| {
+            let info = TemporaryVariableValidationInfo { meta_data };
+            (assignment.target.to_string(), info)
+        }))
+        .collect::<Vec<_>>();
+
+    error
+        .name_space_errors
+        .extend(validate_temporary_variable_name_spaces(
+            &temporary_variables,
+            knot_name,
+            validation_data,
+        ));
+
+    stitch.root.validate(
+        error,
+        log,
+        &current_location,
+        &stitch.meta_data,
+        validation_data,
+    );
+}
+
+/// Get all declarations of temporary variables in a set of nodes, in order.
+fn get_temporary_variable_declarations(items: &[NodeItem]) -> Vec<(Assignment, MetaData)> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            NodeItem::Line(line) => line
+                .chunk
+                .items
+                .iter()
+                .filter_map(|content| match content {
+                    Content::Assignment(assignment) if assignment.temporary => {
+                        Some((assignment.clone(), line.meta_data.clone()))
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            NodeItem::BranchingPoint(branches) => branches
+                .iter()
+                .flat_map(|branch| get_temporary_variable_declarations(&branch.items))
+                .collect(),
+            NodeItem::Conditional(branches)
+            | NodeItem::Alternative(AlternativeBlock { branches, .. }) => branches
+                .iter()
+                .flat_map(|branch| get_temporary_variable_declarations(&branch.items))
+                .collect(),
+            NodeItem::Label(..) => Vec::new(),
+        })
+        .collect()
+}
+
+/// Set the temporary variables of a stitch to the validation data.
+///
+/// Declarations are evaluated in order, to determine the type of every variable. Declarations
+/// which cannot be evaluated are skipped: their errors will be found when the content
+/// of the stitch is validated. Variables which are assigned values that are unknown until
+/// the story is followed get a placeholder value and are marked as unknown. This includes
+/// the given parameters, which are set before any declaration.
+fn set_temporary_variables(
+    parameters: &[String],
+    declarations: &[(Assignment, MetaData)],
+    current_location: &Address,
+    data: &mut ValidationData,
+) {
+    data.follow_data.temp_variables.clear();
+    data.unknown_temporary_variables.clear();
+
+    for name in parameters {
+        data.follow_data
+            .temp_variables
+            .insert(name.clone(), Variable::Int(0));
+        data.unknown_temporary_variables.insert(name.clone());
+    }
+
+    for (assignment, meta_data) in declarations {
+        let mut assignment = assignment.clone();
+        let mut error = ValidationError::new();
+
+        assignment.validate(
+            &mut error,
+            &mut Logger::default(),
+            current_location,
+            meta_data,
+            data,
+        );
+
+        if !error.is_empty() {
+            continue;
+        }
+
+        let name = assignment.target.to_string();
+
+        if assignment.has_unknown_value(data) {
+            data.follow_data
+                .temp_variables
+                .insert(name.clone(), Variable::Int(0));
+            data.unknown_temporary_variables.insert(name);
+        } else if assignment.evaluate(&mut data.follow_data).is_ok() {
+            data.unknown_temporary_variables.remove(&name);
+        }
+    }
+}
+
 #[cfg(test)]
 pub(super) mod tests {
     use super::*;
 
     use crate::{
         consts::ROOT_KNOT_NAME,
+        error::parse::address::InvalidAddressErrorKind,
         follow::FollowDataBuilder,
         knot::{Knot, Stitch},
         line::Variable,
@@ -198,7 +589,7 @@ pub(super) mod tests {
 
                         let stitch = Stitch {
                             root,
-                            stack: Vec::new(),
+                            parameters: Vec::new(),
                             meta_data: line_index.into(),
                         };
 
@@ -233,7 +624,8 @@ pub(super) mod tests {
 
     fn get_validation_data_from_string(content: &str) -> (KnotSet, FollowData) {
         let mut log = Logger::default();
-        let (knots, variables, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, _, variables, _, _, _) =
+            read_story_content_from_string(content, &mut log).unwrap();
 
         let data = FollowDataBuilder::new()
             .with_knots(get_empty_knot_counts(&knots))
@@ -244,17 +636,17 @@ pub(super) mod tests {
     }
 
     fn get_validation_result_from_string(content: &str) -> Result<(), ValidationError> {
-        let (mut knots, data) = get_validation_data_from_string(content);
+        let (mut knots, mut data) = get_validation_data_from_string(content);
         let mut log = Logger::default();
 
-        validate_story_content(&mut knots, &data, &mut log)
+        validate_story_content(&mut knots, &mut HashMap::new(), &mut data, &mut log)
     }
 
     fn get_validation_error_from_string(content: &str) -> ValidationError {
-        let (mut knots, data) = get_validation_data_from_string(content);
+        let (mut knots, mut data) = get_validation_data_from_string(content);
         let mut log = Logger::default();
 
-        validate_story_content(&mut knots, &data, &mut log).unwrap_err()
+        validate_story_content(&mut knots, &mut HashMap::new(), &mut data, &mut log).unwrap_err()
     }
 
     #[test]
@@ -273,7 +665,7 @@ pub(super) mod tests {
 ";
 
         let mut log = Logger::default();
-        let (knots, _, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, _, _, _, _, _) = read_story_content_from_string(content, &mut log).unwrap();
 
         let data = ValidationData::from_data(&knots, &HashMap::new());
 
@@ -302,7 +694,7 @@ pub(super) mod tests {
 ";
 
         let mut log = Logger::default();
-        let (knots, _, _) = read_story_content_from_string(content, &mut log).unwrap();
+        let (knots, _, _, _, _, _) = read_story_content_from_string(content, &mut log).unwrap();
 
         let data = ValidationData::from_data(&knots, &HashMap::new());
 
@@ -462,7 +854,7 @@ VAR variable = true
 
 ";
 
-        let (mut knots, data) = get_validation_data_from_string(content);
+        let (mut knots, mut data) = get_validation_data_from_string(content);
         let mut log = Logger::default();
 
         let pre_validated_addresses = format!("{:?}", &knots).matches("Validated(").count();
@@ -470,7 +862,7 @@ VAR variable = true
 
         assert!(pre_raw_addresses >= 2);
 
-        validate_story_content(&mut knots, &data, &mut log).unwrap();
+        validate_story_content(&mut knots, &mut HashMap::new(), &mut data, &mut log).unwrap();
 
         let validated_addresses = format!("{:?}", &knots).matches("Validated(").count();
         let raw_addresses = format!("{:?}", &knots).matches("Raw(").count();
@@ -582,14 +974,14 @@ Line
 
 ";
 
-        let (mut knots, data) = get_validation_data_from_string(content);
+        let (mut knots, mut data) = get_validation_data_from_string(content);
         let mut log = Logger::default();
 
         let pre_raw_addresses = format!("{:?}", &knots).matches("Raw(").count();
 
         assert!(pre_raw_addresses >= 3);
 
-        validate_story_content(&mut knots, &data, &mut log).unwrap();
+        validate_story_content(&mut knots, &mut HashMap::new(), &mut data, &mut log).unwrap();
 
         dbg!(&knots);
 
@@ -662,4 +1054,160 @@ Line
 
         assert_eq!(error.variable_errors.len(), 1);
     }
+
+    #[test]
+    fn validating_story_raises_error_if_assignment_target_does_not_exist() {
+        let content = "
+
+~ coins = 5
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.invalid_address_errors.len(), 1);
+    }
+
+    #[test]
+    fn validating_story_raises_error_if_assignment_target_is_not_a_variable() {
+        let content = "
+
+~ tripoli = 5
+
+== tripoli
+-> END
+
+";
+        let error = get_validation_error_from_string(content);
+
+        match &error.invalid_address_errors[0].kind {
+            InvalidAddressErrorKind::NotAVariable { .. } => (),
+            other => panic!(
+                "expected `InvalidAddressErrorKind::NotAVariable` but got {:?}",
+                other
+            ),
+        }
+    }
+
+    #[test]
+    fn validating_story_raises_error_if_assigned_value_has_different_type() {
+        let content = "
+
+VAR coins = 0
+
+~ coins = true
+~ coins = \"string\"
+~ coins += 1.5
+~ coins = 1 + true
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.variable_errors.len(), 4);
+    }
+
+    #[test]
+    fn validating_story_raises_error_if_constant_is_assigned_to() {
+        let content = "
+
+CONST coins = 0
+
+~ coins = 5
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.variable_errors.len(), 1);
+    }
+
+    #[test]
+    fn validating_story_with_valid_assignments_yields_no_errors() {
+        let content = "
+
+VAR coins = 0
+VAR rank = \"Sergent\"
+
+~ coins = coins + 4
+~ coins++
+~ coins -= 2
+~ rank = \"Capitaine\"
+
+";
+        assert!(get_validation_result_from_string(content).is_ok());
+    }
+
+    #[test]
+    fn temporary_variables_can_be_used_after_declaration_in_the_same_stitch() {
+        let content = "
+
+~ temp coins = 5
+~ coins = coins + 1
+{coins}
+
+";
+        assert!(get_validation_result_from_string(content).is_ok());
+    }
+
+    #[test]
+    fn temporary_variables_can_be_used_in_nested_choices() {
+        let content = "
+
+*   Choice
+    ~ temp coins = 5
+    {coins}
+    **  {coins > 3} Nested choice
+
+";
+        assert!(get_validation_result_from_string(content).is_ok());
+    }
+
+    #[test]
+    fn temporary_variables_are_type_checked_when_assigned_to() {
+        let content = "
+
+~ temp coins = 5
+~ coins = true
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.variable_errors.len(), 1);
+    }
+
+    #[test]
+    fn temporary_variables_are_not_available_in_other_stitches() {
+        let content = "
+
+== knot
+~ temp coins = 5
+-> other
+
+= other
+{coins}
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.invalid_address_errors.len(), 1);
+    }
+
+    #[test]
+    fn temporary_variables_that_shadow_other_names_raise_name_space_errors() {
+        let content = "
+
+VAR global = 0
+
+== knot
+~ temp global = 1
+~ temp knot = 1
+~ temp stitch = 1
+-> END
+
+= stitch
+-> END
+
+";
+        let error = get_validation_error_from_string(content);
+
+        assert_eq!(error.name_space_errors.len(), 3);
+    }
 }
@@ -0,0 +1,147 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn numeric_functions_convert_and_round_numbers() {
+    let content = "
+
+VAR number = 3.7
+
+{INT(number)} {FLOAT(2)} {FLOOR(number)} {FLOOR(2)}
+{POW(2, 10)} {POW(4, 0.5)}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "3 2 3 2\n");
+    assert_eq!(&line_buffer[1].text, "1024 2\n");
+}
+
+#[test]
+fn random_numbers_can_be_assigned_to_variables() {
+    let content = "
+
+VAR roll = 0
+
+~ roll = RANDOM(1, 6)
+~ temp bonus = RANDOM(2, 2) + roll
+{bonus > 2 and bonus < 9: The roll is within range.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The roll is within range.\n");
+
+    match story.get_variable("roll").unwrap() {
+        Variable::Int(value) => assert!((1..=6).contains(&value)),
+        other => panic!("expected an integer but got {:?}", other),
+    }
+}
+
+#[test]
+fn seed_random_can_be_called_from_a_logic_line() {
+    let content = "
+
+~ SEED_RANDOM(100)
+Rolled {RANDOM(1, 1)}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Rolled 1.\n");
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn seeding_the_random_generator_makes_rolls_repeatable() {
+    let content = "
+
+~ SEED_RANDOM(42)
+{RANDOM(1, 1000)} {RANDOM(1, 1000)} {RANDOM(1, 1000)}
+~ SEED_RANDOM(42)
+{RANDOM(1, 1000)} {RANDOM(1, 1000)} {RANDOM(1, 1000)}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, &line_buffer[1].text);
+}
+
+#[test]
+fn built_in_functions_with_wrong_argument_types_yield_validation_error() {
+    let content = "
+
+{RANDOM(1, \"six\")}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => assert_eq!(err.function_call_errors.len(), 1),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn built_in_function_arguments_from_variables_are_checked_with_their_current_values() {
+    let content = "
+
+VAR high = 0
+
+~ high = 6
+{RANDOM(1, high) <= 6:The die was cast.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The die was cast.\n");
+}
+
+#[test]
+fn built_in_functions_with_invalid_constant_arguments_yield_validation_error() {
+    let content = "
+
+CONST high = 0
+
+{RANDOM(1, high)}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => assert_eq!(err.function_call_errors.len(), 1),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn built_in_functions_with_wrong_number_of_arguments_yield_validation_error() {
+    let content = "
+
+{POW(2)}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => assert_eq!(err.function_call_errors.len(), 1),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
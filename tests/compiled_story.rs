@@ -0,0 +1,115 @@
+use inkling::*;
+
+use std::sync::Arc;
+
+#[test]
+fn stories_sharing_a_compiled_story_play_through_it_independently() {
+    let content = "
+
+-> gate
+
+== gate ==
+The gate was {&closed|open|guarded}.
+*   Knock on the gate.
+    Nobody answered.
+*   Walk away.
+-   -> gate
+
+";
+
+    let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+    let mut first = Story::new(Arc::clone(&compiled));
+    let mut second = Story::new(Arc::clone(&compiled));
+
+    let mut line_buffer = Vec::new();
+
+    first.resume(&mut line_buffer).unwrap();
+    first.make_choice(0).unwrap();
+    first.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer.last().unwrap().text, "The gate was open.\n");
+
+    line_buffer.clear();
+    second.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The gate was closed.\n");
+}
+
+#[test]
+fn choices_are_counted_separately_for_stories_sharing_a_compiled_story() {
+    let content = "
+
+-> hall
+
+== hall ==
+*   Open the chest.
+*   Leave.
+-   -> hall
+
+";
+
+    let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+    let mut first = Story::new(Arc::clone(&compiled));
+    let mut second = Story::new(Arc::clone(&compiled));
+
+    let mut line_buffer = Vec::new();
+
+    first.resume(&mut line_buffer).unwrap();
+    first.make_choice(0).unwrap();
+
+    let first_choices = first
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+    let second_choices = second
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(first_choices.len(), 1);
+    assert_eq!(second_choices.len(), 2);
+}
+
+#[test]
+fn story_restored_from_state_continues_from_where_the_state_was_taken() {
+    let content = "
+
+A man stood by the gate.
+*   Greet him.
+    He nodded at you.
+    -> DONE
+*   Pass him by.
+    -> DONE
+
+";
+
+    let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+    let mut story = Story::new(Arc::clone(&compiled));
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let mut restored = Story::from_state(Arc::clone(&compiled), story.get_state().clone());
+
+    line_buffer.clear();
+    restored.make_choice(0).unwrap();
+    restored.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[1].text, "He nodded at you.\n");
+}
+
+#[test]
+fn read_stories_share_their_compiled_story_when_cloned() {
+    let story = read_story_from_string("The lights went out.").unwrap();
+    let clone = story.clone();
+
+    assert!(Arc::ptr_eq(
+        story.get_compiled_story(),
+        clone.get_compiled_story()
+    ));
+}
@@ -0,0 +1,267 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn if_else_blocks_follow_the_branch_matching_the_condition() {
+    let content = "
+
+VAR visited_paris = true
+
+{visited_paris:
+    \"Bonjour, mon ami!\"
+    I greeted him in French.
+- else:
+    \"Hello, friend!\"
+}
+He nodded at me.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "\"Bonjour, mon ami!\"\n");
+    assert_eq!(&line_buffer[1].text, "I greeted him in French.\n");
+    assert_eq!(&line_buffer[2].text, "He nodded at me.\n");
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.set_variable("visited_paris", false).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "\"Hello, friend!\"\n");
+    assert_eq!(&line_buffer[1].text, "He nodded at me.\n");
+}
+
+#[test]
+fn blocks_with_many_conditions_follow_the_first_fulfilled_branch() {
+    let content = "
+
+VAR coins = 3
+
+{
+    - coins > 5:
+        I am rich!
+    - coins > 2:
+        I can afford a baguette.
+    - coins > 0:
+        I can afford a croissant.
+    - else:
+        I am broke.
+}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "I can afford a baguette.\n");
+}
+
+#[test]
+fn blocks_are_skipped_if_no_condition_is_fulfilled() {
+    let content = "
+
+VAR coins = 0
+
+{
+    - coins > 2:
+        I can afford a baguette.
+    - coins > 0:
+        I can afford a croissant.
+}
+I walk past the bakery.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "I walk past the bakery.\n");
+}
+
+#[test]
+fn switch_blocks_follow_the_branch_with_matching_value() {
+    let content = "
+
+VAR day = 2
+
+{day:
+    - 1: It is Monday.
+    - 2: It is Tuesday.
+    - else: It is some other day.
+}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "It is Tuesday.\n");
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.set_variable("day", 5).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "It is some other day.\n");
+}
+
+#[test]
+fn choices_inside_blocks_continue_after_the_block_when_followed() {
+    let content = "
+
+VAR hungry = true
+
+{hungry:
+    The bakery smells wonderful.
+    *   [Buy a croissant]
+        I buy a croissant.
+    *   [Walk on]
+        I walk on.
+    -   I leave the bakery.
+- else:
+    I pass the bakery.
+}
+The street is busy.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "The bakery smells wonderful.\n");
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 4);
+    assert_eq!(&line_buffer[1].text, "I buy a croissant.\n");
+    assert_eq!(&line_buffer[2].text, "I leave the bakery.\n");
+    assert_eq!(&line_buffer[3].text, "The street is busy.\n");
+}
+
+#[test]
+fn blocks_can_be_nested_inside_blocks_and_choices() {
+    let content = "
+
+VAR coins = 3
+VAR hungry = true
+
+*   [Enter the bakery]
+    {coins > 0:
+        {hungry:
+            I buy a croissant.
+        - else:
+            I buy a loaf of bread for later.
+        }
+    - else:
+        I cannot afford anything.
+    }
+-   I leave.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "I buy a croissant.\n");
+    assert_eq!(&line_buffer[1].text, "I leave.\n");
+}
+
+#[test]
+fn diverts_inside_blocks_leave_the_block() {
+    let content = "
+
+VAR coins = 3
+
+{coins > 0:
+    -> bakery
+}
+I walk home.
+
+== bakery ==
+I enter the bakery.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "I enter the bakery.\n");
+}
+
+#[test]
+fn unclosed_blocks_yield_parse_errors() {
+    let content = "
+
+{
+    - true:
+        I am in a block.
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(..)) => (),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn unmatched_block_ends_yield_parse_errors() {
+    let content = "
+
+I am not in a block.
+}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(..)) => (),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn unknown_variables_in_block_conditions_are_validated() {
+    let content = "
+
+{
+    - unknown_variable > 2:
+        Text
+}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        _ => panic!(),
+    }
+}
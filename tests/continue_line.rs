@@ -0,0 +1,346 @@
+use inkling::*;
+
+use std::sync::{Arc, Mutex};
+
+/// Read all lines from the story until it reaches a set of choices or its end.
+fn continue_lines(story: &mut Story) -> Vec<Line> {
+    let mut lines = Vec::new();
+
+    while story.can_continue() {
+        lines.extend(story.continue_line().unwrap());
+    }
+
+    lines
+}
+
+#[test]
+fn continuing_line_by_line_yields_the_same_lines_as_resume() {
+    let content = "
+
+-> harbor
+
+== harbor ==
+~ temp boats = 3
+The harbor was quiet <>
+at this hour.
+{boats > 2: There were {boats} boats by the pier.}
+{&The gulls were screaming.|The gulls were silent.}
+-> lighthouse ->
+<- market
++   Board a boat.
+    The boat rocked under your feet.
+    -> harbor
+*   Walk home.
+    -> END
+
+== lighthouse ==
+The lighthouse blinked twice.
+->->
+
+== market ==
+A merchant waved at you.
+*   Buy some fish.
+    The fish smelled awful.
+    -> DONE
+
+";
+
+    let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+    let mut resumed = Story::new(Arc::clone(&compiled));
+    let mut continued = Story::new(Arc::clone(&compiled));
+
+    for &choice in [1, 1, 2].iter() {
+        let mut line_buffer = Vec::new();
+        let choices = resumed.resume(&mut line_buffer).unwrap().get_choices();
+
+        assert_eq!(continue_lines(&mut continued), line_buffer);
+        assert_eq!(continued.get_current_choices(), choices);
+        assert!(choices.is_some());
+
+        resumed.make_choice(choice).unwrap();
+        continued.make_choice(choice).unwrap();
+    }
+
+    let mut line_buffer = Vec::new();
+    resumed.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(continue_lines(&mut continued), line_buffer);
+    assert!(continued.get_current_choices().is_none());
+}
+
+#[test]
+fn lines_glued_to_the_next_line_are_returned_without_a_newline() {
+    let content = "
+
+The harbor was quiet
+<> at this hour.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let line = story.continue_line().unwrap().unwrap();
+    assert_eq!(&line.text, "The harbor was quiet ");
+
+    let line = story.continue_line().unwrap().unwrap();
+    assert_eq!(&line.text, "at this hour.\n");
+
+    assert!(!story.can_continue());
+    assert!(story.continue_line().unwrap().is_none());
+}
+
+#[test]
+fn external_functions_after_the_returned_line_are_called_when_continuing_to_the_next_line() {
+    let content = "
+
+EXTERNAL play_sound(name)
+
+~ play_sound(\"waves.wav\")
+The waves crashed against the rocks.
+~ play_sound(\"gulls.wav\")
+The gulls took flight.
+~ play_sound(\"bell.wav\")
+A bell rang in the distance.
+~ play_sound(\"wind.wav\")
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let played_sounds = Arc::new(Mutex::new(Vec::new()));
+    let played_sounds_clone = played_sounds.clone();
+
+    story
+        .bind_external_function("play_sound", 1, move |arguments: &[Variable]| {
+            played_sounds_clone
+                .lock()
+                .unwrap()
+                .push(arguments[0].to_string().unwrap());
+
+            Ok::<_, String>(Variable::Bool(true))
+        })
+        .unwrap();
+
+    let line = story.continue_line().unwrap().unwrap();
+
+    assert_eq!(&line.text, "The waves crashed against the rocks.\n");
+    assert_eq!(*played_sounds.lock().unwrap(), &["waves.wav"]);
+
+    story.continue_line().unwrap().unwrap();
+
+    assert_eq!(*played_sounds.lock().unwrap(), &["waves.wav", "gulls.wav"]);
+
+    story.continue_line().unwrap().unwrap();
+
+    assert_eq!(
+        *played_sounds.lock().unwrap(),
+        &["waves.wav", "gulls.wav", "bell.wav"]
+    );
+
+    assert!(story.continue_line().unwrap().is_none());
+
+    assert_eq!(
+        *played_sounds.lock().unwrap(),
+        &["waves.wav", "gulls.wav", "bell.wav", "wind.wav"]
+    );
+}
+
+#[test]
+fn variable_assignments_after_the_returned_line_are_evaluated_when_continuing_to_the_next_line() {
+    let content = "
+
+VAR x = 0
+
+One
+~ x = 1
+Two
+~ x = 2
+Three
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let line = story.continue_line().unwrap().unwrap();
+    assert_eq!(&line.text, "One\n");
+    assert_eq!(story.get_variable("x"), Some(Variable::Int(0)));
+
+    let line = story.continue_line().unwrap().unwrap();
+    assert_eq!(&line.text, "Two\n");
+    assert_eq!(story.get_variable("x"), Some(Variable::Int(1)));
+}
+
+#[test]
+fn story_cannot_continue_after_the_last_line_before_choices() {
+    let content = "
+
+The ferry was about to leave.
+*   Get on board.
+*   Stay ashore.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(story.continue_line().unwrap().is_some());
+    assert!(!story.can_continue());
+    assert_eq!(story.get_current_choices().unwrap().len(), 2);
+}
+
+#[test]
+fn logic_after_the_last_line_before_choices_is_evaluated_when_continuing_past_it() {
+    let content = "
+
+VAR x = 0
+
+The ferry was about to leave.
+~ x = 1
+*   Get on board.
+*   Stay ashore.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(story.continue_line().unwrap().is_some());
+    assert_eq!(story.get_variable("x"), Some(Variable::Int(0)));
+
+    assert!(story.can_continue());
+    assert!(story.continue_line().unwrap().is_none());
+
+    assert!(!story.can_continue());
+    assert_eq!(story.get_current_choices().unwrap().len(), 2);
+    assert_eq!(story.get_variable("x"), Some(Variable::Int(1)));
+}
+
+#[test]
+fn lines_are_not_glued_across_function_calls_when_continuing() {
+    let content = "
+
+EXTERNAL ping()
+
+Hello
+~ ping()
+<> world.
+
+";
+
+    let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+    let mut resumed = Story::new(Arc::clone(&compiled));
+    let mut continued = Story::new(Arc::clone(&compiled));
+
+    resumed
+        .bind_external_function("ping", 0, |_: &[Variable]| {
+            Ok::<_, String>(Variable::Int(0))
+        })
+        .unwrap();
+    continued
+        .bind_external_function("ping", 0, |_: &[Variable]| {
+            Ok::<_, String>(Variable::Int(0))
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+    resumed.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Hello ");
+    assert_eq!(&line_buffer[1].text, "world.\n");
+
+    let lines = continue_lines(&mut continued);
+
+    assert_eq!(&lines[0].text, "Hello\n");
+    assert_eq!(&lines[1].text, "world.\n");
+}
+
+#[test]
+fn story_cannot_continue_if_only_choices_remain() {
+    let content = "
+
+*   Get on board.
+*   Stay ashore.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(!story.can_continue());
+    assert!(story.continue_line().unwrap().is_none());
+    assert_eq!(story.get_current_choices().unwrap().len(), 2);
+}
+
+#[test]
+fn story_cannot_continue_at_choices_until_a_choice_is_made() {
+    let content = "
+
+The ferry was about to leave.
+*   Get on board.
+    You found a seat by the window.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(story.can_continue());
+    story.continue_line().unwrap().unwrap();
+
+    assert!(!story.can_continue());
+    assert!(story.continue_line().unwrap().is_none());
+    assert_eq!(story.get_current_choices().unwrap().len(), 1);
+
+    story.make_choice(0).unwrap();
+
+    assert!(story.can_continue());
+    assert_eq!(
+        continue_lines(&mut story)
+            .into_iter()
+            .map(|line| line.text)
+            .collect::<Vec<_>>(),
+        &["Get on board.\n", "You found a seat by the window.\n"]
+    );
+}
+
+#[test]
+fn resume_returns_the_lines_which_were_not_yet_continued_to() {
+    let content = "
+
+The first bell rang.
+The second bell rang.
+The third bell rang.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story.continue_line().unwrap().unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "The second bell rang.\n");
+    assert_eq!(&line_buffer[1].text, "The third bell rang.\n");
+}
+
+#[test]
+fn moving_to_a_location_discards_lines_which_were_not_yet_continued_to() {
+    let content = "
+
+-> bells
+
+== bells ==
+The first bell rang.
+The second bell rang.
+The third bell rang.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story.continue_line().unwrap().unwrap();
+    story.move_to(&Location::from("bells")).unwrap();
+
+    let line = story.continue_line().unwrap().unwrap();
+    assert_eq!(&line.text, "The first bell rang.\n");
+}
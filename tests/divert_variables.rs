@@ -0,0 +1,157 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn diverts_to_global_variables_follow_their_divert_target() {
+    let content = "
+
+VAR next = -> addis_ababa
+
+-> next
+
+=== addis_ababa ===
+You find yourself in Addis Ababa.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You find yourself in Addis Ababa.\n");
+}
+
+#[test]
+fn divert_target_variables_can_be_assigned_and_compared() {
+    let content = "
+
+VAR next = -> addis_ababa
+
+~ next = -> nairobi
+
+{next == -> nairobi: Heading south.}
+-> next
+
+=== addis_ababa ===
+You find yourself in Addis Ababa.
+-> END
+
+=== nairobi ===
+You find yourself in Nairobi.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Heading south.\n");
+    assert_eq!(&line_buffer[1].text, "You find yourself in Nairobi.\n");
+}
+
+#[test]
+fn divert_targets_can_be_passed_as_arguments_and_followed() {
+    let content = "
+
+-> travel(-> nairobi)
+
+=== travel(destination) ===
+You board the train.
+-> destination
+
+=== nairobi ===
+You find yourself in Nairobi.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You board the train.\n");
+    assert_eq!(&line_buffer[1].text, "You find yourself in Nairobi.\n");
+}
+
+#[test]
+fn temporary_divert_target_variables_are_followed() {
+    let content = "
+
+~ temp next = -> nairobi
+-> next
+
+=== nairobi ===
+You find yourself in Nairobi.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You find yourself in Nairobi.\n");
+}
+
+#[test]
+fn tunnels_can_be_called_through_divert_target_variables() {
+    let content = "
+
+VAR tunnel = -> station
+
+-> tunnel ->
+You leave the station.
+-> END
+
+=== station ===
+You buy a ticket.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You buy a ticket.\n");
+    assert_eq!(&line_buffer[1].text, "You leave the station.\n");
+}
+
+#[test]
+fn diverts_to_variables_which_are_not_divert_targets_yield_validation_errors() {
+    let content = "
+
+VAR next = 2
+-> next
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        Err(err) => panic!("expected a validation error but got {:?}", err),
+        Ok(_) => panic!("expected a validation error but the story was read"),
+    }
+}
+
+#[test]
+fn divert_target_variables_with_unknown_addresses_yield_validation_errors() {
+    let content = "
+
+VAR next = -> nowhere
+-> next
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        Err(err) => panic!("expected a validation error but got {:?}", err),
+        Ok(_) => panic!("expected a validation error but the story was read"),
+    }
+}
@@ -0,0 +1,114 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn line_tags_print_variables_and_conditions() {
+    let content = "
+
+VAR character = \"anna\"
+VAR mood = 3
+
+I looked up. # portrait_{character} # mood: {mood > 2: happy|sad}
+~ character = \"bert\"
+~ mood = 1
+He looked back. # portrait_{character} # mood: {mood > 2: happy|sad}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].tags, &["portrait_anna", "mood: happy"]);
+    assert_eq!(&line_buffer[1].tags, &["portrait_bert", "mood: sad"]);
+}
+
+#[test]
+fn alternatives_in_line_tags_advance_when_the_line_is_visited() {
+    let content = "
+
+-> hall
+
+=== hall ===
+The clock struck. # sound: {cycle: tick|tock}.ogg
++ [Wait] -> hall
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut tags = Vec::new();
+
+    for _ in 0..3 {
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+
+        tags.push(line_buffer[0].tags[0].clone());
+    }
+
+    assert_eq!(
+        &tags,
+        &["sound: tick.ogg", "sound: tock.ogg", "sound: tick.ogg"]
+    );
+}
+
+#[test]
+fn choice_tags_are_processed_when_the_choices_are_presented() {
+    let content = "
+
+VAR coins = 5
+
+* Buy the apple. # price: {coins - 2}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => assert_eq!(&choices[0].tags, &["price: 3"]),
+        other => panic!("expected a choice but got {:?}", other),
+    }
+}
+
+#[test]
+fn knot_tags_are_processed_with_the_current_state_of_the_story() {
+    let content = "
+
+VAR time = \"day\"
+
+-> garden
+
+=== garden ===
+# light: {time}
+~ time = \"night\"
+The garden was quiet.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert_eq!(story.get_knot_tags("garden").unwrap(), &["light: day"]);
+
+    story.resume(&mut Vec::new()).unwrap();
+
+    assert_eq!(story.get_knot_tags("garden").unwrap(), &["light: night"]);
+}
+
+#[test]
+fn tags_with_unknown_variables_yield_validation_errors() {
+    let content = "
+
+Hello! # portrait_{character}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        Err(err) => panic!("expected a validation error but got {:?}", err),
+        Ok(_) => panic!("expected a validation error but the story was read"),
+    }
+}
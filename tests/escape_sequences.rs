@@ -0,0 +1,112 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn escaped_markers_are_printed_as_text() {
+    let content = r#"
+
+Issue \#42 is fixed \-> see https:\/\/example.com // Comment
+Use \{braces\}, \[brackets\] and pipes \| freely.
+A backslash: \\
+Glue is written as \<>
+
+"#;
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Issue #42 is fixed -> see https://example.com\n"
+    );
+    assert!(line_buffer[0].tags.is_empty());
+    assert_eq!(
+        &line_buffer[1].text,
+        "Use {braces}, [brackets] and pipes | freely.\n"
+    );
+    assert_eq!(&line_buffer[2].text, "A backslash: \\\n");
+    assert_eq!(&line_buffer[3].text, "Glue is written as <>\n");
+}
+
+#[test]
+fn escaped_markers_at_the_beginning_of_lines_do_not_begin_choices_or_gathers() {
+    let content = r#"
+
+\* Not a choice
+\- Not a gather
+\~ Not a logic line
+
+"#;
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "* Not a choice\n");
+    assert_eq!(&line_buffer[1].text, "- Not a gather\n");
+    assert_eq!(&line_buffer[2].text, "~ Not a logic line\n");
+}
+
+#[test]
+fn choices_can_contain_escaped_brackets_and_tag_markers() {
+    let content = r#"
+
+*   Open the \[locked\] box[.] carefully.
+*   Ring the bell. # sound: bell\#2.ogg
+
+"#;
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => {
+            assert_eq!(&choices[0].text, "Open the [locked] box.");
+            assert_eq!(&choices[1].tags, &["sound: bell#2.ogg"]);
+        }
+        other => panic!("expected a choice but got {:?}", other),
+    }
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Open the [locked] box carefully.\n");
+}
+
+#[test]
+fn strings_can_contain_escaped_quotes_and_markers() {
+    let content = r#"
+
+VAR quote = "She said \"Hello\" \| waved"
+
+{quote}
+{"Path\: C\:\\Games"}
+
+"#;
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "She said \"Hello\" | waved\n");
+    assert_eq!(&line_buffer[1].text, "Path: C:\\Games\n");
+}
+
+#[test]
+fn unescaped_brackets_are_still_errors() {
+    let content = r#"
+
+*   Open the [locked\] box
+
+"#;
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(..)) => (),
+        Err(err) => panic!("expected a parse error but got {:?}", err),
+        Ok(_) => panic!("expected a parse error but the story was read"),
+    }
+}
@@ -0,0 +1,174 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+fn read_lines(content: &str) -> Vec<String> {
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    line_buffer.into_iter().map(|line| line.text).collect()
+}
+
+#[test]
+fn comparisons_can_be_printed() {
+    let content = "
+
+VAR x = 5
+
+{x > 3} {x < 3} {x == 5} {x != 5} {x >= 5} {x <= 4}
+
+";
+
+    assert_eq!(&read_lines(content)[0], "1 0 1 0 1 0\n");
+}
+
+#[test]
+fn logical_operators_can_be_assigned() {
+    let content = "
+
+VAR a = true
+VAR b = false
+VAR ok = false
+
+~ ok = a and b
+{ok}
+~ ok = a or b
+{ok}
+~ ok = not b && a
+{ok}
+~ ok = !a || b
+{ok}
+
+";
+
+    assert_eq!(read_lines(content), &["0\n", "1\n", "1\n", "0\n"]);
+}
+
+#[test]
+fn logical_or_is_not_mistaken_for_alternatives() {
+    let content = "
+
+VAR a = true
+VAR b = false
+
+{a || b}
+
+";
+
+    assert_eq!(&read_lines(content)[0], "1\n");
+}
+
+#[test]
+fn comparisons_have_lower_precedence_than_mathematical_operators() {
+    let content = "
+
+VAR x = 2
+
+{x + 1 == 3 and x * 2 > 3}
+{not (x == 2)}
+
+";
+
+    assert_eq!(read_lines(content), &["1\n", "0\n"]);
+}
+
+#[test]
+fn negations_apply_to_the_operand_after_them() {
+    let content = "
+
+VAR x = 2
+
+{not x == false}
+
+";
+
+    assert_eq!(read_lines(content), &["1\n"]);
+}
+
+#[test]
+fn strings_can_be_checked_for_containing_other_strings() {
+    let content = "
+
+VAR greeting = \"Hello, World!\"
+
+{greeting ? \"World\"} {greeting !? \"World\"}
+{greeting ? \"Moon\": Hello, Moon! | Goodbye, Moon!}
+
+";
+
+    assert_eq!(read_lines(content), &["1 0\n", "Goodbye, Moon!\n"]);
+}
+
+#[test]
+fn comparisons_can_be_given_as_function_arguments() {
+    let content = "
+
+VAR x = 5
+
+{describe(x > 3)}
+
+=== function describe(is_large)
+{ is_large:
+    ~ return \"large\"
+}
+~ return \"small\"
+
+";
+
+    assert_eq!(&read_lines(content)[0], "large\n");
+}
+
+#[test]
+fn comparisons_inside_function_arguments_work_in_conditions() {
+    let content = "
+
+VAR x = 5
+
+{describe(x > 3) == \"large\": The comparison was true.}
+
+=== function describe(is_large)
+{ is_large:
+    ~ return \"large\"
+}
+~ return \"small\"
+
+";
+
+    assert_eq!(&read_lines(content)[0], "The comparison was true.\n");
+}
+
+#[test]
+fn comparisons_between_invalid_types_yield_validation_errors() {
+    let content = "
+
+VAR x = 5
+VAR ok = false
+
+~ ok = x > \"five\"
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        Err(err) => panic!("expected a validation error but got {:?}", err),
+        Ok(_) => panic!("expected a validation error but the story was read"),
+    }
+}
+
+#[test]
+fn logical_expressions_must_assign_to_boolean_variables() {
+    let content = "
+
+VAR x = 5
+
+~ x = x > 3
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        Err(err) => panic!("expected a validation error but got {:?}", err),
+        Ok(_) => panic!("expected a validation error but the story was read"),
+    }
+}
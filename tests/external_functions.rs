@@ -0,0 +1,256 @@
+use inkling::error::{parse::validate::InvalidFunctionCallKind, InklingError, ReadError};
+use inkling::*;
+
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn external_functions_can_be_called_from_expressions_conditions_and_logic_lines() {
+    let content = "
+
+EXTERNAL play_sound(name)
+EXTERNAL get_weather()
+EXTERNAL is_raining()
+
+~ play_sound(\"thunder.wav\")
+The forecast said {get_weather()}.
+{is_raining(): I brought an umbrella.|I left the umbrella at home.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let played_sounds = Arc::new(Mutex::new(Vec::new()));
+    let played_sounds_clone = played_sounds.clone();
+
+    story
+        .bind_external_function("play_sound", 1, move |arguments: &[Variable]| {
+            played_sounds_clone
+                .lock()
+                .unwrap()
+                .push(arguments[0].clone());
+
+            Ok::<_, String>(Variable::Bool(true))
+        })
+        .unwrap();
+
+    story
+        .bind_external_function("get_weather", 0, |_: &[Variable]| {
+            Ok::<_, String>(Variable::from("rain"))
+        })
+        .unwrap();
+
+    story
+        .bind_external_function("is_raining", 0, |_: &[Variable]| {
+            Ok::<_, String>(Variable::Bool(true))
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The forecast said rain.\n");
+    assert_eq!(&line_buffer[1].text, "I brought an umbrella.\n");
+
+    assert_eq!(
+        *played_sounds.lock().unwrap(),
+        vec![Variable::String("thunder.wav".to_string())]
+    );
+}
+
+#[test]
+fn external_function_values_can_be_assigned_to_variables() {
+    let content = "
+
+EXTERNAL add(a, b)
+VAR sum = 0
+
+~ temp x = add(1, 2)
+~ sum = add(x, 10)
+The sum is {sum}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .bind_external_function("add", 2, |arguments: &[Variable]| {
+            arguments[0].add(&arguments[1])
+        })
+        .unwrap();
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The sum is 13.\n");
+    assert_eq!(story.get_variable("sum"), Some(Variable::Int(13)));
+}
+
+#[test]
+fn binding_function_with_wrong_number_of_arguments_yields_error() {
+    let content = "
+
+EXTERNAL play_sound(name, volume)
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    match story.bind_external_function("play_sound", 1, |_: &[Variable]| {
+        Ok::<_, String>(Variable::Bool(true))
+    }) {
+        Err(InklingError::InvalidExternalFunctionArity {
+            num_declared,
+            num_bound,
+            ..
+        }) => {
+            assert_eq!(num_declared, 2);
+            assert_eq!(num_bound, 1);
+        }
+        other => panic!(
+            "expected `InklingError::InvalidExternalFunctionArity` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn binding_function_to_undeclared_name_yields_error() {
+    let mut story = read_story_from_string("Hello, World!").unwrap();
+
+    match story.bind_external_function("play_sound", 1, |_: &[Variable]| {
+        Ok::<_, String>(Variable::Bool(true))
+    }) {
+        Err(InklingError::UnknownExternalFunction { name }) => assert_eq!(&name, "play_sound"),
+        other => panic!(
+            "expected `InklingError::UnknownExternalFunction` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn resuming_story_with_unbound_external_functions_yields_error_with_their_names() {
+    let content = "
+
+EXTERNAL play_sound(name)
+EXTERNAL get_weather()
+
+Hello, World!
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer) {
+        Err(InklingError::UnboundExternalFunctions { names }) => assert_eq!(
+            names,
+            vec!["get_weather".to_string(), "play_sound".to_string()]
+        ),
+        other => panic!(
+            "expected `InklingError::UnboundExternalFunctions` but got {:?}",
+            other
+        ),
+    }
+
+    assert!(line_buffer.is_empty());
+}
+
+#[test]
+fn errors_from_external_functions_are_returned_when_resuming() {
+    let content = "
+
+EXTERNAL fail()
+
+{fail()}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .bind_external_function("fail", 0, |_: &[Variable]| {
+            Err::<Variable, _>("the function failed")
+        })
+        .unwrap();
+
+    match story.resume(&mut Vec::new()) {
+        Err(InklingError::ExternalFunctionError { name, message }) => {
+            assert_eq!(&name, "fail");
+            assert_eq!(&message, "the function failed");
+        }
+        other => panic!(
+            "expected `InklingError::ExternalFunctionError` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn errors_from_external_functions_in_choice_conditions_are_returned_when_resuming() {
+    let content = "
+
+EXTERNAL fail()
+
+*   {fail()} A choice behind a failing condition.
+*   Another choice.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story
+        .bind_external_function("fail", 0, |_: &[Variable]| {
+            Err::<Variable, _>("the function failed")
+        })
+        .unwrap();
+
+    match story.resume(&mut Vec::new()) {
+        Err(InklingError::ExternalFunctionError { name, message }) => {
+            assert_eq!(&name, "fail");
+            assert_eq!(&message, "the function failed");
+        }
+        other => panic!(
+            "expected `InklingError::ExternalFunctionError` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn calling_undeclared_functions_or_with_wrong_number_of_arguments_yields_validation_errors() {
+    let content = "
+
+EXTERNAL play_sound(name)
+
+~ play_sound()
+{get_weather()}
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(error)) => {
+            assert_eq!(error.function_call_errors.len(), 2);
+
+            match &error.function_call_errors[0].kind {
+                &InvalidFunctionCallKind::WrongNumberOfArguments { expected, found } => {
+                    assert_eq!(expected, 1);
+                    assert_eq!(found, 0);
+                }
+                other => panic!(
+                    "expected `InvalidFunctionCallKind::WrongNumberOfArguments` but got {:?}",
+                    other
+                ),
+            }
+
+            match &error.function_call_errors[1].kind {
+                InvalidFunctionCallKind::UnknownFunction => (),
+                other => panic!(
+                    "expected `InvalidFunctionCallKind::UnknownFunction` but got {:?}",
+                    other
+                ),
+            }
+        }
+        other => panic!("expected `ReadError::ValidationError` but got {:?}", other),
+    }
+}
@@ -0,0 +1,293 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn functions_return_values_to_expressions_and_assignments() {
+    let content = "
+
+VAR total = 0
+
+~ total = add(1, 2)
+One plus two is {add(1, 2)}.
+The total is {total}, doubled is {double(total)}.
+
+=== function add(a, b) ===
+~ return a + b
+
+=== function double(x) ===
+~ temp result = x * 2
+~ return result
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "One plus two is 3.\n");
+    assert_eq!(&line_buffer[1].text, "The total is 3, doubled is 6.\n");
+    assert_eq!(story.get_variable("total").unwrap(), Variable::Int(3));
+}
+
+#[test]
+fn functions_can_be_used_in_conditions() {
+    let content = "
+
+{is_large(10): Large|Small}
+{is_large(1): Large|Small}
+
+=== function is_large(x) ===
+{ x > 5:
+    ~ return true
+}
+~ return false
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Large\n");
+    assert_eq!(&line_buffer[1].text, "Small\n");
+}
+
+#[test]
+fn functions_can_call_other_functions_and_recurse() {
+    let content = "
+
+Five factorial is {factorial(5)}.
+Three squared plus one is {increment(square(3))}.
+
+=== function factorial(n) ===
+{ n <= 1:
+    ~ return 1
+}
+~ return n * factorial(n - 1)
+
+=== function square(x) ===
+~ return x * x
+
+=== function increment(x) ===
+~ return x + 1
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Five factorial is 120.\n");
+    assert_eq!(&line_buffer[1].text, "Three squared plus one is 10.\n");
+}
+
+#[test]
+fn text_printed_by_functions_is_added_to_the_calling_line() {
+    let content = "
+
+~ greet(\"Phileas\")
+The weather was {describe_weather()} that day.
+
+=== function greet(name) ===
+Hello, {name}!
+
+=== function describe_weather() ===
+<> rainy <>
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Hello, Phileas!\n");
+    assert_eq!(&line_buffer[1].text, "The weather was rainy that day.\n");
+}
+
+#[test]
+fn functions_which_print_several_lines_add_them_as_separate_lines() {
+    let content = "
+
+~ announce()
+Back to the story.
+
+=== function announce() ===
+Attention, please.
+The train is leaving.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 3);
+    assert_eq!(&line_buffer[0].text, "Attention, please.\n");
+    assert_eq!(&line_buffer[1].text, "The train is leaving.\n");
+    assert_eq!(&line_buffer[2].text, "Back to the story.\n");
+}
+
+#[test]
+fn ref_parameters_write_back_into_the_variables_of_the_caller() {
+    let content = "
+
+VAR gold = 10
+
+~ temp silver = 3
+~ add_to(gold, 5)
+~ add_to(silver, 2)
+Gold: {gold}, silver: {silver}.
+
+=== function add_to(ref x, amount) ===
+~ x = x + amount
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Gold: 15, silver: 5.\n");
+    assert_eq!(story.get_variable("gold").unwrap(), Variable::Int(15));
+}
+
+#[test]
+fn temporary_variables_of_the_caller_are_not_visible_inside_functions() {
+    let content = "
+
+~ temp x = 1
+~ set_x()
+The value is {x}.
+
+=== function set_x() ===
+~ temp x = 10
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The value is 1.\n");
+}
+
+#[test]
+fn ink_function_is_used_as_fallback_for_unbound_external_function() {
+    let content = "
+
+EXTERNAL get_name()
+
+My name is {get_name()}.
+
+=== function get_name() ===
+~ return \"Passepartout\"
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "My name is Passepartout.\n");
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story
+        .bind_external_function("get_name", 0, |_: &[Variable]| {
+            Ok::<_, String>(Variable::from("Fogg"))
+        })
+        .unwrap();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "My name is Fogg.\n");
+}
+
+#[test]
+fn functions_which_divert_yield_a_validation_error() {
+    let content = "
+
+{leave()}
+
+=== function leave() ===
+{true: -> away}
+
+=== away ===
+Gone.
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => {
+            assert_eq!(err.function_content_errors.len(), 1);
+            assert_eq!(&err.function_content_errors[0].name, "leave");
+        }
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn functions_with_choices_threads_or_tunnels_yield_validation_errors() {
+    let content = "
+
+{choose()} {branch()} {visit()}
+
+=== function choose() ===
+*   A choice.
+
+=== function branch() ===
+<- away
+
+=== function visit() ===
+-> away ->
+
+=== away ===
+Gone.
+->->
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => {
+            let mut names = err
+                .function_content_errors
+                .iter()
+                .map(|err| err.name.as_str())
+                .collect::<Vec<_>>();
+
+            names.sort();
+
+            assert_eq!(names, &["branch", "choose", "visit"]);
+        }
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn functions_and_knots_cannot_share_names() {
+    let content = "
+
+-> double
+
+=== double ===
+Knot.
+
+=== function double(x) ===
+~ return x * 2
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(..)) => (),
+        other => panic!("expected a parse error but got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,170 @@
+use inkling::error::{parse::prelude::PreludeErrorKind, ReadError};
+use inkling::*;
+
+use std::collections::HashMap;
+
+#[test]
+fn variables_can_be_declared_between_and_inside_knots() {
+    let content = "
+
+-> hotel
+
+VAR coins = 3
+
+=== hotel ===
+VAR has_room = false
+~ has_room = coins > 2
+{has_room: I rented a room.}
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "I rented a room.\n");
+}
+
+#[test]
+fn initial_values_are_evaluated_from_constants() {
+    let content = "
+
+VAR hp = MAX_HP * 2
+CONST MAX_HP = 10
+CONST NAME = \"Anna\"
+VAR greeting = \"Hello, \" + NAME
+
+{greeting}, you have {hp} hit points.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "Hello, Anna, you have 20 hit points.\n"
+    );
+
+    assert_eq!(story.get_variable("hp"), Some(Variable::Int(20)));
+}
+
+#[test]
+fn variables_can_be_declared_in_the_knots_of_included_files() {
+    let main = "
+
+INCLUDE paris.ink
+-> paris
+
+";
+
+    let paris = "
+
+== paris ==
+CONST COINS = 3
+VAR coins = COINS + 1
+I arrived in Paris with {coins} coins.
+-> END
+
+";
+
+    let files = vec![("main.ink", main), ("paris.ink", paris)]
+        .into_iter()
+        .map(|(name, content)| (name.to_string(), content.to_string()))
+        .collect::<HashMap<_, _>>();
+
+    let mut story = read_story_with_loader("main.ink", &files).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 1);
+    assert_eq!(&line_buffer[0].text, "I arrived in Paris with 4 coins.\n");
+}
+
+#[test]
+fn cycles_and_references_to_variables_yield_prelude_errors() {
+    let content = "
+
+CONST A = B
+CONST B = A
+VAR c = 2
+VAR d = c + 1
+
+Hello, World!
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(error)) => {
+            assert_eq!(error.prelude_errors.len(), 2);
+
+            match &error.prelude_errors[0].kind {
+                PreludeErrorKind::ConstantCycle { names } => {
+                    assert_eq!(names, &["A", "B", "A"]);
+                }
+                other => panic!("expected a `ConstantCycle` error but got {:?}", other),
+            }
+
+            match &error.prelude_errors[1].kind {
+                PreludeErrorKind::NonConstantReference { name } => assert_eq!(name, "c"),
+                other => panic!(
+                    "expected a `NonConstantReference` error but got {:?}",
+                    other
+                ),
+            }
+        }
+        Err(err) => panic!("expected a parse error but got {:?}", err),
+        Ok(_) => panic!("expected a parse error but the story was read"),
+    }
+}
+
+#[test]
+fn initial_values_of_global_variables_may_be_list_items() {
+    let content = "
+
+LIST letters = a, b, c
+VAR pair = (b, c)
+VAR single = b
+VAR qualified = letters.c
+
+{pair} {single} {qualified}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "b, c b c\n");
+}
+
+#[test]
+fn unknown_names_in_initial_values_yield_prelude_errors() {
+    let content = "
+
+VAR a = C
+
+Hello, World!
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(error)) => {
+            assert_eq!(error.prelude_errors.len(), 1);
+
+            match &error.prelude_errors[0].kind {
+                PreludeErrorKind::UnknownReference { name } => assert_eq!(name, "C"),
+                other => panic!("expected an `UnknownReference` error but got {:?}", other),
+            }
+        }
+        Err(err) => panic!("expected a parse error but got {:?}", err),
+        Ok(_) => panic!("expected a parse error but the story was read"),
+    }
+}
@@ -0,0 +1,103 @@
+use inkling::error::{parse::include::IncludeErrorKind, ReadError};
+use inkling::*;
+
+use std::collections::HashMap;
+
+fn get_files(files: &[(&str, &str)]) -> HashMap<String, String> {
+    files
+        .iter()
+        .map(|(name, content)| (name.to_string(), content.to_string()))
+        .collect()
+}
+
+#[test]
+fn stories_can_divert_to_knots_in_included_files() {
+    let main = "
+
+INCLUDE paris.ink
+VAR visited_paris = false
+
+I took the train to Paris.
+-> paris
+
+";
+
+    let paris = "
+
+VAR coins = 3
+
+== paris ==
+~ visited_paris = true
+I arrived in Paris with {coins} coins.
+-> END
+
+";
+
+    let files = get_files(&[("main.ink", main), ("paris.ink", paris)]);
+
+    let mut story = read_story_with_loader("main.ink", &files).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "I took the train to Paris.\n");
+    assert_eq!(&line_buffer[1].text, "I arrived in Paris with 3 coins.\n");
+
+    assert_eq!(
+        story.get_variable("visited_paris"),
+        Some(Variable::Bool(true))
+    );
+}
+
+#[test]
+fn stories_can_be_read_from_files_in_a_directory() {
+    let directory = std::env::temp_dir().join("inkling_include_integration_test");
+    std::fs::create_dir_all(&directory).unwrap();
+
+    std::fs::write(directory.join("main.ink"), "INCLUDE paris.ink\n-> paris").unwrap();
+    std::fs::write(directory.join("paris.ink"), "== paris ==\nBonjour!").unwrap();
+
+    let loader = FileStoryLoader::new(&directory);
+
+    let mut story = read_story_with_loader("main.ink", &loader).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Bonjour!\n");
+}
+
+#[test]
+fn include_cycles_yield_include_errors() {
+    let files = get_files(&[
+        ("main.ink", "INCLUDE paris.ink\n-> paris"),
+        ("paris.ink", "INCLUDE main.ink\n== paris ==\nBonjour!"),
+    ]);
+
+    match read_story_with_loader("main.ink", &files) {
+        Err(ReadError::IncludeError(error)) => match error.kind {
+            IncludeErrorKind::IncludeCycle { cycle } => {
+                assert_eq!(cycle, &["main.ink", "paris.ink", "main.ink"]);
+            }
+            other => panic!("expected include cycle but got {:?}", other),
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn errors_from_included_files_are_printed_with_the_file_name() {
+    let files = get_files(&[
+        ("main.ink", "INCLUDE paris.ink\n-> paris"),
+        ("paris.ink", "== paris ==\n-> louvre"),
+    ]);
+
+    match read_story_with_loader("main.ink", &files) {
+        Err(error @ ReadError::ValidationError(..)) => {
+            let message = error::parse::print_read_error(&error).unwrap();
+            assert!(message.contains("paris.ink, line 2"));
+        }
+        _ => panic!(),
+    }
+}
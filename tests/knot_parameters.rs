@@ -0,0 +1,256 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn arguments_in_diverts_are_bound_to_knot_parameters() {
+    let content = "
+
+-> meet(\"Anna\", 3)
+
+=== meet(character, mood) ===
+You meet {character}, who is in mood {mood}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You meet Anna, who is in mood 3.\n");
+}
+
+#[test]
+fn knots_with_parameters_can_be_entered_with_different_arguments() {
+    let content = "
+
+VAR guest = \"Bertil\"
+
+*   [Anna] -> meet(\"Anna\")
+*   [Guest] -> meet(guest)
+
+=== meet(character) ===
+Hello, {character}!
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Hello, Bertil!\n");
+}
+
+#[test]
+fn arguments_are_evaluated_as_expressions_before_the_divert() {
+    let content = "
+
+~ temp count = 2
+-> counter(count * 10 + 1, \"coins\")
+
+=== counter(amount, unit) ===
+~ amount = amount + 1
+You have {amount} {unit}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You have 22 coins.\n");
+}
+
+#[test]
+fn stitches_may_have_parameters() {
+    let content = "
+
+-> shop.greet(\"Cecilia\")
+
+=== shop ===
+= greet(name)
+Welcome to the shop, {name}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Welcome to the shop, Cecilia.\n");
+}
+
+#[test]
+fn parameters_are_kept_when_diverting_to_labels_in_the_same_stitch() {
+    let content = "
+
+-> meet(\"Anna\")
+
+=== meet(character) ===
+- (talk) You talk to {character}.
++   [Again] -> talk
+*   [Leave] -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "You talk to Anna.\nYou talk to Anna.\n");
+}
+
+#[test]
+fn parameters_are_not_kept_after_leaving_the_knot() {
+    let content = "
+
+-> meet(\"Anna\")
+
+=== meet(character) ===
+You meet {character}.
+-> leave
+
+=== leave ===
+~ temp character = \"nobody\"
+You leave {character}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "You meet Anna.\nYou leave nobody.\n");
+}
+
+#[test]
+fn move_to_with_args_binds_arguments_to_parameters() {
+    let content = "
+
+=== meet(character, mood) ===
+You meet {character}, who is in mood {mood}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story
+        .move_to_with_args(&"meet".into(), &["Anna".into(), Variable::Int(3)])
+        .unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You meet Anna, who is in mood 3.\n");
+}
+
+#[test]
+fn moving_to_knot_with_wrong_number_of_arguments_yields_error() {
+    let content = "
+
+=== meet(character, mood) ===
+You meet {character}, who is in mood {mood}.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    match story.move_to_with_args(&"meet".into(), &["Anna".into()]) {
+        Err(InklingError::InvalidNumberOfArguments {
+            num_parameters: 2,
+            num_arguments: 1,
+            ..
+        }) => (),
+        other => panic!(
+            "expected `InklingError::InvalidNumberOfArguments` but got {:?}",
+            other
+        ),
+    }
+
+    match story.move_to(&"meet".into()) {
+        Err(InklingError::InvalidNumberOfArguments {
+            num_parameters: 2,
+            num_arguments: 0,
+            ..
+        }) => (),
+        other => panic!(
+            "expected `InklingError::InvalidNumberOfArguments` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn diverts_with_wrong_number_of_arguments_are_validated() {
+    let content = "
+
+-> meet(\"Anna\")
+
+=== meet(character, mood) ===
+You meet {character}, who is in mood {mood}.
+-> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn diverts_without_arguments_to_knots_with_parameters_are_validated() {
+    let content = "
+
+-> meet
+
+=== meet(character) ===
+You meet {character}.
+-> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn divert_arguments_are_validated_as_expressions() {
+    let content = "
+
+-> meet(unknown_variable)
+
+=== meet(character) ===
+You meet {character}.
+-> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
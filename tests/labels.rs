@@ -0,0 +1,231 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+#[test]
+fn gathers_can_be_labelled_and_diverted_to() {
+    let content = "
+
+-> hallway
+
+== hallway ==
+- (loop) You are in the hallway.
++   [Wait] -> loop
+*   [Leave]
+    You leave the hallway.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You are in the hallway.\n");
+    assert_eq!(&line_buffer[1].text, "You are in the hallway.\n");
+    assert_eq!(&line_buffer[2].text, "You leave the hallway.\n");
+}
+
+#[test]
+fn temporary_variables_are_kept_when_looping_back_to_a_label() {
+    let content = "
+
+-> counting
+
+== counting ==
+~ temp x = 0
+- (loop)
+~ x++
+Count: {x}.
+{x < 3: -> loop}
+Done counting.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = line_buffer
+        .iter()
+        .map(|line| line.text.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        text,
+        &[
+            "Count: 1.\n",
+            "Count: 2.\n",
+            "Count: 3.\n",
+            "Done counting.\n"
+        ]
+    );
+}
+
+#[test]
+fn labels_count_their_visits_and_can_be_used_as_variables() {
+    let content = "
+
+-> hallway
+
+== hallway ==
+- (loop) You have been in the hallway {loop} times.
++   [Wait] -> loop
+*   (leave) [Leave]
+-   {leave > 0: You left.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(
+        &line_buffer[0].text,
+        "You have been in the hallway 1 times.\n"
+    );
+    assert_eq!(
+        &line_buffer[1].text,
+        "You have been in the hallway 2 times.\n"
+    );
+    assert_eq!(&line_buffer[2].text, "You left.\n");
+
+    assert_eq!(
+        story.get_num_visited(&Location::from("hallway.loop")),
+        Some(2)
+    );
+    assert_eq!(
+        story.get_num_visited(&Location::from("hallway.leave")),
+        Some(1)
+    );
+}
+
+#[test]
+fn diverts_to_labelled_choices_continue_inside_the_branch() {
+    let content = "
+
+-> hall.enter
+
+== hall ==
+You stand before the hall.
+*   (enter) [Enter] You enter the hall.
+    It is dark inside.
+*   [Leave] You leave.
+-   The door closes behind you.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "It is dark inside.\n");
+    assert_eq!(&line_buffer[1].text, "The door closes behind you.\n");
+}
+
+#[test]
+fn labels_can_be_addressed_from_other_stitches_and_knots() {
+    let content = "
+
+-> cellar
+
+== house ==
+= kitchen
+- (table) The kitchen table is set.
+-> END
+
+== cellar ==
+= stairs
+-> wine_rack.rack
+
+= wine_rack
+- (rack) The rack is empty.
+-> house.kitchen.table
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The rack is empty.\n");
+    assert_eq!(&line_buffer[1].text, "The kitchen table is set.\n");
+}
+
+#[test]
+fn story_can_be_moved_to_a_label() {
+    let content = "
+
+== hall ==
+You stand before the hall.
+*   (enter) [Enter] You enter the hall.
+    It is dark inside.
+*   [Leave] You leave.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.move_to(&Location::from("hall.enter")).unwrap();
+    assert_eq!(
+        story.get_current_location(),
+        Location::with_label("hall", None, "enter")
+    );
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "It is dark inside.\n");
+    assert_eq!(
+        story.get_num_visited(&Location::from("hall.enter")),
+        Some(1)
+    );
+}
+
+#[test]
+fn moving_to_an_unknown_label_yields_error() {
+    let content = "
+
+== hall ==
+- (inside) You stand in the hall.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    assert!(story.move_to(&Location::from("hall.outside")).is_err());
+    assert!(story
+        .move_to(&Location::with_label("hall", None, "outside"))
+        .is_err());
+    assert!(story
+        .get_num_visited(&Location::from("hall.outside"))
+        .is_none());
+}
+
+#[test]
+fn diverts_to_unknown_labels_are_validated() {
+    let content = "
+
+== hall ==
+- (inside) You stand in the hall.
+-> hall.outside
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(..)) => (),
+        _ => panic!(),
+    }
+}
@@ -0,0 +1,340 @@
+use inkling::*;
+
+fn get_list(story: &Story, name: &str) -> InkList {
+    match story.get_variable(name).unwrap() {
+        Variable::List(list) => list,
+        other => panic!("expected a list variable but got {:?}", other),
+    }
+}
+
+fn get_item_names(list: &InkList) -> Vec<&str> {
+    list.items().iter().map(|item| item.name.as_str()).collect()
+}
+
+#[test]
+fn list_variables_start_with_the_items_in_parenthesis() {
+    let content = "
+
+LIST inventory = (sword), lamp, (rope)
+
+You carry: {inventory}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You carry: sword, rope.\n");
+    assert_eq!(
+        get_item_names(&get_list(&story, "inventory")),
+        vec!["sword", "rope"]
+    );
+}
+
+#[test]
+fn items_are_added_and_removed_with_addition_and_subtraction() {
+    let content = "
+
+LIST inventory = (sword), lamp, rope
+
+~ inventory += lamp
+~ inventory -= sword
+~ inventory = inventory + (rope, sword)
+You carry: {inventory}.
+~ inventory = ()
+You carry: {inventory}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You carry: sword, lamp, rope.\n");
+    assert_eq!(&line_buffer[1].text, "You carry: .\n");
+}
+
+#[test]
+fn has_and_hasnt_check_whether_lists_contain_items() {
+    let content = "
+
+LIST inventory = (sword), lamp, (rope)
+
+{inventory has sword: You have a sword.}
+{inventory ? (sword, rope): You have a sword and rope.}
+{inventory hasnt lamp: You have no lamp.}
+{inventory !? (lamp, rope): You do not have both a lamp and rope.}
+{inventory has lamp: You have a lamp.|You have no lamp.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "You have a sword.\nYou have a sword and rope.\nYou have no lamp.\n\
+         You do not have both a lamp and rope.\nYou have no lamp.\n"
+    );
+}
+
+#[test]
+fn items_can_be_qualified_with_their_list_name() {
+    let content = "
+
+LIST colors = red, green
+LIST fruits = apple, (orange)
+
+~ temp choice = colors.red + fruits.orange
+{choice}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "red, orange\n");
+}
+
+#[test]
+fn intersection_yields_items_in_both_lists() {
+    let content = "
+
+LIST inventory = (sword), (lamp), rope
+
+{inventory ^ (lamp, rope)}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "lamp\n");
+}
+
+#[test]
+fn lists_are_compared_by_the_values_of_their_items() {
+    let content = "
+
+LIST progress = start, (middle), finale
+
+{progress > start: Past the start.}
+{progress < finale: Before the finale.}
+{progress >= middle: At least in the middle.}
+{progress <= start: Still at the start.|Not at the start.}
+{progress == middle: In the middle.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Past the start.\nBefore the finale.\nAt least in the middle.\nNot at the start.\n\
+         In the middle.\n"
+    );
+}
+
+#[test]
+fn lists_are_greater_or_less_than_or_equal_if_their_lowest_and_highest_items_are() {
+    let content = "
+
+LIST letters = a, b, c
+
+~ temp outer = (a, c)
+~ temp inner = (b)
+~ temp low = (a, b)
+~ temp high = (b, c)
+~ temp none = ()
+
+{outer >= inner: Greater.|Not greater.}
+{outer <= inner: Less.|Not less.}
+{high >= low: Greater.|Not greater.}
+{low <= high: Less.|Not less.}
+{none >= inner: Greater.|Not greater.}
+{none <= inner: Less.|Not less.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Not greater.\nNot less.\nGreater.\nLess.\nNot greater.\nNot less.\n"
+    );
+}
+
+#[test]
+fn adding_numbers_to_lists_moves_their_items() {
+    let content = "
+
+LIST progress = (start), middle, finale
+
+~ progress++
+{progress}
+~ progress += 1
+{progress}
+~ progress += 1
+Done: {progress}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "middle\nfinale\nDone: .\n");
+}
+
+#[test]
+fn list_functions_operate_on_lists() {
+    let content = "
+
+LIST colors = red, (green), (blue), yellow = 10
+
+{LIST_COUNT(colors)}
+{LIST_MIN(colors)} {LIST_MAX(colors)}
+{LIST_ALL(colors)}
+{LIST_INVERT(colors)}
+{LIST_RANGE(LIST_ALL(colors), 2, 10)}
+{LIST_VALUE(yellow)}
+{LIST_RANDOM(green)}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "2\ngreen blue\nred, green, blue, yellow\nred, yellow\ngreen, blue, yellow\n10\ngreen\n"
+    );
+}
+
+#[test]
+fn empty_lists_are_false_and_lists_with_items_are_true() {
+    let content = "
+
+LIST inventory = sword, lamp
+
+{inventory: You carry something.|You carry nothing.}
+~ inventory += lamp
+{inventory: You carry something.|You carry nothing.}
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "You carry nothing.\nYou carry something.\n");
+}
+
+#[test]
+fn list_variables_can_be_set_from_the_caller() {
+    let content = "
+
+LIST inventory = sword, lamp, rope
+
+You carry: {inventory}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let list: InkList = vec![
+        ListItem::new("inventory", "rope"),
+        ListItem::new("inventory", "sword"),
+    ]
+    .into_iter()
+    .collect();
+
+    story.set_variable("inventory", list).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "You carry: sword, rope.\n");
+
+    let list = get_list(&story, "inventory");
+    assert_eq!(list.items()[1].value, 3);
+}
+
+#[test]
+fn setting_list_variable_with_undefined_item_yields_error() {
+    let content = "
+
+LIST inventory = sword, lamp, rope
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    let list: InkList = vec![ListItem::new("inventory", "shield")]
+        .into_iter()
+        .collect();
+
+    match story.set_variable("inventory", list) {
+        Err(InklingError::InvalidListItem { name }) => assert_eq!(&name, "inventory.shield"),
+        other => panic!(
+            "expected `InklingError::InvalidListItem` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn items_which_exist_in_several_lists_must_be_qualified() {
+    let content = "
+
+LIST colors = red, orange
+LIST fruits = apple, orange
+
+{colors ? orange}
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn lists_with_same_name_as_variable_yield_error() {
+    let content = "
+
+VAR inventory = 0
+LIST inventory = sword, lamp
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
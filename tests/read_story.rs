@@ -75,6 +75,39 @@ He befriended thousands of climbers and children sightseeing in Switzerland.
     }
 }
 
+#[test]
+fn multiline_comments_are_removed() {
+    let content = "
+
+Mont Blanc was a world-renowned mountain guide. /* need to
+introduce Mont Blanc */
+He befriended /* TODO: how many? */thousands of climbers.
+
+-> DONE
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    assert_eq!(story.get_log().todo_comments.len(), 1);
+
+    match story.resume(&mut line_buffer) {
+        Ok(Prompt::Done) => {
+            assert_eq!(line_buffer.len(), 2);
+            assert_eq!(
+                &line_buffer[0].text,
+                "Mont Blanc was a world-renowned mountain guide.\n"
+            );
+            assert_eq!(
+                &line_buffer[1].text,
+                "He befriended thousands of climbers.\n"
+            );
+        }
+        _ => panic!("error while reading a flat story from string"),
+    }
+}
+
 #[test]
 fn story_can_start_with_named_knot() {
     let content = "
@@ -0,0 +1,324 @@
+use inkling::error::{ReplayError, ReplayErrorKind};
+use inkling::*;
+
+const CONTENT: &str = "
+
+VAR coins = 3
+
+-> market
+
+== market ==
+A merchant showed you a lamp.
++   Buy the lamp.
+    ~ coins = coins - 2
+    You bought the lamp.
+    -> market
++   Leave.
+    -> road
+
+== road ==
+The road was long.
+*   Walk on.
+    You had {coins} coins left.
+    -> END
+
+";
+
+/// Play through a story while recording a replay log.
+fn record_playthrough(content: &str) -> (Story, ReplayLog) {
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_replay_log();
+
+    story.set_variable("coins", 10).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.continue_line().unwrap();
+    story.continue_line().unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let log = story.get_replay_log().unwrap().clone();
+
+    (story, log)
+}
+
+/// Get the recorded steps of a log with the hashes of their lines set to zero.
+fn steps_without_hashes(log: &ReplayLog) -> Vec<ReplayStep> {
+    log.get_steps()
+        .iter()
+        .cloned()
+        .map(|step| match step {
+            ReplayStep::ContinueLines { count, .. } => ReplayStep::ContinueLines {
+                count,
+                lines_hash: 0,
+            },
+            ReplayStep::Resume { .. } => ReplayStep::Resume { lines_hash: 0 },
+            step => step,
+        })
+        .collect()
+}
+
+#[test]
+fn replay_log_records_the_calls_made_to_the_story() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    assert_eq!(
+        steps_without_hashes(&log),
+        &[
+            ReplayStep::SetVariable {
+                name: "coins".to_string(),
+                value: Variable::Int(10)
+            },
+            ReplayStep::Resume { lines_hash: 0 },
+            ReplayStep::Choice {
+                index: 0,
+                text: "Buy the lamp.".to_string()
+            },
+            ReplayStep::ContinueLines {
+                count: 2,
+                lines_hash: 0
+            },
+            ReplayStep::Resume { lines_hash: 0 },
+            ReplayStep::Choice {
+                index: 1,
+                text: "Leave.".to_string()
+            },
+            ReplayStep::Resume { lines_hash: 0 },
+        ]
+    );
+}
+
+#[test]
+fn replay_log_records_a_hash_of_the_returned_lines() {
+    let (_, log) = record_playthrough(CONTENT);
+    let (_, other_log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace("You bought the lamp.", "You bought the old lamp.");
+    let (_, changed_log) = record_playthrough(&changed);
+
+    assert_eq!(log.get_steps(), other_log.get_steps());
+    assert_ne!(log.get_steps()[3], changed_log.get_steps()[3]);
+    assert_eq!(log.get_steps()[4], changed_log.get_steps()[4]);
+}
+
+#[test]
+fn replaying_a_log_rebuilds_the_state_of_the_playthrough() {
+    let (mut story, log) = record_playthrough(CONTENT);
+
+    let mut replayed = read_story_from_string(CONTENT).unwrap();
+    replayed.replay(&log).unwrap();
+
+    assert_eq!(replayed.get_variable("coins"), Some(Variable::Int(8)));
+    assert_eq!(
+        replayed.get_current_location(),
+        story.get_current_location()
+    );
+    assert_eq!(replayed.get_turn_index(), 2);
+    assert_eq!(replayed.get_current_choices(), story.get_current_choices());
+
+    let mut line_buffer = Vec::new();
+    let mut replayed_line_buffer = Vec::new();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    replayed.make_choice(0).unwrap();
+    replayed.resume(&mut replayed_line_buffer).unwrap();
+
+    assert_eq!(replayed_line_buffer, line_buffer);
+    assert_eq!(&line_buffer[1].text, "You had 8 coins left.\n");
+}
+
+#[test]
+fn replaying_restarts_the_story_from_its_beginning() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let mut replayed = read_story_from_string(CONTENT).unwrap();
+    let mut line_buffer = Vec::new();
+
+    replayed.move_to(&"road".into()).unwrap();
+    replayed.resume(&mut line_buffer).unwrap();
+
+    replayed.replay(&log).unwrap();
+
+    assert_eq!(replayed.get_variable("coins"), Some(Variable::Int(8)));
+    assert_eq!(replayed.get_num_visited(&"market".into()), Some(2));
+}
+
+#[test]
+fn replaying_moves_and_rewinds_repeats_them() {
+    let mut story = read_story_from_string(CONTENT).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_replay_log();
+
+    story.move_to(&"road".into()).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.rewind(1).unwrap();
+
+    let log = story.get_replay_log().unwrap().clone();
+
+    let mut replayed = read_story_from_string(CONTENT).unwrap();
+    replayed.replay(&log).unwrap();
+
+    assert_eq!(replayed.get_current_location(), Location::from("road"));
+    assert_eq!(replayed.get_current_choices(), story.get_current_choices());
+}
+
+#[test]
+fn replaying_reports_the_step_at_which_a_choice_index_became_invalid() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace("+   Leave.\n    -> road\n", "");
+
+    let mut replayed = read_story_from_string(&changed).unwrap();
+
+    match replayed.replay(&log) {
+        Err(ReplayError {
+            step: 5,
+            kind:
+                ReplayErrorKind::InvalidChoice {
+                    index: 1,
+                    num_choices: 1,
+                },
+        }) => (),
+        other => panic!(
+            "expected `ReplayErrorKind::InvalidChoice` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn replaying_reports_the_step_at_which_the_text_of_a_choice_differs() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace("Buy the lamp.", "Buy the old lamp.");
+
+    let mut replayed = read_story_from_string(&changed).unwrap();
+
+    match replayed.replay(&log) {
+        Err(ReplayError {
+            step: 2,
+            kind: ReplayErrorKind::ChoiceTextDiffers { expected, found },
+        }) => {
+            assert_eq!(&expected, "Buy the lamp.");
+            assert_eq!(&found, "Buy the old lamp.");
+        }
+        other => panic!(
+            "expected `ReplayErrorKind::ChoiceTextDiffers` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn replaying_reports_the_step_at_which_the_returned_lines_differ() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace("You bought the lamp.", "You bought the old lamp.");
+
+    let mut replayed = read_story_from_string(&changed).unwrap();
+
+    match replayed.replay(&log) {
+        Err(ReplayError {
+            step: 3,
+            kind: ReplayErrorKind::LinesDiffer,
+        }) => (),
+        other => panic!(
+            "expected `ReplayErrorKind::LinesDiffer` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn replaying_reports_the_step_at_which_the_story_was_not_at_a_choice() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace(
+        "You bought the lamp.\n    -> market",
+        "You bought the lamp.\n    A merchant showed you a lamp.\n    -> END",
+    );
+
+    let mut replayed = read_story_from_string(&changed).unwrap();
+
+    match replayed.replay(&log) {
+        Err(ReplayError {
+            step: 5,
+            kind: ReplayErrorKind::NotAtChoice,
+        }) => (),
+        other => panic!(
+            "expected `ReplayErrorKind::NotAtChoice` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[test]
+fn replaying_reports_errors_from_steps_which_can_no_longer_be_made() {
+    let (_, log) = record_playthrough(CONTENT);
+
+    let changed = CONTENT.replace("VAR coins = 3", "VAR gold = 3");
+    let changed = changed.replace("coins", "gold");
+
+    let mut replayed = read_story_from_string(&changed).unwrap();
+
+    match replayed.replay(&log) {
+        Err(ReplayError {
+            step: 0,
+            kind: ReplayErrorKind::Error(InklingError::InvalidVariable { name }),
+        }) => assert_eq!(&name, "coins"),
+        other => panic!(
+            "expected `InklingError::InvalidVariable` but got {:?}",
+            other
+        ),
+    }
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn replaying_uses_the_seed_of_the_recorded_playthrough() {
+    let content = "
+
+-> table
+
+== table ==
++   Roll the die.
+    You rolled {RANDOM(1, 1000000)}.
+    -> table
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_replay_log();
+
+    for _ in 0..3 {
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+    }
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let log = story.get_replay_log().unwrap().clone();
+    assert!(log.get_seed().is_some());
+
+    let mut replayed = read_story_from_string(content).unwrap();
+    let mut replayed_line_buffer = Vec::new();
+
+    replayed.replay(&log).unwrap();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    replayed.make_choice(0).unwrap();
+    replayed.resume(&mut replayed_line_buffer).unwrap();
+
+    assert_eq!(replayed_line_buffer.last(), line_buffer.last());
+}
@@ -0,0 +1,284 @@
+use inkling::*;
+
+#[test]
+fn rewinding_restores_variables_and_visit_counts_at_the_choice() {
+    let content = "
+
+VAR coins = 3
+
+-> market
+
+== market ==
+A merchant showed you a lamp.
++   Buy it.
+    ~ coins = coins - 2
+    -> market
++   Leave.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(story.get_variable("coins").unwrap(), Variable::Int(1));
+    assert_eq!(story.get_num_visited(&"market".into()), Some(2));
+
+    story.rewind(1).unwrap();
+
+    assert_eq!(story.get_variable("coins").unwrap(), Variable::Int(3));
+    assert_eq!(story.get_num_visited(&"market".into()), Some(1));
+    assert_eq!(story.get_turn_index(), 0);
+    assert_eq!(story.checkpoints().len(), 1);
+}
+
+#[test]
+fn rewinding_resets_visit_counts_of_content_first_visited_after_the_choice() {
+    let content = "
+
+-> market
+
+== market ==
+A merchant showed you a lamp.
+*   (buy) Buy it.
+    -> shop.counter
+*   Leave.
+    -> END
+
+== shop ==
+= counter
+The merchant wrapped up the lamp.
+*   Thank them.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(story.get_num_visited(&"shop.counter".into()), Some(1));
+
+    story.rewind(1).unwrap();
+
+    assert_eq!(story.get_num_visited(&"shop.counter".into()), Some(0));
+    assert_eq!(story.get_num_visited(&"market".into()), Some(1));
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(story.get_num_visited(&"shop.counter".into()), Some(0));
+}
+
+#[test]
+fn rewinding_returns_to_the_choices_that_were_presented() {
+    let content = "
+
+The door creaked open.
+*   Step inside.
+    The hall was dark.
+    * *     Light a match.
+    * *     Wait.
+*   Run away.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(story.checkpoints().len(), 2);
+
+    story.rewind(1).unwrap();
+
+    assert_eq!(story.get_current_choices().unwrap(), choices);
+    assert!(!story.can_continue());
+
+    line_buffer.clear();
+
+    let prompt = story.resume(&mut line_buffer).unwrap();
+    assert_eq!(prompt.get_choices().unwrap(), choices);
+    assert!(line_buffer.is_empty());
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Run away.\n");
+}
+
+#[test]
+fn rewinding_after_a_choice_was_made_undoes_that_choice() {
+    let content = "
+
+The door creaked open.
+*   Step inside.
+    The hall was dark.
+*   Run away.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+
+    story.rewind(1).unwrap();
+
+    assert_eq!(story.get_current_choices().unwrap().len(), 2);
+}
+
+#[test]
+fn rewinding_by_several_choices_drops_the_later_checkpoints() {
+    let content = "
+
+-> hall
+
+== hall ==
+The bell rang {&once|twice|thrice}.
++   Listen again.
+    -> hall
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    for _ in 0..3 {
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+    }
+
+    assert_eq!(story.checkpoints().len(), 4);
+
+    story.rewind(2).unwrap();
+
+    assert_eq!(story.checkpoints().len(), 2);
+    assert_eq!(story.checkpoints()[1].get_turn_index(), 1);
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[1].text, "The bell rang thrice.\n");
+}
+
+#[test]
+fn rewinding_further_than_the_history_yields_an_error() {
+    let content = "
+
+*   Step inside.
+    * *     Light a match.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    match story.rewind(1) {
+        Err(InklingError::InvalidRewind {
+            steps: 1,
+            num_recorded: 0,
+        }) => (),
+        other => panic!("expected `InklingError::InvalidRewind` but got {:?}", other),
+    }
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert!(story.rewind(2).is_err());
+    assert!(story.rewind(1).is_ok());
+}
+
+#[test]
+fn history_keeps_at_most_the_maximum_number_of_checkpoints() {
+    let content = "
+
+-> hall
+
+== hall ==
++   Listen again.
+    -> hall
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    for _ in 0..4 {
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+    }
+
+    story.set_max_history(2);
+
+    assert_eq!(story.checkpoints().len(), 2);
+    assert_eq!(story.checkpoints()[0].get_turn_index(), 3);
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(story.checkpoints().len(), 2);
+    assert_eq!(story.checkpoints()[1].get_turn_index(), 5);
+
+    story.set_max_history(0);
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert!(story.checkpoints().is_empty());
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn rewinding_restores_the_position_of_the_random_generator() {
+    let content = "
+
+-> table
+
+== table ==
++   Roll the die.
+    You rolled {RANDOM(1, 1000000)}.
+    -> table
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let first_roll = line_buffer[1].text.clone();
+
+    story.rewind(1).unwrap();
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer[1].text, first_roll);
+}
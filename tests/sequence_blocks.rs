@@ -0,0 +1,204 @@
+use inkling::error::ReadError;
+use inkling::*;
+
+fn read_lines_over_visits(content: &str, num_visits: usize) -> Vec<String> {
+    let mut story = read_story_from_string(content).unwrap();
+    let mut lines = Vec::new();
+
+    for _ in 0..num_visits {
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+        lines.push(
+            line_buffer
+                .into_iter()
+                .map(|line| line.text)
+                .collect::<String>(),
+        );
+
+        story.make_choice(0).unwrap();
+    }
+
+    lines
+}
+
+#[test]
+fn stopping_blocks_follow_branches_in_order_then_repeat_the_final_branch() {
+    let content = "
+
+-> casino
+
+=== casino ===
+{stopping:
+    - I entered the casino.
+    - I entered the casino again.
+      The croupier nodded.
+    - Once more, I went inside.
+}
++ [Continue] -> casino
+
+";
+
+    assert_eq!(
+        read_lines_over_visits(content, 4),
+        &[
+            "I entered the casino.\n",
+            "I entered the casino again.\nThe croupier nodded.\n",
+            "Once more, I went inside.\n",
+            "Once more, I went inside.\n",
+        ]
+    );
+}
+
+#[test]
+fn cycle_and_once_blocks_follow_their_kind() {
+    let content = "
+
+-> day
+
+=== day ===
+{cycle:
+    - It was day.
+    - It was night.
+}
+{once:
+    - The sun was rising.
+}
++ [Continue] -> day
+
+";
+
+    assert_eq!(
+        read_lines_over_visits(content, 3),
+        &[
+            "It was day.\nThe sun was rising.\n",
+            "It was night.\n",
+            "It was day.\n",
+        ]
+    );
+}
+
+#[test]
+fn branches_of_alternative_blocks_can_contain_choices_and_diverts() {
+    let content = "
+
+{stopping:
+    - The guard blocked the gate.
+      * [Bribe him] He took the coin and let me pass.
+      * [Leave] -> END
+    - -> gate
+}
+Done.
+
+=== gate ===
+The gate was open.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => assert_eq!(choices.len(), 2),
+        other => panic!("expected a choice but got {:?}", other),
+    }
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The guard blocked the gate.\n");
+    assert_eq!(&line_buffer[1].text, "He took the coin and let me pass.\n");
+    assert_eq!(&line_buffer[2].text, "Done.\n");
+}
+
+#[test]
+fn inline_alternatives_can_be_marked_with_sequence_keywords() {
+    let content = "
+
+-> visit
+
+=== visit ===
+{cycle: Hello|Goodbye}, {once: first|second}.
++ [Continue] -> visit
+
+";
+
+    assert_eq!(
+        read_lines_over_visits(content, 3),
+        &["Hello, first.\n", "Goodbye, second.\n", "Hello, .\n"]
+    );
+}
+
+#[cfg(not(feature = "random"))]
+#[test]
+fn shuffle_once_and_shuffle_stopping_go_through_branches_in_order_if_not_random() {
+    let content = "
+
+-> rumor
+
+=== rumor ===
+{shuffle once:
+    - The king is ill.
+    - The queen is away.
+}
+{shuffle stopping: Rain|Snow|Sun}
++ [Continue] -> rumor
+
+";
+
+    assert_eq!(
+        read_lines_over_visits(content, 3),
+        &[
+            "The king is ill.\nRain\n",
+            "The queen is away.\nSnow\n",
+            "Sun\n",
+        ]
+    );
+}
+
+#[cfg(feature = "random")]
+#[test]
+fn shuffle_stopping_blocks_end_with_the_final_branch() {
+    let content = "
+
+-> weather
+
+=== weather ===
+{shuffle stopping:
+    - Rain
+    - Snow
+    - Hail
+    - Sun
+}
++ [Continue] -> weather
+
+";
+
+    let mut lines = read_lines_over_visits(content, 5);
+
+    assert_eq!(&lines[3], "Sun\n");
+    assert_eq!(&lines[4], "Sun\n");
+
+    lines.truncate(3);
+    lines.sort();
+
+    assert_eq!(lines, &["Hail\n", "Rain\n", "Snow\n"]);
+}
+
+#[test]
+fn unclosed_alternative_blocks_yield_errors() {
+    let content = "
+
+{cycle:
+    - One
+    - Two
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ParseError(..)) => (),
+        Err(err) => panic!("expected a parse error but got {:?}", err),
+        Ok(_) => panic!("expected a parse error but the story was read"),
+    }
+}
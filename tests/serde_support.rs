@@ -9,6 +9,8 @@ pub mod tests {
     use inkling::*;
     use serde_json;
 
+    use std::sync::Arc;
+
     #[test]
     fn serialization_saves_current_state() {
         let content = "
@@ -71,4 +73,389 @@ You head back.
         assert_eq!(choices_without_torch.len(), 1);
         assert_eq!(choices_with_torch.len(), 2);
     }
+
+    #[test]
+    fn serialization_saves_temporary_variables_in_the_current_stitch() {
+        let content = "
+
+-> passage
+
+== passage ==
+
+~ temp coins = 3
+You have {coins} coins.
+
++   Find another coin.
+    ~ coins++
+    You now have {coins} coins.
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer.last().unwrap().text, "You now have 4 coins.\n");
+    }
+
+    #[test]
+    fn serialization_saves_functions_and_their_state() {
+        let content = "
+
+-> passage
+
+== passage ==
+
+The clock strikes {strike()}.
+
++   Wait.
+    The clock strikes {strike()}.
+
+=== function strike() ===
+<>{one|two|three}<>
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            &line_buffer.last().unwrap().text,
+            "The clock strikes two.\n"
+        );
+    }
+
+    #[test]
+    fn serialization_saves_tunnels_which_are_being_followed() {
+        let content = "
+
+-> shop ->
+You leave the shop.
+
+=== shop ===
+*   Buy bread.
+*   Buy milk.
+- ->->
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer.last().unwrap().text, "You leave the shop.\n");
+    }
+
+    #[test]
+    fn serialization_saves_threads_of_presented_choices() {
+        let content = "
+
+<- cellar
+*   Make tea.
+    -> END
+
+=== cellar ===
+~ temp room = \"cellar\"
+*   Go down.
+    You are in the {room}.
+    -> END
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            &line_buffer.last().unwrap().text,
+            "You are in the cellar.\n"
+        );
+    }
+
+    #[test]
+    fn serialization_saves_list_variables_and_definitions() {
+        let content = "
+
+LIST inventory = (sword), lamp, rope
+
+~ inventory += lamp
+*   Drop the sword.
+    ~ inventory -= sword
+    ~ inventory = LIST_INVERT(inventory)
+    You carry: {inventory}.
+    -> END
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(
+            &line_buffer.last().unwrap().text,
+            "You carry: sword, rope.\n"
+        );
+    }
+
+    #[test]
+    fn story_state_can_be_saved_and_restored_without_the_story_content() {
+        let content = "
+
+-> hall
+
+== hall ==
+The bell rang {&once|twice|thrice}.
++   Listen again.
+    -> hall
++   Leave.
+    -> END
+
+";
+
+        let compiled = Arc::new(compile_story_from_string(content).unwrap());
+
+        let mut story = Story::new(Arc::clone(&compiled));
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(story.get_state()).unwrap();
+        let state: StoryState = serde_json::from_str(&serialized).unwrap();
+
+        let mut restored = Story::from_state(Arc::clone(&compiled), state);
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer.last().unwrap().text, "The bell rang thrice.\n");
+    }
+
+    #[test]
+    fn saved_states_keep_the_sequences_of_knots_which_were_not_edited() {
+        let content = "
+
+-> hall
+
+== garden ==
+The garden was quiet.
+-> END
+
+== hall ==
+The bell rang {&once|twice|thrice}.
++   Listen again.
+    -> hall
++   Leave.
+    -> garden
+
+";
+
+        let edited = "
+
+-> hall
+
+== garden ==
+The garden was {quiet|silent}.
+{~A bird sang.|A dog barked.}
+-> END
+
+== hall ==
+The bell rang {&once|twice|thrice}.
++   Listen again.
+    -> hall
++   Leave.
+    -> garden
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(story.get_state()).unwrap();
+        let state: StoryState = serde_json::from_str(&serialized).unwrap();
+
+        let compiled = Arc::new(compile_story_from_string(edited).unwrap());
+        let mut restored = Story::from_state(compiled, state);
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer.last().unwrap().text, "The bell rang twice.\n");
+    }
+
+    #[test]
+    fn serialization_saves_checkpoints_which_can_be_rewound_to() {
+        let content = "
+
+VAR coins = 3
+
+-> market
+
+== market ==
+The lamp cost {&two|three} coins.
++   Buy it.
+    ~ coins = coins - 2
+    -> market
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let mut restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(restored.checkpoints().len(), 2);
+
+        restored.rewind(1).unwrap();
+
+        assert_eq!(restored.get_variable("coins").unwrap(), Variable::Int(3));
+
+        line_buffer.clear();
+
+        restored.make_choice(0).unwrap();
+        restored.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[1].text, "The lamp cost three coins.\n");
+    }
+
+    #[test]
+    fn serialized_size_of_checkpoints_is_limited_by_default() {
+        let mut content = String::from("-> hub\n\n== hub ==\n+   Wait.\n    -> hub\n\n");
+
+        for i in 0..2000 {
+            content.push_str(&format!("== knot_{} ==\nLine {}.\n-> END\n\n", i, i));
+        }
+
+        let mut story = read_story_from_string(&content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.resume(&mut line_buffer).unwrap();
+
+        let initial_size = serde_json::to_string(story.get_state()).unwrap().len();
+
+        for _ in 0..100 {
+            story.make_choice(0).unwrap();
+            story.resume(&mut line_buffer).unwrap();
+        }
+
+        let size = serde_json::to_string(story.get_state()).unwrap().len();
+
+        assert_eq!(story.checkpoints().len(), 10);
+        assert!(size < initial_size + initial_size / 10);
+    }
+
+    #[test]
+    fn serialization_saves_the_transcript() {
+        let content = "
+
+The train came to a halt.
+*   Get off the train.
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.enable_transcript(Some(10));
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+        story.resume(&mut line_buffer).unwrap();
+
+        let serialized = serde_json::to_string(&story).unwrap();
+        let restored: Story = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(restored.get_transcript(), story.get_transcript());
+        assert_eq!(restored.get_transcript().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn replay_logs_can_be_saved_and_replayed() {
+        let content = "
+
+VAR coins = 3
+
+*   Buy a lamp.
+    ~ coins = coins - 2
+    You had {coins} coins left.
+
+";
+
+        let mut story = read_story_from_string(content).unwrap();
+        let mut line_buffer = Vec::new();
+
+        story.enable_replay_log();
+
+        story.resume(&mut line_buffer).unwrap();
+        story.make_choice(0).unwrap();
+
+        let serialized = serde_json::to_string(story.get_replay_log().unwrap()).unwrap();
+        let log: ReplayLog = serde_json::from_str(&serialized).unwrap();
+
+        let mut replayed = read_story_from_string(content).unwrap();
+        replayed.replay(&log).unwrap();
+
+        line_buffer.clear();
+        replayed.resume(&mut line_buffer).unwrap();
+
+        assert_eq!(&line_buffer[1].text, "You had 1 coins left.\n");
+    }
 }
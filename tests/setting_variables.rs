@@ -128,3 +128,213 @@ The latest measurement is {value} {unit}. {not is_hazardous: Not terrible, not g
         "The latest measurement is 15000 Röntgen. Oh no.\n"
     );
 }
+
+#[test]
+fn variables_can_be_assigned_to_from_the_script() {
+    let content = "
+
+VAR coins = 0
+VAR rank = \"Sergent\"
+
+-> root
+
+== root
+
+~ coins = coins + 4
+~ rank = \"Capitaine\"
+The {rank} has {coins} coins.
+~ coins += 3
+~ coins++
+Now the {rank} has {coins} coins.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[0].text, "The Capitaine has 4 coins.\n");
+    assert_eq!(&line_buffer[1].text, "Now the Capitaine has 8 coins.\n");
+
+    assert_eq!(story.get_variable("coins").unwrap(), Variable::Int(8));
+}
+
+#[test]
+fn assignments_persist_across_choices() {
+    let content = "
+
+VAR visits = 0
+
+-> root
+
+== root
+
+~ visits++
+Visit number {visits}.
+
++   [Again] -> root
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Visit number 1.\n");
+    assert_eq!(&line_buffer[1].text, "Visit number 2.\n");
+}
+
+#[test]
+fn assignments_do_not_break_glue_between_lines() {
+    let content = "
+
+VAR coins = 0
+
+Hello <>
+~ coins = 5
+, World!
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(line_buffer.len(), 2);
+    assert_eq!(&line_buffer[1].text, ", World!\n");
+}
+
+#[test]
+fn assigning_to_constant_variables_from_the_script_yields_error() {
+    let content = "
+
+CONST coins = 0
+
+~ coins = 5
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn temporary_variables_can_be_declared_and_used_in_a_stitch() {
+    let content = "
+
+VAR coins = 10
+
+-> market
+
+== market ==
+
+~ temp price = 4
+~ temp discount = 1
+~ price -= discount
+The apple costs {price} coins.
+~ coins = coins - price
+You have {coins} coins left.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "The apple costs 3 coins.\n");
+    assert_eq!(&line_buffer[1].text, "You have 7 coins left.\n");
+
+    assert!(story.get_variable("price").is_none());
+}
+
+#[test]
+fn temporary_variables_persist_across_choices_in_the_same_stitch() {
+    let content = "
+
+-> market
+
+== market ==
+
+~ temp apples = 0
+
+*   Take an apple.
+    ~ apples++
+*   Take two apples.
+    ~ apples += 2
+-   You carry {apples} apples.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer.last().unwrap().text, "You carry 2 apples.\n");
+}
+
+#[test]
+fn temporary_variables_are_reset_when_the_stitch_is_visited_again() {
+    let content = "
+
+-> counter
+
+== counter ==
+
+~ temp count = 0
+~ count++
+Count: {count}.
+
++   [Again] -> counter
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[0].text, "Count: 1.\n");
+    assert_eq!(&line_buffer[1].text, "Count: 1.\n");
+}
+
+#[test]
+fn temporary_variables_are_not_available_in_other_stitches() {
+    let content = "
+
+-> one
+
+== one ==
+~ temp count = 0
+-> two
+
+== two ==
+Count: {count}.
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
+
+#[test]
+fn temporary_variables_may_not_shadow_global_variables() {
+    let content = "
+
+VAR count = 0
+
+~ temp count = 0
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}
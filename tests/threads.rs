@@ -0,0 +1,374 @@
+use inkling::*;
+
+#[test]
+fn threads_merge_their_choices_with_the_choices_of_the_story() {
+    let content = "
+
+We are in the kitchen.
+<- cellar
+*   Make tea
+    You make some tea.
+    -> DONE
+
+=== cellar ===
+There is a door to the cellar.
+*   Go down to the cellar
+    You go down the stairs.
+    -> DONE
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "We are in the kitchen.\nThere is a door to the cellar.\n"
+    );
+
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Go down to the cellar");
+    assert_eq!(&choices[1].text, "Make tea");
+}
+
+#[test]
+fn selecting_a_choice_from_a_thread_continues_the_story_in_the_thread() {
+    let content = "
+
+<- cellar
+*   Make tea
+    You make some tea.
+    -> END
+
+=== cellar ===
+*   Go down to the cellar
+    You go down the stairs.
+    It is dark.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Go down to the cellar\nYou go down the stairs.\nIt is dark.\n"
+    );
+}
+
+#[test]
+fn selecting_a_choice_from_the_main_flow_continues_the_story_in_the_main_flow() {
+    let content = "
+
+<- cellar
+*   Make tea
+    You make some tea.
+    -> END
+
+=== cellar ===
+*   Go down to the cellar
+    You go down the stairs.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    line_buffer.clear();
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Make tea\nYou make some tea.\n");
+}
+
+#[test]
+fn done_in_thread_returns_to_the_line_after_the_thread() {
+    let content = "
+
+Before.
+<- weather
+After.
+-> END
+
+=== weather ===
+It is raining.
+-> DONE
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Done => (),
+        other => panic!("expected the story to be done but got {:?}", other),
+    }
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Before.\nIt is raining.\nAfter.\n");
+}
+
+#[test]
+fn end_in_thread_ends_the_entire_story() {
+    let content = "
+
+Before.
+<- storm
+After.
+
+=== storm ===
+The storm ends everything.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Done => (),
+        other => panic!("expected the story to be done but got {:?}", other),
+    }
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Before.\nThe storm ends everything.\n");
+}
+
+#[test]
+fn choices_from_threads_are_presented_when_the_main_flow_is_done() {
+    let content = "
+
+<- kitchen
+<- cellar
+-> DONE
+
+=== kitchen ===
+*   Make tea
+    -> END
+
+=== cellar ===
+*   Go down to the cellar
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Make tea");
+    assert_eq!(&choices[1].text, "Go down to the cellar");
+}
+
+#[test]
+fn threads_can_be_nested() {
+    let content = "
+
+<- house
+*   Stay outside
+    -> END
+
+=== house ===
+<- cellar
+*   Stay in the house
+    -> END
+
+=== cellar ===
+*   Go down to the cellar
+    You go down the stairs.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 3);
+    assert_eq!(&choices[0].text, "Go down to the cellar");
+    assert_eq!(&choices[1].text, "Stay in the house");
+    assert_eq!(&choices[2].text, "Stay outside");
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Go down to the cellar\nYou go down the stairs.\n");
+}
+
+#[test]
+fn threads_keep_their_own_temporary_variables() {
+    let content = "
+
+~ temp room = \"kitchen\"
+<- cellar
+You are in the {room}.
+*   Stay
+    -> END
+
+=== cellar ===
+~ temp room = \"cellar\"
+*   Look around
+    You are in the {room}.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+    assert_eq!(text, "You are in the kitchen.\n");
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+    assert_eq!(text, "Look around\nYou are in the cellar.\n");
+}
+
+#[test]
+fn threads_can_be_started_again_after_a_choice_is_made() {
+    let content = "
+
+-> room
+
+=== room ===
+<- door
++   Wait
+    You wait.
+    -> room
+
+=== door ===
+*   Leave
+    You leave.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    story.make_choice(1).unwrap();
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    assert_eq!(choices.len(), 2);
+    assert_eq!(&choices[0].text, "Leave");
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Leave\nYou leave.\n");
+}
+
+#[test]
+fn threads_can_be_started_with_arguments() {
+    let content = "
+
+<- door(\"red\")
+<- door(\"blue\")
+*   Wait
+    -> END
+
+=== door(colour) ===
+*   Open the {colour} door
+    You opened the {colour} door.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+
+    let texts = choices
+        .iter()
+        .map(|choice| choice.text.as_str())
+        .collect::<Vec<_>>();
+
+    assert_eq!(texts, &["Open the red door", "Open the blue door", "Wait"]);
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Open the blue door\nYou opened the blue door.\n");
+}
+
+#[test]
+fn threads_with_the_wrong_number_of_arguments_yield_validation_error() {
+    let content = "
+
+<- door
+
+=== door(colour) ===
+*   Open the {colour} door
+    -> END
+
+";
+
+    match read_story_from_string(content) {
+        Err(error::ReadError::ValidationError(err)) => {
+            assert_eq!(err.invalid_address_errors.len(), 1)
+        }
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
@@ -0,0 +1,199 @@
+use inkling::*;
+
+#[test]
+fn transcript_is_not_recorded_unless_enabled() {
+    let content = "
+
+The train came to a halt.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    assert!(story.get_transcript().is_none());
+}
+
+#[test]
+fn transcript_records_lines_and_choices_with_their_locations_and_turns() {
+    let content = "
+
+-> station
+
+== station ==
+The train came to a halt. # arrival
+*   Get off the train. # leave
+    -> platform
+
+== platform ==
+= north
+The platform was empty.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_transcript(None);
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let entries = story
+        .get_transcript()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    assert_eq!(entries.len(), 4);
+
+    assert_eq!(entries[0].kind, TranscriptEntryKind::Line);
+    assert_eq!(&entries[0].text, "The train came to a halt.\n");
+    assert_eq!(&entries[0].tags, &["arrival".to_string()]);
+    assert_eq!(entries[0].location, Location::from("station"));
+    assert_eq!(entries[0].meta_data.line(), 6);
+    assert_eq!(entries[0].turn_index, 0);
+
+    assert_eq!(entries[1].kind, TranscriptEntryKind::Choice { index: 0 });
+    assert_eq!(&entries[1].text, "Get off the train.");
+    assert_eq!(&entries[1].tags, &["leave".to_string()]);
+    assert_eq!(entries[1].location, Location::from("station"));
+    assert_eq!(entries[1].meta_data.line(), 7);
+    assert_eq!(entries[1].turn_index, 0);
+
+    assert_eq!(entries[2].kind, TranscriptEntryKind::Line);
+    assert_eq!(&entries[2].text, "Get off the train.\n");
+    assert_eq!(entries[2].location, Location::from("station"));
+    assert_eq!(entries[2].turn_index, 1);
+
+    assert_eq!(&entries[3].text, "The platform was empty.\n");
+    assert_eq!(entries[3].location, Location::from("platform.north"));
+    assert_eq!(entries[3].meta_data.line(), 12);
+    assert_eq!(entries[3].turn_index, 1);
+}
+
+#[test]
+fn choices_from_threads_are_recorded_at_the_thread_location() {
+    let content = "
+
+<- kitchen
+*   Stay in bed.
+    -> END
+
+== kitchen ==
+*   Make some tea.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_transcript(None);
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+    let index = choices
+        .iter()
+        .position(|choice| choice.text == "Make some tea.")
+        .unwrap();
+
+    story.make_choice(index).unwrap();
+
+    let entry = story.get_transcript().unwrap().iter().last().unwrap();
+
+    assert_eq!(entry.kind, TranscriptEntryKind::Choice { index });
+    assert_eq!(entry.location, Location::from("kitchen"));
+}
+
+#[test]
+fn lines_are_recorded_when_they_are_continued_to() {
+    let content = "
+
+The first bell rang.
+The second bell rang.
+The third bell rang.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+
+    story.enable_transcript(None);
+
+    story.continue_line().unwrap().unwrap();
+
+    let transcript = story.get_transcript().unwrap();
+
+    assert_eq!(transcript.len(), 1);
+    assert_eq!(
+        &transcript.iter().next().unwrap().text,
+        "The first bell rang.\n"
+    );
+
+    let mut line_buffer = Vec::new();
+    story.resume(&mut line_buffer).unwrap();
+
+    let texts = story
+        .get_transcript()
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.text.clone())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        texts,
+        &[
+            "The first bell rang.\n",
+            "The second bell rang.\n",
+            "The third bell rang.\n"
+        ]
+    );
+}
+
+#[test]
+fn transcript_keeps_the_latest_entries_up_to_its_maximum() {
+    let content = "
+
+-> hall
+
+== hall ==
+The bell rang {&once|twice|thrice}.
++   Listen again.
+    -> hall
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.enable_transcript(Some(3));
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let transcript = story.get_transcript().unwrap();
+
+    assert_eq!(transcript.len(), 3);
+    assert_eq!(transcript.get_max_entries(), Some(3));
+    assert_eq!(
+        transcript
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect::<Vec<_>>(),
+        &["Listen again.", "Listen again.\n", "The bell rang twice.\n"]
+    );
+
+    story.enable_transcript(Some(1));
+    assert_eq!(story.get_transcript().unwrap().len(), 1);
+
+    story.disable_transcript();
+    assert!(story.get_transcript().is_none());
+}
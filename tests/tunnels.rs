@@ -0,0 +1,321 @@
+use inkling::error::{InklingError, ReadError};
+use inkling::*;
+
+#[test]
+fn tunnels_return_to_the_line_after_the_call() {
+    let content = "
+
+We walked into town.
+-> shop ->
+We walked back home.
+
+=== shop ===
+The shopkeeper greeted us.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = line_buffer
+        .iter()
+        .map(|line| line.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        text,
+        vec![
+            "We walked into town.",
+            "The shopkeeper greeted us.",
+            "We walked back home."
+        ]
+    );
+}
+
+#[test]
+fn tunnels_can_be_called_from_several_places() {
+    let content = "
+
+-> morning
+
+=== morning ===
+Morning.
+-> shop ->
+-> evening
+
+=== evening ===
+Evening.
+-> shop ->
+Night.
+-> END
+
+=== shop ===
+Shopping.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Morning.\nShopping.\nEvening.\nShopping.\nNight.\n");
+}
+
+#[test]
+fn tunnels_which_encounter_choices_return_after_the_choice_is_made() {
+    let content = "
+
+-> shop ->
+Thank you for shopping.
+
+=== shop ===
+What do you want to buy?
+*   Bread
+    Fresh bread.
+*   Milk
+    Cold milk.
+- ->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    let choices = story
+        .resume(&mut line_buffer)
+        .unwrap()
+        .get_choices()
+        .unwrap();
+    assert_eq!(choices.len(), 2);
+
+    line_buffer.clear();
+
+    story.make_choice(1).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Milk\nCold milk.\nThank you for shopping.\n");
+}
+
+#[test]
+fn tunnels_can_be_called_from_inside_choices() {
+    let content = "
+
+*   Visit the shop
+    -> shop ->
+    You leave the shop.
+*   Go home
+- The end.
+
+=== shop ===
+Shopping.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Visit the shop\nShopping.\nYou leave the shop.\nThe end.\n"
+    );
+}
+
+#[test]
+fn tunnels_can_be_nested() {
+    let content = "
+
+Outside.
+-> shop ->
+Outside again.
+
+=== shop ===
+In the shop.
+-> back_room ->
+Back in the shop.
+->->
+
+=== back_room ===
+In the back room.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Outside.\nIn the shop.\nIn the back room.\nBack in the shop.\nOutside again.\n"
+    );
+}
+
+#[test]
+fn chained_tunnels_are_called_in_order_before_the_final_divert() {
+    let content = "
+
+Start.
+-> one -> two -> finale
+
+=== one ===
+One.
+->->
+
+=== two ===
+Two.
+->->
+
+=== finale ===
+Finale.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Start.\nOne.\nTwo.\nFinale.\n");
+}
+
+#[test]
+fn temporary_variables_are_restored_when_tunnels_return() {
+    let content = "
+
+~ temp coins = 3
+-> shop ->
+You have {coins} coins.
+
+=== shop ===
+~ temp coins = 10
+The shop has {coins} coins.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "The shop has 10 coins.\nYou have 3 coins.\n");
+}
+
+#[test]
+fn tunnels_can_be_called_with_arguments() {
+    let content = "
+
+~ temp coins = 3
+-> shop(\"bread\", coins) -> shop(\"milk\", coins + 1) ->
+You have {coins} coins.
+
+=== shop(item, price) ===
+You bought {item} for {price} coins.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "You bought bread for 3 coins.\nYou bought milk for 4 coins.\nYou have 3 coins.\n"
+    );
+}
+
+#[test]
+fn tunnels_with_the_wrong_number_of_arguments_yield_validation_error() {
+    let content = "
+
+-> shop ->
+
+=== shop(item) ===
+You bought {item}.
+->->
+
+";
+
+    match read_story_from_string(content) {
+        Err(ReadError::ValidationError(err)) => assert_eq!(err.invalid_address_errors.len(), 1),
+        other => panic!("expected a validation error but got {:?}", other),
+    }
+}
+
+#[test]
+fn tunnel_return_with_an_address_diverts_to_it_instead_of_returning() {
+    let content = "
+
+-> shop ->
+You walked home.
+
+=== shop ===
+The shop was closed.
+->-> street
+
+=== street ===
+You stood in the street.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "The shop was closed.\nYou stood in the street.\n");
+}
+
+#[test]
+fn tunnel_return_outside_of_tunnel_yields_error() {
+    let content = "
+
+Hello.
+->->
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer) {
+        Err(InklingError::TunnelReturnOutsideTunnel { .. }) => (),
+        other => panic!(
+            "expected `InklingError::TunnelReturnOutsideTunnel` but got {:?}",
+            other
+        ),
+    }
+}
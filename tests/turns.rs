@@ -0,0 +1,177 @@
+use inkling::*;
+
+#[test]
+fn turns_counts_the_number_of_choices_made() {
+    let content = "
+
+Turn {TURNS()}.
+*   First.
+- Turn {TURNS()}.
+*   Second.
+- Turn {TURNS()}.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(text, "Turn 0.\nFirst.\nTurn 1.\nSecond.\nTurn 2.\n");
+    assert_eq!(story.get_turn_index(), 2);
+}
+
+#[test]
+fn turns_since_counts_choices_made_since_last_visit() {
+    let content = "
+
+-> bar
+
+=== bar ===
+{TURNS_SINCE(-> bar)} turns since the bar.
+*   Leave. -> street
+
+=== street ===
+{TURNS_SINCE(-> bar)} turns since the bar, never been home: {TURNS_SINCE(-> home)}.
++   Wait. -> street
+*   {TURNS_SINCE(-> bar) > 2} Go back. -> bar
+
+=== home ===
+Home.
+-> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    assert_eq!(&line_buffer[0].text, "0 turns since the bar.\n");
+
+    line_buffer.clear();
+
+    story.make_choice(0).unwrap();
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => assert_eq!(choices.len(), 1),
+        Prompt::Done => panic!("expected a choice"),
+    }
+
+    assert_eq!(
+        &line_buffer[1].text,
+        "1 turns since the bar, never been home: -1.\n"
+    );
+
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => assert_eq!(choices.len(), 2),
+        Prompt::Done => panic!("expected a choice"),
+    }
+
+    let bar = Location::from("bar");
+    assert_eq!(story.get_turns_since(&bar), Some(3));
+    assert_eq!(story.get_turns_since(&Location::from("home")), Some(-1));
+    assert_eq!(story.get_turns_since(&Location::from("cellar")), None);
+}
+
+#[test]
+fn read_count_yields_number_of_visits_to_address() {
+    let content = "
+
+-> hall
+
+=== hall ===
+Visited the hall {READ_COUNT(-> hall)} times.
+*   (look) Look around.
+    Looked around {READ_COUNT(-> look)} times.
+    -> hall
+*   Leave.
+    -> END
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+    story.resume(&mut line_buffer).unwrap();
+
+    let text = copy_lines_into_string(&line_buffer);
+
+    assert_eq!(
+        text,
+        "Visited the hall 1 times.\nLook around.\nLooked around 1 times.\n\
+         Visited the hall 2 times.\n"
+    );
+}
+
+#[test]
+fn choice_count_yields_number_of_choices_accepted_before_a_choice() {
+    let content = "
+
+*   One.
+*   Two.
+*   {CHOICE_COUNT() < 2} Three.
+*   {CHOICE_COUNT() < 2} Four.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    match story.resume(&mut line_buffer).unwrap() {
+        Prompt::Choice(choices) => {
+            let texts = choices
+                .iter()
+                .map(|choice| choice.text.as_str())
+                .collect::<Vec<_>>();
+
+            assert_eq!(texts, vec!["One.", "Two."]);
+        }
+        Prompt::Done => panic!("expected a choice"),
+    }
+}
+
+#[test]
+fn choice_count_is_reset_after_a_choice_is_made() {
+    let content = "
+
+*   One.
+*   Two.
+*   Three.
+- {CHOICE_COUNT()} choices so far.
+*   Four.
+
+";
+
+    let mut story = read_story_from_string(content).unwrap();
+    let mut line_buffer = Vec::new();
+
+    story.resume(&mut line_buffer).unwrap();
+    story.make_choice(0).unwrap();
+
+    line_buffer.clear();
+    story.resume(&mut line_buffer).unwrap();
+
+    assert_eq!(&line_buffer[1].text, "0 choices so far.\n");
+}
+
+#[test]
+fn turns_since_with_argument_which_is_not_a_divert_yields_error() {
+    let content = "
+
+{TURNS_SINCE(2)}
+
+";
+
+    assert!(read_story_from_string(content).is_err());
+}